@@ -0,0 +1,97 @@
+//! Cost of `MultiReceiver::cleanup()` called after each `push()`, with many idle objects in progress
+//!
+//! Run with `cargo bench --bench cleanup`
+
+use flute::core::alc::{parse_alc_pkt, parse_payload_id};
+use flute::core::lct::Cenc;
+use flute::core::{Oti, UDPEndpoint};
+use flute::receiver::{writer::ObjectWriterBufferBuilder, Config, MultiReceiver};
+use flute::sender::{ObjectDesc, Sender};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+const NB_IDLE_OBJECTS: usize = 10000;
+const NB_PUSH: usize = 10000;
+
+/// Return the FDT packets and the first packet of `NB_IDLE_OBJECTS` objects of 2 packets
+fn create_packets() -> Vec<Vec<u8>> {
+    let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3000);
+    let oti = Oti::default();
+    let mut sender = Sender::new(endpoint, 1, &oti, &Default::default());
+    let content = vec![0u8; oti.encoding_symbol_length as usize * 2];
+
+    for i in 0..NB_IDLE_OBJECTS {
+        let obj = ObjectDesc::create_from_buffer(
+            &content,
+            "application/octet-stream",
+            &url::Url::parse(&format!("file:///object{}", i)).unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            Cenc::Null,
+            true,
+            None,
+            false,
+        )
+        .unwrap();
+        sender.add_object(0, obj).unwrap();
+    }
+    sender.publish(SystemTime::now()).unwrap();
+
+    let mut packets = Vec::new();
+    let now = SystemTime::now();
+    while let Some(pkt) = sender.read(now) {
+        let alc = parse_alc_pkt(&pkt).unwrap();
+        let first_symbol = parse_payload_id(&alc, &oti)
+            .map(|payload_id| payload_id.sbn == 0 && payload_id.esi == 0)
+            .unwrap();
+        if alc.lct.toi == 0 || first_symbol {
+            packets.push(pkt);
+        }
+    }
+    packets
+}
+
+/// Average duration of a `push()` followed by a `cleanup()`
+fn push_cleanup(packets: &[Vec<u8>], cleanup_interval: Duration) -> Duration {
+    let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3000);
+    let writer = Rc::new(ObjectWriterBufferBuilder::new());
+    let config = Config {
+        cleanup_interval,
+        ..Default::default()
+    };
+    let mut receiver = MultiReceiver::new(writer, Some(config), false);
+    let now = SystemTime::now();
+    for pkt in packets {
+        receiver.push(&endpoint, pkt, now).unwrap();
+    }
+    assert_eq!(receiver.nb_objects(), NB_IDLE_OBJECTS);
+
+    // The FDT is received again, the objects stay idle
+    let fdt = &packets[0];
+    let start = Instant::now();
+    for _ in 0..NB_PUSH {
+        receiver.push(&endpoint, fdt, now).unwrap();
+        receiver.cleanup(now);
+    }
+    let duration = start.elapsed();
+    assert_eq!(receiver.nb_objects(), NB_IDLE_OBJECTS);
+    duration / NB_PUSH as u32
+}
+
+fn main() {
+    let packets = create_packets();
+    for (name, interval) in [
+        ("scan at each call", Duration::ZERO),
+        ("default interval", Config::default().cleanup_interval),
+    ] {
+        println!(
+            "{:<18} {} idle objects: {:?} per push + cleanup",
+            name,
+            NB_IDLE_OBJECTS,
+            push_cleanup(&packets, interval)
+        );
+    }
+}
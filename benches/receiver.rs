@@ -0,0 +1,108 @@
+//! Throughput of the receiver for a session of small objects
+//!
+//! Run with `cargo bench --bench receiver`
+
+use flute::core::lct::Cenc;
+use flute::core::{Oti, UDPEndpoint};
+use flute::receiver::{writer::ObjectWriterBufferBuilder, MultiReceiver};
+use flute::sender::{ObjectDesc, Sender};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime};
+
+const NB_OBJECTS: usize = 2000;
+const OBJECT_SIZE: usize = 4096;
+/// Number of packets pushed with `MultiReceiver::push_batch()`, 0 to push the packets one by one
+const BATCH_SIZE: usize = 64;
+
+/// Packets of a session transferring `NB_OBJECTS` objects of `OBJECT_SIZE` bytes
+fn create_packets(md5: bool) -> Vec<Vec<u8>> {
+    let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3000);
+    let oti = Oti::default();
+    let mut sender = Sender::new(endpoint, 1, &oti, &Default::default());
+    let content: Vec<u8> = (0..OBJECT_SIZE).map(|i| i as u8).collect();
+
+    for i in 0..NB_OBJECTS {
+        let obj = ObjectDesc::create_from_buffer(
+            &content,
+            "application/octet-stream",
+            &url::Url::parse(&format!("file:///object{}", i)).unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            Cenc::Null,
+            true,
+            None,
+            md5,
+        )
+        .unwrap();
+        sender.add_object(0, obj).unwrap();
+    }
+    sender.publish(SystemTime::now()).unwrap();
+
+    let mut packets = Vec::new();
+    let now = SystemTime::now();
+    while let Some(pkt) = sender.read(now) {
+        packets.push(pkt);
+    }
+    packets
+}
+
+fn receive(packets: &[Vec<u8>], batch_size: usize) -> std::time::Duration {
+    let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3000);
+    let writer = Rc::new(ObjectWriterBufferBuilder::new());
+    let mut receiver = MultiReceiver::new(writer.clone(), None, false);
+    let now = SystemTime::now();
+
+    let batches: Vec<Vec<&[u8]>> = packets
+        .chunks(batch_size.max(1))
+        .map(|batch| batch.iter().map(|pkt| pkt.as_slice()).collect())
+        .collect();
+
+    let start = Instant::now();
+    if batch_size == 0 {
+        for pkt in packets {
+            receiver.push(&endpoint, pkt, now).unwrap();
+        }
+    } else {
+        for batch in &batches {
+            for ret in receiver.push_batch(&endpoint, batch, now) {
+                ret.unwrap();
+            }
+        }
+    }
+    let duration = start.elapsed();
+
+    let objects = writer.objects.borrow();
+    assert!(objects.iter().filter(|obj| obj.borrow().complete).count() >= NB_OBJECTS);
+    duration
+}
+
+fn bench(name: &str, md5: bool, batch_size: usize) {
+    let packets = create_packets(md5);
+    let bytes: usize = packets.iter().map(|pkt| pkt.len()).sum();
+
+    let iterations = 5;
+    let best = (0..iterations)
+        .map(|_| receive(&packets, batch_size))
+        .min()
+        .unwrap();
+
+    println!(
+        "{:<16} {} objects of {} bytes: {:?} ({:.0} objects/s, {:.1} MB/s)",
+        name,
+        NB_OBJECTS,
+        OBJECT_SIZE,
+        best,
+        NB_OBJECTS as f64 / best.as_secs_f64(),
+        bytes as f64 / best.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    bench("no-md5", false, 0);
+    bench("md5", true, 0);
+    bench("no-md5 batch", false, BATCH_SIZE);
+    bench("md5 batch", true, BATCH_SIZE);
+}
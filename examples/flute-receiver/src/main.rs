@@ -11,13 +11,16 @@ fn main() {
 
     let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_string(), 3400);
 
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let list = args.iter().any(|arg| arg == "--list");
+    args.retain(|arg| arg != "--list");
     if args.len() == 1 {
         println!(
             "Save FLUTE objects to a destination folder received from UDP/FLUTE {:?}",
             endpoint
         );
-        println!("Usage: {} path/to/destination_folder", args[0]);
+        println!("Usage: {} [--list] path/to/destination_folder", args[0]);
+        println!("  --list  print the FLUTE sessions discovered every 5 seconds");
         std::process::exit(0);
     }
 
@@ -36,18 +39,56 @@ fn main() {
     let socket = msocket::MSocket::new(&endpoint, Some("127.0.0.1"), false)
         .expect("Fail to create Multicast Socket");
 
+    if list {
+        socket
+            .sock
+            .set_read_timeout(Some(LIST_INTERVAL))
+            .expect("Fail to set the socket read timeout");
+    }
+
     let mut buf = [0; 2048];
+    let mut last_list = std::time::Instant::now();
     loop {
-        let (n, _src) = socket
-            .sock
-            .recv_from(&mut buf)
-            .expect("Failed to receive data");
-
-        let now = std::time::SystemTime::now();
-        match receiver.push(&endpoint, &buf[..n], now) {
-            Err(_) => log::error!("Wrong ALC/LCT packet"),
-            _ => {}
-        };
-        receiver.cleanup(now);
+        match socket.sock.recv_from(&mut buf) {
+            Ok((n, _src)) => {
+                let now = std::time::SystemTime::now();
+                match receiver.push(&endpoint, &buf[..n], now) {
+                    Err(_) => log::error!("Wrong ALC/LCT packet"),
+                    _ => {}
+                };
+                receiver.cleanup(now);
+            }
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => panic!("Failed to receive data {:?}", e),
+        }
+
+        if list && last_list.elapsed() >= LIST_INTERVAL {
+            last_list = std::time::Instant::now();
+            print_session_directory(&receiver);
+        }
+    }
+}
+
+const LIST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+fn print_session_directory(receiver: &MultiReceiver) {
+    let sessions = receiver.session_directory();
+    println!("{} FLUTE session(s)", sessions.len());
+    for session in sessions {
+        let idle = session.last_activity.elapsed().unwrap_or_default();
+        println!(
+            "  {}:{} tsi={} fdt={:?} files={} bytes={} completed={} complete={} idle={}s",
+            session.endpoint.destination_group_address,
+            session.endpoint.port,
+            session.tsi,
+            session.latest_fdt_instance_id,
+            session.announced_files,
+            session.announced_bytes,
+            session.objects_completed,
+            session.complete,
+            idle.as_secs()
+        );
     }
 }
@@ -1,7 +1,7 @@
 use flute::{
     core::UDPEndpoint,
     core::lct::Cenc,
-    sender::{ObjectDesc, Sender},
+    sender::{ObjectDesc, Sender, SenderActivity},
 };
 use std::{net::UdpSocket, time::SystemTime};
 
@@ -60,8 +60,19 @@ fn main() {
     log::info!("Publish FDT update");
     sender.publish(SystemTime::now()).unwrap();
 
-    while let Some(pkt) = sender.read(SystemTime::now()) {
-        udp_socket.send(&pkt).unwrap();
-        std::thread::sleep(std::time::Duration::from_millis(1));
+    loop {
+        let now = SystemTime::now();
+        match sender.next_activity(now) {
+            SenderActivity::PacketReady => {
+                if let Some(pkt) = sender.read(now) {
+                    udp_socket.send(&pkt).unwrap();
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+            }
+            SenderActivity::WaitUntil(time) => {
+                std::thread::sleep(time.duration_since(now).unwrap_or_default())
+            }
+            SenderActivity::Idle => break,
+        }
     }
 }
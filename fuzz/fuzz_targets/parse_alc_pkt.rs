@@ -0,0 +1,29 @@
+//! Fuzz `core::alc::parse_alc_pkt()` and the parsers of the fields of the ALC/LCT packet
+//!
+//! Run with `cargo +nightly fuzz run parse_alc_pkt` from the root of the repository.
+//! The seed corpus `fuzz/corpus/parse_alc_pkt` contains packets generated by the sender for each FEC scheme,
+//! and malformed packets that used to crash the parser (null symbol length or alignment, truncated headers...)
+
+#![no_main]
+
+use flute::core::alc;
+use flute::core::Oti;
+use flute::error::FluteError;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Parsing never panics, malformed packets are reported with a `FluteError`
+    let pkt = match alc::parse_alc_pkt(data) {
+        Ok(pkt) => pkt,
+        Err(FluteError { .. }) => return,
+    };
+
+    let _: Result<_, FluteError> = alc::get_sender_current_time(&pkt);
+
+    // The FEC Payload ID is parsed with the OTI of the inband FTI, as done by the receiver,
+    // and with the OTI of the session announced in the FDT
+    if let Some(oti) = pkt.oti.as_ref() {
+        let _: Result<_, FluteError> = alc::parse_payload_id(&pkt, oti);
+    }
+    let _: Result<_, FluteError> = alc::parse_payload_id(&pkt, &Oti::default());
+});
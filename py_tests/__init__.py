@@ -154,5 +154,57 @@ class SenderReceiverTestCase(TestCase):
         assert(lct.sbn == 0)
         assert(lct.esi == 0)
 
+    def test_send_file_carousel(self):
+        from flute import sender, receiver
+        import os
+        import tempfile
+
+        print("------- test_send_file_carousel--------")
+
+        tsi = 1
+        content = os.urandom(10000)
+
+        with tempfile.TemporaryDirectory() as src_dir, tempfile.TemporaryDirectory() as dest_dir:
+            filepath = os.path.join(src_dir, "file.bin")
+            with open(filepath, "wb") as f:
+                f.write(content)
+
+            sender_config = sender.Config()
+            sender_config.priority_queues = {0: 1, 1: 2}
+            sender_config.interleave_blocks = 2
+            assert(sender_config.priority_queues == {0: 1, 1: 2})
+            oti = sender.Oti.new_no_code(1400, 64)
+            flute_sender = sender.Sender(tsi, oti, sender_config)
+
+            toi = flute_sender.add_file(filepath,
+                                        0,
+                                        "application/octet-stream",
+                                        content_location="file:///file.bin",
+                                        priority=1,
+                                        max_transfer_count=3,
+                                        carousel_delay_ms=3600 * 1000)
+            flute_sender.publish()
+            assert(b'file:///file.bin' in flute_sender.fdt_xml_data())
+
+            receiver_writer = receiver.ObjectWriterBuilder(dest_dir)
+            receiver_config = receiver.Config()
+            flute_receiver = receiver.MultiReceiver(receiver_writer, receiver_config)
+            udp_endpoint = receiver.UDPEndpoint("224.0.0.1", 1234)
+
+            while True:
+                pkt = flute_sender.read()
+                if pkt == None:
+                    break
+                assert(isinstance(pkt, bytes))
+                flute_receiver.push(udp_endpoint, pkt)
+
+            assert(flute_sender.nb_transfers(toi) == 3)
+            with open(os.path.join(dest_dir, "file.bin"), "rb") as f:
+                assert(f.read() == content)
+
+            assert(flute_sender.rewind_object(toi))
+            assert(flute_sender.remove_object(toi))
+            assert(flute_sender.nb_transfers(toi) == None)
+
 if __name__ == '__main__':
     unittest.main()
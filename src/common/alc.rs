@@ -2,6 +2,9 @@ use super::{alccodec::AlcCodec, lct, oti, pkt::Pkt, Profile};
 use crate::tools::{self, error::FluteError, error::Result};
 use std::time::SystemTime;
 
+/// NTP seconds of 2000-01-01T00:00:00Z, SCT values before this date are considered as invalid
+const NTP_SECONDS_2000: u32 = 3155673600;
+
 /// ALC Packet
 #[derive(Debug)]
 pub struct AlcPkt<'a> {
@@ -13,7 +16,7 @@ pub struct AlcPkt<'a> {
     pub transfer_length: Option<u64>,
     /// CENC
     pub cenc: Option<lct::Cenc>,
-    /// Server Time
+    /// Sender Current Time (SCT) of the EXT_TIME extension, `None` if missing or invalid
     pub server_time: Option<SystemTime>,
     /// Data
     pub data: &'a [u8],
@@ -105,7 +108,8 @@ pub fn new_alc_pkt_close_session(cci: &u128, tsi: u64) -> Vec<u8> {
     );
     // push_fdt(&mut data, 1, 0);
     let codec = <dyn AlcCodec>::instance(oti.fec_encoding_id);
-    codec.add_fti(&mut data, &oti, 0);
+    let res = codec.add_fti(&mut data, &oti, 0);
+    debug_assert!(res.is_ok());
     // Add FEC Payload ID
     data.extend(0u32.to_be_bytes());
     data
@@ -118,7 +122,7 @@ pub fn new_alc_pkt(
     pkt: &Pkt,
     profile: Profile,
     now: SystemTime,
-) -> Vec<u8> {
+) -> Result<Vec<u8>> {
     let mut data = Vec::new();
     log::debug!("Send ALC sbn={} esi={} toi={}", pkt.sbn, pkt.esi, pkt.toi);
     lct::push_lct_header(
@@ -140,32 +144,76 @@ pub fn new_alc_pkt(
             Profile::RFC3926 => 1,
         };
 
-        push_fdt(&mut data, version, pkt.fdt_id.unwrap())
+        push_fdt(&mut data, version, pkt.fdt_id.unwrap())?;
     }
 
     // In case of FDT, we must push Cenc if Cenc is not null
     if (pkt.toi == lct::TOI_FDT && (pkt.cenc != lct::Cenc::Null)) || pkt.inband_cenc {
-        push_cenc(&mut data, pkt.cenc as u8);
+        push_cenc(&mut data, pkt.cenc as u8)?;
     }
 
     if pkt.sender_current_time {
         match profile {
-            Profile::RFC6726 => push_sct(&mut data, now),
-            Profile::RFC3926 => push_sct(&mut data, now),
+            Profile::RFC6726 => push_sct(&mut data, now)?,
+            Profile::RFC3926 => push_sct(&mut data, now)?,
         };
     }
 
     let codec = <dyn AlcCodec>::instance(oti.fec_encoding_id);
+    debug_assert!(codec.validate_packet_parameters(oti, pkt).is_ok());
     if pkt.toi == lct::TOI_FDT || oti.inband_fti {
-        codec.add_fti(&mut data, oti, pkt.transfer_length);
+        codec.add_fti(&mut data, oti, pkt.transfer_length)?;
     }
-    codec.add_fec_payload_id(&mut data, oti, pkt);
+    codec.add_fec_payload_id(&mut data, oti, &pkt.payload_id());
     push_payload(&mut data, pkt);
-    data
+    Ok(data)
+}
+
+///
+/// Return the maximum number of payload bytes of an ALC/LCT packet that fit within `mtu`
+///
+/// The size of the LCT header is computed from the TSI and the TOI, with a CCI on 32 bits.
+/// The FEC Payload ID and the extensions that may be added to the packet are subtracted :
+/// EXT_FDT (FDT only), EXT_FTI (FDT or in-band FTI), EXT_CENC and EXT_TIME (SCT).
+///
+/// # Returns
+///
+/// `0` if the headers do not fit within `mtu`
+///
+pub fn alc_pkt_max_payload_size(oti: &oti::Oti, tsi: u64, toi: u128, mtu: usize) -> usize {
+    let mut data = Vec::new();
+    lct::push_lct_header(
+        &mut data,
+        0,
+        &0u128,
+        tsi,
+        &toi,
+        oti.fec_encoding_id as u8,
+        false,
+        false,
+    );
+
+    // HDR_LEN can not overflow, the LCT header and all its extensions fit in 1020 bytes
+    if toi == lct::TOI_FDT {
+        push_fdt(&mut data, 2, 0).ok();
+    }
+    push_cenc(&mut data, lct::Cenc::Null as u8).ok();
+    push_sct(&mut data, SystemTime::now()).ok();
+
+    let codec = <dyn AlcCodec>::instance(oti.fec_encoding_id);
+    if toi == lct::TOI_FDT || oti.inband_fti {
+        codec.add_fti(&mut data, oti, 0).ok();
+    }
+
+    let overhead = data.len() + codec.fec_payload_id_block_length();
+    mtu.saturating_sub(overhead)
 }
 
 /// Parse a buffer to AlcPkt
-pub fn parse_alc_pkt(data: &[u8]) -> Result<AlcPkt> {
+///
+/// Any input can be parsed without panic, malformed packets are rejected with a `FluteError`.
+/// This entry point is fuzzed by the `parse_alc_pkt` target of `fuzz/`
+pub fn parse_alc_pkt(data: &[u8]) -> Result<AlcPkt<'_>> {
     let lct_header = lct::parse_lct_header(data)?;
 
     let fec: oti::FECEncodingID = lct_header
@@ -206,12 +254,17 @@ pub fn parse_alc_pkt(data: &[u8]) -> Result<AlcPkt> {
         };
     }
 
+    let server_time = match lct::get_ext(data, &lct_header, lct::Ext::Time as u8)? {
+        Some(ext) => parse_sct(ext).ok().flatten(),
+        None => None,
+    };
+
     Ok(AlcPkt {
         lct: lct_header,
         oti: fti.as_ref().map(|fti| fti.0.clone()),
         transfer_length: fti.map(|fti| fti.1),
         cenc,
-        server_time: None,
+        server_time,
         data,
         data_alc_header_offset,
         data_payload_offset,
@@ -220,6 +273,9 @@ pub fn parse_alc_pkt(data: &[u8]) -> Result<AlcPkt> {
 }
 
 /// Get Sender Current Time (EXT_TIME)
+///
+/// Returns an error if the extension is malformed or if the SCT is not a plausible NTP timestamp
+/// (unset or before the year 2000).
 pub fn get_sender_current_time(pkt: &AlcPkt) -> Result<Option<SystemTime>> {
     let ext = match lct::get_ext(pkt.data, &pkt.lct, lct::Ext::Time as u8)? {
         Some(res) => res,
@@ -268,7 +324,7 @@ fn parse_ext_fdt(ext: &[u8]) -> Result<Option<ExtFDT>> {
     }))
 }
 
-fn push_fdt(data: &mut Vec<u8>, version: u8, fdt_id: u32) {
+pub(crate) fn push_fdt(data: &mut Vec<u8>, version: u8, fdt_id: u32) -> Result<()> {
     /*
     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
     |   HET = 192   |   V   |          FDT Instance ID              |
@@ -276,10 +332,10 @@ fn push_fdt(data: &mut Vec<u8>, version: u8, fdt_id: u32) {
      */
     let ext = (lct::Ext::Fdt as u32) << 24 | (version as u32) << 20 | fdt_id;
     data.extend(ext.to_be_bytes());
-    lct::inc_hdr_len(data, 1);
+    lct::inc_hdr_len(data, 1)
 }
 
-fn push_cenc(data: &mut Vec<u8>, cenc: u8) {
+pub(crate) fn push_cenc(data: &mut Vec<u8>, cenc: u8) -> Result<()> {
     /*
      0                   1                   2                   3
      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
@@ -289,7 +345,7 @@ fn push_cenc(data: &mut Vec<u8>, cenc: u8) {
       */
     let ext = (lct::Ext::Cenc as u32) << 24 | (cenc as u32) << 16;
     data.extend(ext.to_be_bytes());
-    lct::inc_hdr_len(data, 1);
+    lct::inc_hdr_len(data, 1)
 }
 
 fn parse_cenc(ext: &[u8]) -> Result<lct::Cenc> {
@@ -301,7 +357,7 @@ fn parse_cenc(ext: &[u8]) -> Result<lct::Cenc> {
         .map_err(|_| FluteError::new("Cenc not supported"))
 }
 
-fn push_sct(data: &mut Vec<u8>, time: std::time::SystemTime) {
+pub(crate) fn push_sct(data: &mut Vec<u8>, time: std::time::SystemTime) -> Result<()> {
     /*
      0                   1                   2                   3
      0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
@@ -328,11 +384,11 @@ fn push_sct(data: &mut Vec<u8>, time: std::time::SystemTime) {
     // Convert UTC to NTP
     let ntp = match tools::system_time_to_ntp(time) {
         Ok(res) => res,
-        Err(_) => return,
+        Err(_) => return Ok(()),
     };
     data.extend(header.to_be_bytes());
     data.extend(ntp.to_be_bytes());
-    lct::inc_hdr_len(data, 3);
+    lct::inc_hdr_len(data, 3)
 }
 
 fn parse_sct(ext: &[u8]) -> Result<Option<std::time::SystemTime>> {
@@ -357,6 +413,13 @@ fn parse_sct(ext: &[u8]) -> Result<Option<std::time::SystemTime>> {
     }
 
     let ntp_seconds: u32 = u32::from_be_bytes(ext[4..8].as_ref().try_into().unwrap());
+    if ntp_seconds < NTP_SECONDS_2000 {
+        return Err(FluteError::new(format!(
+            "Invalid Sender Current Time, NTP seconds {}",
+            ntp_seconds
+        )));
+    }
+
     let ntp_faction: u32 = match sct_low {
         1 => u32::from_be_bytes(ext[8..12].as_ref().try_into().unwrap()),
         _ => 0,
@@ -404,10 +467,221 @@ mod tests {
         };
 
         let alc_pkt =
-            super::new_alc_pkt(&oti, &cci, tsi, &pkt, Profile::RFC6726, SystemTime::now());
+            super::new_alc_pkt(&oti, &cci, tsi, &pkt, Profile::RFC6726, SystemTime::now()).unwrap();
         let decoded_pkt = super::parse_alc_pkt(&alc_pkt).unwrap();
         assert!(decoded_pkt.lct.toi == pkt.toi);
         assert!(decoded_pkt.lct.cci == cci);
         assert!(decoded_pkt.lct.tsi == tsi);
     }
+
+    #[test]
+    pub fn test_alc_sender_current_time() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let now = SystemTime::now();
+        let pkt = pkt::Pkt {
+            payload: vec![0u8; 4],
+            esi: 0,
+            sbn: 0,
+            toi: 1,
+            fdt_id: None,
+            cenc: lct::Cenc::Null,
+            inband_cenc: false,
+            transfer_length: 4,
+            close_object: false,
+            source_block_length: 1,
+            sender_current_time: true,
+        };
+
+        let alc_pkt = super::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+        let decoded_pkt = super::parse_alc_pkt(&alc_pkt).unwrap();
+        let sct = super::get_sender_current_time(&decoded_pkt)
+            .unwrap()
+            .unwrap();
+        let diff = match sct > now {
+            true => sct.duration_since(now).unwrap(),
+            false => now.duration_since(sct).unwrap(),
+        };
+        assert!(diff < std::time::Duration::from_millis(1));
+
+        // SCT Hi + SCT Low set, NTP seconds close to the NTP epoch
+        let ext: Vec<u8> = vec![2, 3, 0xC0, 0, 0, 0, 0, 10, 0, 0, 0, 0];
+        assert!(super::parse_sct(&ext).is_err());
+    }
+
+    #[test]
+    pub fn test_alc_payload_id_invalid_parameters() {
+        crate::tests::init();
+
+        let mut oti = oti::Oti::new_no_code(1400, 64);
+        let pkt = pkt::Pkt {
+            payload: vec![0u8; 4],
+            esi: 1,
+            sbn: 0,
+            toi: 1,
+            fdt_id: None,
+            cenc: lct::Cenc::Null,
+            inband_cenc: false,
+            transfer_length: 4,
+            close_object: false,
+            source_block_length: 1,
+            sender_current_time: false,
+        };
+        let alc_pkt =
+            super::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, SystemTime::now()).unwrap();
+        let decoded_pkt = super::parse_alc_pkt(&alc_pkt).unwrap();
+        assert!(super::parse_payload_id(&decoded_pkt, &oti).is_ok());
+
+        oti.maximum_source_block_length = 1;
+        assert!(super::parse_payload_id(&decoded_pkt, &oti).is_err());
+
+        oti.maximum_source_block_length = 64;
+        oti.encoding_symbol_length = 0;
+        assert!(super::parse_payload_id(&decoded_pkt, &oti).is_err());
+    }
+
+    #[test]
+    pub fn test_alc_pkt_max_payload_size() {
+        crate::tests::init();
+
+        let mtu = 1500;
+        let otis = vec![
+            oti::Oti::new_no_code(1400, 64),
+            oti::Oti::new_reed_solomon_rs28(1400, 200, 50).unwrap(),
+            oti::Oti::new_raptorq(1400, 64, 10, 1, 4).unwrap(),
+            oti::Oti::new_raptor(1400, 64, 10, 1, 4).unwrap(),
+        ];
+
+        for oti in otis {
+            for (toi, tsi) in [
+                (lct::TOI_FDT, 1u64),
+                (1, 1),
+                (0x1234_5678_9abc, 0x1234_5678),
+            ] {
+                let payload_size = super::alc_pkt_max_payload_size(&oti, tsi, toi, mtu);
+                // Packet with all the extensions enabled
+                let pkt = pkt::Pkt {
+                    payload: vec![0u8; payload_size],
+                    esi: 1,
+                    sbn: 0,
+                    toi,
+                    fdt_id: Some(1),
+                    cenc: lct::Cenc::Gzip,
+                    inband_cenc: true,
+                    transfer_length: 100000,
+                    close_object: false,
+                    source_block_length: 64,
+                    sender_current_time: true,
+                };
+                let alc_pkt = super::new_alc_pkt(
+                    &oti,
+                    &0u128,
+                    tsi,
+                    &pkt,
+                    Profile::RFC6726,
+                    SystemTime::now(),
+                )
+                .unwrap();
+                assert_eq!(alc_pkt.len(), mtu);
+            }
+        }
+
+        let oti = oti::Oti::new_no_code(1400, 64);
+        assert_eq!(super::alc_pkt_max_payload_size(&oti, 1, 1, 10), 0);
+    }
+
+    #[test]
+    pub fn test_alc_fuzz() {
+        use rand::Rng;
+        crate::tests::init();
+
+        let otis = vec![
+            oti::Oti::new_no_code(1400, 64),
+            oti::Oti::new_reed_solomon_rs28(1400, 64, 20).unwrap(),
+            oti::Oti::new_reed_solomon_rs28_under_specified(1400, 64, 20).unwrap(),
+            oti::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap(),
+            oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap(),
+        ];
+
+        let mut rng = rand::thread_rng();
+        for oti in &otis {
+            let pkt = pkt::Pkt {
+                payload: vec![0u8; 16],
+                esi: 0,
+                sbn: 0,
+                toi: 1,
+                fdt_id: None,
+                cenc: lct::Cenc::Null,
+                inband_cenc: true,
+                transfer_length: 16,
+                close_object: false,
+                source_block_length: 1,
+                sender_current_time: true,
+            };
+            let valid_pkt =
+                super::new_alc_pkt(oti, &0u128, 1, &pkt, Profile::RFC6726, SystemTime::now())
+                    .unwrap();
+
+            for _ in 0..1000 {
+                // Corrupt a few bytes of a valid packet, or generate a random packet
+                let data: Vec<u8> = match rng.gen_bool(0.5) {
+                    true => {
+                        let mut data = valid_pkt.clone();
+                        for _ in 0..rng.gen_range(1..4) {
+                            let index = rng.gen_range(0..data.len());
+                            data[index] = rng.gen();
+                        }
+                        data
+                    }
+                    false => (0..rng.gen_range(0..64)).map(|_| rng.gen()).collect(),
+                };
+
+                let alc_pkt = match super::parse_alc_pkt(&data) {
+                    Ok(alc_pkt) => alc_pkt,
+                    Err(_) => continue,
+                };
+
+                let _ = super::get_fec_inline_payload_id(&alc_pkt);
+                let _ = super::get_sender_current_time(&alc_pkt);
+                let _ = super::parse_payload_id(&alc_pkt, oti);
+                if let Some(fti_oti) = alc_pkt.oti.as_ref() {
+                    let _ = super::parse_payload_id(&alc_pkt, fti_oti);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_alc_hdr_len_overflow() {
+        crate::tests::init();
+
+        let mut data = Vec::new();
+        lct::push_lct_header(&mut data, 0, &0u128, 1, &1u128, 0, false, false);
+        let hdr_len = data[2];
+
+        // Each EXT_CENC adds 1 to HDR_LEN, the header can not exceed 255 32-bit words
+        let nb_ext = 255 - hdr_len as usize;
+        for _ in 0..nb_ext {
+            super::push_cenc(&mut data, lct::Cenc::Null as u8).unwrap();
+        }
+        assert_eq!(data[2], 255);
+
+        assert!(super::push_cenc(&mut data, lct::Cenc::Null as u8).is_err());
+        assert!(super::push_fdt(&mut data, 2, 1).is_err());
+        assert!(super::push_sct(&mut data, SystemTime::now()).is_err());
+        assert!(lct::inc_hdr_len(&mut data, 1).is_err());
+        assert_eq!(data[2], 255);
+
+        // The builder rejects the packet instead of wrapping HDR_LEN
+        let mut builder = crate::core::alc::PacketBuilder::new()
+            .oti(oti::Oti::new_no_code(4, 2))
+            .tsi(1)
+            .toi(1)
+            .payload(vec![0u8; 4]);
+        for _ in 0..nb_ext + 1 {
+            builder = builder.cenc(lct::Cenc::Null);
+        }
+        assert!(builder.build().is_err());
+    }
 }
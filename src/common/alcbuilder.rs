@@ -0,0 +1,696 @@
+use super::{
+    alc::{self, PayloadID},
+    alccodec::AlcCodec,
+    lct::{self, LctHeaderFlags},
+    oti::{self, SchemeSpecific},
+};
+use crate::tools::error::{FluteError, Result};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+enum HeaderExtension {
+    Fdt { version: u8, fdt_instance_id: u32 },
+    Cenc(lct::Cenc),
+    Fti(u64),
+    Time(SystemTime),
+    Raw(Vec<u8>),
+}
+
+///
+/// Build an ALC/LCT packet field by field
+///
+/// Meant to test a receiver with packets that the FLUTE `Sender` does not generate :
+/// explicit width of the CCI, TSI and TOI fields, any combination of header extensions,
+/// boundary FEC Payload IDs...
+///
+/// The header extensions are written in the order of the calls, the packet is serialized
+/// with the same functions as the `Sender`.
+///
+/// * `build()` validates the consistency of the packet and checks that it can be parsed back
+/// * `build_unchecked()` serializes the fields as they are, for example a wrong `HDR_LEN`,
+///   to test the robustness of a receiver
+///
+/// # Example
+///
+/// ```
+/// use flute::core::alc::{parse_alc_pkt, PacketBuilder};
+/// use flute::core::lct::Cenc;
+/// use flute::core::Oti;
+///
+/// let pkt = PacketBuilder::new()
+///     .oti(Oti::new_no_code(1400, 64))
+///     .tsi(1)
+///     .toi(0x1234)
+///     .toi_width(6)
+///     .cenc(Cenc::Gzip)
+///     .fti(1000)
+///     .payload_id(0, 3)
+///     .payload(vec![0u8; 1400])
+///     .build()
+///     .unwrap();
+///
+/// let alc_pkt = parse_alc_pkt(&pkt).unwrap();
+/// assert_eq!(alc_pkt.lct.toi, 0x1234);
+/// assert_eq!(alc_pkt.transfer_length, Some(1000));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PacketBuilder {
+    version: u8,
+    psi: u8,
+    cci: u128,
+    cci_width: Option<u8>,
+    tsi: u64,
+    tsi_width: Option<u8>,
+    toi: u128,
+    toi_width: Option<u8>,
+    codepoint: Option<u8>,
+    close_session: bool,
+    close_object: bool,
+    hdr_len: Option<u8>,
+    extensions: Vec<HeaderExtension>,
+    oti: oti::Oti,
+    sbn: u32,
+    esi: u32,
+    source_block_length: Option<u32>,
+    payload: Vec<u8>,
+}
+
+impl Default for PacketBuilder {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            psi: 0,
+            cci: 0,
+            cci_width: None,
+            tsi: 0,
+            tsi_width: None,
+            toi: 0,
+            toi_width: None,
+            codepoint: None,
+            close_session: false,
+            close_object: false,
+            hdr_len: None,
+            extensions: Vec::new(),
+            oti: Default::default(),
+            sbn: 0,
+            esi: 0,
+            source_block_length: None,
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl PacketBuilder {
+    /// Return a new `PacketBuilder`, LCT version 1, TSI 0 and TOI 0 (FDT), with the default `Oti`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the LCT version number (V), default 1
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the Protocol-Specific Indication (PSI), default 0
+    pub fn psi(mut self, psi: u8) -> Self {
+        self.psi = psi;
+        self
+    }
+
+    /// Set the Congestion Control Information (CCI), default 0
+    pub fn cci(mut self, cci: u128) -> Self {
+        self.cci = cci;
+        self
+    }
+
+    /// Set the size in bytes of the CCI field (4, 8, 12 or 16), by default the smallest size that fits the CCI
+    pub fn cci_width(mut self, width: u8) -> Self {
+        self.cci_width = Some(width);
+        self
+    }
+
+    /// Set the Transport Session Identifier (TSI), default 0
+    pub fn tsi(mut self, tsi: u64) -> Self {
+        self.tsi = tsi;
+        self
+    }
+
+    /// Set the size in bytes of the TSI field (0, 2, 4 or 6), by default the smallest size that fits the TSI
+    pub fn tsi_width(mut self, width: u8) -> Self {
+        self.tsi_width = Some(width);
+        self
+    }
+
+    /// Set the Transport Object Identifier (TOI), default 0 (FDT)
+    pub fn toi(mut self, toi: u128) -> Self {
+        self.toi = toi;
+        self
+    }
+
+    /// Set the size in bytes of the TOI field (0 to 14, multiple of 2), by default the smallest size that fits the TOI
+    pub fn toi_width(mut self, width: u8) -> Self {
+        self.toi_width = Some(width);
+        self
+    }
+
+    /// Set the Codepoint (CP), by default the FEC Encoding ID of the OTI
+    pub fn codepoint(mut self, codepoint: u8) -> Self {
+        self.codepoint = Some(codepoint);
+        self
+    }
+
+    /// Set the Close Session flag (A), default false
+    pub fn close_session(mut self, close_session: bool) -> Self {
+        self.close_session = close_session;
+        self
+    }
+
+    /// Set the Close Object flag (B), default false
+    pub fn close_object(mut self, close_object: bool) -> Self {
+        self.close_object = close_object;
+        self
+    }
+
+    /// Override the LCT header length (HDR_LEN) in 32-bit words, by default the length of the serialized header
+    pub fn hdr_len(mut self, hdr_len: u8) -> Self {
+        self.hdr_len = Some(hdr_len);
+        self
+    }
+
+    /// Set the FEC parameters used to encode the EXT_FTI extension and the FEC Payload ID, default `Oti::default()`
+    pub fn oti(mut self, oti: oti::Oti) -> Self {
+        self.oti = oti;
+        self
+    }
+
+    /// Add an EXT_FDT extension
+    pub fn fdt(mut self, version: u8, fdt_instance_id: u32) -> Self {
+        self.extensions.push(HeaderExtension::Fdt {
+            version,
+            fdt_instance_id,
+        });
+        self
+    }
+
+    /// Add an EXT_CENC extension
+    pub fn cenc(mut self, cenc: lct::Cenc) -> Self {
+        self.extensions.push(HeaderExtension::Cenc(cenc));
+        self
+    }
+
+    /// Add an EXT_FTI extension encoded with the FEC scheme of the OTI
+    pub fn fti(mut self, transfer_length: u64) -> Self {
+        self.extensions.push(HeaderExtension::Fti(transfer_length));
+        self
+    }
+
+    /// Add an EXT_TIME extension with a Sender Current Time (SCT)
+    pub fn sender_current_time(mut self, time: SystemTime) -> Self {
+        self.extensions.push(HeaderExtension::Time(time));
+        self
+    }
+
+    /// Add an extension, `ext` contains the HET, the HEL (HET < 128) and the content of the extension
+    pub fn raw_extension(mut self, ext: Vec<u8>) -> Self {
+        self.extensions.push(HeaderExtension::Raw(ext));
+        self
+    }
+
+    /// Set the Source Block Number and the Encoding Symbol ID of the FEC Payload ID, default 0
+    pub fn payload_id(mut self, sbn: u32, esi: u32) -> Self {
+        self.sbn = sbn;
+        self.esi = esi;
+        self
+    }
+
+    /// Set the Source Block Length of the FEC Payload ID (Reed-Solomon GF(2^8) under-specified),
+    /// by default the maximum source block length of the OTI
+    pub fn source_block_length(mut self, source_block_length: u32) -> Self {
+        self.source_block_length = Some(source_block_length);
+        self
+    }
+
+    /// Set the encoding symbol carried by the packet, default empty
+    pub fn payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    ///
+    /// Validate the fields and serialize the packet
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if
+    /// * the version is not 1 or 2, or the PSI does not fit in 2 bits
+    /// * a width is not valid, the TSI and TOI widths do not share the same half-word flag,
+    ///   or a value does not fit in its field
+    /// * the codepoint is not the FEC Encoding ID of the OTI
+    /// * the EXT_FDT is missing from the FDT (TOI 0) or present in an object
+    /// * a raw extension is not a multiple of 32 bits or its HEL does not match its length
+    /// * the header is longer than 255 words, or does not match the `hdr_len()` override
+    /// * the FEC Payload ID is out of the range of the OTI
+    /// * the packet can not be parsed back
+    ///
+    pub fn build(&self) -> Result<Vec<u8>> {
+        self.validate()?;
+        let (data, header_len) = self.serialize();
+
+        if header_len > 255 * 4 {
+            return Err(FluteError::new(format!(
+                "LCT header of {} bytes exceeds HDR_LEN",
+                header_len
+            )));
+        }
+
+        if let Some(hdr_len) = self.hdr_len {
+            if hdr_len as usize * 4 != header_len {
+                return Err(FluteError::new(format!(
+                    "HDR_LEN {} does not match the LCT header of {} bytes",
+                    hdr_len, header_len
+                )));
+            }
+        }
+
+        let alc_pkt = alc::parse_alc_pkt(&data)?;
+        alc::parse_payload_id(&alc_pkt, &self.oti)?;
+        Ok(data)
+    }
+
+    ///
+    /// Serialize the packet without any validation, fields are truncated to their size
+    ///
+    /// # Panics
+    ///
+    /// Panics if an EXT_FTI is added with an OTI of a FEC scheme that requires scheme specific
+    /// parameters (Raptor, RaptorQ, Reed-Solomon GF(2^m)) without them
+    ///
+    pub fn build_unchecked(&self) -> Vec<u8> {
+        self.serialize().0
+    }
+
+    fn field_sizes(&self) -> (u32, u32, u32) {
+        let cci_size = match self.cci_width {
+            Some(width) => width as u32,
+            None => lct::nb_bytes_128(&self.cci, 0),
+        };
+        let tsi_size = match self.tsi_width {
+            Some(width) => width as u32,
+            None => lct::nb_bytes_64(self.tsi, 2),
+        };
+        let toi_size = match self.toi_width {
+            Some(width) => width as u32,
+            None => lct::nb_bytes_128(&self.toi, 2),
+        };
+        (cci_size, tsi_size, toi_size)
+    }
+
+    fn flags(&self) -> LctHeaderFlags {
+        let (cci_size, tsi_size, toi_size) = self.field_sizes();
+        let mut flags = LctHeaderFlags::from_sizes(cci_size, tsi_size, toi_size);
+        flags.version = self.version;
+        flags.psi = self.psi;
+        flags.close_session = self.close_session;
+        flags.close_object = self.close_object;
+        flags.codepoint = self.codepoint.unwrap_or(self.oti.fec_encoding_id as u8);
+        flags
+    }
+
+    fn fec_payload_id(&self) -> PayloadID {
+        PayloadID {
+            sbn: self.sbn,
+            esi: self.esi,
+            source_block_length: Some(
+                self.source_block_length
+                    .unwrap_or(self.oti.maximum_source_block_length),
+            ),
+        }
+    }
+
+    /// Return the packet and the length of its LCT header
+    fn serialize(&self) -> (Vec<u8>, usize) {
+        let codec = <dyn AlcCodec>::instance(self.oti.fec_encoding_id);
+        let mut data = Vec::new();
+        lct::push_lct_header_flags(&mut data, &self.flags(), &self.cci, self.tsi, &self.toi);
+
+        for ext in &self.extensions {
+            // HDR_LEN is left unchanged when it overflows, the LCT header is then rejected by build()
+            let _ = match ext {
+                HeaderExtension::Fdt {
+                    version,
+                    fdt_instance_id,
+                } => alc::push_fdt(&mut data, *version, *fdt_instance_id),
+                HeaderExtension::Cenc(cenc) => alc::push_cenc(&mut data, *cenc as u8),
+                HeaderExtension::Fti(transfer_length) => {
+                    codec.add_fti(&mut data, &self.oti, *transfer_length)
+                }
+                HeaderExtension::Time(time) => alc::push_sct(&mut data, *time),
+                HeaderExtension::Raw(ext) => {
+                    data.extend(ext);
+                    lct::inc_hdr_len(&mut data, (ext.len() / 4) as u8)
+                }
+            };
+        }
+
+        let header_len = data.len();
+        if let Some(hdr_len) = self.hdr_len {
+            data[2] = hdr_len;
+        }
+
+        codec.add_fec_payload_id(&mut data, &self.oti, &self.fec_payload_id());
+        data.extend(&self.payload);
+        (data, header_len)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.version != 1 && self.version != 2 {
+            return Err(FluteError::new(format!(
+                "LCT version {} is not supported",
+                self.version
+            )));
+        }
+
+        if self.psi > 3 {
+            return Err(FluteError::new(format!(
+                "PSI {} does not fit in 2 bits",
+                self.psi
+            )));
+        }
+
+        if let Some(width) = self.cci_width {
+            if !matches!(width, 4 | 8 | 12 | 16) {
+                return Err(FluteError::new(format!("Invalid CCI width {}", width)));
+            }
+        }
+
+        if let Some(width) = self.tsi_width {
+            if width > 6 || width % 2 != 0 {
+                return Err(FluteError::new(format!("Invalid TSI width {}", width)));
+            }
+        }
+
+        if let Some(width) = self.toi_width {
+            if width > 14 || width % 2 != 0 {
+                return Err(FluteError::new(format!("Invalid TOI width {}", width)));
+            }
+        }
+
+        let (cci_size, tsi_size, toi_size) = self.flags().sizes();
+        if self
+            .tsi_width
+            .map_or(false, |width| width as usize != tsi_size)
+            || self
+                .toi_width
+                .map_or(false, |width| width as usize != toi_size)
+        {
+            return Err(FluteError::new(format!(
+                "TSI width {:?} and TOI width {:?} do not share the same half-word flag",
+                self.tsi_width, self.toi_width
+            )));
+        }
+
+        if self.cci.checked_shr(cci_size as u32 * 8).unwrap_or(0) != 0
+            || self.tsi.checked_shr(tsi_size as u32 * 8).unwrap_or(0) != 0
+            || self.toi.checked_shr(toi_size as u32 * 8).unwrap_or(0) != 0
+        {
+            return Err(FluteError::new(format!(
+                "CCI, TSI or TOI does not fit in fields of {}, {} and {} bytes",
+                cci_size, tsi_size, toi_size
+            )));
+        }
+
+        let codepoint = self.flags().codepoint;
+        if codepoint != self.oti.fec_encoding_id as u8 {
+            return Err(FluteError::new(format!(
+                "Codepoint {} does not match the FEC Encoding ID {:?}",
+                codepoint, self.oti.fec_encoding_id
+            )));
+        }
+
+        let mut has_fdt = false;
+        for ext in &self.extensions {
+            match ext {
+                HeaderExtension::Fdt {
+                    version,
+                    fdt_instance_id,
+                } => {
+                    if *version > 0xF || *fdt_instance_id > 0xFFFFF {
+                        return Err(FluteError::new(format!(
+                            "EXT_FDT version {} or FDT Instance ID {} out of range",
+                            version, fdt_instance_id
+                        )));
+                    }
+                    has_fdt = true;
+                }
+                HeaderExtension::Fti(_) => self.validate_scheme_specific()?,
+                HeaderExtension::Raw(ext) => Self::validate_raw_extension(ext)?,
+                HeaderExtension::Cenc(_) | HeaderExtension::Time(_) => {}
+            }
+        }
+
+        if has_fdt != (self.toi == lct::TOI_FDT) {
+            return Err(FluteError::new(match has_fdt {
+                true => "EXT_FDT is only allowed in the FDT (TOI 0)",
+                false => "FDT (TOI 0) without EXT_FDT",
+            }));
+        }
+
+        let codec = <dyn AlcCodec>::instance(self.oti.fec_encoding_id);
+        codec.validate_payload_id(&self.oti, &self.fec_payload_id())
+    }
+
+    fn validate_scheme_specific(&self) -> Result<()> {
+        let valid = match (self.oti.fec_encoding_id, self.oti.scheme_specific.as_ref()) {
+            (oti::FECEncodingID::Raptor, Some(SchemeSpecific::Raptor(_))) => true,
+            (oti::FECEncodingID::RaptorQ, Some(SchemeSpecific::RaptorQ(_))) => true,
+            (oti::FECEncodingID::ReedSolomonGF2M, Some(SchemeSpecific::ReedSolomon(_))) => true,
+            (oti::FECEncodingID::Raptor, _)
+            | (oti::FECEncodingID::RaptorQ, _)
+            | (oti::FECEncodingID::ReedSolomonGF2M, _) => false,
+            _ => true,
+        };
+
+        if !valid {
+            return Err(FluteError::new(format!(
+                "Scheme specific parameters of FEC Encoding ID {:?} are missing",
+                self.oti.fec_encoding_id
+            )));
+        }
+        Ok(())
+    }
+
+    fn validate_raw_extension(ext: &[u8]) -> Result<()> {
+        if ext.len() < 4 || ext.len() % 4 != 0 {
+            return Err(FluteError::new(format!(
+                "Extension of {} bytes is not a multiple of 32 bits",
+                ext.len()
+            )));
+        }
+
+        // HET >= 128 : fixed size of 32 bits, HET < 128 : size given by HEL
+        let expected_len = match ext[0] {
+            het if het >= 128 => 4,
+            _ => ext[1] as usize * 4,
+        };
+        if ext.len() != expected_len {
+            return Err(FluteError::new(format!(
+                "Extension HET={} of {} bytes, expected {} bytes",
+                ext[0],
+                ext.len(),
+                expected_len
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PacketBuilder;
+    use crate::common::{alc, lct, oti};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    pub fn test_packet_builder_roundtrip() {
+        crate::tests::init();
+
+        let now = SystemTime::now();
+        let otis = vec![
+            oti::Oti::new_no_code(1400, 64),
+            oti::Oti::new_reed_solomon_rs28(1400, 64, 20).unwrap(),
+            oti::Oti::new_reed_solomon_rs28_under_specified(1400, 64, 20).unwrap(),
+            oti::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap(),
+            oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap(),
+        ];
+
+        for mut oti in otis {
+            // 100000 bytes, 2 source blocks of 64 symbols max
+            match oti.scheme_specific.as_mut() {
+                Some(oti::SchemeSpecific::Raptor(scheme)) => scheme.source_blocks_length = 2,
+                Some(oti::SchemeSpecific::RaptorQ(scheme)) => scheme.source_blocks_length = 2,
+                _ => {}
+            }
+
+            let pkt = PacketBuilder::new()
+                .version(2)
+                .psi(2)
+                .cci(0x1234)
+                .cci_width(8)
+                .tsi(0x42)
+                .tsi_width(6)
+                .toi(0x1234_5678)
+                .toi_width(10)
+                .close_object(true)
+                .close_session(true)
+                .oti(oti.clone())
+                .cenc(lct::Cenc::Deflate)
+                .fti(100000)
+                .sender_current_time(now)
+                .raw_extension(vec![64 + 1, 2, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF])
+                .raw_extension(vec![200, 1, 2, 3])
+                .payload_id(1, 17)
+                .source_block_length(64)
+                .payload(vec![0x55; 1400])
+                .build()
+                .unwrap();
+
+            // V=2, PSI=2, C=1, S=1, O=2, H=1, A=1, B=1
+            assert_eq!(pkt[0], 2 << 4 | 1 << 2 | 2);
+            assert_eq!(pkt[1], 1 << 7 | 2 << 5 | 1 << 4 | 1 << 1 | 1);
+
+            let alc_pkt = alc::parse_alc_pkt(&pkt).unwrap();
+            assert_eq!(alc_pkt.lct.cci, 0x1234);
+            assert_eq!(alc_pkt.lct.tsi, 0x42);
+            assert_eq!(alc_pkt.lct.toi, 0x1234_5678);
+            assert_eq!(alc_pkt.lct.cp, oti.fec_encoding_id as u8);
+            assert!(alc_pkt.lct.close_object);
+            assert!(alc_pkt.lct.close_session);
+            assert_eq!(alc_pkt.lct.header_ext_offset, 4 + 8 + 6 + 10);
+            assert_eq!(alc_pkt.cenc, Some(lct::Cenc::Deflate));
+            assert_eq!(alc_pkt.transfer_length, Some(100000));
+            assert_eq!(
+                alc_pkt.oti.as_ref().unwrap().fec_encoding_id,
+                oti.fec_encoding_id
+            );
+            assert_eq!(
+                alc_pkt.oti.as_ref().unwrap().encoding_symbol_length,
+                oti.encoding_symbol_length
+            );
+            let sct = alc_pkt.server_time.unwrap();
+            assert!(sct.max(now).duration_since(sct.min(now)).unwrap() < Duration::from_millis(1));
+            assert_eq!(
+                lct::get_ext(&pkt, &alc_pkt.lct, 64 + 1).unwrap().unwrap(),
+                &[64 + 1, 2, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]
+            );
+            assert_eq!(
+                lct::get_ext(&pkt, &alc_pkt.lct, 200).unwrap().unwrap(),
+                &[200, 1, 2, 3]
+            );
+
+            let payload_id = alc::parse_payload_id(&alc_pkt, &oti).unwrap();
+            assert_eq!(payload_id.sbn, 1);
+            assert_eq!(payload_id.esi, 17);
+            assert_eq!(&pkt[alc_pkt.data_payload_offset..], &[0x55; 1400]);
+        }
+    }
+
+    #[test]
+    pub fn test_packet_builder_fdt() {
+        crate::tests::init();
+
+        let pkt = PacketBuilder::new()
+            .fdt(2, 0xABCDE)
+            .fti(1000)
+            .build()
+            .unwrap();
+        let alc_pkt = alc::parse_alc_pkt(&pkt).unwrap();
+        assert_eq!(alc_pkt.lct.toi, lct::TOI_FDT);
+        let fdt_info = alc_pkt.fdt_info.unwrap();
+        assert_eq!(fdt_info.version, 2);
+        assert_eq!(fdt_info.fdt_instance_id, 0xABCDE);
+
+        // Same packet as the sender
+        let oti = oti::Oti::default();
+        let sender_pkt = crate::common::pkt::Pkt {
+            payload: Vec::new(),
+            transfer_length: 1000,
+            esi: 0,
+            sbn: 0,
+            toi: lct::TOI_FDT,
+            fdt_id: Some(0xABCDE),
+            cenc: lct::Cenc::Null,
+            inband_cenc: false,
+            close_object: false,
+            source_block_length: 64,
+            sender_current_time: false,
+        };
+        let expected = alc::new_alc_pkt(
+            &oti,
+            &0,
+            0,
+            &sender_pkt,
+            crate::common::Profile::RFC6726,
+            SystemTime::now(),
+        )
+        .unwrap();
+        assert_eq!(pkt, expected);
+    }
+
+    #[test]
+    pub fn test_packet_builder_invalid() {
+        crate::tests::init();
+
+        let object = || PacketBuilder::new().toi(1);
+        assert!(object().build().is_ok());
+
+        // FDT without EXT_FDT, EXT_FDT in an object
+        assert!(PacketBuilder::new().build().is_err());
+        assert!(object().fdt(2, 1).build().is_err());
+
+        assert!(object().version(3).build().is_err());
+        assert!(object().psi(4).build().is_err());
+        assert!(object().cci_width(6).build().is_err());
+        assert!(object().tsi_width(8).build().is_err());
+        assert!(object().toi(0x1_0000).toi_width(2).build().is_err());
+        // TSI on 32 bits and TOI on 16 bits, the half-word flag can not be set for the TOI only
+        assert!(object().tsi_width(4).toi_width(2).build().is_err());
+        assert!(object().codepoint(6).build().is_err());
+        assert!(object().raw_extension(vec![64, 2, 0, 0]).build().is_err());
+        assert!(object().raw_extension(vec![200, 0, 0]).build().is_err());
+        assert!(object().hdr_len(2).build().is_err());
+        assert!(object().payload_id(0, 64).build().is_err());
+
+        let mut oti = oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        oti.scheme_specific = None;
+        assert!(object().oti(oti).fti(1000).build().is_err());
+    }
+
+    #[test]
+    pub fn test_packet_builder_unchecked() {
+        crate::tests::init();
+
+        // HDR_LEN beyond the end of the packet
+        let pkt = PacketBuilder::new().toi(1).hdr_len(255).build_unchecked();
+        assert_eq!(pkt[2], 255);
+        assert!(alc::parse_alc_pkt(&pkt).is_err());
+
+        // HDR_LEN stops in the middle of the EXT_FTI
+        let pkt = PacketBuilder::new()
+            .toi(1)
+            .fti(1000)
+            .hdr_len(4)
+            .payload(vec![0; 32])
+            .build_unchecked();
+        let _ = alc::parse_alc_pkt(&pkt);
+
+        // Fields truncated to their width
+        let pkt = PacketBuilder::new()
+            .toi(0x1_0001)
+            .toi_width(2)
+            .fdt(2, 1)
+            .build_unchecked();
+        let alc_pkt = alc::parse_alc_pkt(&pkt).unwrap();
+        assert_eq!(alc_pkt.lct.toi, 1);
+    }
+}
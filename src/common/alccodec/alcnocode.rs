@@ -1,13 +1,18 @@
 use super::AlcCodec;
 use crate::{
-    common::{alc, lct, oti, pkt},
+    common::{alc, lct, oti},
     error::FluteError,
 };
 
 pub struct AlcNoCode {}
 
 impl AlcCodec for AlcNoCode {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) {
+    fn add_fti(
+        &self,
+        data: &mut Vec<u8>,
+        oti: &oti::Oti,
+        transfer_length: u64,
+    ) -> crate::error::Result<()> {
         // https://tools.ietf.org/html/rfc5445
         /*
         +-
@@ -32,7 +37,7 @@ impl AlcCodec for AlcNoCode {
         data.extend(esl.to_be_bytes());
         data.extend(sbl_msb.to_be_bytes());
         data.extend(sbl_lsb.to_be_bytes());
-        lct::inc_hdr_len(data, 4);
+        lct::inc_hdr_len(data, 4)
     }
 
     fn get_fti(
@@ -73,16 +78,18 @@ impl AlcCodec for AlcNoCode {
     fn get_fec_payload_id(
         &self,
         pkt: &alc::AlcPkt,
-        _oti: &oti::Oti,
+        oti: &oti::Oti,
     ) -> crate::error::Result<alc::PayloadID> {
-        self.get_fec_inline_payload_id(pkt)
+        let payload_id = self.get_fec_inline_payload_id(pkt)?;
+        self.validate_payload_id(oti, &payload_id)?;
+        Ok(payload_id)
     }
 
     fn get_fec_inline_payload_id(&self, pkt: &alc::AlcPkt) -> crate::error::Result<alc::PayloadID> {
         let data = &pkt.data[pkt.data_alc_header_offset..pkt.data_payload_offset];
         let arr: [u8; 4] = match data.try_into() {
             Ok(arr) => arr,
-            Err(e) => return Err(FluteError::new(e.to_string())),
+            Err(e) => return Err(FluteError::from(e)),
         };
         let payload_id_header = u32::from_be_bytes(arr);
         let sbn = payload_id_header >> 16;
@@ -94,9 +101,9 @@ impl AlcCodec for AlcNoCode {
         })
     }
 
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, pkt: &pkt::Pkt) {
-        let sbn = pkt.sbn;
-        let esi = pkt.esi;
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, payload_id: &alc::PayloadID) {
+        let sbn = payload_id.sbn;
+        let esi = payload_id.esi;
         /*
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
         |     Source Block Number  16 bits | Enc. Symb. ID  16 bits     |
@@ -3,7 +3,6 @@ use crate::{
     common::{
         alc, lct,
         oti::{self, SchemeSpecific},
-        pkt,
     },
     error::FluteError,
 };
@@ -11,7 +10,12 @@ use crate::{
 pub struct AlcRaptorQ {}
 
 impl AlcCodec for AlcRaptorQ {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) {
+    fn add_fti(
+        &self,
+        data: &mut Vec<u8>,
+        oti: &oti::Oti,
+        transfer_length: u64,
+    ) -> crate::error::Result<()> {
         /*
          +-
         | FTI <127 8bits|  LEN (8bit)   |
@@ -44,9 +48,10 @@ impl AlcCodec for AlcRaptorQ {
             data.extend(raptorq.sub_blocks_length.to_be_bytes());
             data.push(raptorq.symbol_alignment);
             data.extend(padding.to_be_bytes());
-            lct::inc_hdr_len(data, len);
+            lct::inc_hdr_len(data, len)
         } else {
             debug_assert!(false);
+            Ok(())
         }
     }
 
@@ -78,7 +83,8 @@ impl AlcCodec for AlcRaptorQ {
             al
         );
 
-        if z == 0 {
+        // An empty object is transferred without any source block
+        if z == 0 && transfer_length != 0 {
             return Err(FluteError::new("Z is null"));
         }
 
@@ -86,11 +92,15 @@ impl AlcCodec for AlcRaptorQ {
             return Err(FluteError::new("AL must be at least 1"));
         }
 
+        if symbol_size == 0 {
+            return Err(FluteError::new("Symbol size is null"));
+        }
+
         if symbol_size % al as u16 != 0 {
             return Err(FluteError::new("Symbol size is not properly aligned"));
         }
 
-        let block_size = num_integer::div_ceil(transfer_length, z as u64);
+        let block_size = num_integer::div_ceil(transfer_length, z.max(1) as u64);
         let maximum_source_block_length = num_integer::div_ceil(block_size, symbol_size as u64);
 
         let oti = oti::Oti {
@@ -110,7 +120,7 @@ impl AlcCodec for AlcRaptorQ {
         Ok(Some((oti, transfer_length)))
     }
 
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, pkt: &pkt::Pkt) {
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, payload_id: &alc::PayloadID) {
         /*
          0                   1                   2                   3
          0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
@@ -119,23 +129,25 @@ impl AlcCodec for AlcRaptorQ {
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
         */
 
-        let payload_id = (pkt.sbn & 0xFFu32) << 24 | pkt.esi & 0xFFFFFFu32;
-        data.extend(payload_id.to_be_bytes());
+        let header = (payload_id.sbn & 0xFFu32) << 24 | payload_id.esi & 0xFFFFFFu32;
+        data.extend(header.to_be_bytes());
     }
 
     fn get_fec_payload_id(
         &self,
         pkt: &alc::AlcPkt,
-        _oti: &oti::Oti,
+        oti: &oti::Oti,
     ) -> crate::error::Result<alc::PayloadID> {
-        self.get_fec_inline_payload_id(pkt)
+        let payload_id = self.get_fec_inline_payload_id(pkt)?;
+        self.validate_payload_id(oti, &payload_id)?;
+        Ok(payload_id)
     }
 
     fn get_fec_inline_payload_id(&self, pkt: &alc::AlcPkt) -> crate::error::Result<alc::PayloadID> {
         let data = &pkt.data[pkt.data_alc_header_offset..pkt.data_payload_offset];
         let arr: [u8; 4] = match data.try_into() {
             Ok(arr) => arr,
-            Err(e) => return Err(FluteError::new(e.to_string())),
+            Err(e) => return Err(FluteError::from(e)),
         };
         let payload_id_header = u32::from_be_bytes(arr);
         let sbn = payload_id_header >> 24;
@@ -150,4 +162,34 @@ impl AlcCodec for AlcRaptorQ {
     fn fec_payload_id_block_length(&self) -> usize {
         4
     }
+
+    fn validate_payload_id(
+        &self,
+        oti: &oti::Oti,
+        payload_id: &alc::PayloadID,
+    ) -> crate::error::Result<()> {
+        if oti.encoding_symbol_length == 0 {
+            return Err(FluteError::new("Encoding symbol length is null"));
+        }
+
+        // The number of repair symbols is not bounded, only the SBN is checked
+        // An empty object is transferred without any source block, in a single packet with SBN 0
+        let source_blocks_length = match oti.scheme_specific.as_ref() {
+            Some(SchemeSpecific::RaptorQ(scheme_specific)) => scheme_specific.source_blocks_length,
+            _ => {
+                return Err(FluteError::new(
+                    "RaptorQ scheme specific parameters are missing",
+                ))
+            }
+        };
+
+        if payload_id.sbn >= source_blocks_length.max(1) as u32 {
+            return Err(FluteError::new(format!(
+                "SBN {} is out of range, number of source blocks is {}",
+                payload_id.sbn, source_blocks_length
+            )));
+        }
+
+        Ok(())
+    }
 }
@@ -1,13 +1,18 @@
 use super::AlcCodec;
 use crate::{
-    common::{alc, lct, oti, pkt},
+    common::{alc, lct, oti},
     error::FluteError,
 };
 
 pub struct AlcRS28 {}
 
 impl AlcCodec for AlcRS28 {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) {
+    fn add_fti(
+        &self,
+        data: &mut Vec<u8>,
+        oti: &oti::Oti,
+        transfer_length: u64,
+    ) -> crate::error::Result<()> {
         /*0                   1                   2                   3
          0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
@@ -27,7 +32,7 @@ impl AlcCodec for AlcRS28 {
             | max_n;
         data.extend(ext_header_l.to_be_bytes());
         data.extend(e_b_n.to_be_bytes());
-        lct::inc_hdr_len(data, 3);
+        lct::inc_hdr_len(data, 3)
     }
 
     fn get_fti(
@@ -53,6 +58,11 @@ impl AlcCodec for AlcRS28 {
 
         let maximum_source_block_length = fti[10];
         let num_encoding_symbols = fti[11];
+        if num_encoding_symbols < maximum_source_block_length {
+            return Err(FluteError::new(
+                "Max number of encoding symbols is lower than the max source block length",
+            ));
+        }
 
         let oti = oti::Oti {
             fec_encoding_id: oti::FECEncodingID::ReedSolomonGF28,
@@ -68,15 +78,15 @@ impl AlcCodec for AlcRS28 {
         Ok(Some((oti, transfer_length)))
     }
 
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, pkt: &pkt::Pkt) {
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, payload_id: &alc::PayloadID) {
         /*
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
         |     Source Block Number (24)                 | Enc. Symb. ID  |
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
          */
 
-        let sbn = pkt.sbn & 0xFFFFFF;
-        let esi = pkt.esi & 0xFF;
+        let sbn = payload_id.sbn & 0xFFFFFF;
+        let esi = payload_id.esi & 0xFF;
 
         let header: u32 = (sbn << 8) | esi & 0xFF;
         data.extend(header.to_be_bytes());
@@ -85,16 +95,18 @@ impl AlcCodec for AlcRS28 {
     fn get_fec_payload_id(
         &self,
         pkt: &alc::AlcPkt,
-        _oti: &oti::Oti,
+        oti: &oti::Oti,
     ) -> crate::error::Result<alc::PayloadID> {
-        self.get_fec_inline_payload_id(pkt)
+        let payload_id = self.get_fec_inline_payload_id(pkt)?;
+        self.validate_payload_id(oti, &payload_id)?;
+        Ok(payload_id)
     }
 
     fn get_fec_inline_payload_id(&self, pkt: &alc::AlcPkt) -> crate::error::Result<alc::PayloadID> {
         let data = &pkt.data[pkt.data_alc_header_offset..pkt.data_payload_offset];
         let arr: [u8; 4] = match data.try_into() {
             Ok(arr) => arr,
-            Err(e) => return Err(FluteError::new(e.to_string())),
+            Err(e) => return Err(FluteError::from(e)),
         };
         let payload_id_header = u32::from_be_bytes(arr);
         let sbn = payload_id_header >> 8;
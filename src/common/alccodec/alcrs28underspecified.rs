@@ -1,13 +1,18 @@
 use super::AlcCodec;
 use crate::{
-    common::{alc, lct, oti, pkt},
+    common::{alc, lct, oti},
     error::FluteError,
 };
 
 pub struct AlcRS28UnderSpecified {}
 
 impl AlcCodec for AlcRS28UnderSpecified {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) {
+    fn add_fti(
+        &self,
+        data: &mut Vec<u8>,
+        oti: &oti::Oti,
+        transfer_length: u64,
+    ) -> crate::error::Result<()> {
         /*
         * 0                   1                   2                   3
           0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
@@ -33,7 +38,7 @@ impl AlcCodec for AlcRS28UnderSpecified {
         data.extend(esl.to_be_bytes());
         data.extend(sbl.to_be_bytes());
         data.extend(mne.to_be_bytes());
-        lct::inc_hdr_len(data, 4);
+        lct::inc_hdr_len(data, 4)
     }
 
     fn get_fti(
@@ -64,6 +69,11 @@ impl AlcCodec for AlcRS28UnderSpecified {
         let maximum_source_block_length =
             u16::from_be_bytes(fti[12..14].as_ref().try_into().unwrap());
         let num_encoding_symbols = u16::from_be_bytes(fti[14..16].as_ref().try_into().unwrap());
+        if num_encoding_symbols < maximum_source_block_length {
+            return Err(FluteError::new(
+                "Max number of encoding symbols is lower than the max source block length",
+            ));
+        }
 
         let oti = oti::Oti {
             fec_encoding_id: oti::FECEncodingID::ReedSolomonGF28UnderSpecified,
@@ -79,7 +89,7 @@ impl AlcCodec for AlcRS28UnderSpecified {
         Ok(Some((oti, transfer_length)))
     }
 
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, pkt: &pkt::Pkt) {
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, _oti: &oti::Oti, payload_id: &alc::PayloadID) {
         /*0                   1                   2                   3
              0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
             +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
@@ -88,9 +98,9 @@ impl AlcCodec for AlcRS28UnderSpecified {
             |      Source Block Length      |       Encoding Symbol ID      |
             +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
         */
-        let sbn = pkt.sbn;
-        let source_block_length = pkt.source_block_length as u16;
-        let esi = pkt.esi as u16;
+        let sbn = payload_id.sbn;
+        let source_block_length = payload_id.source_block_length.unwrap_or_default() as u16;
+        let esi = payload_id.esi as u16;
 
         data.extend(sbn.to_be_bytes());
         data.extend(source_block_length.to_be_bytes());
@@ -100,16 +110,18 @@ impl AlcCodec for AlcRS28UnderSpecified {
     fn get_fec_payload_id(
         &self,
         pkt: &alc::AlcPkt,
-        _oti: &oti::Oti,
+        oti: &oti::Oti,
     ) -> crate::error::Result<alc::PayloadID> {
-        self.get_fec_inline_payload_id(pkt)
+        let payload_id = self.get_fec_inline_payload_id(pkt)?;
+        self.validate_payload_id(oti, &payload_id)?;
+        Ok(payload_id)
     }
 
     fn get_fec_inline_payload_id(&self, pkt: &alc::AlcPkt) -> crate::error::Result<alc::PayloadID> {
         let data = &pkt.data[pkt.data_alc_header_offset..pkt.data_payload_offset];
         let arr: [u8; 8] = match data.try_into() {
             Ok(arr) => arr,
-            Err(e) => return Err(FluteError::new(e.to_string())),
+            Err(e) => return Err(FluteError::from(e)),
         };
 
         /*0                   1                   2                   3
@@ -3,7 +3,6 @@ use crate::{
     common::{
         alc, lct,
         oti::{self, ReedSolomonGF2MSchemeSpecific, SchemeSpecific},
-        pkt,
     },
     error::FluteError,
 };
@@ -11,7 +10,12 @@ use crate::{
 pub struct AlcRS2m {}
 
 impl AlcCodec for AlcRS2m {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) {
+    fn add_fti(
+        &self,
+        data: &mut Vec<u8>,
+        oti: &oti::Oti,
+        transfer_length: u64,
+    ) -> crate::error::Result<()> {
         /*  0                   1                   2                   3
          0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
@@ -38,9 +42,10 @@ impl AlcCodec for AlcRS2m {
             data.extend(oti.encoding_symbol_length.to_be_bytes());
             data.extend(b.to_be_bytes());
             data.extend(max_n.to_be_bytes());
-            lct::inc_hdr_len(data, 4);
+            lct::inc_hdr_len(data, 4)
         } else {
             debug_assert!(false);
+            Ok(())
         }
     }
 
@@ -68,6 +73,11 @@ impl AlcCodec for AlcRS2m {
         let encoding_symbol_length = u16::from_be_bytes(fti[10..12].as_ref().try_into().unwrap());
         let b = u16::from_be_bytes(fti[12..14].as_ref().try_into().unwrap());
         let max_n = u16::from_be_bytes(fti[14..16].as_ref().try_into().unwrap());
+        if max_n < b {
+            return Err(FluteError::new(
+                "Max number of encoding symbols is lower than the max source block length",
+            ));
+        }
 
         let oti = oti::Oti {
             fec_encoding_id: oti::FECEncodingID::ReedSolomonGF2M,
@@ -91,7 +101,7 @@ impl AlcCodec for AlcRS2m {
         Ok(Some((oti, transfer_length)))
     }
 
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, oti: &oti::Oti, pkt: &pkt::Pkt) {
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, oti: &oti::Oti, payload_id: &alc::PayloadID) {
         let m = oti
             .scheme_specific
             .as_ref()
@@ -101,8 +111,8 @@ impl AlcCodec for AlcRS2m {
             })
             .unwrap_or(8);
 
-        let sbn = pkt.sbn;
-        let esi = pkt.esi;
+        let sbn = payload_id.sbn;
+        let esi = payload_id.esi;
 
         /*
         +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
@@ -126,7 +136,7 @@ impl AlcCodec for AlcRS2m {
         let data = &pkt.data[pkt.data_alc_header_offset..pkt.data_payload_offset];
         let arr: [u8; 4] = match data.try_into() {
             Ok(arr) => arr,
-            Err(e) => return Err(FluteError::new(e.to_string())),
+            Err(e) => return Err(FluteError::from(e)),
         };
         let payload_id_header = u32::from_be_bytes(arr);
 
@@ -139,15 +149,24 @@ impl AlcCodec for AlcRS2m {
             })
             .unwrap_or(8);
 
+        if m == 0 || m > 16 {
+            return Err(FluteError::new(format!(
+                "Reed Solomon m={} is not valid",
+                m
+            )));
+        }
+
         let sbn = payload_id_header >> m;
         let esi_mask = (1u32 << m) - 1u32;
         let esi = payload_id_header & esi_mask;
 
-        Ok(alc::PayloadID {
+        let payload_id = alc::PayloadID {
             esi,
             sbn,
             source_block_length: None,
-        })
+        };
+        self.validate_payload_id(oti, &payload_id)?;
+        Ok(payload_id)
     }
 
     fn get_fec_inline_payload_id(
@@ -160,4 +179,26 @@ impl AlcCodec for AlcRS2m {
     fn fec_payload_id_block_length(&self) -> usize {
         4
     }
+
+    fn validate_payload_id(
+        &self,
+        oti: &oti::Oti,
+        payload_id: &alc::PayloadID,
+    ) -> crate::error::Result<()> {
+        if oti.encoding_symbol_length == 0 {
+            return Err(FluteError::new("Encoding symbol length is null"));
+        }
+
+        // The SBN is bounded by its size (32 - m bits) in the FEC Payload ID
+        let max_nb_encoding_symbols =
+            oti.maximum_source_block_length as u64 + oti.max_number_of_parity_symbols as u64;
+        if payload_id.esi as u64 >= max_nb_encoding_symbols {
+            return Err(FluteError::new(format!(
+                "ESI {} is out of range, max number of encoding symbols is {}",
+                payload_id.esi, max_nb_encoding_symbols
+            )));
+        }
+
+        Ok(())
+    }
 }
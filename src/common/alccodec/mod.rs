@@ -2,7 +2,7 @@ use super::{
     alc::{AlcPkt, PayloadID},
     lct, oti, pkt,
 };
-use crate::tools::error::Result;
+use crate::tools::error::{FluteError, Result};
 
 mod alcnocode;
 mod alcraptor;
@@ -12,12 +12,43 @@ mod alcrs28underspecified;
 mod alcrs2m;
 
 pub trait AlcCodec {
-    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64);
+    fn add_fti(&self, data: &mut Vec<u8>, oti: &oti::Oti, transfer_length: u64) -> Result<()>;
     fn get_fti(&self, data: &[u8], lct_header: &lct::LCTHeader) -> Result<Option<(oti::Oti, u64)>>;
-    fn add_fec_payload_id(&self, data: &mut Vec<u8>, oti: &oti::Oti, pkt: &pkt::Pkt);
+    fn add_fec_payload_id(&self, data: &mut Vec<u8>, oti: &oti::Oti, payload_id: &PayloadID);
     fn get_fec_payload_id(&self, pkt: &AlcPkt, oti: &oti::Oti) -> Result<PayloadID>;
     fn get_fec_inline_payload_id(&self, pkt: &AlcPkt) -> Result<PayloadID>;
     fn fec_payload_id_block_length(&self) -> usize;
+
+    /// Check the parameters of a packet before it is encoded
+    fn validate_packet_parameters(&self, oti: &oti::Oti, pkt: &pkt::Pkt) -> Result<()> {
+        self.validate_payload_id(oti, &pkt.payload_id())
+    }
+
+    /// Check the FEC Payload ID of a packet against the FEC parameters of the object
+    fn validate_payload_id(&self, oti: &oti::Oti, payload_id: &PayloadID) -> Result<()> {
+        if oti.encoding_symbol_length == 0 {
+            return Err(FluteError::new("Encoding symbol length is null"));
+        }
+
+        let max_nb_encoding_symbols =
+            oti.maximum_source_block_length as u64 + oti.max_number_of_parity_symbols as u64;
+        if payload_id.esi as u64 >= max_nb_encoding_symbols {
+            return Err(FluteError::new(format!(
+                "ESI {} is out of range, max number of encoding symbols is {}",
+                payload_id.esi, max_nb_encoding_symbols
+            )));
+        }
+
+        if payload_id.sbn as u64 >= oti.max_source_blocks_number() as u64 {
+            return Err(FluteError::new(format!(
+                "SBN {} is out of range, max number of source blocks is {}",
+                payload_id.sbn,
+                oti.max_source_blocks_number()
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl dyn AlcCodec {
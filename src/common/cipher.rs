@@ -0,0 +1,154 @@
+use crate::tools::error::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+///
+/// Encryption of the content of an object, applied by the sender after the compression (`Cenc`)
+///
+/// The key URI is advertised as `Decryption-KEY-URI` in the FDT,
+/// the receiver decrypts the object with the `ObjectDecipher` registered for this URI.
+///
+pub trait ObjectCipher: std::fmt::Debug + Send + Sync {
+    /// Encrypt the content of an object
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>>;
+    /// URI of the key required to decrypt the object
+    fn key_uri(&self) -> String;
+}
+
+///
+/// Decryption of the content of an object, applied by the receiver before the decompression (`Cenc`)
+///
+pub trait ObjectDecipher: std::fmt::Debug + Send + Sync {
+    /// Decrypt the content of an object
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+///
+/// Deciphers of the receiver, indexed by the `Decryption-KEY-URI` of the objects
+///
+#[derive(Debug, Clone, Default)]
+pub struct DecipherRegistry {
+    deciphers: HashMap<String, Arc<dyn ObjectDecipher>>,
+}
+
+impl DecipherRegistry {
+    /// Return an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the decipher of the objects encrypted with the key `key_uri`
+    pub fn insert(&mut self, key_uri: &str, decipher: Arc<dyn ObjectDecipher>) {
+        self.deciphers.insert(key_uri.to_owned(), decipher);
+    }
+
+    /// Remove the decipher of the key `key_uri`
+    pub fn remove(&mut self, key_uri: &str) -> Option<Arc<dyn ObjectDecipher>> {
+        self.deciphers.remove(key_uri)
+    }
+
+    /// Return the decipher of the key `key_uri`
+    pub fn get(&self, key_uri: &str) -> Option<Arc<dyn ObjectDecipher>> {
+        self.deciphers.get(key_uri).cloned()
+    }
+
+    /// Return `true` if no decipher is registered
+    pub fn is_empty(&self) -> bool {
+        self.deciphers.is_empty()
+    }
+}
+
+///
+/// AES-128-GCM reference implementation of `ObjectCipher` and `ObjectDecipher`
+///
+/// A random nonce of 12 bytes is generated for each object and prepended to the encrypted content,
+/// the authentication tag of 16 bytes is appended.
+///
+#[cfg(feature = "crypto")]
+pub struct Aes128GcmCipher {
+    cipher: aes_gcm::Aes128Gcm,
+    key_uri: String,
+}
+
+#[cfg(feature = "crypto")]
+impl Aes128GcmCipher {
+    const NONCE_LENGTH: usize = 12;
+
+    /// Return a cipher using `key`, identified by `key_uri` in the FDT
+    pub fn new(key: &[u8; 16], key_uri: &str) -> Self {
+        use aes_gcm::KeyInit;
+        Self {
+            cipher: aes_gcm::Aes128Gcm::new(key.into()),
+            key_uri: key_uri.to_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl std::fmt::Debug for Aes128GcmCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Aes128GcmCipher")
+            .field("key_uri", &self.key_uri)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl ObjectCipher for Aes128GcmCipher {
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use aes_gcm::aead::Aead;
+        use rand::RngCore;
+
+        let mut nonce = [0u8; Self::NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let encrypted = self
+            .cipher
+            .encrypt(&nonce.into(), data)
+            .map_err(|_| crate::tools::error::FluteError::new("Fail to encrypt the object"))?;
+
+        let mut output = Vec::with_capacity(nonce.len() + encrypted.len());
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&encrypted);
+        Ok(output)
+    }
+
+    fn key_uri(&self) -> String {
+        self.key_uri.clone()
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl ObjectDecipher for Aes128GcmCipher {
+    fn transform(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use aes_gcm::aead::Aead;
+
+        if data.len() < Self::NONCE_LENGTH {
+            return Err(crate::tools::error::FluteError::new(
+                "Encrypted object is shorter than the nonce",
+            ));
+        }
+
+        let (nonce, encrypted) = data.split_at(Self::NONCE_LENGTH);
+        self.cipher
+            .decrypt(aes_gcm::Nonce::from_slice(nonce), encrypted)
+            .map_err(|_| crate::tools::error::FluteError::new("Fail to decrypt the object"))
+    }
+}
+
+#[cfg(all(test, feature = "crypto"))]
+mod tests {
+    use super::{Aes128GcmCipher, ObjectCipher, ObjectDecipher};
+
+    #[test]
+    pub fn test_aes128gcm() {
+        crate::tests::init();
+        let cipher = Aes128GcmCipher::new(&[7u8; 16], "https://example.com/key");
+        let encrypted = ObjectCipher::transform(&cipher, b"hello world").unwrap();
+        assert_eq!(encrypted.len(), 12 + 11 + 16);
+        let decrypted = ObjectDecipher::transform(&cipher, &encrypted).unwrap();
+        assert_eq!(decrypted, b"hello world");
+
+        let other = Aes128GcmCipher::new(&[8u8; 16], "https://example.com/key");
+        assert!(ObjectDecipher::transform(&other, &encrypted).is_err());
+    }
+}
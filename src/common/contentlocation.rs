@@ -0,0 +1,141 @@
+use crate::tools::error::{FluteError, Result};
+
+///
+/// Normalize a Content-Location following the syntax-based normalization of
+/// <https://www.rfc-editor.org/rfc/rfc3986#section-6.2.2>
+///
+/// - Scheme and host are lowercased, internationalized host names are converted to punycode
+/// - Dot-segments (`.` and `..`) are removed from the path
+/// - Percent-encoded unreserved characters are decoded (ex: `%7E` -> `~`),
+///   other percent-encoded octets are kept with uppercase hexadecimal digits (ex: `%2f` -> `%2F`)
+/// - The case of the path is preserved
+///
+/// Relative locations are resolved against `file:///`, like the receiver does.
+///
+/// # Errors
+/// Returns an error if the Content-Location can not be parsed to an URL
+///
+/// # Example
+///
+/// ```
+/// use flute::core::content_location;
+/// let url = content_location::normalize("HTTP://Example.COM/a/./b/../%7edata%2fFile").unwrap();
+/// assert_eq!(url, "http://example.com/a/~data%2FFile");
+/// ```
+pub fn normalize(content_location: &str) -> Result<String> {
+    let url = parse(content_location)?;
+    let url = url::Url::parse(&normalize_percent_encoding(url.as_str())).map_err(|e| {
+        FluteError::new_kind(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Fail to normalize content-location {}: {}",
+                content_location, e
+            ),
+        )
+    })?;
+    Ok(url.to_string())
+}
+
+/// Parse a Content-Location to an URL, relative locations are resolved against `file:///`
+/// The URL is normalized with `normalize()` when `normalize` is `true`
+pub(crate) fn to_url(content_location: &str, normalize: bool) -> Result<url::Url> {
+    if !normalize {
+        return parse(content_location);
+    }
+
+    let normalized = self::normalize(content_location)?;
+    parse(&normalized)
+}
+
+fn parse(content_location: &str) -> Result<url::Url> {
+    url::Url::parse(content_location)
+        .or_else(|_| url::Url::parse("file:///").unwrap().join(content_location))
+        .map_err(|e| {
+            FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Fail to parse content-location {} to URL: {}",
+                    content_location, e
+                ),
+            )
+        })
+}
+
+fn is_unreserved(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.' | b'_' | b'~')
+}
+
+/// `input` is the serialization of an URL, so contains ASCII characters only
+fn normalize_percent_encoding(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let is_escape = bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+
+        if !is_escape {
+            output.push(bytes[i] as char);
+            i += 1;
+            continue;
+        }
+
+        let value = u8::from_str_radix(&input[i + 1..i + 3], 16).unwrap();
+        match is_unreserved(value) {
+            true => output.push(value as char),
+            false => output.push_str(&format!("%{:02X}", value)),
+        }
+        i += 3;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    pub fn test_normalize() {
+        crate::tests::init();
+        let cases = [
+            (
+                "HTTP://Example.COM/Data/File.bin",
+                "http://example.com/Data/File.bin",
+            ),
+            ("http://example.com", "http://example.com/"),
+            ("http://example.com/a/./b/../c", "http://example.com/a/c"),
+            (
+                "http://example.com/%7euser/%41",
+                "http://example.com/~user/A",
+            ),
+            ("http://example.com/a%2fb", "http://example.com/a%2Fb"),
+            (
+                "http://example.com/caf%c3%a9",
+                "http://example.com/caf%C3%A9",
+            ),
+            (
+                "http://Bücher.example/file",
+                "http://xn--bcher-kva.example/file",
+            ),
+            ("file:///Data/File Name.bin", "file:///Data/File%20Name.bin"),
+            (
+                "file:///Data/File%20Name.bin",
+                "file:///Data/File%20Name.bin",
+            ),
+            ("file:///data/", "file:///data/"),
+            ("Data/./File.bin", "file:///Data/File.bin"),
+            ("http://example.com/100%", "http://example.com/100%"),
+        ];
+
+        for (input, expected) in cases {
+            let normalized = super::normalize(input).unwrap();
+            log::info!("{} -> {}", input, normalized);
+            assert_eq!(normalized, expected);
+            // Normalization is idempotent
+            assert_eq!(super::normalize(&normalized).unwrap(), normalized);
+        }
+
+        assert!(super::normalize("http://[invalid/").is_err());
+    }
+}
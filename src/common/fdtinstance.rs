@@ -15,10 +15,7 @@ use opentelemetry::{
     KeyValue,
 };
 
-use super::oti::{
-    self, RaptorQSchemeSpecific, RaptorSchemeSpecific, ReedSolomonGF2MSchemeSpecific,
-    SchemeSpecific,
-};
+use super::oti::{self, OtiAttributes};
 
 fn xmlns_mbms_2005<S>(os: &Option<String>, serializer: S) -> std::result::Result<S::Ok, S::Error>
 where
@@ -250,6 +247,21 @@ pub struct MBMSSessionIdentityExpiry {
     pub value: u32,
 }
 
+impl MBMSSessionIdentityExpiry {
+    /// Create the expiry of an MBMS Session Identity, `expiry` is in seconds since NTP epoch
+    pub fn new(identity: u8, expiry: u32) -> Self {
+        Self {
+            content: identity,
+            value: expiry,
+        }
+    }
+
+    /// MBMS Session Identity
+    pub fn identity(&self) -> u8 {
+        self.content
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum CacheControlChoice {
@@ -271,6 +283,21 @@ pub struct CacheControl {
     pub value: CacheControlChoice,
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AlternateContentLocation {
+    #[serde(
+        rename = "mbms2012:Alternate-Content-Location",
+        skip_serializing_if = "Vec::is_empty",
+        default
+    )]
+    #[serde(alias = "Alternate-Content-Location")]
+    pub alternate_content_location: Vec<String>,
+
+    #[serde(rename = "@Availability-Time", skip_serializing_if = "Option::is_none")]
+    pub availability_time: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct File {
@@ -294,14 +321,14 @@ pub struct File {
         skip_serializing_if = "Option::is_none"
     )]
     #[serde(alias = "Alternate-Content-Location-1")]
-    pub alternate_content_location_1: Option<Vec<String>>,
+    pub alternate_content_location_1: Option<Vec<AlternateContentLocation>>,
 
     #[serde(
         rename = "mbms2012:Alternate-Content-Location-2",
         skip_serializing_if = "Option::is_none"
     )]
     #[serde(alias = "Alternate-Content-Location-2")]
-    pub alternate_content_location_2: Option<Vec<String>>,
+    pub alternate_content_location_2: Option<Vec<AlternateContentLocation>>,
 
     #[serde(
         rename = "sv:delimiter",
@@ -403,40 +430,10 @@ pub struct File {
         skip_serializing_if = "Option::is_none"
     )]
     pub optel_propagator: Option<String>,
-}
-
-fn reed_solomon_scheme_specific(
-    fec_oti_scheme_specific_info: &Option<String>,
-) -> Result<Option<SchemeSpecific>> {
-    if fec_oti_scheme_specific_info.is_none() {
-        return Ok(None);
-    }
 
-    let scheme =
-        ReedSolomonGF2MSchemeSpecific::decode(fec_oti_scheme_specific_info.as_ref().unwrap())?;
-    Ok(Some(SchemeSpecific::ReedSolomon(scheme)))
-}
-
-fn raptorq_scheme_specific(
-    fec_oti_scheme_specific_info: &Option<String>,
-) -> Result<Option<SchemeSpecific>> {
-    if fec_oti_scheme_specific_info.is_none() {
-        return Ok(None);
-    }
-
-    let scheme = RaptorQSchemeSpecific::decode(fec_oti_scheme_specific_info.as_ref().unwrap())?;
-    Ok(Some(SchemeSpecific::RaptorQ(scheme)))
-}
-
-fn raptor_scheme_specific(
-    fec_oti_scheme_specific_info: &Option<String>,
-) -> Result<Option<SchemeSpecific>> {
-    if fec_oti_scheme_specific_info.is_none() {
-        return Ok(None);
-    }
-
-    let scheme = RaptorSchemeSpecific::decode(fec_oti_scheme_specific_info.as_ref().unwrap())?;
-    Ok(Some(SchemeSpecific::Raptor(scheme)))
+    // Content-coding kept as-is by the receiver, unlike Content-Encoding
+    #[serde(rename = "@X-Content-Coding", skip_serializing_if = "Option::is_none")]
+    pub content_coding: Option<String>,
 }
 
 impl FdtInstance {
@@ -453,8 +450,7 @@ impl FdtInstance {
         #[cfg(feature = "opentelemetry")]
         let _span = Self::op_start(buffer);
 
-        let instance: Result<FdtInstance> =
-            from_reader(buffer).map_err(|err| FluteError::new(err.to_string()));
+        let instance: Result<FdtInstance> = from_reader(buffer).map_err(FluteError::from);
         instance
     }
 
@@ -478,58 +474,142 @@ impl FdtInstance {
             .and_then(|file| file.iter().find(|file| file.toi == toi))
     }
 
-    pub fn get_oti_for_file(&self, file: &File) -> Option<oti::Oti> {
-        let oti = file.get_oti();
-        if oti.is_some() {
-            return oti;
-        }
+    /// Remove the files whose `Cache-Control` `Expires` is earlier than `now`
+    ///
+    /// Return the number of files removed
+    pub fn remove_expired_files(&mut self, now: SystemTime) -> usize {
+        let files = match self.file.as_mut() {
+            Some(files) => files,
+            None => return 0,
+        };
 
-        self.get_oti()
+        let nb_files = files.len();
+        files.retain(|file| {
+            file.get_expiration_time()
+                .map(|expires| expires >= now)
+                .unwrap_or(true)
+        });
+        nb_files - files.len()
     }
 
-    pub fn get_oti(&self) -> Option<oti::Oti> {
-        if self.fec_oti_fec_encoding_id.is_none()
-            || self.fec_oti_maximum_source_block_length.is_none()
-            || self.fec_oti_encoding_symbol_length.is_none()
-        {
-            return None;
+    /// Return the Base-URL-1 and Base-URL-2 of the FDT
+    pub fn get_base_urls(&self) -> Option<Vec<String>> {
+        let base_urls: Vec<String> = self
+            .base_url_1
+            .iter()
+            .chain(self.base_url_2.iter())
+            .flatten()
+            .cloned()
+            .collect();
+
+        match base_urls.is_empty() {
+            true => None,
+            false => Some(base_urls),
         }
+    }
+
+    /// Return the MBMS Session Identities of the FDT and their expiry in seconds since NTP epoch,
+    /// as defined in 3GPP TS 26.346 Section 7.2.10
+    pub fn get_mbms_session_identity_expiry(&self) -> Option<Vec<(u8, u32)>> {
+        self.mbms_session_identity_expiry.as_ref().map(|entries| {
+            entries
+                .iter()
+                .map(|entry| (entry.identity(), entry.value))
+                .collect()
+        })
+    }
 
-        let fec_encoding_id: oti::FECEncodingID =
-            self.fec_oti_fec_encoding_id.unwrap().try_into().ok()?;
+    /// Return the Content-Type of the file, inherited from the FDT-Instance if the file does not define it
+    pub fn get_effective_content_type(&self, file: &File) -> Option<String> {
+        file.content_type
+            .clone()
+            .or_else(|| self.content_type.clone())
+    }
 
-        let scheme_specific = match fec_encoding_id {
-            oti::FECEncodingID::ReedSolomonGF2M => {
-                reed_solomon_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
-            }
-            oti::FECEncodingID::RaptorQ => {
-                raptorq_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
-            }
-            oti::FECEncodingID::Raptor => {
-                raptor_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
+    /// Return the Content-Encoding of the file, inherited from the FDT-Instance if the file does not define it
+    pub fn get_effective_content_encoding(&self, file: &File) -> Option<String> {
+        file.content_encoding
+            .clone()
+            .or_else(|| self.content_encoding.clone())
+    }
+
+    pub fn get_oti_for_file(&self, file: &File) -> Option<oti::Oti> {
+        if file.fec_oti_fec_encoding_id.is_some() {
+            if let Ok(oti) = file.get_oti() {
+                return Some(oti);
             }
-            _ => None,
-        };
+        } else {
+            log::debug!("Cannot find OTI {:?}", file);
+        }
 
-        let fec_oti_max_number_of_encoding_symbols = self
-            .fec_oti_max_number_of_encoding_symbols
-            .unwrap_or(self.fec_oti_maximum_source_block_length.unwrap());
-
-        Some(oti::Oti {
-            fec_encoding_id,
-            fec_instance_id: self.fec_oti_fec_instance_id.unwrap_or(0) as u16,
-            maximum_source_block_length: self.fec_oti_maximum_source_block_length.unwrap() as u32,
-            encoding_symbol_length: self.fec_oti_encoding_symbol_length.unwrap() as u16,
-            max_number_of_parity_symbols: (fec_oti_max_number_of_encoding_symbols
-                - self.fec_oti_maximum_source_block_length.unwrap())
-                as u32,
-            scheme_specific,
-            inband_fti: false,
-        })
+        self.fec_oti_fec_encoding_id
+            .and_then(|_| self.get_oti().ok())
+    }
+
+    pub fn get_oti(&self) -> Result<oti::Oti> {
+        oti::Oti::new_from_fdt_attributes(&self.get_oti_attributes())
+    }
+
+    fn get_oti_attributes(&self) -> OtiAttributes {
+        OtiAttributes {
+            fec_oti_fec_encoding_id: self.fec_oti_fec_encoding_id,
+            fec_oti_fec_instance_id: self.fec_oti_fec_instance_id,
+            fec_oti_maximum_source_block_length: self.fec_oti_maximum_source_block_length,
+            fec_oti_encoding_symbol_length: self.fec_oti_encoding_symbol_length,
+            fec_oti_max_number_of_encoding_symbols: self.fec_oti_max_number_of_encoding_symbols,
+            fec_oti_scheme_specific_info: self.fec_oti_scheme_specific_info.clone(),
+        }
     }
 }
 
 impl File {
+    /// Return the byte offsets listed in the IndependentUnitPositions attribute of the file
+    pub fn get_independent_unit_positions(&self) -> Option<Vec<u64>> {
+        let positions = self.independent_unit_positions.as_ref()?;
+        let positions: Vec<u64> = positions
+            .split_whitespace()
+            .filter_map(|position| match position.parse() {
+                Ok(position) => Some(position),
+                Err(_) => {
+                    log::warn!("Invalid independent unit position {}", position);
+                    None
+                }
+            })
+            .collect();
+
+        match positions.is_empty() {
+            true => None,
+            false => Some(positions),
+        }
+    }
+
+    /// Return the Alternate-Content-Location-1 and Alternate-Content-Location-2 of the file
+    pub fn get_alternate_content_locations(&self) -> Option<Vec<String>> {
+        let locations: Vec<String> = self
+            .alternate_content_location_1
+            .iter()
+            .chain(self.alternate_content_location_2.iter())
+            .flatten()
+            .flat_map(|alternate| alternate.alternate_content_location.iter())
+            .cloned()
+            .collect();
+
+        match locations.is_empty() {
+            true => None,
+            false => Some(locations),
+        }
+    }
+
+    /// Return the expiration time of the file, when its `Cache-Control` is `Expires`
+    pub fn get_expiration_time(&self) -> Option<SystemTime> {
+        match self.cache_control.as_ref()?.value {
+            CacheControlChoice::Expires(time) => {
+                tools::ntp_to_system_time((time as u64) << 32).ok()
+            }
+            _ => None,
+        }
+    }
+
     pub fn get_cache_duration(
         &self,
         fdt_expiration_time: Option<SystemTime>,
@@ -571,45 +651,19 @@ impl File {
         0
     }
 
-    pub fn get_oti(&self) -> Option<oti::Oti> {
-        if self.fec_oti_fec_encoding_id.is_none()
-            || self.fec_oti_maximum_source_block_length.is_none()
-            || self.fec_oti_encoding_symbol_length.is_none()
-        {
-            log::debug!("Cannot find OTI {:?}", self);
-            return None;
-        }
-        let fec_encoding_id: oti::FECEncodingID =
-            self.fec_oti_fec_encoding_id.unwrap().try_into().ok()?;
-
-        let scheme_specific = match fec_encoding_id {
-            oti::FECEncodingID::ReedSolomonGF2M => {
-                reed_solomon_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
-            }
-            oti::FECEncodingID::RaptorQ => {
-                raptorq_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
-            }
-            oti::FECEncodingID::Raptor => {
-                raptor_scheme_specific(&self.fec_oti_scheme_specific_info).unwrap_or(None)
-            }
-            _ => None,
-        };
+    pub fn get_oti(&self) -> Result<oti::Oti> {
+        oti::Oti::new_from_fdt_attributes(&self.get_oti_attributes())
+    }
 
-        let fec_oti_max_number_of_encoding_symbols = self
-            .fec_oti_max_number_of_encoding_symbols
-            .unwrap_or(self.fec_oti_maximum_source_block_length.unwrap());
-
-        Some(oti::Oti {
-            fec_encoding_id,
-            fec_instance_id: self.fec_oti_fec_instance_id.unwrap_or(0) as u16,
-            maximum_source_block_length: self.fec_oti_maximum_source_block_length.unwrap() as u32,
-            encoding_symbol_length: self.fec_oti_encoding_symbol_length.unwrap() as u16,
-            max_number_of_parity_symbols: (fec_oti_max_number_of_encoding_symbols
-                - self.fec_oti_maximum_source_block_length.unwrap())
-                as u32,
-            scheme_specific,
-            inband_fti: false,
-        })
+    fn get_oti_attributes(&self) -> OtiAttributes {
+        OtiAttributes {
+            fec_oti_fec_encoding_id: self.fec_oti_fec_encoding_id,
+            fec_oti_fec_instance_id: self.fec_oti_fec_instance_id,
+            fec_oti_maximum_source_block_length: self.fec_oti_maximum_source_block_length,
+            fec_oti_encoding_symbol_length: self.fec_oti_encoding_symbol_length,
+            fec_oti_max_number_of_encoding_symbols: self.fec_oti_max_number_of_encoding_symbols,
+            fec_oti_scheme_specific_info: self.fec_oti_scheme_specific_info.clone(),
+        }
     }
 
     #[cfg(feature = "opentelemetry")]
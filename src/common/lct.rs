@@ -91,7 +91,7 @@ impl Cenc {
     }
 }
 
-fn nb_bytes_128(cci: &u128, min: u32) -> u32 {
+pub(crate) fn nb_bytes_128(cci: &u128, min: u32) -> u32 {
     if (cci & 0xFFFF0000000000000000000000000000) != 0x0 {
         return 16;
     }
@@ -127,7 +127,7 @@ fn nb_bytes_128(cci: &u128, min: u32) -> u32 {
     min
 }
 
-fn nb_bytes_64(n: u64, min: u32) -> u32 {
+pub(crate) fn nb_bytes_64(n: u64, min: u32) -> u32 {
     if (n & 0xFFFF000000000000) != 0x0 {
         return 8;
     }
@@ -249,59 +249,111 @@ pub fn push_lct_header(
     close_object: bool,
     close_session: bool,
 ) {
-    let cci_size = nb_bytes_128(cci, 0);
-    let tsi_size = nb_bytes_64(tsi, 2);
-    let toi_size = nb_bytes_128(toi, 2);
-
-    let h_tsi = (tsi_size & 2) >> 1; // Is TSI half-word ?
-    let h_toi = (toi_size & 2) >> 1; // Is TOI half-word ?
-
-    let h = h_tsi | h_toi; // Half-word flag
-    let b: u8 = match close_object {
-        true => 1,
-        false => 0,
-    };
-    let a: u8 = match close_session {
-        true => 1,
-        false => 0,
-    };
-    let o = (toi_size >> 2) & 0x3;
-    let s = (tsi_size >> 2) & 1;
-    let c = match cci_size {
-        size if size <= 4 => 0,
-        size if size <= 8 => 1,
-        size if size <= 12 => 2,
-        _ => 3,
-    };
-    let hdr_len: u8 = (2 + o + s + h + c) as u8;
-    let v = 1;
-    let lct_header: u32 = (codepoint as u32)
-        | ((hdr_len as u32) << 8)
-        | (b as u32) << 16
-        | (a as u32) << 17
-        | (h) << 20
-        | (o) << 21
-        | (s) << 23
-        | (psi as u32) << 24
-        | (c) << 26
-        | (v as u32) << 28;
+    let mut flags = LctHeaderFlags::from_sizes(
+        nb_bytes_128(cci, 0),
+        nb_bytes_64(tsi, 2),
+        nb_bytes_128(toi, 2),
+    );
+    flags.psi = psi;
+    flags.codepoint = codepoint;
+    flags.close_object = close_object;
+    flags.close_session = close_session;
+    push_lct_header_flags(data, &flags, cci, tsi, toi);
+}
+
+/// First 32-bit word of an LCT Header
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LctHeaderFlags {
+    pub version: u8,
+    pub c: u8,
+    pub psi: u8,
+    pub s: u8,
+    pub o: u8,
+    pub h: u8,
+    pub close_session: bool,
+    pub close_object: bool,
+    pub hdr_len: u8,
+    pub codepoint: u8,
+}
+
+impl LctHeaderFlags {
+    /// Flags of a header without extension, with a CCI, a TSI and a TOI of respectively
+    /// `cci_size`, `tsi_size` and `toi_size` bytes
+    pub fn from_sizes(cci_size: u32, tsi_size: u32, toi_size: u32) -> Self {
+        let h_tsi = (tsi_size & 2) >> 1; // Is TSI half-word ?
+        let h_toi = (toi_size & 2) >> 1; // Is TOI half-word ?
+
+        let h = h_tsi | h_toi; // Half-word flag
+        let o = (toi_size >> 2) & 0x3;
+        let s = (tsi_size >> 2) & 1;
+        let c = match cci_size {
+            size if size <= 4 => 0,
+            size if size <= 8 => 1,
+            size if size <= 12 => 2,
+            _ => 3,
+        };
+
+        LctHeaderFlags {
+            version: 1,
+            c,
+            psi: 0,
+            s: s as u8,
+            o: o as u8,
+            h: h as u8,
+            close_session: false,
+            close_object: false,
+            hdr_len: (2 + o + s + h + c as u32) as u8,
+            codepoint: 0,
+        }
+    }
+
+    /// Size in bytes of the CCI, TSI and TOI fields
+    pub fn sizes(&self) -> (usize, usize, usize) {
+        let c = (self.c & 0x3) as usize;
+        let s = (self.s & 0x1) as usize;
+        let o = (self.o & 0x3) as usize;
+        let h = (self.h & 0x1) as usize;
+        ((c + 1) << 2, (s << 2) + (h << 1), (o << 2) + (h << 1))
+    }
+}
+
+/// Inserts an LCT Header described by `flags` into the provided data vector.
+///
+/// The flags are truncated to the size of their field,
+/// the CCI, the TSI and the TOI are truncated to the size given by the flags.
+pub(crate) fn push_lct_header_flags(
+    data: &mut Vec<u8>,
+    flags: &LctHeaderFlags,
+    cci: &u128,
+    tsi: u64,
+    toi: &u128,
+) {
+    let lct_header: u32 = (flags.codepoint as u32)
+        | (flags.hdr_len as u32) << 8
+        | (flags.close_object as u32) << 16
+        | (flags.close_session as u32) << 17
+        | ((flags.h & 0x1) as u32) << 20
+        | ((flags.o & 0x3) as u32) << 21
+        | ((flags.s & 0x1) as u32) << 23
+        | ((flags.psi & 0x3) as u32) << 24
+        | ((flags.c & 0x3) as u32) << 26
+        | ((flags.version & 0xF) as u32) << 28;
 
     data.extend(lct_header.to_be_bytes());
 
+    let (cci_size, tsi_size, toi_size) = flags.sizes();
+
     // Insert CCI
     let cci_net = cci.to_be_bytes();
-    let cci_net_start: usize = cci_net.len() - ((c + 1) << 2) as usize;
-    data.extend(&cci_net[cci_net_start..]);
+    data.extend(&cci_net[cci_net.len() - cci_size..]);
 
     // Insert TSI
     let tsi_net = tsi.to_be_bytes();
-    let tsi_net_start = tsi_net.len() - ((s << 2) + (h << 1)) as usize;
-    data.extend(&tsi_net[tsi_net_start..]);
+    data.extend(&tsi_net[tsi_net.len() - tsi_size..]);
 
     // Insert TOI
     let toi_net = toi.to_be_bytes();
-    let toi_net_start = toi_net.len() - ((o << 2) + (h << 1)) as usize;
-    data.extend(&toi_net[toi_net_start..]);
+    data.extend(&toi_net[toi_net.len() - toi_size..]);
 }
 
 /// Increases the length of the LCT Header.
@@ -312,8 +364,18 @@ pub fn push_lct_header(
 ///
 /// * `data`: The vector containing the LCT Header.
 /// * `val`: The increment value specifying by how many bits the header length should be increased.
-pub fn inc_hdr_len(data: &mut [u8], val: u8) {
-    data[2] += val;
+///
+/// # Errors
+///
+/// Returns an error, and leaves the header unchanged, if the header length would exceed 255 (1020 bytes).
+pub fn inc_hdr_len(data: &mut [u8], val: u8) -> Result<()> {
+    data[2] = data[2].checked_add(val).ok_or_else(|| {
+        FluteError::new(format!(
+            "LCT header length {} + {} exceeds the max header length of 255",
+            data[2], val
+        ))
+    })?;
+    Ok(())
 }
 
 pub fn parse_lct_header(data: &[u8]) -> Result<LCTHeader> {
@@ -340,7 +402,7 @@ pub fn parse_lct_header(data: &[u8]) -> Result<LCTHeader> {
         |&v| Ok((v as usize) << 2),
     )?;
 
-    if len > data.len() {
+    if len < 4 || len > data.len() {
         return Err(FluteError::new(format!(
             "lct header size is {} whereas pkt size is {}",
             len,
@@ -433,7 +495,7 @@ pub fn get_ext<'a>(data: &'a [u8], lct: &LCTHeader, ext: u8) -> Result<Option<&'
         let het = lct_ext_ext[0];
         let hel = match het {
             het if het >= 128 => 4_usize,
-            _ => (lct_ext_ext[1] << 2) as usize,
+            _ => (lct_ext_ext[1] as usize) << 2,
         };
 
         if hel == 0 || hel > lct_ext_ext.len() {
@@ -469,4 +531,17 @@ mod tests {
         let codepoint: u8 = 0;
         super::push_lct_header(&mut lct, psi, &cci, tsi, &toi, codepoint, false, false)
     }
+
+    #[test]
+    pub fn test_lct_toi_roundtrip() {
+        crate::tests::init();
+        let tsi: u64 = 1;
+        for toi in [1u128, 0xFFFE, 0xFFFF, 0x10000, (1u128 << 112) - 1] {
+            let mut lct = Vec::new();
+            super::push_lct_header(&mut lct, 0, &0, tsi, &toi, 0, false, false);
+            let header = super::parse_lct_header(&lct).unwrap();
+            assert_eq!(header.toi, toi);
+            assert_eq!(header.tsi, tsi);
+        }
+    }
 }
@@ -12,9 +12,14 @@ pub enum Profile {
 }
 
 pub mod alc;
+pub mod alcbuilder;
+pub mod cipher;
+pub mod contentlocation;
 pub mod fdtinstance;
 pub mod lct;
 pub mod oti;
 pub mod partition;
 pub mod pkt;
+#[cfg(feature = "sdp")]
+pub mod sdp;
 pub mod udpendpoint;
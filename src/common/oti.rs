@@ -1,6 +1,18 @@
+use crate::common::{alc, lct, partition, pkt, Profile};
 use crate::tools::error::{FluteError, Result};
 use base64::Engine;
 use serde::Serialize;
+use std::time::Duration;
+
+/// IPv6 (40 bytes) + UDP (8 bytes) headers
+const IP_UDP_HEADER_LENGTH: usize = 48;
+/// Maximum number of source symbols per source block for RaptorQ (K'max)
+/// <https://www.rfc-editor.org/rfc/rfc6330.html#section-5.1.2>
+const RAPTORQ_MAX_SOURCE_BLOCK_LENGTH: u64 = 56403;
+/// Working set, size of the sub-blocks that a RaptorQ decoder is expected to fit in cache
+const RAPTORQ_WORKING_SET_SIZE: u64 = 256 * 1024;
+/// Lower bound of the sub-symbol size, in units of Al
+const RAPTORQ_MIN_SUB_SYMBOL_SIZE: u64 = 8;
 
 ///
 /// FEC Type
@@ -45,7 +57,7 @@ impl TryFrom<u8> for FECEncodingID {
 
 ///
 /// Reed Solomon GS2M Scheme Specific parameters
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ReedSolomonGF2MSchemeSpecific {
     /// Length of the finite field elements, in bits
@@ -58,7 +70,7 @@ pub struct ReedSolomonGF2MSchemeSpecific {
 ///
 /// RaptorQ Scheme Specific parameters
 /// <https://www.rfc-editor.org/rfc/rfc6330.html#section-3.3.3>
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct RaptorQSchemeSpecific {
     /// The number of source blocks (Z): 8-bit unsigned integer.  
@@ -77,7 +89,7 @@ pub struct RaptorQSchemeSpecific {
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 ///|             Z                 |      N        |       Al      |
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct RaptorSchemeSpecific {
     /// The number of source blocks (Z): 16-bit unsigned integer.  
@@ -165,7 +177,7 @@ impl RaptorSchemeSpecific {
 ///
 /// Scheme Specific information
 ///
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub enum SchemeSpecific {
     /// if `fec_encoding_id` is `FECEncodingID::ReedSolomonGF2M`
@@ -179,7 +191,7 @@ pub enum SchemeSpecific {
 ///
 /// FEC Object Transmission Information
 /// Contains the parameters using the build the blocks and FEC for the objects transmission
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Oti {
     /// Select the FEC for the object transmission
@@ -399,6 +411,125 @@ impl Oti {
         })
     }
 
+    /// Creates and returns an instance of the `Oti` using the FEC Scheme `RaptorQ`,
+    /// with parameters derived from the size of the object to transfer.
+    ///
+    /// The number of source blocks and sub-blocks are derived following
+    /// <https://www.rfc-editor.org/rfc/rfc6330.html#section-4.3>, with a working set of 256KB.
+    ///
+    /// # Parameters
+    ///
+    ///   * `target_mtu`: Maximum size of the IP packets carrying the ALC/LCT packets.
+    ///     The encoding symbol length is the MTU minus the estimated packet overhead, aligned on 4 bytes.
+    ///
+    ///   * `transfer_length`: Size in bytes of the object to transfer.
+    ///
+    ///   * `redundancy_fraction`: Ratio of repair symbols to generate per source block (ex: 0.25 for 25%).
+    ///
+    ///  # Returns
+    ///
+    /// An instance of the `Oti` struct
+    ///
+    /// # Errors
+    /// Returns an error if the MTU is too small, if the redundancy fraction is invalid
+    /// or if the transfer length cannot be transferred with RaptorQ
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use flute::core::Oti;
+    /// // 10MB file, protected by 25% of repair symbols
+    /// let oti = Oti::new_raptorq_adaptive(1500, 10 * 1024 * 1024, 0.25).unwrap();
+    /// ```
+    ///
+    pub fn new_raptorq_adaptive(
+        target_mtu: u16,
+        transfer_length: u64,
+        redundancy_fraction: f64,
+    ) -> Result<Oti> {
+        const SYMBOL_ALIGNMENT: u64 = 4;
+
+        if !redundancy_fraction.is_finite() || redundancy_fraction < 0.0 {
+            return Err(FluteError::new(format!(
+                "Redundancy fraction {} is invalid",
+                redundancy_fraction
+            )));
+        }
+
+        let overhead = Oti::new_raptorq(SYMBOL_ALIGNMENT as u16, 1, 0, 1, SYMBOL_ALIGNMENT as u8)?
+            .estimated_packet_overhead_bytes() as u64;
+        let encoding_symbol_length =
+            (target_mtu as u64).saturating_sub(overhead) / SYMBOL_ALIGNMENT * SYMBOL_ALIGNMENT;
+        if encoding_symbol_length == 0 {
+            return Err(FluteError::new(format!(
+                "MTU {} is too small, the packet overhead is {} bytes",
+                target_mtu, overhead
+            )));
+        }
+
+        // Kt, total number of source symbols
+        let nb_symbols = num_integer::div_ceil(transfer_length, encoding_symbol_length);
+        let max_sub_blocks =
+            (encoding_symbol_length / (RAPTORQ_MIN_SUB_SYMBOL_SIZE * SYMBOL_ALIGNMENT)).max(1);
+        // KL(n), maximum source block length so a sub-block fits in the working set
+        let max_block_length = |nb_sub_blocks: u64| -> u64 {
+            let sub_symbol_size =
+                num_integer::div_ceil(encoding_symbol_length, SYMBOL_ALIGNMENT * nb_sub_blocks)
+                    * SYMBOL_ALIGNMENT;
+            (RAPTORQ_WORKING_SET_SIZE / sub_symbol_size).min(RAPTORQ_MAX_SOURCE_BLOCK_LENGTH)
+        };
+
+        // Z, number of source blocks
+        let mut nb_blocks =
+            num_integer::div_ceil(nb_symbols, max_block_length(max_sub_blocks)).max(1);
+        if nb_blocks > u8::MAX as u64 {
+            // Keep the number of blocks in one byte, at the expense of the working set
+            nb_blocks = u8::MAX as u64;
+        }
+
+        let maximum_source_block_length = num_integer::div_ceil(nb_symbols, nb_blocks).max(1);
+        if maximum_source_block_length > RAPTORQ_MAX_SOURCE_BLOCK_LENGTH {
+            return Err(FluteError::new(format!(
+                "Transfer length of {} bytes exceeds the maximum supported by RaptorQ with symbols of {} bytes",
+                transfer_length, encoding_symbol_length
+            )));
+        }
+
+        // N, number of sub-blocks
+        let sub_blocks_length = (1..=max_sub_blocks)
+            .find(|n| maximum_source_block_length <= max_block_length(*n))
+            .unwrap_or(max_sub_blocks);
+
+        let max_number_of_parity_symbols =
+            (maximum_source_block_length as f64 * redundancy_fraction).ceil() as u64;
+        if max_number_of_parity_symbols > u16::MAX as u64 {
+            return Err(FluteError::new(format!(
+                "Redundancy fraction {} requires {} parity symbols, the maximum is {}",
+                redundancy_fraction,
+                max_number_of_parity_symbols,
+                u16::MAX
+            )));
+        }
+
+        let oti = Oti::new_raptorq(
+            encoding_symbol_length as u16,
+            maximum_source_block_length as u16,
+            max_number_of_parity_symbols as u16,
+            sub_blocks_length as u16,
+            SYMBOL_ALIGNMENT as u8,
+        )?;
+
+        if transfer_length > oti.max_transfer_length() as u64 {
+            return Err(FluteError::new(format!(
+                "Transfer length of {} bytes exceeds the maximum of {} bytes supported by RaptorQ",
+                transfer_length,
+                oti.max_transfer_length()
+            )));
+        }
+
+        Ok(oti)
+    }
+
     /// Creates and returns an instance of the `Oti` using the FEC Scheme `Raptor`.
     ///
     /// # Parameters
@@ -461,6 +592,93 @@ impl Oti {
         })
     }
 
+    /// Creates and returns a copy of `base` whose number of repair symbols is computed to recover
+    /// the source blocks when a fraction `expected_loss` of the packets is lost.
+    ///
+    /// The number of repair symbols is the smallest one for which the number of symbols received,
+    /// minus a margin of 3 standard deviations of the number of symbols lost,
+    /// is enough to decode a block. RaptorQ and Raptor require 2 symbols more than the source block length.
+    ///
+    /// # Parameters
+    ///
+    ///   * `base`: FEC scheme and encoding symbol length of the returned `Oti`.
+    ///
+    ///   * `expected_loss`: Expected packet loss rate, in `[0.0, 1.0[` (ex: 0.05 for 5%).
+    ///
+    ///   * `block_len`: Maximum source block length, in number of symbols.
+    ///
+    /// # Errors
+    /// Returns an error if the loss rate is invalid, if `base` is `NoCode` and the loss rate is not 0,
+    /// or if the number of repair symbols exceeds the limits of the FEC scheme
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use flute::core::Oti;
+    /// let base = Oti::new_reed_solomon_rs28(1400, 64, 1).unwrap();
+    /// let oti = Oti::for_loss_rate(&base, 0.1, 200).unwrap();
+    /// assert!(oti.max_number_of_parity_symbols > 20);
+    /// ```
+    ///
+    pub fn for_loss_rate(base: &Oti, expected_loss: f64, block_len: u16) -> Result<Oti> {
+        if !expected_loss.is_finite() || !(0.0..1.0).contains(&expected_loss) {
+            return Err(FluteError::new(format!(
+                "Expected loss rate {} is invalid",
+                expected_loss
+            )));
+        }
+
+        if block_len == 0 {
+            return Err(FluteError::new("Source block length must be > 0"));
+        }
+
+        let (decoding_overhead, max_encoding_block_length) = match base.fec_encoding_id {
+            FECEncodingID::NoCode if expected_loss > 0.0 => {
+                return Err(FluteError::new(
+                    "FEC NoCode can not recover lost packets, loss rate must be 0",
+                ))
+            }
+            FECEncodingID::NoCode => (0, block_len as u64),
+            FECEncodingID::ReedSolomonGF28 => (0, u8::MAX as u64),
+            FECEncodingID::ReedSolomonGF28UnderSpecified => (0, u16::MAX as u64),
+            FECEncodingID::RaptorQ | FECEncodingID::Raptor => {
+                (2, block_len as u64 + u16::MAX as u64)
+            }
+            FECEncodingID::ReedSolomonGF2M => {
+                return Err(FluteError::new(
+                    "FEC ReedSolomonGF2M is not supported by the sender",
+                ))
+            }
+        };
+
+        let block_len = block_len as u64;
+        let is_decodable = |nb_parity: u64| -> bool {
+            let encoding_block_length = (block_len + nb_parity) as f64;
+            let lost_std_dev =
+                (encoding_block_length * expected_loss * (1.0 - expected_loss)).sqrt();
+            encoding_block_length * (1.0 - expected_loss) - 3.0 * lost_std_dev
+                >= (block_len + decoding_overhead) as f64
+        };
+
+        let max_nb_parity = max_encoding_block_length.saturating_sub(block_len);
+        let max_number_of_parity_symbols = (0..=max_nb_parity)
+            .find(|nb_parity| is_decodable(*nb_parity))
+            .ok_or_else(|| {
+                FluteError::new(format!(
+                    "Loss rate {} can not be recovered with {:?} and a source block length of {}",
+                    expected_loss, base.fec_encoding_id, block_len
+                ))
+            })?;
+
+        let mut oti = base.clone();
+        oti.maximum_source_block_length = block_len as u32;
+        oti.max_number_of_parity_symbols = match base.fec_encoding_id {
+            FECEncodingID::NoCode => 0,
+            _ => max_number_of_parity_symbols as u32,
+        };
+        Ok(oti)
+    }
+
     /// Return the maximum file transfer length that the Oti can handle.  
     /// Files with an encoding size (CENC) greater than this value cannot be transferred via FLUTE.
     ///
@@ -488,11 +706,85 @@ impl Oti {
         size
     }
 
+    /// Return the number of ALC/LCT packets sent by the sender to transfer an object once.
+    ///
+    /// This is the number of source symbols of the object, plus `max_number_of_parity_symbols` repair symbols
+    /// per source block when a FEC scheme is used.
+    pub fn packets_per_object(&self, transfer_length: u64) -> u64 {
+        let (_, _, _, nb_blocks) = partition::block_partitioning(
+            self.maximum_source_block_length as u64,
+            transfer_length,
+            self.encoding_symbol_length as u64,
+        );
+
+        let source_symbols =
+            num_integer::div_ceil(transfer_length, self.encoding_symbol_length as u64);
+        let repair_symbols = match self.fec_encoding_id {
+            FECEncodingID::NoCode => 0,
+            _ => nb_blocks * self.max_number_of_parity_symbols as u64,
+        };
+        source_symbols + repair_symbols
+    }
+
+    /// Return an estimation of the time needed to transfer an object once at `bitrate_bps`.
+    ///
+    /// The estimation counts the packets of `packets_per_object()`, each made of an encoding symbol
+    /// and of the packet overhead estimated by `estimated_packet_overhead_bytes()`.
+    /// `Duration::MAX` is returned if `bitrate_bps` is 0.
+    pub fn estimate_transfer_time(&self, transfer_length: u64, bitrate_bps: u64) -> Duration {
+        if bitrate_bps == 0 {
+            return Duration::MAX;
+        }
+
+        let packet_size =
+            self.encoding_symbol_length as u64 + self.estimated_packet_overhead_bytes() as u64;
+        let nb_bits = self.packets_per_object(transfer_length) as f64 * packet_size as f64 * 8.0;
+        Duration::from_secs_f64(nb_bits / bitrate_bps as f64)
+    }
+
+    /// Return an estimation of the number of bytes added to each encoding symbol by the IP, UDP and ALC/LCT headers.
+    ///
+    /// The estimation is based on the largest ALC/LCT header that the sender can generate with this `Oti`,
+    /// over IPv6.
+    pub fn estimated_packet_overhead_bytes(&self) -> usize {
+        let pkt = pkt::Pkt {
+            payload: Vec::new(),
+            transfer_length: 0,
+            esi: 0,
+            sbn: 0,
+            toi: u128::MAX,
+            fdt_id: None,
+            cenc: lct::Cenc::Null,
+            inband_cenc: true,
+            close_object: false,
+            source_block_length: self.maximum_source_block_length,
+            sender_current_time: true,
+        };
+        let header = alc::new_alc_pkt(
+            self,
+            &0u128,
+            u64::MAX,
+            &pkt,
+            Profile::RFC6726,
+            std::time::SystemTime::now(),
+        )
+        .map(|header| header.len())
+        .unwrap_or_default();
+        IP_UDP_HEADER_LENGTH + header
+    }
+
     /// Returns the maximum number of source blocks that a file can be divided into, according to the FEC Scheme used.
     pub fn max_source_blocks_number(&self) -> usize {
         match self.fec_encoding_id {
             FECEncodingID::NoCode => u16::MAX as usize,
-            FECEncodingID::ReedSolomonGF2M => todo!(),
+            FECEncodingID::ReedSolomonGF2M => {
+                // Source Block Number is encoded on 32-m bits
+                let m = match self.scheme_specific.as_ref() {
+                    Some(SchemeSpecific::ReedSolomon(scheme)) => scheme.m.clamp(1, 31),
+                    _ => 8,
+                };
+                1usize << (32 - m as u32)
+            }
             FECEncodingID::ReedSolomonGF28 => u8::MAX as usize,
             FECEncodingID::ReedSolomonGF28UnderSpecified => u32::MAX as usize,
             FECEncodingID::RaptorQ => u8::MAX as usize,
@@ -500,8 +792,76 @@ impl Oti {
         }
     }
 
-    /// Convert `Oti` to `OtiAttributes`
-    pub fn get_attributes(&self) -> OtiAttributes {
+    /// Return `true` if the FEC scheme of this `Oti` can be transferred with the FLUTE `profile`
+    ///
+    /// RaptorQ is only specified for FLUTE Version 2 <https://www.rfc-editor.org/rfc/rfc6726.html>
+    pub fn is_compatible_with(&self, profile: &Profile) -> bool {
+        match profile {
+            Profile::RFC6726 => true,
+            Profile::RFC3926 => self.fec_encoding_id != FECEncodingID::RaptorQ,
+        }
+    }
+
+    /// Build an `Oti` from the FEC-OTI attributes of an FDT-Instance or of a File element
+    /// <https://www.rfc-editor.org/rfc/rfc6726.html#section-5>
+    pub fn new_from_fdt_attributes(attrs: &OtiAttributes) -> Result<Oti> {
+        let fec_encoding_id = attrs
+            .fec_oti_fec_encoding_id
+            .ok_or_else(|| FluteError::new("FEC-OTI-FEC-Encoding-ID is missing"))?;
+        let fec_encoding_id: FECEncodingID = fec_encoding_id.try_into().map_err(|_| {
+            FluteError::new(format!(
+                "FEC Encoding ID {} is not supported",
+                fec_encoding_id
+            ))
+        })?;
+
+        let maximum_source_block_length = attrs
+            .fec_oti_maximum_source_block_length
+            .ok_or_else(|| FluteError::new("FEC-OTI-Maximum-Source-Block-Length is missing"))?;
+        let encoding_symbol_length = attrs
+            .fec_oti_encoding_symbol_length
+            .ok_or_else(|| FluteError::new("FEC-OTI-Encoding-Symbol-Length is missing"))?;
+        let max_number_of_encoding_symbols = attrs
+            .fec_oti_max_number_of_encoding_symbols
+            .unwrap_or(maximum_source_block_length);
+
+        if max_number_of_encoding_symbols < maximum_source_block_length {
+            return Err(FluteError::new(format!(
+                "FEC-OTI-Max-Number-of-Encoding-Symbols ({}) is lower than FEC-OTI-Maximum-Source-Block-Length ({})",
+                max_number_of_encoding_symbols, maximum_source_block_length
+            )));
+        }
+
+        let scheme_specific = match attrs.fec_oti_scheme_specific_info.as_ref() {
+            None => None,
+            Some(info) => match fec_encoding_id {
+                FECEncodingID::ReedSolomonGF2M => Some(SchemeSpecific::ReedSolomon(
+                    ReedSolomonGF2MSchemeSpecific::decode(info)?,
+                )),
+                FECEncodingID::RaptorQ => Some(SchemeSpecific::RaptorQ(
+                    RaptorQSchemeSpecific::decode(info)?,
+                )),
+                FECEncodingID::Raptor => {
+                    Some(SchemeSpecific::Raptor(RaptorSchemeSpecific::decode(info)?))
+                }
+                _ => None,
+            },
+        };
+
+        Ok(Oti {
+            fec_encoding_id,
+            fec_instance_id: attrs.fec_oti_fec_instance_id.unwrap_or(0) as u16,
+            maximum_source_block_length: maximum_source_block_length as u32,
+            encoding_symbol_length: encoding_symbol_length as u16,
+            max_number_of_parity_symbols: (max_number_of_encoding_symbols
+                - maximum_source_block_length) as u32,
+            scheme_specific,
+            inband_fti: false,
+        })
+    }
+
+    /// Convert `Oti` to `OtiAttributes`, inverse of `Oti::new_from_fdt_attributes`
+    pub fn to_fdt_attributes(&self) -> OtiAttributes {
         OtiAttributes {
             fec_oti_fec_encoding_id: Some(self.fec_encoding_id as u8),
             fec_oti_fec_instance_id: Some(self.fec_instance_id as u64),
@@ -514,6 +874,12 @@ impl Oti {
         }
     }
 
+    /// Convert `Oti` to `OtiAttributes`
+    #[deprecated(note = "use `Oti::to_fdt_attributes` instead")]
+    pub fn get_attributes(&self) -> OtiAttributes {
+        self.to_fdt_attributes()
+    }
+
     fn scheme_specific_info(&self) -> Option<String> {
         match self.fec_encoding_id {
             FECEncodingID::NoCode => None,
@@ -583,4 +949,126 @@ mod tests {
             rs28_under_specified.max_transfer_length()
         );
     }
+
+    fn check_round_trip(oti: super::Oti) {
+        let attributes = oti.to_fdt_attributes();
+        let decoded = super::Oti::new_from_fdt_attributes(&attributes).unwrap();
+        // FTI is not carried by the FDT attributes
+        let expected = super::Oti {
+            inband_fti: false,
+            ..oti
+        };
+        assert_eq!(expected, decoded);
+        assert_eq!(attributes, decoded.to_fdt_attributes());
+        #[allow(deprecated)]
+        let legacy = decoded.get_attributes();
+        assert_eq!(attributes, legacy);
+    }
+
+    #[test]
+    pub fn test_oti_fdt_attributes_round_trip() {
+        crate::tests::init();
+        check_round_trip(super::Oti::new_no_code(1400, 64));
+        check_round_trip(super::Oti::new_reed_solomon_rs28(1400, 60, 4).unwrap());
+        check_round_trip(super::Oti::new_reed_solomon_rs28_under_specified(1400, 60, 4).unwrap());
+        check_round_trip(super::Oti::new_raptorq(1024, 60, 4, 1, 4).unwrap());
+        check_round_trip(super::Oti::new_raptor(1024, 60, 4, 1, 4).unwrap());
+    }
+
+    #[test]
+    pub fn test_oti_fdt_attributes_missing_encoding_id() {
+        crate::tests::init();
+        let mut attributes = super::Oti::new_no_code(1400, 64).to_fdt_attributes();
+        attributes.fec_oti_fec_encoding_id = None;
+        assert!(super::Oti::new_from_fdt_attributes(&attributes).is_err());
+    }
+
+    #[test]
+    pub fn test_oti_raptorq_adaptive() {
+        crate::tests::init();
+        for transfer_length in [0u64, 1, 1024 * 1024, 10 * 1024 * 1024, 1024 * 1024 * 1024] {
+            let oti = super::Oti::new_raptorq_adaptive(1500, transfer_length, 0.25).unwrap();
+            log::info!("transfer_length={} oti={:?}", transfer_length, oti);
+            assert!(oti.encoding_symbol_length % 4 == 0);
+            assert!(
+                oti.encoding_symbol_length as usize + oti.estimated_packet_overhead_bytes() <= 1500
+            );
+            assert!(oti.maximum_source_block_length <= 56403);
+            assert!(
+                oti.max_number_of_parity_symbols
+                    == (oti.maximum_source_block_length as f64 * 0.25).ceil() as u32
+            );
+
+            let (_, _, _, nb_blocks) = crate::common::partition::block_partitioning(
+                oti.maximum_source_block_length as u64,
+                transfer_length,
+                oti.encoding_symbol_length as u64,
+            );
+            assert!(nb_blocks <= u8::MAX as u64);
+        }
+
+        assert!(super::Oti::new_raptorq_adaptive(1500, 1 << 40, 0.25).is_err());
+        assert!(super::Oti::new_raptorq_adaptive(1500, 1024, -1.0).is_err());
+        assert!(super::Oti::new_raptorq_adaptive(64, 1024, 0.25).is_err());
+    }
+
+    #[test]
+    pub fn test_packets_per_object() {
+        crate::tests::init();
+        let no_code = super::Oti::new_no_code(1400, 64);
+        assert_eq!(no_code.packets_per_object(0), 0);
+        assert_eq!(no_code.packets_per_object(10000), 8);
+
+        // 143 source symbols in 3 blocks, 4 repair symbols per block
+        let rs28 = super::Oti::new_reed_solomon_rs28(1400, 60, 4).unwrap();
+        assert_eq!(rs28.packets_per_object(200000), 143 + 3 * 4);
+    }
+
+    #[test]
+    pub fn test_estimate_transfer_time() {
+        crate::tests::init();
+        let no_code = super::Oti::new_no_code(1400, 64);
+        let packet_bits = (1400 + no_code.estimated_packet_overhead_bytes()) as u64 * 8;
+        // 8 packets at 1 packet per second
+        let duration = no_code.estimate_transfer_time(10000, packet_bits);
+        assert_eq!(duration, std::time::Duration::from_secs(8));
+        assert_eq!(
+            no_code.estimate_transfer_time(10000, 0),
+            std::time::Duration::MAX
+        );
+    }
+
+    #[test]
+    pub fn test_oti_for_loss_rate() {
+        crate::tests::init();
+        let rs28 = super::Oti::new_reed_solomon_rs28(1400, 64, 1).unwrap();
+        let raptorq = super::Oti::new_raptorq(1400, 64, 1, 1, 4).unwrap();
+        for base in [&rs28, &raptorq] {
+            let mut previous = 0;
+            for loss in [0.0, 0.01, 0.05, 0.1, 0.2] {
+                let oti = super::Oti::for_loss_rate(base, loss, 100).unwrap();
+                log::info!(
+                    "{:?} loss={} parity={}",
+                    base.fec_encoding_id,
+                    loss,
+                    oti.max_number_of_parity_symbols
+                );
+                assert_eq!(oti.fec_encoding_id, base.fec_encoding_id);
+                assert_eq!(oti.maximum_source_block_length, 100);
+                assert!(oti.max_number_of_parity_symbols as f64 >= 100.0 * loss);
+                assert!(loss == 0.0 || oti.max_number_of_parity_symbols > previous);
+                previous = oti.max_number_of_parity_symbols;
+            }
+        }
+
+        // Encoding block length of RS28 is limited to 255 symbols
+        assert!(super::Oti::for_loss_rate(&rs28, 0.5, 200).is_err());
+        assert!(super::Oti::for_loss_rate(&rs28, 1.0, 200).is_err());
+        assert!(super::Oti::for_loss_rate(&rs28, -0.1, 200).is_err());
+
+        let no_code = super::Oti::new_no_code(1400, 64);
+        assert!(super::Oti::for_loss_rate(&no_code, 0.01, 200).is_err());
+        let oti = super::Oti::for_loss_rate(&no_code, 0.0, 200).unwrap();
+        assert_eq!(oti.max_number_of_parity_symbols, 0);
+    }
 }
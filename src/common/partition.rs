@@ -1,3 +1,5 @@
+use super::oti;
+
 ///
 /// Block Partitioning Algorithm  
 /// See <https://www.rfc-editor.org/rfc/rfc5052#section-9.1>
@@ -63,6 +65,20 @@ pub fn block_partitioning(b: u64, l: u64, e: u64) -> (u64, u64, u64, u64) {
 ///
 /// The size of the block in octets.
 ///
+/// # Example
+///
+/// ```
+/// use flute::core::partition;
+///
+/// // 1 000 000 bytes split in 10 blocks of 64 symbols and 1 block of 63 symbols of 1424 bytes
+/// assert_eq!(partition::block_length(64, 63, 10, 1_000_000, 1424, 0), 64 * 1424);
+/// // The last block contains the remaining bytes
+/// assert_eq!(
+///     partition::block_length(64, 63, 10, 1_000_000, 1424, 10),
+///     1_000_000 - 10 * 64 * 1424
+/// );
+/// ```
+///
 pub fn block_length(a_large: u64, a_small: u64, nb_a_large: u64, l: u64, e: u64, sbn: u32) -> u64 {
     let sbn = sbn as u64;
 
@@ -93,6 +109,72 @@ pub fn block_length(a_large: u64, a_small: u64, nb_a_large: u64, l: u64, e: u64,
     l - (sbn * small_block_size)
 }
 
+/// Layout of the source blocks of an object, see `simulate()`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockLayout {
+    /// Total number of source blocks
+    pub nb_blocks: u64,
+    /// Number of source blocks composed of `large_block_symbols` symbols
+    pub nb_large_blocks: u64,
+    /// Number of source symbols of the larger blocks
+    pub large_block_symbols: u64,
+    /// Number of source symbols of the smaller blocks
+    pub small_block_symbols: u64,
+    /// Number of source symbols of the last block
+    pub last_block_symbols: u64,
+    /// Total number of source symbols of the object
+    pub total_symbols: u64,
+}
+
+/// Compute the source blocks layout of an object with the block partitioning algorithm of RFC 5052
+///
+/// Can be used to know how many source symbols (ALC/LCT packets without FEC) are generated
+/// for an object before creating a sender.
+///
+/// # Arguments
+///
+/// * `oti`: FEC Object Transmission Information used to transfer the object.
+/// * `transfer_length`: Transfer length of the object in octets.
+///
+/// # Example
+///
+/// ```
+/// use flute::core::partition;
+/// use flute::core::Oti;
+///
+/// // 1 000 000 bytes transferred with symbols of 1424 bytes and blocks of 64 symbols
+/// let oti = Oti::new_no_code(1424, 64);
+/// let layout = partition::simulate(&oti, 1_000_000);
+/// assert_eq!(layout.total_symbols, 703);
+/// assert_eq!(layout.nb_blocks, 11);
+/// assert_eq!(layout.nb_large_blocks, 10);
+/// assert_eq!(layout.large_block_symbols, 64);
+/// assert_eq!(layout.small_block_symbols, 63);
+/// assert_eq!(layout.last_block_symbols, 63);
+/// ```
+pub fn simulate(oti: &oti::Oti, transfer_length: u64) -> BlockLayout {
+    let (a_large, a_small, nb_a_large, nb_blocks) = block_partitioning(
+        oti.maximum_source_block_length as u64,
+        transfer_length,
+        oti.encoding_symbol_length as u64,
+    );
+
+    let last_block_symbols = match nb_blocks {
+        0 => 0,
+        n if nb_a_large == n => a_large,
+        _ => a_small,
+    };
+
+    BlockLayout {
+        nb_blocks,
+        nb_large_blocks: nb_a_large,
+        large_block_symbols: a_large,
+        small_block_symbols: a_small,
+        last_block_symbols,
+        total_symbols: nb_a_large * a_large + (nb_blocks - nb_a_large) * a_small,
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
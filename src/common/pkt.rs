@@ -1,4 +1,4 @@
-use super::lct;
+use super::{alc, lct};
 
 #[derive(Debug)]
 pub struct Pkt {
@@ -14,3 +14,14 @@ pub struct Pkt {
     pub source_block_length: u32,
     pub sender_current_time: bool,
 }
+
+impl Pkt {
+    /// FEC Payload ID of the packet
+    pub fn payload_id(&self) -> alc::PayloadID {
+        alc::PayloadID {
+            sbn: self.sbn,
+            esi: self.esi,
+            source_block_length: Some(self.source_block_length),
+        }
+    }
+}
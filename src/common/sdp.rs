@@ -0,0 +1,504 @@
+use super::oti::{Oti, OtiAttributes};
+use super::udpendpoint::UDPEndpoint;
+use crate::tools::error::{FluteError, Result};
+use std::collections::HashMap;
+
+///
+/// Parameters of a FLUTE session announced by a Session Description (SDP)
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FluteSessionParams {
+    /// Destination group address, port and source address (SSM) of the session
+    pub endpoint: UDPEndpoint,
+    /// Transport Session Identifier
+    pub tsi: u64,
+    /// Object Transmission Information (OTI) delivered out-of-band, `None` when the SDP does not declare it
+    pub oti: Option<Oti>,
+}
+
+///
+/// FLUTE Session Description
+///
+/// See [rfc4566](https://www.rfc-editor.org/rfc/rfc4566) for the SDP syntax,
+/// [rfc6364](https://www.rfc-editor.org/rfc/rfc6364) for the FEC declarations
+/// and 3GPP TS 26.346 for the FLUTE attributes (`a=flute-tsi`)
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionDescription {
+    /// Session name (`s=`)
+    pub session_name: String,
+    /// FLUTE sessions, one media description (`m=application`) per session
+    pub sessions: Vec<FluteSessionParams>,
+}
+
+/// Attributes that can be declared at session level and overridden at media level
+#[derive(Debug, Clone, Default)]
+struct SdpScope {
+    destination_group_address: Option<String>,
+    source_address: Option<String>,
+    tsi: Option<u64>,
+    fec_ref: Option<String>,
+    fec_declarations: HashMap<String, FecDeclaration>,
+    oti: OtiScope,
+}
+
+#[derive(Debug, Clone, Default)]
+struct FecDeclaration {
+    encoding_id: Option<u8>,
+    instance_id: Option<u64>,
+    scheme_specific_info: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct OtiScope {
+    fec_encoding_id: Option<u8>,
+    fec_instance_id: Option<u64>,
+    maximum_source_block_length: Option<u64>,
+    encoding_symbol_length: Option<u64>,
+    max_number_of_encoding_symbols: Option<u64>,
+    scheme_specific_info: Option<String>,
+}
+
+impl OtiScope {
+    fn is_declared(&self) -> bool {
+        self.fec_encoding_id.is_some()
+            || self.maximum_source_block_length.is_some()
+            || self.encoding_symbol_length.is_some()
+            || self.max_number_of_encoding_symbols.is_some()
+    }
+
+    fn merge(&self, media: &OtiScope) -> OtiScope {
+        OtiScope {
+            fec_encoding_id: media.fec_encoding_id.or(self.fec_encoding_id),
+            fec_instance_id: media.fec_instance_id.or(self.fec_instance_id),
+            maximum_source_block_length: media
+                .maximum_source_block_length
+                .or(self.maximum_source_block_length),
+            encoding_symbol_length: media.encoding_symbol_length.or(self.encoding_symbol_length),
+            max_number_of_encoding_symbols: media
+                .max_number_of_encoding_symbols
+                .or(self.max_number_of_encoding_symbols),
+            scheme_specific_info: media
+                .scheme_specific_info
+                .clone()
+                .or_else(|| self.scheme_specific_info.clone()),
+        }
+    }
+}
+
+struct MediaDescription {
+    port: u16,
+    scope: SdpScope,
+}
+
+impl SessionDescription {
+    /// Create a Session Description of one or several FLUTE sessions
+    #[cfg(feature = "sdp")]
+    pub fn new(session_name: &str, sessions: Vec<FluteSessionParams>) -> Self {
+        SessionDescription {
+            session_name: session_name.to_owned(),
+            sessions,
+        }
+    }
+
+    ///
+    /// Extract the FLUTE sessions of a Session Description
+    ///
+    /// One session is returned per `m=application <port> FLUTE/UDP` media description.
+    /// The following lines are read, at session level or overridden at media level
+    /// * `c=` destination group address (IPv4 or IPv6)
+    /// * `a=source-filter: incl` source address (SSM)
+    /// * `a=flute-tsi:` TSI
+    /// * `a=FEC-declaration:`, `a=FEC:` and `a=FEC-OTI-extension:` FEC declaration
+    /// * `a=FEC-OTI-<FDT attribute>:`, `a=flute-fec-encoding-id:`, `a=flute-encoding-symbol-length:`,
+    ///   `a=flute-max-source-block-length:` Object Transmission Information (OTI)
+    ///
+    /// The OTI is only returned when at least one of its parameters is declared with an `a=FEC-OTI-` or `a=flute-` attribute,
+    /// a FEC declaration alone only announces the FEC scheme used in-band.
+    /// Other lines and unknown attributes are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use flute::sdp::SessionDescription;
+    ///
+    /// let sdp = "v=0\r\n\
+    ///            o=- 0 0 IN IP6 2001:db8::1\r\n\
+    ///            s=FLUTE session\r\n\
+    ///            t=0 0\r\n\
+    ///            a=source-filter: incl IN IP6 * 2001:db8::1\r\n\
+    ///            a=flute-tsi:3\r\n\
+    ///            m=application 12345 FLUTE/UDP 0\r\n\
+    ///            c=IN IP6 ff1e::1234/1\r\n";
+    ///
+    /// let sessions = SessionDescription::from_sdp(sdp).unwrap();
+    /// assert_eq!(sessions.len(), 1);
+    /// assert_eq!(sessions[0].endpoint.destination_group_address, "ff1e::1234");
+    /// assert_eq!(sessions[0].endpoint.source_address.as_deref(), Some("2001:db8::1"));
+    /// assert_eq!(sessions[0].endpoint.port, 12345);
+    /// assert_eq!(sessions[0].tsi, 3);
+    /// ```
+    pub fn from_sdp(sdp: &str) -> Result<Vec<FluteSessionParams>> {
+        let mut session = SdpScope::default();
+        let mut medias: Vec<MediaDescription> = Vec::new();
+        let mut in_flute_media = false;
+        let mut in_media = false;
+
+        for line in sdp.lines().map(|line| line.trim()) {
+            if let Some(media) = line.strip_prefix("m=") {
+                // m=application 12345 FLUTE/UDP 0
+                in_media = true;
+                let fields: Vec<&str> = media.split_whitespace().collect();
+                in_flute_media = fields.len() >= 3
+                    && fields[0] == "application"
+                    && fields[2].to_ascii_uppercase().starts_with("FLUTE");
+                if !in_flute_media {
+                    continue;
+                }
+                let port = fields[1]
+                    .split('/')
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .ok_or_else(|| parse_error(line))?;
+                medias.push(MediaDescription {
+                    port,
+                    scope: SdpScope::default(),
+                });
+                continue;
+            }
+
+            let scope = match (in_media, in_flute_media) {
+                (false, _) => &mut session,
+                (true, true) => &mut medias.last_mut().unwrap().scope,
+                (true, false) => continue,
+            };
+            parse_line(scope, line)?;
+        }
+
+        if medias.is_empty() {
+            return Err(FluteError::new(
+                "SDP media description (m=application FLUTE/UDP) is missing",
+            ));
+        }
+
+        medias
+            .into_iter()
+            .map(|media| media_to_session(&session, media))
+            .collect()
+    }
+
+    ///
+    /// Generate the Session Description (SDP) of the FLUTE sessions
+    ///
+    /// Each session is described by a `m=application <port> FLUTE/UDP 0` media description
+    /// with its connection address, its source filter, its TSI and its FEC declaration
+    ///
+    #[cfg(feature = "sdp")]
+    pub fn to_sdp(&self) -> String {
+        let origin = self
+            .sessions
+            .first()
+            .map(|session| {
+                session
+                    .endpoint
+                    .source_address
+                    .clone()
+                    .unwrap_or_else(|| session.endpoint.destination_group_address.clone())
+            })
+            .unwrap_or_else(|| "0.0.0.0".to_owned());
+
+        let mut sdp = String::new();
+        sdp.push_str("v=0\r\n");
+        sdp.push_str(&format!(
+            "o=- {} 0 IN {} {}\r\n",
+            self.sessions.first().map(|s| s.tsi).unwrap_or_default(),
+            address_type(&origin),
+            origin
+        ));
+        sdp.push_str(&format!("s={}\r\n", self.session_name));
+        sdp.push_str("t=0 0\r\n");
+
+        for (index, session) in self.sessions.iter().enumerate() {
+            if let Some(oti) = &session.oti {
+                let attributes = oti.to_fdt_attributes();
+                sdp.push_str(&format!(
+                    "a=FEC-declaration:{} encoding-id={}",
+                    index, oti.fec_encoding_id as u8
+                ));
+                if oti.fec_encoding_id as u8 >= 128 {
+                    sdp.push_str(&format!(
+                        "; instance-id={}",
+                        attributes.fec_oti_fec_instance_id.unwrap_or_default()
+                    ));
+                }
+                sdp.push_str("\r\n");
+            }
+        }
+
+        for (index, session) in self.sessions.iter().enumerate() {
+            let group = &session.endpoint.destination_group_address;
+            let address_type = address_type(group);
+            sdp.push_str(&format!(
+                "m=application {} FLUTE/UDP 0\r\n",
+                session.endpoint.port
+            ));
+            match address_type {
+                "IP4" => sdp.push_str(&format!("c=IN IP4 {}/255\r\n", group)),
+                _ => sdp.push_str(&format!("c=IN {} {}\r\n", address_type, group)),
+            }
+            if let Some(source) = &session.endpoint.source_address {
+                sdp.push_str(&format!(
+                    "a=source-filter: incl IN {} {} {}\r\n",
+                    address_type, group, source
+                ));
+            }
+            sdp.push_str(&format!("a=flute-tsi:{}\r\n", session.tsi));
+
+            if let Some(oti) = &session.oti {
+                let attributes = oti.to_fdt_attributes();
+                sdp.push_str(&format!("a=FEC:{}\r\n", index));
+                sdp.push_str(&format!(
+                    "a=FEC-OTI-FEC-Encoding-ID:{}\r\n",
+                    oti.fec_encoding_id as u8
+                ));
+                if let Some(length) = attributes.fec_oti_maximum_source_block_length {
+                    sdp.push_str(&format!(
+                        "a=FEC-OTI-Maximum-Source-Block-Length:{}\r\n",
+                        length
+                    ));
+                }
+                if let Some(length) = attributes.fec_oti_encoding_symbol_length {
+                    sdp.push_str(&format!("a=FEC-OTI-Encoding-Symbol-Length:{}\r\n", length));
+                }
+                if let Some(nb) = attributes.fec_oti_max_number_of_encoding_symbols {
+                    sdp.push_str(&format!(
+                        "a=FEC-OTI-Max-Number-of-Encoding-Symbols:{}\r\n",
+                        nb
+                    ));
+                }
+                if let Some(info) = attributes.fec_oti_scheme_specific_info {
+                    sdp.push_str(&format!("a=FEC-OTI-extension:{} {}\r\n", index, info));
+                }
+            }
+        }
+
+        sdp
+    }
+}
+
+fn parse_error(line: &str) -> FluteError {
+    FluteError::new(format!("Invalid SDP line {}", line))
+}
+
+#[cfg(feature = "sdp")]
+fn address_type(address: &str) -> &'static str {
+    match address.contains(':') {
+        true => "IP6",
+        false => "IP4",
+    }
+}
+
+fn parse_line(scope: &mut SdpScope, line: &str) -> Result<()> {
+    if let Some(connection) = line.strip_prefix("c=") {
+        // c=IN IP4 233.1.1.1/255
+        // c=IN IP6 FF1E:03AD::7F2E:172A:1E24/1
+        let address = connection
+            .split_whitespace()
+            .nth(2)
+            .ok_or_else(|| parse_error(line))?;
+        let address = address.split('/').next().unwrap_or(address);
+        scope.destination_group_address = Some(address.to_owned());
+        return Ok(());
+    }
+
+    let (name, value) = match line
+        .strip_prefix("a=")
+        .and_then(|attribute| attribute.split_once(':'))
+    {
+        Some((name, value)) => (name, value.trim()),
+        None => return Ok(()),
+    };
+
+    let parse_u64 = || value.parse::<u64>().map_err(|_| parse_error(line));
+    let parse_u8 = || value.parse::<u8>().map_err(|_| parse_error(line));
+
+    match name {
+        "source-filter" => {
+            // a=source-filter: incl IN IP4 233.1.1.1 10.0.0.1
+            let fields: Vec<&str> = value.split_whitespace().collect();
+            if fields.len() < 5 {
+                return Err(parse_error(line));
+            }
+            if fields[0] == "incl" {
+                scope.source_address = Some(fields[4].to_owned());
+            }
+        }
+        "flute-tsi" => scope.tsi = Some(parse_u64()?),
+        "FEC" => scope.fec_ref = Some(value.to_owned()),
+        "FEC-declaration" => {
+            // a=FEC-declaration:0 encoding-id=1; instance-id=0
+            let (fec_ref, params) = value.split_once(' ').ok_or_else(|| parse_error(line))?;
+            let declaration = scope
+                .fec_declarations
+                .entry(fec_ref.to_owned())
+                .or_default();
+            for (key, param) in params
+                .split(';')
+                .filter_map(|param| param.trim().split_once('='))
+            {
+                let param = param.trim();
+                match key.trim() {
+                    "encoding-id" => {
+                        declaration.encoding_id =
+                            Some(param.parse().map_err(|_| parse_error(line))?)
+                    }
+                    "instance-id" => {
+                        declaration.instance_id =
+                            Some(param.parse().map_err(|_| parse_error(line))?)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        "FEC-OTI-extension" => {
+            // a=FEC-OTI-extension:0 <base64 scheme specific info>
+            let (fec_ref, info) = value.split_once(' ').ok_or_else(|| parse_error(line))?;
+            scope
+                .fec_declarations
+                .entry(fec_ref.to_owned())
+                .or_default()
+                .scheme_specific_info = Some(info.trim().to_owned());
+        }
+        "FEC-OTI-FEC-Encoding-ID" | "flute-fec-encoding-id" => {
+            scope.oti.fec_encoding_id = Some(parse_u8()?)
+        }
+        "FEC-OTI-FEC-Instance-ID" => scope.oti.fec_instance_id = Some(parse_u64()?),
+        "FEC-OTI-Maximum-Source-Block-Length" | "flute-max-source-block-length" => {
+            scope.oti.maximum_source_block_length = Some(parse_u64()?)
+        }
+        "FEC-OTI-Encoding-Symbol-Length" | "flute-encoding-symbol-length" => {
+            scope.oti.encoding_symbol_length = Some(parse_u64()?)
+        }
+        "FEC-OTI-Max-Number-of-Encoding-Symbols" => {
+            scope.oti.max_number_of_encoding_symbols = Some(parse_u64()?)
+        }
+        "FEC-OTI-Scheme-Specific-Info" => scope.oti.scheme_specific_info = Some(value.to_owned()),
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn media_to_session(session: &SdpScope, media: MediaDescription) -> Result<FluteSessionParams> {
+    let scope = media.scope;
+    let destination_group_address = scope
+        .destination_group_address
+        .or_else(|| session.destination_group_address.clone())
+        .ok_or_else(|| FluteError::new("SDP connection address (c=) is missing"))?;
+    let source_address = scope
+        .source_address
+        .or_else(|| session.source_address.clone());
+    let tsi = scope
+        .tsi
+        .or(session.tsi)
+        .ok_or_else(|| FluteError::new("SDP attribute a=flute-tsi is missing"))?;
+
+    // a=FEC:<ref> selects the FEC declaration, media level first
+    let fec_ref = scope.fec_ref.clone().or_else(|| session.fec_ref.clone());
+    let declaration = match fec_ref {
+        Some(fec_ref) => scope
+            .fec_declarations
+            .get(&fec_ref)
+            .or_else(|| session.fec_declarations.get(&fec_ref))
+            .cloned(),
+        None => {
+            let mut declarations = scope
+                .fec_declarations
+                .values()
+                .chain(session.fec_declarations.values());
+            match (declarations.next(), declarations.next()) {
+                (Some(declaration), None) => Some(declaration.clone()),
+                _ => None,
+            }
+        }
+    }
+    .unwrap_or_default();
+
+    let oti = session.oti.merge(&scope.oti);
+    let oti = match oti.is_declared() {
+        true => Some(Oti::new_from_fdt_attributes(&OtiAttributes {
+            fec_oti_fec_encoding_id: oti.fec_encoding_id.or(declaration.encoding_id),
+            fec_oti_fec_instance_id: oti.fec_instance_id.or(declaration.instance_id),
+            fec_oti_maximum_source_block_length: oti.maximum_source_block_length,
+            fec_oti_encoding_symbol_length: oti.encoding_symbol_length,
+            fec_oti_max_number_of_encoding_symbols: oti.max_number_of_encoding_symbols,
+            fec_oti_scheme_specific_info: oti
+                .scheme_specific_info
+                .or(declaration.scheme_specific_info),
+        })?),
+        false => None,
+    };
+
+    Ok(FluteSessionParams {
+        endpoint: UDPEndpoint::new(source_address, destination_group_address, media.port),
+        tsi,
+        oti,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::oti::FECEncodingID;
+
+    #[test]
+    pub fn test_sdp_multiple_sessions() {
+        crate::tests::init();
+        let sdp = "v=0\r\n\
+                   o=- 1 1 IN IP4 10.0.0.1\r\n\
+                   s=Sessions\r\n\
+                   c=IN IP4 233.1.1.1/255\r\n\
+                   t=0 0\r\n\
+                   a=x-unknown-attribute:foo bar\r\n\
+                   a=recvonly\r\n\
+                   a=FEC-declaration:0 encoding-id=5\r\n\
+                   a=flute-tsi:1\r\n\
+                   m=video 6000 RTP/AVP 96\r\n\
+                   a=flute-tsi:not-a-number\r\n\
+                   m=application 5000 FLUTE/UDP 0\r\n\
+                   a=FEC:0\r\n\
+                   a=FEC-OTI-Maximum-Source-Block-Length:64\r\n\
+                   a=FEC-OTI-Encoding-Symbol-Length:1024\r\n\
+                   a=FEC-OTI-Max-Number-of-Encoding-Symbols:84\r\n\
+                   m=application 5002 FLUTE/UDP 0\r\n\
+                   c=IN IP4 233.1.1.2/255\r\n\
+                   a=source-filter: incl IN IP4 233.1.1.2 10.0.0.2\r\n\
+                   a=flute-tsi:2\r\n";
+
+        let sessions = super::SessionDescription::from_sdp(sdp).unwrap();
+        assert_eq!(sessions.len(), 2);
+
+        assert_eq!(sessions[0].endpoint.destination_group_address, "233.1.1.1");
+        assert_eq!(sessions[0].endpoint.port, 5000);
+        assert_eq!(sessions[0].endpoint.source_address, None);
+        assert_eq!(sessions[0].tsi, 1);
+        let oti = sessions[0].oti.as_ref().unwrap();
+        assert_eq!(oti.fec_encoding_id, FECEncodingID::ReedSolomonGF28);
+        assert_eq!(oti.maximum_source_block_length, 64);
+        assert_eq!(oti.encoding_symbol_length, 1024);
+        assert_eq!(oti.max_number_of_parity_symbols, 20);
+
+        assert_eq!(sessions[1].endpoint.destination_group_address, "233.1.1.2");
+        assert_eq!(
+            sessions[1].endpoint.source_address.as_deref(),
+            Some("10.0.0.2")
+        );
+        assert_eq!(sessions[1].endpoint.port, 5002);
+        assert_eq!(sessions[1].tsi, 2);
+        assert!(sessions[1].oti.is_none());
+
+        assert!(super::SessionDescription::from_sdp("v=0\r\na=flute-tsi:1\r\n").is_err());
+        assert!(super::SessionDescription::from_sdp(
+            "c=IN IP4 233.1.1.1\r\nm=application 5000 FLUTE/UDP 0\r\n"
+        )
+        .is_err());
+    }
+}
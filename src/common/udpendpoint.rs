@@ -1,6 +1,7 @@
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    net::IpAddr,
     time::SystemTime,
 };
 
@@ -29,6 +30,20 @@ impl UDPEndpoint {
         }
     }
 
+    /// Return `true` if a packet sent from `src` belongs to this endpoint
+    ///
+    /// Any source is accepted when `source_address` is not set.
+    /// A `source_address` that is not a valid IP address does not match any source.
+    pub fn is_source_allowed(&self, src: &IpAddr) -> bool {
+        match self.source_address.as_ref() {
+            Some(source_address) => source_address
+                .parse::<IpAddr>()
+                .map(|addr| addr == *src)
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+
     /// Generate a u128bits Trace-ID
     pub fn trace_id(
         &self,
@@ -21,6 +21,7 @@ pub trait FecShard: Send + Sync + std::fmt::Debug {
 pub struct DataFecShard {
     shard: Vec<u8>,
     index: u32,
+    #[allow(dead_code)]
     shard_type: ShardType,
 }
 
@@ -3,62 +3,71 @@ use crate::error::FluteError;
 use super::FecDecoder;
 
 pub struct NoCodeDecoder {
-    shards: Vec<Option<Vec<u8>>>,
+    received: Vec<bool>,
     nb_symbols: usize,
-    data: Option<Vec<u8>>,
+    encoding_symbol_length: usize,
+    data: Vec<u8>,
+    data_length: usize,
 }
 
 impl NoCodeDecoder {
-    pub fn new(nb_source_symbols: usize) -> NoCodeDecoder {
+    pub fn new(nb_source_symbols: usize, encoding_symbol_length: usize) -> NoCodeDecoder {
         NoCodeDecoder {
-            shards: vec![None; nb_source_symbols],
+            received: vec![false; nb_source_symbols],
             nb_symbols: 0,
-            data: None,
+            encoding_symbol_length,
+            data: Vec::new(),
+            data_length: 0,
         }
     }
 }
 
 impl FecDecoder for NoCodeDecoder {
     fn push_symbol(&mut self, encoding_symbol: &[u8], esi: u32) {
-        if self.shards.len() <= esi as usize {
-            log::error!("ESI {} > {}", esi, self.shards.len());
+        if self.received.len() <= esi as usize {
+            log::error!("ESI {} > {}", esi, self.received.len());
             return;
         }
 
-        if self.shards[esi as usize].is_some() {
+        if self.received[esi as usize] {
             return;
         }
 
-        self.shards[esi as usize] = Some(encoding_symbol.to_vec());
+        if encoding_symbol.len() > self.encoding_symbol_length {
+            log::error!(
+                "Symbol length {} > {}",
+                encoding_symbol.len(),
+                self.encoding_symbol_length
+            );
+            return;
+        }
+
+        // Symbols are copied directly at their position inside the source block
+        if self.data.is_empty() {
+            self.data
+                .resize(self.received.len() * self.encoding_symbol_length, 0);
+        }
+
+        let offset = esi as usize * self.encoding_symbol_length;
+        let end = offset + encoding_symbol.len();
+        self.data[offset..end].copy_from_slice(encoding_symbol);
+        self.data_length = self.data_length.max(end);
+        self.received[esi as usize] = true;
         self.nb_symbols += 1;
     }
 
     fn can_decode(&self) -> bool {
-        self.nb_symbols == self.shards.len()
+        self.nb_symbols == self.received.len()
     }
 
     fn decode(&mut self) -> bool {
-        if self.data.is_some() {
-            return true;
-        }
-
-        if !self.can_decode() {
-            return false;
-        }
-
-        let mut output = Vec::new();
-        for shard in &self.shards {
-            output.extend(shard.as_ref().unwrap());
-        }
-
-        self.data = Some(output);
-        true
+        self.can_decode()
     }
 
     fn source_block(&self) -> crate::error::Result<&[u8]> {
-        match self.data.as_ref() {
-            Some(e) => Ok(e),
-            None => Err(FluteError::new("Block not decoded")),
+        match self.can_decode() {
+            true => Ok(&self.data[..self.data_length]),
+            false => Err(FluteError::new("Block not decoded")),
         }
     }
 }
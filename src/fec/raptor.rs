@@ -17,7 +17,8 @@ impl RaptorEncoder {
 
 impl FecEncoder for RaptorEncoder {
     fn encode(&self, data: &[u8]) -> Result<Vec<Box<dyn super::FecShard>>> {
-        let mut encoder = raptor_code::SourceBlockEncoder::new(data, self.nb_source_symbols);
+        let mut encoder = raptor_code::SourceBlockEncoder::new(data, self.nb_source_symbols)
+            .map_err(FluteError::new)?;
         let nb_source_symbols = encoder.nb_source_symbols() as usize;
         let n = nb_source_symbols + self.nb_parity_symbols;
 
@@ -42,6 +43,7 @@ impl FecEncoder for RaptorEncoder {
 
 pub struct RaptorDecoder {
     source_block_size: usize,
+    encoding_symbol_length: usize,
     decoder: raptor_code::SourceBlockDecoder,
     data: Option<Vec<u8>>,
 }
@@ -56,6 +58,7 @@ impl RaptorDecoder {
         RaptorDecoder {
             decoder: raptor_code::SourceBlockDecoder::new(nb_source_symbols),
             source_block_size,
+            encoding_symbol_length: num_integer::div_ceil(source_block_size, nb_source_symbols),
             data: None,
         }
     }
@@ -73,6 +76,18 @@ impl FecDecoder for RaptorDecoder {
             self.source_block_size
         );
 
+        // Source block is partitioned in symbols of `encoding_symbol_length` or `encoding_symbol_length - 1` bytes
+        if encoding_symbol.len() != self.encoding_symbol_length
+            && encoding_symbol.len() + 1 != self.encoding_symbol_length
+        {
+            log::warn!(
+                "Encoding symbol length {} does not match the expected length {}",
+                encoding_symbol.len(),
+                self.encoding_symbol_length
+            );
+            return;
+        }
+
         self.decoder.push_encoding_symbol(encoding_symbol, esi)
     }
 
@@ -11,6 +11,7 @@ pub struct RaptorQEncoder {
 #[derive(Debug)]
 struct RaptorFecShard {
     pkt: raptorq::EncodingPacket,
+    #[allow(dead_code)]
     shard_type: ShardType,
 }
 
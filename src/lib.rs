@@ -221,6 +221,7 @@
 //! use flute::sender::Sender;
 //! use flute::sender::ObjectDesc;
 //! use flute::sender::TargetAcquisition;
+//! use flute::sender::SenderActivity;
 //! use flute::core::lct::Cenc;
 //! use flute::core::UDPEndpoint;
 //! use std::net::UdpSocket;
@@ -250,12 +251,19 @@
 //! // Always call publish after adding objects
 //! sender.publish(SystemTime::now());
 //!
-//! // Send FLUTE packets over UDP/IP
-//! while sender.nb_objects() > 0  {
-//!     if let Some(pkt) = sender.read(SystemTime::now()) {
-//!         udp_socket.send(&pkt).unwrap();
-//!     } else {
-//!        std::thread::sleep(std::time::Duration::from_millis(1));
+//! // Send FLUTE packets over UDP/IP, sleep until the next packet is ready
+//! loop {
+//!     let now = SystemTime::now();
+//!     match sender.next_activity(now) {
+//!         SenderActivity::PacketReady => {
+//!             if let Some(pkt) = sender.read(now) {
+//!                 udp_socket.send(&pkt).unwrap();
+//!             }
+//!         }
+//!         SenderActivity::WaitUntil(time) => {
+//!             std::thread::sleep(time.duration_since(now).unwrap_or_default())
+//!         }
+//!         SenderActivity::Idle => break,
 //!     }
 //! }
 //!```
@@ -269,6 +277,7 @@
 //! use flute::sender::Sender;
 //! use flute::sender::ObjectDesc;
 //! use flute::sender::TargetAcquisition;
+//! use flute::sender::SenderActivity;
 //! use flute::core::lct::Cenc;
 //! use flute::core::UDPEndpoint;
 //! use std::net::UdpSocket;
@@ -299,12 +308,19 @@
 //! // Always call publish after adding objects
 //! sender.publish(SystemTime::now());
 //! 
-//! // Send FLUTE packets over UDP/IP
-//! while sender.nb_objects() > 0  {
-//!     if let Some(pkt) = sender.read(SystemTime::now()) {
-//!         udp_socket.send(&pkt).unwrap();
-//!     } else {
-//!        std::thread::sleep(std::time::Duration::from_millis(1));
+//! // Send FLUTE packets over UDP/IP, sleep until the next packet is ready
+//! loop {
+//!     let now = SystemTime::now();
+//!     match sender.next_activity(now) {
+//!         SenderActivity::PacketReady => {
+//!             if let Some(pkt) = sender.read(now) {
+//!                 udp_socket.send(&pkt).unwrap();
+//!             }
+//!         }
+//!         SenderActivity::WaitUntil(time) => {
+//!             std::thread::sleep(time.duration_since(now).unwrap_or_default())
+//!         }
+//!         SenderActivity::Idle => break,
 //!     }
 //! }
 //!```
@@ -326,11 +342,13 @@ pub mod core {
 
     /// ALC packets
     pub mod alc {
+        pub use crate::common::alc::alc_pkt_max_payload_size;
         pub use crate::common::alc::get_sender_current_time;
         pub use crate::common::alc::parse_alc_pkt;
         pub use crate::common::alc::parse_payload_id;
         pub use crate::common::alc::AlcPkt;
         pub use crate::common::alc::PayloadID;
+        pub use crate::common::alcbuilder::PacketBuilder;
     }
 
     /// LCT packets
@@ -342,11 +360,39 @@ pub mod core {
         pub use crate::common::lct::LCTHeader;
     }
 
+    /// Block partitioning
+    pub mod partition {
+        pub use crate::common::partition::block_length;
+        pub use crate::common::partition::simulate;
+        pub use crate::common::partition::BlockLayout;
+    }
+
+    /// Content-Location of the objects
+    pub mod content_location {
+        pub use crate::common::contentlocation::normalize;
+    }
+
+    /// Encryption of the content of the objects
+    pub mod cipher {
+        #[cfg(feature = "crypto")]
+        pub use crate::common::cipher::Aes128GcmCipher;
+        pub use crate::common::cipher::DecipherRegistry;
+        pub use crate::common::cipher::ObjectCipher;
+        pub use crate::common::cipher::ObjectDecipher;
+    }
+
     pub use crate::common::oti::FECEncodingID;
     pub use crate::common::oti::Oti;
     pub use crate::common::udpendpoint::UDPEndpoint;
 }
 
+/// FLUTE Session Description (SDP)
+#[cfg(feature = "sdp")]
+pub mod sdp {
+    pub use crate::common::sdp::FluteSessionParams;
+    pub use crate::common::sdp::SessionDescription;
+}
+
 #[cfg(feature = "python")]
 mod py;
 
@@ -53,4 +53,29 @@ impl Config {
         self.0.object_timeout = value.map(|timeout| std::time::Duration::from_millis(timeout));
         Ok(())
     }
+
+    #[getter]
+    fn get_source_block_timeout_ms(&self) -> PyResult<Option<u64>> {
+        Ok(self
+            .0
+            .source_block_timeout
+            .map(|timeout| timeout.as_millis() as u64))
+    }
+
+    #[setter]
+    fn set_source_block_timeout_ms(&mut self, value: Option<u64>) -> PyResult<()> {
+        self.0.source_block_timeout = value.map(std::time::Duration::from_millis);
+        Ok(())
+    }
+
+    #[getter]
+    fn get_fdt_timeout_ms(&self) -> PyResult<Option<u64>> {
+        Ok(self.0.fdt_timeout.map(|timeout| timeout.as_millis() as u64))
+    }
+
+    #[setter]
+    fn set_fdt_timeout_ms(&mut self, value: Option<u64>) -> PyResult<()> {
+        self.0.fdt_timeout = value.map(std::time::Duration::from_millis);
+        Ok(())
+    }
 }
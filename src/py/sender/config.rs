@@ -1,4 +1,5 @@
 use pyo3::{exceptions::PyTypeError, prelude::*};
+use std::collections::BTreeMap;
 
 #[pyclass]
 #[derive(Debug)]
@@ -75,6 +76,45 @@ impl Config {
         Ok(())
     }
 
+    #[getter]
+    pub fn get_fdt_carousel_ms(&self) -> PyResult<u64> {
+        Ok(self.0.fdt_carousel.as_millis() as u64)
+    }
+
+    #[setter]
+    pub fn set_fdt_carousel_ms(&mut self, value: u64) -> PyResult<()> {
+        self.0.fdt_carousel = std::time::Duration::from_millis(value);
+        Ok(())
+    }
+
+    /// Priority queues, as a dict mapping each priority to its max number of files transferred in parallel
+    #[getter]
+    pub fn get_priority_queues(&self) -> PyResult<BTreeMap<u32, u32>> {
+        Ok(self
+            .0
+            .priority_queues
+            .iter()
+            .map(|(priority, queue)| (*priority, queue.multiplex_files))
+            .collect())
+    }
+
+    #[setter]
+    pub fn set_priority_queues(&mut self, value: BTreeMap<u32, u32>) -> PyResult<()> {
+        if value.is_empty() {
+            return Err(PyTypeError::new_err(
+                "At least one priority queue is required",
+            ));
+        }
+
+        self.0.priority_queues = value
+            .into_iter()
+            .map(|(priority, multiplex_files)| {
+                (priority, crate::sender::PriorityQueue::new(multiplex_files))
+            })
+            .collect();
+        Ok(())
+    }
+
     #[getter]
     pub fn get_interleave_blocks(&self) -> PyResult<u8> {
         Ok(self.0.interleave_blocks)
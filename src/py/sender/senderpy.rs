@@ -1,6 +1,7 @@
 use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
-use std::time::SystemTime;
+use pyo3::types::PyBytes;
+use std::time::{Duration, SystemTime};
 
 use super::config;
 use super::oti;
@@ -9,6 +10,45 @@ use super::oti;
 #[derive(Debug)]
 pub struct Sender(crate::sender::Sender);
 
+/// Transfer parameters of an object, shared by `add_file()` and `add_object_bytes()`
+struct TransferParams {
+    cenc: crate::core::lct::Cenc,
+    max_transfer_count: u32,
+    carousel_delay: Option<Duration>,
+    target_acquisition: Option<crate::sender::TargetAcquisition>,
+    groups: Option<Vec<String>>,
+    oti: Option<crate::core::Oti>,
+}
+
+impl TransferParams {
+    fn new(
+        cenc: u8,
+        max_transfer_count: u32,
+        carousel_delay_ms: Option<u64>,
+        target_acquisition_ms: Option<u64>,
+        groups: Option<Vec<String>>,
+        oti: Option<&oti::Oti>,
+    ) -> PyResult<Self> {
+        let cenc = cenc
+            .try_into()
+            .map_err(|_| PyTypeError::new_err("Unknown cenc"))?;
+        Ok(Self {
+            cenc,
+            max_transfer_count,
+            carousel_delay: carousel_delay_ms.map(Duration::from_millis),
+            target_acquisition: target_acquisition_ms.map(|ms| {
+                crate::sender::TargetAcquisition::WithinDuration(Duration::from_millis(ms))
+            }),
+            groups,
+            oti: oti.map(|o| o.0.clone()),
+        })
+    }
+}
+
+fn parse_url(url: &str) -> PyResult<url::Url> {
+    url::Url::parse(url).map_err(|e| PyTypeError::new_err(e.to_string()))
+}
+
 #[pymethods]
 impl Sender {
     #[new]
@@ -31,32 +71,71 @@ impl Sender {
         content_location: &str,
         oti: Option<&oti::Oti>,
     ) -> PyResult<u128> {
-        let content_location =
-            url::Url::parse(content_location).map_err(|e| PyTypeError::new_err(e.to_string()))?;
-
-        let oti = oti.map(|o| o.0.clone());
-        let object = crate::sender::ObjectDesc::create_from_buffer(
+        self.add_object_bytes(
             content,
+            content_location,
             content_type,
-            &content_location,
+            0,
+            0,
             1,
             None,
             None,
             None,
+            oti,
+        )
+    }
+
+    /// Add an object from a buffer, return its TOI
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (content, content_location, content_type="application/octet-stream", *, cenc=0, priority=0, max_transfer_count=1, carousel_delay_ms=None, target_acquisition_ms=None, groups=None, oti=None))]
+    fn add_object_bytes(
+        &mut self,
+        content: &[u8],
+        content_location: &str,
+        content_type: &str,
+        cenc: u8,
+        priority: u32,
+        max_transfer_count: u32,
+        carousel_delay_ms: Option<u64>,
+        target_acquisition_ms: Option<u64>,
+        groups: Option<Vec<String>>,
+        oti: Option<&oti::Oti>,
+    ) -> PyResult<u128> {
+        let content_location = parse_url(content_location)?;
+        let params = TransferParams::new(
+            cenc,
+            max_transfer_count,
+            carousel_delay_ms,
+            target_acquisition_ms,
+            groups,
+            oti,
+        )?;
+
+        let object = crate::sender::ObjectDesc::create_from_buffer(
+            content,
+            content_type,
+            &content_location,
+            params.max_transfer_count,
+            params.carousel_delay,
+            params.target_acquisition,
             None,
-            crate::core::lct::Cenc::Null,
+            params.groups,
+            params.cenc,
             true,
-            oti,
+            params.oti,
             true,
         )
         .map_err(|e| PyTypeError::new_err(e.0.to_string()))?;
 
         self.0
-            .add_object(0, object)
+            .add_object(priority, object)
             .map_err(|e| PyTypeError::new_err(e.0.to_string()))
     }
 
-    #[pyo3(signature = (filepath, cenc, content_type, content_location=None, oti=None))]
+    /// Add an object from a file, return its TOI
+    /// The content of the file is loaded in RAM, unless `cache_in_ram` is `False`: it is then read during the transfer
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (filepath, cenc, content_type, content_location=None, oti=None, *, priority=0, max_transfer_count=1, carousel_delay_ms=None, target_acquisition_ms=None, groups=None, cache_in_ram=true))]
     fn add_file(
         &mut self,
         filepath: &str,
@@ -64,39 +143,42 @@ impl Sender {
         content_type: &str,
         content_location: Option<&str>,
         oti: Option<&oti::Oti>,
+        priority: u32,
+        max_transfer_count: u32,
+        carousel_delay_ms: Option<u64>,
+        target_acquisition_ms: Option<u64>,
+        groups: Option<Vec<String>>,
+        cache_in_ram: bool,
     ) -> PyResult<u128> {
-        let cenc = cenc
-            .try_into()
-            .map_err(|_| PyTypeError::new_err("Unknown cenc"))?;
-        let content_location = content_location.map(|content_location| {
-            url::Url::parse(content_location).map_err(|e| PyTypeError::new_err(e.to_string()))
-        });
-        let content_location = match content_location {
-            Some(Err(e)) => return Err(PyTypeError::new_err(e)),
-            Some(Ok(url)) => Some(url),
-            None => None,
-        };
-
-        let oti = oti.map(|o| o.0.clone());
+        let content_location = content_location.map(parse_url).transpose()?;
+        let params = TransferParams::new(
+            cenc,
+            max_transfer_count,
+            carousel_delay_ms,
+            target_acquisition_ms,
+            groups,
+            oti,
+        )?;
+
         let object = crate::sender::ObjectDesc::create_from_file(
             std::path::Path::new(filepath),
             content_location.as_ref(),
             content_type,
-            true,
-            1,
-            None,
-            None,
+            cache_in_ram,
+            params.max_transfer_count,
+            params.carousel_delay,
+            params.target_acquisition,
             None,
-            None,
-            cenc,
+            params.groups,
+            params.cenc,
             true,
-            oti,
+            params.oti,
             true,
         )
         .map_err(|e| PyTypeError::new_err(e.0.to_string()))?;
 
         self.0
-            .add_object(0, object)
+            .add_object(priority, object)
             .map_err(|e| PyTypeError::new_err(e.0.to_string()))
     }
 
@@ -104,17 +186,38 @@ impl Sender {
         self.0.remove_object(toi)
     }
 
+    /// Restart the transfer of an object now, return `False` if the object is not in the FDT
+    fn rewind_object(&mut self, toi: u128) -> bool {
+        self.0.rewind_object(toi, SystemTime::now())
+    }
+
+    /// Number of times an object has been transferred, `None` if the object is not in the FDT
+    fn nb_transfers(&mut self, toi: u128) -> Option<u64> {
+        self.0.nb_transfers(toi)
+    }
+
     fn nb_objects(&self) -> usize {
         self.0.nb_objects()
     }
 
+    fn fdt_xml_data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self
+            .0
+            .fdt_xml_data(SystemTime::now())
+            .map_err(|e| PyTypeError::new_err(e.0.to_string()))?;
+        Ok(PyBytes::new(py, &data))
+    }
+
     fn publish(&mut self) -> PyResult<()> {
         self.0
             .publish(SystemTime::now())
             .map_err(|e| PyTypeError::new_err(e.0.to_string()))
     }
 
-    fn read(&mut self) -> PyResult<Option<Vec<u8>>> {
-        Ok(self.0.read(SystemTime::now()))
+    fn read<'py>(&mut self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyBytes>>> {
+        Ok(self
+            .0
+            .read(SystemTime::now())
+            .map(|data| PyBytes::new(py, &data)))
     }
 }
@@ -8,13 +8,22 @@ use crate::fec::nocode;
 use crate::fec::rscodec;
 use crate::fec::FecDecoder;
 use crate::tools::error::Result;
+use std::collections::HashSet;
+use std::time::SystemTime;
 
 #[derive(Debug)]
 pub struct BlockDecoder {
     pub completed: bool,
     pub initialized: bool,
     pub block_size: usize,
+    /// `true` when the decoding of the block started before the FDT was attached to the object
+    pub received_before_fdt: bool,
     decoder: Option<Box<dyn FecDecoder>>,
+    nb_source_symbols: u32,
+    nb_source_symbols_received: u32,
+    nb_repair_symbols_received: u32,
+    received_esi: HashSet<u32>,
+    last_symbol_received: Option<SystemTime>,
 }
 
 impl BlockDecoder {
@@ -24,6 +33,12 @@ impl BlockDecoder {
             initialized: false,
             decoder: None,
             block_size: 0,
+            received_before_fdt: false,
+            last_symbol_received: None,
+            nb_source_symbols: 0,
+            nb_source_symbols_received: 0,
+            nb_repair_symbols_received: 0,
+            received_esi: HashSet::new(),
         }
     }
 
@@ -40,7 +55,10 @@ impl BlockDecoder {
 
         match oti.fec_encoding_id {
             oti::FECEncodingID::NoCode => {
-                let codec = nocode::NoCodeDecoder::new(nb_source_symbols as usize);
+                let codec = nocode::NoCodeDecoder::new(
+                    nb_source_symbols as usize,
+                    oti.encoding_symbol_length as usize,
+                );
                 self.decoder = Some(Box::new(codec));
             }
             oti::FECEncodingID::ReedSolomonGF28 => {
@@ -64,6 +82,20 @@ impl BlockDecoder {
             }
             oti::FECEncodingID::RaptorQ => {
                 if let Some(SchemeSpecific::RaptorQ(scheme)) = oti.scheme_specific.as_ref() {
+                    // RFC 6330 supports source blocks of up to 56403 source symbols
+                    if nb_source_symbols == 0 || nb_source_symbols > 56403 {
+                        return Err(FluteError::new(format!(
+                            "RaptorQ source block of {} source symbols is not supported",
+                            nb_source_symbols
+                        )));
+                    }
+
+                    if scheme.symbol_alignment == 0
+                        || oti.encoding_symbol_length % scheme.symbol_alignment as u16 != 0
+                    {
+                        return Err(FluteError::new("RaptorQ symbol alignment is not valid"));
+                    }
+
                     let codec = fec::raptorq::RaptorQDecoder::new(
                         sbn,
                         nb_source_symbols as usize,
@@ -80,6 +112,14 @@ impl BlockDecoder {
                     return Err(FluteError::new("Raptor Scheme not found"));
                 }
 
+                // RFC 5053 supports source blocks of up to 8192 source symbols
+                if nb_source_symbols == 0 || nb_source_symbols > 8192 {
+                    return Err(FluteError::new(format!(
+                        "Raptor source block of {} source symbols is not supported",
+                        nb_source_symbols
+                    )));
+                }
+
                 let codec = fec::raptor::RaptorDecoder::new(nb_source_symbols as usize, block_size);
                 self.decoder = Some(Box::new(codec));
             }
@@ -87,6 +127,7 @@ impl BlockDecoder {
 
         self.initialized = true;
         self.block_size = block_size;
+        self.nb_source_symbols = nb_source_symbols;
         Ok(())
     }
 
@@ -101,15 +142,59 @@ impl BlockDecoder {
     pub fn deallocate(&mut self) {
         self.decoder = None;
         self.block_size = 0;
+        self.received_esi = HashSet::new();
+    }
+
+    /// Register the ESI of a received symbol, return `false` if this symbol is a duplicate
+    pub fn register_symbol(&mut self, esi: u32) -> bool {
+        self.received_esi.insert(esi)
+    }
+
+    /// Return `true` if some source symbols have been recovered with repair symbols
+    pub fn is_decoded_with_fec(&self) -> bool {
+        self.completed && self.nb_source_symbols_received < self.nb_source_symbols
+    }
+
+    /// Longest run of consecutive ESIs missing before the highest ESI received
+    pub fn max_burst_loss(&self) -> u32 {
+        let mut esis: Vec<u32> = self.received_esi.iter().copied().collect();
+        esis.sort_unstable();
+        let first = esis.first().copied().unwrap_or_default();
+        esis.windows(2)
+            .map(|esi| esi[1] - esi[0] - 1)
+            .fold(first, u32::max)
+    }
+
+    /// Time of the last symbol pushed to the block, `None` if no symbol has been pushed
+    pub fn last_symbol_received(&self) -> Option<SystemTime> {
+        self.last_symbol_received
     }
 
-    pub fn push(&mut self, pkt: &alc::AlcPkt, payload_id: &alc::PayloadID) {
+    pub fn nb_source_symbols(&self) -> u32 {
+        self.nb_source_symbols
+    }
+
+    pub fn nb_source_symbols_received(&self) -> u32 {
+        self.nb_source_symbols_received
+    }
+
+    pub fn nb_repair_symbols_received(&self) -> u32 {
+        self.nb_repair_symbols_received
+    }
+
+    pub fn push(&mut self, pkt: &alc::AlcPkt, payload_id: &alc::PayloadID, now: SystemTime) {
         debug_assert!(self.initialized);
 
         if self.completed {
             return;
         }
 
+        self.last_symbol_received = Some(now);
+        match payload_id.esi < self.nb_source_symbols {
+            true => self.nb_source_symbols_received += 1,
+            false => self.nb_repair_symbols_received += 1,
+        }
+
         let payload = &pkt.data[pkt.data_payload_offset..];
         let decoder = self.decoder.as_mut().unwrap();
         decoder.push_symbol(payload, payload_id.esi);
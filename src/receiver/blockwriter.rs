@@ -1,27 +1,41 @@
+use std::sync::Arc;
 use std::time::SystemTime;
 
 use base64::Engine;
 
 use crate::error::{FluteError, Result};
 
+use crate::common::cipher::ObjectDecipher;
 use crate::common::lct;
+use crate::tools::crc32::crc32;
+use crate::tools::etag::{ETagAlgorithm, ETagHasher};
 
 use super::{
     blockdecoder::BlockDecoder,
-    uncompress::{Decompress, DecompressGzip},
+    uncompress::{self, Decompress, DecompressGzip},
     uncompress::{DecompressDeflate, DecompressZlib},
     writer::ObjectWriter,
 };
 
 pub struct BlockWriter {
     sbn: u32,
+    transfer_length: usize,
     bytes_left: usize,
+    bytes_output: usize,
     content_length_left: Option<usize>,
     cenc: lct::Cenc,
+    autodetect_cenc: bool,
+    cenc_autodetected: bool,
     decoder: Option<Box<dyn Decompress>>,
     buffer: Vec<u8>,
     md5_context: Option<md5::Context>,
     md5: Option<String>,
+    etag_hasher: Option<ETagHasher>,
+    etag: Option<String>,
+    block_crcs: Option<Vec<u32>>,
+    decipher: Option<Arc<dyn ObjectDecipher>>,
+    encrypted: Vec<u8>,
+    blocked: bool,
 }
 
 impl std::fmt::Debug for BlockWriter {
@@ -34,6 +48,12 @@ impl std::fmt::Debug for BlockWriter {
             .field("buffer", &self.buffer)
             .field("md5_context", &self.md5_context.is_some())
             .field("md5", &self.md5)
+            .field("etag_hasher", &self.etag_hasher)
+            .field("etag", &self.etag)
+            .field("block_crcs", &self.block_crcs)
+            .field("decipher", &self.decipher)
+            .field("encrypted", &self.encrypted.len())
+            .field("blocked", &self.blocked)
             .finish()
     }
 }
@@ -44,12 +64,18 @@ impl BlockWriter {
         content_length: Option<usize>,
         cenc: lct::Cenc,
         md5: bool,
+        etag: Option<ETagAlgorithm>,
+        autodetect_cenc: bool,
     ) -> BlockWriter {
         BlockWriter {
             sbn: 0,
+            transfer_length,
             bytes_left: transfer_length,
+            bytes_output: 0,
             content_length_left: content_length,
             cenc,
+            autodetect_cenc,
+            cenc_autodetected: false,
             decoder: None,
             buffer: Vec::new(),
             md5_context: match md5 {
@@ -57,9 +83,33 @@ impl BlockWriter {
                 false => None,
             },
             md5: None,
+            etag_hasher: etag.map(ETagHasher::new),
+            etag: None,
+            block_crcs: None,
+            decipher: None,
+            encrypted: Vec::new(),
+            blocked: false,
         }
     }
 
+    /// Decrypt the object with `decipher` once all its blocks are written
+    pub fn set_decipher(&mut self, decipher: Option<Arc<dyn ObjectDecipher>>) {
+        self.decipher = decipher;
+    }
+
+    /// Keep the CRC-32 of the data of each block written, for diagnostics
+    pub fn set_block_crcs(&mut self, enable: bool) {
+        self.block_crcs = match enable {
+            true => Some(Vec::new()),
+            false => None,
+        };
+    }
+
+    /// CRC-32 of the blocks written so far, indexed by SBN
+    pub fn block_crcs(&self) -> Option<&[u32]> {
+        self.block_crcs.as_deref()
+    }
+
     pub fn check_md5(&self, md5: &str) -> bool {
         self.md5.as_ref().map(|m| m.eq(md5)).unwrap_or(true)
     }
@@ -68,6 +118,14 @@ impl BlockWriter {
         self.md5.as_deref()
     }
 
+    pub fn check_etag(&self, etag: &str) -> bool {
+        self.etag.as_ref().map(|e| e.eq(etag)).unwrap_or(true)
+    }
+
+    pub fn get_etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
     pub fn write(
         &mut self,
         sbn: u32,
@@ -78,6 +136,17 @@ impl BlockWriter {
         if self.sbn != sbn {
             return Ok(false);
         }
+
+        // The block is written again later when the writer is not ready
+        self.blocked = false;
+        if let Err(e) = writer.ready(now) {
+            if e.kind() == std::io::ErrorKind::WouldBlock {
+                self.blocked = true;
+                return Ok(false);
+            }
+            return Err(e);
+        }
+
         debug_assert!(block.completed);
         let data = block.source_block()?;
 
@@ -87,10 +156,14 @@ impl BlockWriter {
             false => &data[..self.bytes_left],
         };
 
-        if self.cenc == lct::Cenc::Null {
-            self.write_pkt_cenc_null(data, writer, now);
-        } else {
-            self.decode_write_pkt(data, writer, now)?;
+        if let Some(block_crcs) = self.block_crcs.as_mut() {
+            block_crcs.push(crc32(data));
+        }
+
+        match self.decipher.is_some() {
+            // The encrypted content can only be decrypted as a whole
+            true => self.encrypted.extend_from_slice(data),
+            false => self.write_content(self.sbn == 0, data, writer, now)?,
         }
 
         debug_assert!(data.len() <= self.bytes_left);
@@ -99,6 +172,12 @@ impl BlockWriter {
         self.sbn += 1;
 
         if self.is_completed() {
+            if let Some(decipher) = self.decipher.as_ref() {
+                let content = decipher.transform(&self.encrypted)?;
+                self.encrypted = Vec::new();
+                self.write_content(true, &content, writer, now)?;
+            }
+
             // All blocks have been received -> flush the decoder
             if self.decoder.is_some() {
                 self.decoder.as_mut().unwrap().finish();
@@ -108,11 +187,51 @@ impl BlockWriter {
             let output = self.md5_context.take().map(|ctx| ctx.compute().0);
             self.md5 =
                 output.map(|output| base64::engine::general_purpose::STANDARD.encode(output));
+            self.etag = self.etag_hasher.take().map(|hasher| hasher.compute());
         }
 
         Ok(true)
     }
 
+    /// Write the (decrypted) content, decompressed if needed
+    fn write_content(
+        &mut self,
+        first: bool,
+        data: &[u8],
+        writer: &dyn ObjectWriter,
+        now: SystemTime,
+    ) -> Result<()> {
+        if first && self.cenc == lct::Cenc::Null && self.autodetect_cenc {
+            self.detect_cenc(data);
+        }
+
+        if self.cenc_autodetected {
+            // Content-MD5 and Content-Length of the FDT describe the transferred (compressed) content
+            if let Some(ctx) = self.md5_context.as_mut() {
+                ctx.consume(data)
+            }
+            if let Some(hasher) = self.etag_hasher.as_mut() {
+                hasher.consume(data)
+            }
+        }
+
+        if self.cenc == lct::Cenc::Null {
+            self.write_pkt_cenc_null(data, writer, now);
+            Ok(())
+        } else {
+            self.decode_write_pkt(data, writer, now)
+        }
+    }
+
+    fn detect_cenc(&mut self, data: &[u8]) {
+        if let Some(cenc) = uncompress::detect_cenc(data) {
+            log::warn!("CENC is Null but content is detected as {:?}", cenc);
+            self.cenc = cenc;
+            self.cenc_autodetected = true;
+            self.content_length_left = None;
+        }
+    }
+
     fn init_decoder(&mut self, data: &[u8]) {
         debug_assert!(self.decoder.is_none());
         self.decoder = match self.cenc {
@@ -128,7 +247,11 @@ impl BlockWriter {
         if let Some(ctx) = self.md5_context.as_mut() {
             ctx.consume(data)
         }
+        if let Some(hasher) = self.etag_hasher.as_mut() {
+            hasher.consume(data)
+        }
         writer.write(data, now);
+        self.bytes_output += data.len();
     }
 
     fn decode_write_pkt(
@@ -173,11 +296,17 @@ impl BlockWriter {
                 return Ok(());
             }
 
-            if let Some(ctx) = self.md5_context.as_mut() {
-                ctx.consume(&self.buffer[..size])
+            if !self.cenc_autodetected {
+                if let Some(ctx) = self.md5_context.as_mut() {
+                    ctx.consume(&self.buffer[..size])
+                }
+                if let Some(hasher) = self.etag_hasher.as_mut() {
+                    hasher.consume(&self.buffer[..size])
+                }
             }
 
             writer.write(&self.buffer[..size], now);
+            self.bytes_output += size;
 
             if let Some(content_length_left) = self.content_length_left.as_mut() {
                 *content_length_left = content_length_left.saturating_sub(size);
@@ -188,10 +317,39 @@ impl BlockWriter {
         }
     }
 
-    pub fn left(&self) -> usize {
+    /// Number of bytes of the transferred object that have been written
+    pub fn bytes_written(&self) -> usize {
+        self.transfer_length - self.bytes_left
+    }
+
+    /// Number of bytes written to the `ObjectWriter`, after decompression
+    pub fn bytes_output(&self) -> usize {
+        self.bytes_output
+    }
+
+    /// Number of bytes of the transferred object that are not written yet
+    pub fn bytes_remaining(&self) -> usize {
         self.bytes_left
     }
 
+    /// Fraction of the transferred object that has been written, in `[0.0, 1.0]`
+    pub fn progress_fraction(&self) -> f64 {
+        if self.transfer_length == 0 {
+            return 1.0;
+        }
+        self.bytes_written() as f64 / self.transfer_length as f64
+    }
+
+    /// `true` when the last block could not be written because the `ObjectWriter` was not ready
+    pub fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
+    /// Source block number of the next block to write
+    pub fn next_sbn(&self) -> u32 {
+        self.sbn
+    }
+
     pub fn is_completed(&self) -> bool {
         self.bytes_left == 0
     }
@@ -0,0 +1,90 @@
+use crate::common::fdtinstance::{FdtInstance, File};
+use std::collections::BTreeMap;
+
+///
+/// Summary of a file announced in the FDT
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSummary {
+    /// Content-Location of the file
+    pub content_location: String,
+    /// TOI of the file
+    pub toi: u128,
+    /// Content-Length of the file, or its Transfer-Length when the Content-Length is missing
+    pub size: u64,
+    /// Content-MD5 of the file
+    pub md5: Option<String>,
+    /// File-ETag of the file
+    pub etag: Option<String>,
+    /// Groups of the file and of the FDT-Instance
+    pub groups: Option<Vec<String>>,
+}
+
+///
+/// Difference between two consecutive FDT instances, files are identified by their Content-Location
+///
+/// The expiration date of the FDT is not taken into account
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FdtDiff {
+    /// Files announced in the new FDT that were not in the previous one
+    pub added: Vec<FileSummary>,
+    /// Files of the previous FDT that are not announced anymore
+    pub removed: Vec<FileSummary>,
+    /// Files announced in both FDT with different attributes (old, new)
+    pub modified: Vec<(FileSummary, FileSummary)>,
+}
+
+impl FileSummary {
+    fn new(file: &File, fdt: &FdtInstance) -> Self {
+        let mut groups = fdt.group.clone().unwrap_or_default();
+        if let Some(group) = file.group.as_ref() {
+            groups.extend(group.iter().cloned());
+        }
+
+        FileSummary {
+            content_location: file.content_location.clone(),
+            toi: file.toi.parse().unwrap_or_default(),
+            size: file.content_length.unwrap_or(file.get_transfer_length()),
+            md5: file.content_md5.clone(),
+            etag: file.file_etag.clone(),
+            groups: match groups.is_empty() {
+                true => None,
+                false => Some(groups),
+            },
+        }
+    }
+}
+
+impl FdtDiff {
+    /// Compute the difference between the previous FDT and the new one
+    /// When there is no previous FDT, all the files of the new FDT are added
+    pub(crate) fn new(previous: Option<&FdtInstance>, current: &FdtInstance) -> Self {
+        let previous_files = previous.map(Self::summaries).unwrap_or_default();
+        let mut current_files = Self::summaries(current);
+
+        let mut diff = FdtDiff::default();
+        for (content_location, old) in previous_files {
+            match current_files.remove(&content_location) {
+                None => diff.removed.push(old),
+                Some(new) if new != old => diff.modified.push((old, new)),
+                Some(_) => {}
+            }
+        }
+        diff.added = current_files.into_values().collect();
+        diff
+    }
+
+    /// Return `true` if both FDT announce the same files
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    fn summaries(fdt: &FdtInstance) -> BTreeMap<String, FileSummary> {
+        fdt.file
+            .iter()
+            .flatten()
+            .map(|file| (file.content_location.clone(), FileSummary::new(file, fdt)))
+            .collect()
+    }
+}
@@ -4,7 +4,11 @@ use crate::common::udpendpoint::UDPEndpoint;
 use crate::common::{alc, fdtinstance::FdtInstance, lct};
 use crate::{receiver::writer::ObjectMetadata, tools};
 use crate::{receiver::writer::ObjectWriter, tools::error::Result};
-use std::{cell::RefCell, rc::Rc, time::SystemTime};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum FDTState {
@@ -19,10 +23,9 @@ pub struct FdtReceiver {
     obj: Option<Box<objectreceiver::ObjectReceiver>>,
     inner: Rc<RefCell<FdtWriterInner>>,
     fdt_instance: Option<FdtInstance>,
-    sender_current_time_offset: Option<std::time::Duration>,
-    sender_current_time_late: bool,
     pub ext_time: Option<std::time::SystemTime>,
     pub reception_start_time: SystemTime,
+    last_activity: SystemTime,
     enable_expired_check: bool,
     meta: Option<ObjectMetadata>,
 }
@@ -34,12 +37,9 @@ impl std::fmt::Debug for FdtReceiver {
             .field("obj", &self.obj)
             .field("inner", &self.inner)
             .field("fdt_instance", &self.fdt_instance)
-            .field(
-                "sender_current_time_offset",
-                &self.sender_current_time_offset,
-            )
-            .field("sender_current_time_late", &self.sender_current_time_late)
+            .field("ext_time", &self.ext_time)
             .field("receiver_start_time", &self.reception_start_time)
+            .field("last_activity", &self.last_activity)
             .finish()
     }
 }
@@ -87,14 +87,17 @@ impl FdtReceiver {
                 Some(fdt_id),
                 fdt_builder,
                 true,
+                false,
+                1024 * 1024,
+                None,
+                objectreceiver::DEFAULT_MAX_BLOCKS_PER_OBJECT,
                 1024 * 1024,
                 now,
             ))),
             inner: inner.clone(),
             fdt_instance: None,
-            sender_current_time_offset: None,
-            sender_current_time_late: true,
             reception_start_time: now,
+            last_activity: now,
             enable_expired_check,
             meta: None,
             ext_time: None,
@@ -102,15 +105,9 @@ impl FdtReceiver {
     }
 
     pub fn push(&mut self, pkt: &alc::AlcPkt, now: std::time::SystemTime) {
-        if let Ok(Some(res)) = alc::get_sender_current_time(pkt) {
-            self.ext_time = Some(res);
-            if res < now {
-                self.sender_current_time_late = true;
-                self.sender_current_time_offset = Some(now.duration_since(res).unwrap())
-            } else {
-                self.sender_current_time_late = false;
-                self.sender_current_time_offset = Some(res.duration_since(now).unwrap())
-            }
+        self.last_activity = now;
+        if pkt.server_time.is_some() {
+            self.ext_time = pkt.server_time;
         }
 
         if let Some(obj) = self.obj.as_mut() {
@@ -121,22 +118,18 @@ impl FdtReceiver {
                     self.meta = Some(obj.create_meta());
                     self.obj = None
                 }
-                objectreceiver::State::Interrupted => self.inner.borrow_mut().state = FDTState::Error,
+                objectreceiver::State::Interrupted => {
+                    self.inner.borrow_mut().state = FDTState::Error
+                }
                 objectreceiver::State::Error => self.inner.borrow_mut().state = FDTState::Error,
+                objectreceiver::State::Skipped => self.inner.borrow_mut().state = FDTState::Error,
             }
         }
     }
 
-    pub fn get_server_time(&self, now: std::time::SystemTime) -> std::time::SystemTime {
-        if let Some(offset) = self.sender_current_time_offset {
-            if self.sender_current_time_late {
-                return now - offset;
-            } else {
-                return now + offset;
-            }
-        }
-
-        now
+    /// Duration since the last packet of this FDT instance was received
+    pub fn last_activity_duration_since(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.last_activity).unwrap_or_default()
     }
 
     pub fn state(&self) -> FDTState {
@@ -161,25 +154,25 @@ impl FdtReceiver {
         self.meta.as_ref()
     }
 
-    pub fn update_expired_state(&self, now: SystemTime) {
+    pub fn update_expired_state(&self, server_time: SystemTime) {
         if self.state() != FDTState::Complete {
             return;
         }
 
-        if self.enable_expired_check && self.is_expired(now) {
+        if self.enable_expired_check && self.is_expired(server_time) {
             let mut inner = self.inner.borrow_mut();
             inner.state = FDTState::Expired;
         }
     }
 
-    fn is_expired(&self, now: SystemTime) -> bool {
+    fn is_expired(&self, server_time: SystemTime) -> bool {
         let inner = self.inner.borrow();
         let expires = match inner.expires {
             Some(expires) => expires,
             None => return true,
         };
 
-        self.get_server_time(now) > expires
+        server_time > expires
     }
 
     pub fn get_expiration_time(&self) -> Option<SystemTime> {
@@ -4,10 +4,12 @@
 
 mod blockdecoder;
 mod blockwriter;
+mod fdtdiff;
 mod fdtreceiver;
 mod multireceiver;
 mod objectreceiver;
 mod receiver;
+mod senderclock;
 mod tsifilter;
 mod uncompress;
 
@@ -15,8 +17,16 @@ mod uncompress;
 mod objectreceiverlogger;
 
 pub mod writer;
+pub use fdtdiff::FdtDiff;
+pub use fdtdiff::FileSummary;
 pub use multireceiver::MultiReceiver;
+pub use multireceiver::MultiReceiverConfig;
 pub use multireceiver::MultiReceiverListener;
+pub use multireceiver::ObjectCompletionEvent;
 pub use multireceiver::ReceiverEndpoint;
+pub use receiver::ClockSource;
 pub use receiver::Config;
+pub use receiver::ObjectProgressInfo;
 pub use receiver::Receiver;
+pub use receiver::SessionInfo;
+pub use receiver::SessionStats;
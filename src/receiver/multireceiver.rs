@@ -1,13 +1,18 @@
-use super::receiver::{Config, Receiver};
+use super::receiver::{
+    CleanupSchedule, Config, ObjectProgressInfo, Receiver, SessionInfo, SessionStats,
+};
 use super::tsifilter::TSIFilter;
-use super::writer::ObjectWriterBuilder;
-use crate::common::alc;
+use super::writer::{ObjectMetadata, ObjectWriterBuilder};
+#[cfg(feature = "sdp")]
+use crate::common::sdp::SessionDescription;
 use crate::common::udpendpoint::UDPEndpoint;
+use crate::common::{alc, oti};
 use crate::tools::error::Result;
 use core::fmt::Debug;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
 use std::rc::Rc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 /// Receiver endpoint
 #[derive(Debug, Hash, Eq, PartialEq, Clone)]
@@ -24,16 +29,114 @@ pub trait MultiReceiverListener {
     fn on_session_open(&self, endpoint: &ReceiverEndpoint);
     /// Called when a FLUTE session is being closed
     fn on_session_closed(&self, endpoint: &ReceiverEndpoint);
+    /// Called when a new FDT is received
+    fn on_fdt(&self, _endpoint: &ReceiverEndpoint, _fdt_xml: &str, _now: SystemTime) {}
+    /// Called when a new FDT is received, with the TOI and the metadata of all the files it announces
+    fn on_fdt_files(
+        &self,
+        _endpoint: &ReceiverEndpoint,
+        _files: &[(u128, ObjectMetadata)],
+        _now: SystemTime,
+    ) {
+    }
+}
+
+/// Object completed by a FLUTE session of the `MultiReceiver`
+#[derive(Debug, Clone)]
+pub struct ObjectCompletionEvent {
+    /// Session of the object
+    pub endpoint: ReceiverEndpoint,
+    /// TOI of the object
+    pub toi: u128,
+    /// Content-Location of the object
+    pub content_location: Option<url::Url>,
 }
 
 type MultiReceiverListenerBox = Box<dyn MultiReceiverListener>;
 
+type OnceCallback<E> = Box<dyn FnOnce(&E) + Send>;
+type ObjectsInProgress = Vec<(u128, ObjectProgressInfo)>;
+
+/// One-shot callbacks called on the next event matching their key
+struct OnceCallbacks<K, E> {
+    callbacks: Vec<(K, OnceCallback<E>)>,
+}
+
+impl<K, E> OnceCallbacks<K, E> {
+    fn new() -> Self {
+        Self {
+            callbacks: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, key: K, cb: OnceCallback<E>) {
+        self.callbacks.push((key, cb));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    /// Call and remove the callbacks whose key matches the event
+    fn notify(&mut self, event: &E, matches: impl Fn(&K) -> bool) {
+        if self.callbacks.is_empty() {
+            return;
+        }
+
+        let (matched, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.callbacks)
+            .into_iter()
+            .partition(|(key, _)| matches(key));
+        self.callbacks = pending;
+        for (_, cb) in matched {
+            cb(event);
+        }
+    }
+}
+
+impl<K, E> Debug for OnceCallbacks<K, E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "OnceCallbacks({})", self.callbacks.len())
+    }
+}
+
 impl Debug for dyn MultiReceiverListener {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "MultiReceiverListener")
     }
 }
 
+///
+/// Configuration of the `MultiReceiver`
+///
+#[derive(Debug, Clone, Default)]
+pub struct MultiReceiverConfig {
+    /// Configuration of the FLUTE sessions that have no specific configuration in `session_configs`
+    pub default_session_config: Config,
+    /// Configuration of the FLUTE sessions, per TSI.
+    /// Overrides `default_session_config`, ex: to set different timeouts for high-priority and low-priority sessions
+    pub session_configs: HashMap<u64, Config>,
+    /// Interval between two calls of `MultiReceiver::cleanup()` done automatically when packets are pushed.
+    /// None : `cleanup()` must be called by the application
+    pub auto_cleanup_interval: Option<Duration>,
+}
+
+impl MultiReceiverConfig {
+    fn session_config(&self, tsi: u64) -> Config {
+        self.session_configs
+            .get(&tsi)
+            .copied()
+            .unwrap_or(self.default_session_config)
+    }
+
+    /// Lowest cleanup interval of the sessions
+    fn cleanup_interval(&self) -> Duration {
+        self.session_configs
+            .values()
+            .map(|config| config.cleanup_interval)
+            .fold(self.default_session_config.cleanup_interval, Duration::min)
+    }
+}
+
 ///
 /// Multi-sessions FLUTE receiver
 /// Demultiplex multiple FLUTE Transport Sessions
@@ -43,10 +146,19 @@ pub struct MultiReceiver {
     alc_receiver: HashMap<ReceiverEndpoint, Box<Receiver>>,
     tsifilter: TSIFilter,
     writer: Rc<dyn ObjectWriterBuilder>,
-    config: Option<Config>,
+    config: MultiReceiverConfig,
+    last_cleanup: Option<SystemTime>,
+    cleanup_schedule: CleanupSchedule,
     enable_tsi_filtering: bool,
     listeners: HashMap<u64, MultiReceiverListenerBox>,
     listeners_id: u64,
+    on_next_session_open: OnceCallbacks<(), ReceiverEndpoint>,
+    on_next_session_closed: OnceCallbacks<u64, ReceiverEndpoint>,
+    on_next_object_completed: OnceCallbacks<Option<u128>, ObjectCompletionEvent>,
+    out_of_band_oti: HashMap<ReceiverEndpoint, oti::Oti>,
+    packet_capture: VecDeque<Vec<u8>>,
+    packet_capture_error: Option<String>,
+    endpoint_aliases: HashMap<UDPEndpoint, UDPEndpoint>,
 }
 
 impl MultiReceiver {
@@ -79,18 +191,185 @@ impl MultiReceiver {
         writer: Rc<dyn ObjectWriterBuilder>,
         config: Option<Config>,
         enable_tsi_filtering: bool,
+    ) -> MultiReceiver {
+        let config = MultiReceiverConfig {
+            default_session_config: config.unwrap_or_default(),
+            ..Default::default()
+        };
+        let mut receiver = MultiReceiver::new_with_config(writer, config);
+        receiver.enable_tsi_filtering = enable_tsi_filtering;
+        receiver
+    }
+
+    ///
+    /// Creates a new `MultiReceiver` instance with a configuration per FLUTE session
+    ///
+    /// The TSI filtering is disabled, see `set_tsi_filtering()`
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Responsible to write object to its final destination.
+    ///
+    /// * `config` - Default configuration of the FLUTE sessions and their configuration per TSI
+    ///
+    /// # Example
+    /// ```
+    /// use flute::receiver::writer::ObjectWriterBufferBuilder;
+    /// use flute::receiver::{Config, MultiReceiver, MultiReceiverConfig};
+    /// use std::rc::Rc;
+    /// use std::time::Duration;
+    ///
+    /// let mut config = MultiReceiverConfig::default();
+    /// // Session with TSI 1 is high-priority, give up its objects faster
+    /// config.session_configs.insert(1, Config {
+    ///     object_timeout: Some(Duration::from_secs(2)),
+    ///     ..Default::default()
+    /// });
+    /// config.auto_cleanup_interval = Some(Duration::from_secs(1));
+    ///
+    /// let writer = Rc::new(ObjectWriterBufferBuilder::new());
+    /// let receiver = MultiReceiver::new_with_config(writer, config);
+    /// ```
+    pub fn new_with_config(
+        writer: Rc<dyn ObjectWriterBuilder>,
+        config: MultiReceiverConfig,
     ) -> MultiReceiver {
         MultiReceiver {
             alc_receiver: HashMap::new(),
             writer,
             config,
+            last_cleanup: None,
+            cleanup_schedule: CleanupSchedule::new(),
+            enable_tsi_filtering: false,
             tsifilter: TSIFilter::new(),
-            enable_tsi_filtering,
             listeners: HashMap::new(),
             listeners_id: 0,
+            on_next_session_open: OnceCallbacks::new(),
+            on_next_session_closed: OnceCallbacks::new(),
+            on_next_object_completed: OnceCallbacks::new(),
+            out_of_band_oti: HashMap::new(),
+            packet_capture: VecDeque::new(),
+            packet_capture_error: None,
+            endpoint_aliases: HashMap::new(),
         }
     }
 
+    ///
+    /// Creates a new `MultiReceiver` from a Session Description Protocol (SDP) announcing a FLUTE session
+    ///
+    /// The following lines are parsed, the other ones are ignored
+    /// * `c=` multicast address of the session
+    /// * `m=application <port> FLUTE/UDP ...` port of the session
+    /// * `a=source-filter: incl` source address of the session
+    /// * `a=flute-tsi:` TSI of the session
+    /// * `a=FEC-declaration:`, `a=FEC-OTI-...:`, `a=flute-fec-encoding-id:`, `a=flute-encoding-symbol-length:`,
+    ///   `a=flute-max-source-block-length:` Object Transmission Information (OTI) delivered out-of-band
+    ///
+    /// The TSI filtering is enabled and the sessions are pre-registered with their out-of-band OTI.
+    /// When the SDP announces several sessions, all of them are registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdp` - Session description
+    ///
+    /// * `writer` - Responsible to write object to its final destination.
+    ///
+    /// * `receiver_config` - Configuration of the FLUTE `Receiver`. if `None`, default `Config` will be used
+    ///
+    /// # Returns
+    ///
+    /// The `MultiReceiver` with the `UDPEndpoint` and the TSI of the first session
+    ///
+    /// # Example
+    /// ```
+    /// use flute::receiver::writer::ObjectWriterBufferBuilder;
+    /// use flute::receiver::MultiReceiver;
+    /// use std::rc::Rc;
+    ///
+    /// let sdp = "v=0\r\n\
+    ///            o=- 0 0 IN IP4 127.0.0.1\r\n\
+    ///            s=FLUTE session\r\n\
+    ///            c=IN IP4 224.0.0.1/255\r\n\
+    ///            t=0 0\r\n\
+    ///            a=flute-tsi:1\r\n\
+    ///            m=application 3000 FLUTE/UDP 0\r\n";
+    ///
+    /// let writer = Rc::new(ObjectWriterBufferBuilder::new());
+    /// let (receiver, endpoint, tsi) = MultiReceiver::from_sdp(sdp, writer, None).unwrap();
+    /// assert_eq!(endpoint.destination_group_address, "224.0.0.1");
+    /// assert_eq!(endpoint.port, 3000);
+    /// assert_eq!(tsi, 1);
+    /// ```
+    #[cfg(feature = "sdp")]
+    pub fn from_sdp(
+        sdp: &str,
+        writer: Rc<dyn ObjectWriterBuilder>,
+        receiver_config: Option<Config>,
+    ) -> Result<(MultiReceiver, UDPEndpoint, u64)> {
+        let sessions = SessionDescription::from_sdp(sdp)?;
+        let mut receiver = MultiReceiver::new(writer, receiver_config, true);
+        for session in &sessions {
+            receiver.add_listen_tsi(session.endpoint.clone(), session.tsi);
+            receiver.set_out_of_band_oti(
+                session.endpoint.clone(),
+                session.tsi,
+                session.oti.clone(),
+            );
+        }
+        let first = sessions.into_iter().next().unwrap();
+        Ok((receiver, first.endpoint, first.tsi))
+    }
+
+    ///
+    /// Set the configuration of the FLUTE sessions with the TSI `tsi`, overriding the default session configuration
+    ///
+    /// The configuration is applied to the sessions created after this call,
+    /// the sessions already in progress keep their configuration.
+    ///
+    /// # Arguments
+    /// * `tsi` - The TSI of the sessions.
+    ///
+    /// * `config` - The configuration of the sessions.
+    ///
+    pub fn set_session_config(&mut self, tsi: u64, config: Config) {
+        self.config.session_configs.insert(tsi, config);
+    }
+
+    ///
+    /// Set the Object Transmission Information (OTI) delivered out-of-band (ex: SDP) for a FLUTE session
+    ///
+    /// This OTI is used for the objects that are neither described by the FDT nor by an inband FTI.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The `UDPEndpoint` of the session.
+    ///
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// * `oti` - The OTI of the session, `None` to remove it
+    ///
+    pub fn set_out_of_band_oti(&mut self, endpoint: UDPEndpoint, tsi: u64, oti: Option<oti::Oti>) {
+        let key = ReceiverEndpoint { endpoint, tsi };
+        if let Some(receiver) = self.alc_receiver.get_mut(&key) {
+            receiver.set_out_of_band_oti(oti.clone());
+        }
+
+        match oti {
+            Some(oti) => self.out_of_band_oti.insert(key, oti),
+            None => self.out_of_band_oti.remove(&key),
+        };
+    }
+
+    ///
+    /// Get the Object Transmission Information (OTI) delivered out-of-band for a FLUTE session
+    ///
+    pub fn out_of_band_oti(&self, endpoint: &UDPEndpoint, tsi: u64) -> Option<&oti::Oti> {
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi,
+        };
+        self.out_of_band_oti.get(&key)
+    }
+
     ///
     /// Add a listener to the MultiReceiver
     /// # Arguments
@@ -115,6 +394,74 @@ impl MultiReceiver {
         self.listeners.remove(&id);
     }
 
+    ///
+    /// Register a callback called once, when the next FLUTE session is opened
+    ///
+    /// # Example
+    /// ```
+    /// use flute::receiver::writer::ObjectWriterBufferBuilder;
+    /// use flute::receiver::MultiReceiver;
+    /// use std::rc::Rc;
+    ///
+    /// let writer = Rc::new(ObjectWriterBufferBuilder::new());
+    /// let mut receiver = MultiReceiver::new(writer, None, false);
+    /// let (tx, _rx) = std::sync::mpsc::channel();
+    /// receiver.on_next_session_open(move |endpoint| {
+    ///     tx.send(endpoint.clone()).ok();
+    /// });
+    /// ```
+    pub fn on_next_session_open(&mut self, cb: impl FnOnce(&ReceiverEndpoint) + Send + 'static) {
+        self.on_next_session_open.add((), Box::new(cb));
+    }
+
+    ///
+    /// Register a callback called once, when the next FLUTE session with this TSI is closed
+    ///
+    /// # Arguments
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// * `cb` - The callback
+    pub fn on_next_session_closed(
+        &mut self,
+        tsi: u64,
+        cb: impl FnOnce(&ReceiverEndpoint) + Send + 'static,
+    ) {
+        self.on_next_session_closed.add(tsi, Box::new(cb));
+    }
+
+    ///
+    /// Register a callback called once, when the next object is completed
+    ///
+    /// # Arguments
+    /// * `toi` - The TOI of the object, `None` to match any object.
+    ///
+    /// * `cb` - The callback
+    pub fn on_next_object_completed(
+        &mut self,
+        toi: Option<u128>,
+        cb: impl FnOnce(&ObjectCompletionEvent) + Send + 'static,
+    ) {
+        self.on_next_object_completed.add(toi, Box::new(cb));
+        for receiver in self.alc_receiver.values_mut() {
+            receiver.set_report_objects_completed(true);
+        }
+    }
+
+    ///
+    /// Return the last packets pushed to the `MultiReceiver`, from the oldest to the newest
+    ///
+    /// The capture is enabled with `Config::packet_capture_size`, otherwise the list is empty.
+    pub fn last_packets(&self) -> Vec<Vec<u8>> {
+        self.packet_capture.iter().cloned().collect()
+    }
+
+    ///
+    /// Return the last error returned by `push()` or `push_parsed()` while the packet capture is enabled
+    ///
+    pub fn last_packet_error(&self) -> Option<String> {
+        self.packet_capture_error.clone()
+    }
+
     ///
     /// Number of objects that are we are receiving
     ///
@@ -135,6 +482,187 @@ impl MultiReceiver {
             .sum()
     }
 
+    ///
+    /// Get the statistics of a FLUTE session
+    ///
+    /// # Arguments
+    /// * `endpoint` - The `UDPEndpoint` of the session.
+    ///
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// # Returns
+    /// `None` if the session does not exist
+    pub fn get_session_stats(&self, endpoint: &UDPEndpoint, tsi: u64) -> Option<SessionStats> {
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi,
+        };
+        self.alc_receiver.get(&key).map(|receiver| receiver.stats())
+    }
+
+    ///
+    /// Get the statistics of all the FLUTE sessions
+    ///
+    pub fn all_session_stats(&self) -> HashMap<ReceiverEndpoint, SessionStats> {
+        self.alc_receiver
+            .iter()
+            .map(|(key, receiver)| (key.clone(), receiver.stats()))
+            .collect()
+    }
+
+    ///
+    /// Get the description of all the FLUTE sessions discovered by the receiver
+    ///
+    /// # Returns
+    /// One `SessionInfo` per session, sorted by endpoint and TSI
+    pub fn session_directory(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .alc_receiver
+            .values()
+            .map(|receiver| receiver.session_info())
+            .collect();
+        sessions.sort_by_cached_key(|session| {
+            (
+                session.endpoint.destination_group_address.clone(),
+                session.endpoint.port,
+                session.endpoint.source_address.clone(),
+                session.tsi,
+            )
+        });
+        sessions
+    }
+
+    ///
+    /// Remove a FLUTE session, the objects being received are discarded
+    ///
+    /// # Arguments
+    /// * `endpoint` - The `UDPEndpoint` of the session.
+    ///
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// # Returns
+    /// `true` if the session has been removed, `false` if it does not exist
+    pub fn remove_session(&mut self, endpoint: &UDPEndpoint, tsi: u64) -> bool {
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi,
+        };
+        if self.alc_receiver.remove(&key).is_none() {
+            return false;
+        }
+        log::info!("Remove session {:?}", key);
+        self.notify_session_closed(&key);
+        true
+    }
+
+    ///
+    /// Close a FLUTE session whose end is signaled out-of-band (ex: management plane),
+    /// without receiving a packet with the Close Session flag (A)
+    ///
+    /// The objects being received are interrupted (`ObjectWriter::interrupted()`), the session is removed
+    /// and `MultiReceiverListener::on_session_closed()` is called.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The `UDPEndpoint` of the session.
+    ///
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// * `now` - The current `SystemTime`.
+    ///
+    /// # Returns
+    /// `true` if the session has been closed, `false` if it does not exist
+    pub fn inject_close_session(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        tsi: u64,
+        now: SystemTime,
+    ) -> bool {
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi,
+        };
+        let mut receiver = match self.alc_receiver.remove(&key) {
+            Some(receiver) => receiver,
+            None => return false,
+        };
+        receiver.handle_close_session(now);
+        log::info!("Close session {:?}", key);
+        self.notify_session_closed(&key);
+        true
+    }
+
+    ///
+    /// Stop waiting for the missing blocks of an object and deliver the data already decoded
+    ///
+    /// Intended for live low-latency delivery, where a late object is more useful partially than not at all.
+    /// The blocks decoded in order are written, then `ObjectWriter::partial_complete()` is called.
+    /// The packets of this object received afterward are ignored while it is tracked in the error list.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The `UDPEndpoint` of the session.
+    ///
+    /// * `tsi` - The TSI of the session.
+    ///
+    /// * `toi` - The TOI of the object.
+    ///
+    /// * `now` - The current `SystemTime`.
+    ///
+    /// # Returns
+    /// `false` if the object is unknown, already completed or in error,
+    /// or if it can not be delivered yet because its FDT has not been received
+    pub fn force_complete(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        tsi: u64,
+        toi: u128,
+        now: SystemTime,
+    ) -> bool {
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi,
+        };
+        let receiver = match self.alc_receiver.get_mut(&key) {
+            Some(receiver) => receiver,
+            None => return false,
+        };
+        let ret = receiver.force_complete(toi, now);
+        let objects_completed = receiver.take_objects_completed();
+        self.notify_objects_completed(&key, objects_completed);
+        ret
+    }
+
+    ///
+    /// Get the progress of the objects being received, for all the FLUTE sessions
+    ///
+    /// # Returns
+    /// The endpoint, the TSI and the objects in progress of each session
+    pub fn all_objects_in_progress(&self) -> Vec<(ReceiverEndpoint, u64, ObjectsInProgress)> {
+        self.alc_receiver
+            .iter()
+            .map(|(key, receiver)| (key.clone(), key.tsi, receiver.objects_in_progress()))
+            .collect()
+    }
+
+    ///
+    /// Get the completed objects that belong to a group, for all the FLUTE sessions
+    ///
+    /// # Arguments
+    /// * `group` - Group of the FDT entries, ex: to route the objects of a service
+    ///
+    /// # Returns
+    /// The session and the TOI of each completed object whose metadata includes `group`
+    pub fn objects_in_group(&self, group: &str) -> Vec<(ReceiverEndpoint, u128)> {
+        self.alc_receiver
+            .iter()
+            .flat_map(|(key, receiver)| {
+                receiver
+                    .objects_in_group(group)
+                    .into_iter()
+                    .map(|toi| (key.clone(), toi))
+            })
+            .collect()
+    }
+
     ///
     /// Enable/Disable  TSI filtering
     ///
@@ -171,6 +699,29 @@ impl MultiReceiver {
         self.tsifilter.remove(endpoint, tsi);
     }
 
+    ///
+    /// Process the packets received from `alias` as if they were received from `endpoint`
+    ///
+    /// Used to merge the LCT layers of a layered coding session (RFC 5775),
+    /// sent to different multicast groups, so the symbols of all the layers feed the same objects.
+    ///
+    /// # Arguments
+    /// * `alias` - Endpoint of an additional layer.
+    ///
+    /// * `endpoint` - Endpoint of the session the packets are merged into.
+    ///
+    pub fn add_endpoint_alias(&mut self, alias: UDPEndpoint, endpoint: UDPEndpoint) {
+        log::info!("Merge packets of {:?} into {:?}", alias, endpoint);
+        self.endpoint_aliases.insert(alias, endpoint);
+    }
+
+    ///
+    /// Remove an alias added with `add_endpoint_alias()`
+    ///
+    pub fn remove_endpoint_alias(&mut self, alias: &UDPEndpoint) {
+        self.endpoint_aliases.remove(alias);
+    }
+
     /// Accepts all TSI sessions for a given endpoint   
     pub fn add_listen_all_tsi(&mut self, endpoint: UDPEndpoint) {
         log::info!("Listen all TSI for {:?}", endpoint);
@@ -211,33 +762,219 @@ impl MultiReceiver {
         pkt: &[u8],
         now: std::time::SystemTime,
     ) -> Result<()> {
-        let alc = alc::parse_alc_pkt(pkt)?;
+        self.capture_packet(pkt);
+        let ret = alc::parse_alc_pkt(pkt).and_then(|alc| self.push_alc(endpoint, &alc, now));
+        self.capture_error(&ret);
+        self.auto_cleanup(now);
+        ret
+    }
+
+    /// Push an ALC/LCT packet received from the source address `src` to the `Receiver`.
+    ///
+    /// Same as `push()`, but the packet is ignored when the source address of the endpoint
+    /// is set (Source-Specific Multicast) and does not match `src`.
+    /// This protects the sessions against spoofed or cross-talk packets sent to a shared multicast group.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The `UDPEndpoint` from where the packet is received.
+    /// * `src` - The source IP address of the UDP/IP packet.
+    /// * `pkt` - The payload of the UDP/IP packet.
+    /// * `now` - The current `SystemTime` to use for time-related operations.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success (`Ok`) or an error (`Err`).
+    /// A packet ignored because of its source address returns `Ok`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is not valid or the receiver is in an error state.
+    ///
+    pub fn push_with_source(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        src: IpAddr,
+        pkt: &[u8],
+        now: std::time::SystemTime,
+    ) -> Result<()> {
+        if !endpoint.is_source_allowed(&src) {
+            log::debug!(
+                "skip pkt from source {} not matching endpoint {:?}",
+                src,
+                endpoint
+            );
+            return Ok(());
+        }
+        self.push(endpoint, pkt, now)
+    }
+
+    /// Push an already parsed ALC/LCT packet to the `Receiver`.
+    ///
+    /// Same as `push()`, but skips the parsing step. This allows feeding packets
+    /// that are not received directly from UDP (ex: tunneled in a custom framing)
+    /// after parsing them with `core::alc::parse_alc_pkt()`.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The `UDPEndpoint` from where the packet is received.
+    /// * `alc` - The ALC/LCT packet returned by `core::alc::parse_alc_pkt()`.
+    /// * `now` - The current `SystemTime` to use for time-related operations.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success (`Ok`) or an error (`Err`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet is not valid or the receiver is in an error state.
+    ///
+    pub fn push_parsed(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        alc: &alc::AlcPkt,
+        now: std::time::SystemTime,
+    ) -> Result<()> {
+        self.capture_packet(alc.data);
+        let ret = self.push_alc(endpoint, alc, now);
+        self.capture_error(&ret);
+        self.auto_cleanup(now);
+        ret
+    }
 
+    /// Push a batch of ALC/LCT packets received from the same endpoint to the `Receiver`.
+    ///
+    /// Same as calling `push()` for each packet, for receivers reading several UDP packets
+    /// per system call (ex: `recvmmsg`). All the packets share the same `now` timestamp.
+    /// The session of the previous packet is reused while the TSI does not change.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The `UDPEndpoint` from where the packets are received.
+    /// * `packets` - The payloads of the UDP/IP packets, in order of reception.
+    /// * `now` - The current `SystemTime` to use for time-related operations.
+    ///
+    /// # Returns
+    ///
+    /// The `Result` of each packet, in the same order as `packets`.
+    ///
+    pub fn push_batch(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        packets: &[&[u8]],
+        now: std::time::SystemTime,
+    ) -> Vec<Result<()>> {
+        let alias = self.endpoint_aliases.get(endpoint).cloned();
+        let endpoint = alias.as_ref().unwrap_or(endpoint);
+        let mut key: Option<ReceiverEndpoint> = None;
+
+        let ret = packets
+            .iter()
+            .map(|pkt| {
+                self.capture_packet(pkt);
+                let ret = alc::parse_alc_pkt(pkt).and_then(|alc| {
+                    if key.as_ref().map(|k| k.tsi != alc.lct.tsi).unwrap_or(true) {
+                        key = Some(ReceiverEndpoint {
+                            endpoint: endpoint.clone(),
+                            tsi: alc.lct.tsi,
+                        });
+                    }
+                    self.push_alc_session(key.as_ref().unwrap(), &alc, now)
+                });
+                self.capture_error(&ret);
+                ret
+            })
+            .collect();
+        self.auto_cleanup(now);
+        ret
+    }
+
+    /// Call `cleanup()` when `MultiReceiverConfig::auto_cleanup_interval` is elapsed
+    fn auto_cleanup(&mut self, now: SystemTime) {
+        let interval = match self.config.auto_cleanup_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        let last_cleanup = *self.last_cleanup.get_or_insert(now);
+        let elapsed = now.duration_since(last_cleanup).unwrap_or_default();
+        if elapsed >= interval {
+            self.cleanup(now);
+        }
+    }
+
+    fn capture_packet(&mut self, pkt: &[u8]) {
+        let capture_size = self.config.default_session_config.packet_capture_size;
+        if capture_size == 0 {
+            return;
+        }
+
+        while self.packet_capture.len() >= capture_size {
+            self.packet_capture.pop_front();
+        }
+        self.packet_capture.push_back(pkt.to_vec());
+    }
+
+    fn capture_error(&mut self, ret: &Result<()>) {
+        if let Err(e) = ret {
+            if !self.packet_capture.is_empty() {
+                self.packet_capture_error = Some(e.0.to_string());
+            }
+        }
+    }
+
+    fn push_alc(
+        &mut self,
+        endpoint: &UDPEndpoint,
+        alc: &alc::AlcPkt,
+        now: std::time::SystemTime,
+    ) -> Result<()> {
+        let alias = self.endpoint_aliases.get(endpoint).cloned();
+        let endpoint = alias.as_ref().unwrap_or(endpoint);
+
+        let key = ReceiverEndpoint {
+            endpoint: endpoint.clone(),
+            tsi: alc.lct.tsi,
+        };
+        self.push_alc_session(&key, alc, now)
+    }
+
+    fn push_alc_session(
+        &mut self,
+        key: &ReceiverEndpoint,
+        alc: &alc::AlcPkt,
+        now: std::time::SystemTime,
+    ) -> Result<()> {
         if self.enable_tsi_filtering {
-            let can_handle = self.tsifilter.is_valid(endpoint, alc.lct.tsi);
+            let can_handle = self.tsifilter.is_valid(&key.endpoint, alc.lct.tsi);
 
             if !can_handle {
                 log::debug!(
                     "skip pkt with tsi {} and endpoint {:?}",
                     alc.lct.tsi,
-                    endpoint
+                    key.endpoint
                 );
                 return Ok(());
             }
         }
 
-        let key = ReceiverEndpoint {
-            endpoint: endpoint.clone(),
-            tsi: alc.lct.tsi,
-        };
-
+        let report_fdt_files = !self.listeners.is_empty();
         if alc.lct.close_session {
             log::info!("Close session is set");
             let mut remove_session = false;
-            let ret = match self.get_receiver(&key) {
+            let ret = match self.get_receiver(key) {
                 Some(receiver) => {
                     remove_session = true;
-                    receiver.push(&alc, now)
+                    let ret = receiver.push(alc, now);
+                    let fdt_xml = receiver.take_fdt_received();
+                    let fdt_files = match (&fdt_xml, report_fdt_files) {
+                        (Some(_), true) => receiver.fdt_files(now),
+                        _ => Vec::new(),
+                    };
+                    let objects_completed = receiver.take_objects_completed();
+                    self.notify_fdt(key, fdt_xml, &fdt_files, now);
+                    self.notify_objects_completed(key, objects_completed);
+                    ret
                 }
                 None => {
                     log::warn!(
@@ -249,15 +986,67 @@ impl MultiReceiver {
 
             if remove_session {
                 log::warn!("Remove closed session");
-                self.alc_receiver.remove(&key);
-                for listener in self.listeners.values() {
-                    listener.on_session_closed(&key);
-                }
+                self.alc_receiver.remove(key);
+                self.notify_session_closed(key);
             }
             ret
         } else {
-            let receiver = self.get_receiver_or_create(&key);
-            receiver.push(&alc, now)
+            let receiver = self.get_receiver_or_create(key);
+            let ret = receiver.push(alc, now);
+            let fdt_xml = receiver.take_fdt_received();
+            let fdt_files = match (&fdt_xml, report_fdt_files) {
+                (Some(_), true) => receiver.fdt_files(now),
+                _ => Vec::new(),
+            };
+            let objects_completed = receiver.take_objects_completed();
+            self.notify_fdt(key, fdt_xml, &fdt_files, now);
+            self.notify_objects_completed(key, objects_completed);
+            ret
+        }
+    }
+
+    fn notify_session_closed(&mut self, key: &ReceiverEndpoint) {
+        for listener in self.listeners.values() {
+            listener.on_session_closed(key);
+        }
+        self.on_next_session_closed
+            .notify(key, |tsi| *tsi == key.tsi);
+    }
+
+    fn notify_objects_completed(
+        &mut self,
+        key: &ReceiverEndpoint,
+        objects_completed: Vec<(u128, Option<url::Url>)>,
+    ) {
+        for (toi, content_location) in objects_completed {
+            let event = ObjectCompletionEvent {
+                endpoint: key.clone(),
+                toi,
+                content_location,
+            };
+            self.on_next_object_completed
+                .notify(&event, |expected| expected.map_or(true, |t| t == toi));
+        }
+
+        if self.on_next_object_completed.is_empty() {
+            if let Some(receiver) = self.alc_receiver.get_mut(key) {
+                receiver.set_report_objects_completed(false);
+            }
+        }
+    }
+
+    fn notify_fdt(
+        &self,
+        key: &ReceiverEndpoint,
+        fdt_xml: Option<String>,
+        fdt_files: &[(u128, ObjectMetadata)],
+        now: SystemTime,
+    ) {
+        if let Some(fdt_xml) = fdt_xml {
+            for listener in self.listeners.values() {
+                listener.on_fdt(key, &fdt_xml, now);
+                listener.on_fdt_files(key, fdt_files, now);
+            }
         }
     }
 
@@ -265,8 +1054,18 @@ impl MultiReceiver {
     /// Remove FLUTE session that are closed or expired
     /// Remove Objects that are expired
     ///
-    /// Cleanup shall be call from time to time to avoid consuming to much memory    
+    /// Cleanup shall be call from time to time to avoid consuming to much memory.
+    /// The sessions are scanned at most once per `Config::cleanup_interval` (the lowest of the session configurations),
+    /// the other calls return immediately.
     pub fn cleanup(&mut self, now: SystemTime) {
+        self.last_cleanup = Some(now);
+        if !self
+            .cleanup_schedule
+            .is_due(self.config.cleanup_interval(), now)
+        {
+            return;
+        }
+
         let mut output = Vec::new();
         for receiver in &self.alc_receiver {
             if receiver.1.is_expired() {
@@ -280,9 +1079,7 @@ impl MultiReceiver {
         }
 
         for endpoint in &output {
-            for listener in self.listeners.values() {
-                listener.on_session_closed(&endpoint);
-            }
+            self.notify_session_closed(endpoint);
         }
     }
 
@@ -299,15 +1096,19 @@ impl MultiReceiver {
                 log::info!("Create FLUTE Receiver {:?}", key);
 
                 for listener in self.listeners.values() {
-                    listener.on_session_open(&key);
+                    listener.on_session_open(key);
                 }
+                self.on_next_session_open.notify(key, |_| true);
 
-                Box::new(Receiver::new(
+                let mut receiver = Box::new(Receiver::new(
                     &key.endpoint,
                     key.tsi,
                     self.writer.clone(),
-                    self.config,
-                ))
+                    Some(self.config.session_config(key.tsi)),
+                ));
+                receiver.set_out_of_band_oti(self.out_of_band_oti.get(key).cloned());
+                receiver.set_report_objects_completed(!self.on_next_object_completed.is_empty());
+                receiver
             })
             .as_mut()
     }
@@ -315,10 +1116,9 @@ impl MultiReceiver {
 
 impl Drop for MultiReceiver {
     fn drop(&mut self) {
-        for endpoint in self.alc_receiver.keys() {
-            for listener in self.listeners.values() {
-                listener.on_session_closed(endpoint);
-            }
+        let endpoints: Vec<ReceiverEndpoint> = self.alc_receiver.keys().cloned().collect();
+        for endpoint in &endpoints {
+            self.notify_session_closed(endpoint);
         }
     }
 }
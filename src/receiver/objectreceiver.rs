@@ -1,23 +1,43 @@
 use super::blockdecoder::BlockDecoder;
 use super::blockwriter::BlockWriter;
+use super::receiver::ObjectProgressInfo;
 use super::writer::ObjectWriterBuilder;
+use crate::common::cipher::ObjectDecipher;
 use crate::common::udpendpoint::UDPEndpoint;
-use crate::common::{alc, fdtinstance::FdtInstance, lct, oti, partition};
-use crate::receiver::writer::{ObjectMetadata, ObjectWriter};
+use crate::common::{
+    alc, contentlocation,
+    fdtinstance::{CacheControlChoice, FdtInstance},
+    lct, oti, partition,
+};
+use crate::receiver::writer::{
+    BlockDiagnostics, Md5FailureReport, Md5Policy, ObjectFilterDecision, ObjectMetadata,
+    ObjectReceptionStats, ObjectWriter,
+};
 use crate::tools::error::{FluteError, Result};
+use crate::tools::etag::ETagAlgorithm;
+use std::collections::VecDeque;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Instant;
 use std::time::{Duration, SystemTime};
 
 #[cfg(feature = "opentelemetry")]
 use super::objectreceiverlogger::ObjectReceiverLogger;
 
+/// Default maximum number of source blocks accepted for an object
+pub(crate) const DEFAULT_MAX_BLOCKS_PER_OBJECT: usize = 1 << 20;
+
+/// Default maximum size of the packets cached before the OTI of an object is known
+pub(crate) const DEFAULT_PRE_FDT_CACHE_SIZE: usize = 10 * 1024 * 1024;
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum State {
     Receiving,
     Completed,
     Interrupted,
     Error,
+    /// Object is not received, see `ObjectWriterBuilder::object_filter()`
+    Skipped,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -41,15 +61,22 @@ pub struct ObjectReceiver {
     pub tsi: u64,
     pub endpoint: UDPEndpoint,
     oti: Option<oti::Oti>,
-    cache: Vec<Box<alc::AlcPktCache>>,
+    oti_from_fdt: bool,
+    out_of_band_oti: Option<oti::Oti>,
+    cache: VecDeque<Box<alc::AlcPktCache>>,
     cache_size: usize,
+    max_cache_size: usize,
+    cache_dropped: u64,
     max_size_allocated: usize,
+    max_transfer_length: Option<u64>,
+    max_blocks: usize,
     blocks: Vec<BlockDecoder>,
     blocks_variable_size: bool,
     pub transfer_length: Option<u64>,
     cenc: Option<lct::Cenc>,
     pub content_md5: Option<String>,
     enable_md5_check: bool,
+    autodetect_cenc: bool,
     a_large: u64,
     a_small: u64,
     nb_a_large: u64,
@@ -58,17 +85,40 @@ pub struct ObjectReceiver {
     block_writer: Option<BlockWriter>,
     pub fdt_instance_id: Option<u32>,
     last_activity: Instant,
+    start_time: Instant,
     pub cache_expiration_date: Option<SystemTime>,
+    /// Cache-Control of the object in the FDT
+    pub cache_control: Option<CacheControlChoice>,
     pub content_location: Option<url::Url>,
+    pub file_etag: Option<String>,
+    enable_etag_check: bool,
+    enable_partial_delivery: bool,
+    normalize_content_location: bool,
+    md5_failure_diagnostics: bool,
+    source_block_timeout: Option<Duration>,
+    decryption_key_uri: Option<String>,
+    content_encoding: Option<String>,
+    decipher: Option<Arc<dyn ObjectDecipher>>,
+    md5_policy: Md5Policy,
+    md5_mismatch: bool,
+    /// The object is delivered partially, see `deliver_partial()`
+    partial: bool,
+    fdt_transfer_length: Option<u64>,
+    fti_transfer_length: Option<u64>,
     nb_allocated_blocks: usize,
     total_allocated_blocks_size: usize,
+    blocks_allocated: u64,
     #[cfg(feature = "opentelemetry")]
     logger: Option<ObjectReceiverLogger>,
     content_length: Option<usize>,
     content_type: Option<String>,
     cache_duration: Option<Duration>,
     groups: Vec<String>,
+    alternate_locations: Option<Vec<String>>,
+    independent_unit_positions: Option<Vec<u64>>,
+    base_urls: Option<Vec<String>>,
     last_timestamp: SystemTime,
+    stats: ObjectReceptionStats,
 }
 
 impl ObjectReceiver {
@@ -79,21 +129,32 @@ impl ObjectReceiver {
         _fdt_instance_id: Option<u32>,
         object_writer_builder: Rc<dyn ObjectWriterBuilder>,
         enable_md5_check: bool,
+        autodetect_cenc: bool,
         max_size_allocated: usize,
+        max_transfer_length: Option<u64>,
+        max_blocks: usize,
+        max_cache_size: usize,
         now: SystemTime,
     ) -> ObjectReceiver {
         log::debug!("Create new Object Receiver with toi {}", toi);
         ObjectReceiver {
             state: State::Receiving,
             oti: None,
-            cache: Vec::new(),
+            oti_from_fdt: false,
+            out_of_band_oti: None,
+            cache: VecDeque::new(),
             cache_size: 0,
+            max_cache_size,
+            cache_dropped: 0,
             max_size_allocated,
+            max_transfer_length,
+            max_blocks,
             blocks: Vec::new(),
             transfer_length: None,
             cenc: None,
             content_md5: None,
             enable_md5_check,
+            autodetect_cenc,
             blocks_variable_size: false,
             a_large: 0,
             a_small: 0,
@@ -106,20 +167,41 @@ impl ObjectReceiver {
             toi: *toi,
             endpoint: endpoint.clone(),
             last_activity: Instant::now(),
+            start_time: Instant::now(),
             cache_expiration_date: None,
+            cache_control: None,
             content_location: match *toi == lct::TOI_FDT {
                 true => Some(url::Url::parse("flute://fdt").unwrap()),
                 false => None,
             },
+            file_etag: None,
+            enable_etag_check: false,
+            enable_partial_delivery: false,
+            normalize_content_location: true,
+            md5_failure_diagnostics: false,
+            source_block_timeout: None,
+            decryption_key_uri: None,
+            content_encoding: None,
+            decipher: None,
+            md5_policy: Md5Policy::Verify,
+            md5_mismatch: false,
+            partial: false,
+            fdt_transfer_length: None,
+            fti_transfer_length: None,
             nb_allocated_blocks: 0,
             total_allocated_blocks_size: 0,
+            blocks_allocated: 0,
             #[cfg(feature = "opentelemetry")]
             logger: None,
             content_length: None,
             content_type: None,
             cache_duration: None,
             groups: Vec::new(),
+            alternate_locations: None,
+            independent_unit_positions: None,
+            base_urls: None,
             last_timestamp: now,
+            stats: ObjectReceptionStats::default(),
         }
     }
 
@@ -135,6 +217,62 @@ impl ObjectReceiver {
         self.blocks.len()
     }
 
+    /// OTI used when neither the FDT nor the inband FTI describe the object
+    pub fn set_out_of_band_oti(&mut self, oti: Option<oti::Oti>) {
+        self.out_of_band_oti = oti;
+    }
+
+    /// Check the `File-ETag` of the object against the hash of the received content
+    /// Only the ETags computed with `ObjectDesc::compute_e_tag()` can be checked
+    pub fn set_etag_check(&mut self, enable: bool) {
+        self.enable_etag_check = enable;
+    }
+
+    /// Deliver the data already written when the object times out or is interrupted,
+    /// if its `ObjectWriter` allows it
+    pub fn set_partial_delivery(&mut self, enable: bool) {
+        self.enable_partial_delivery = enable;
+    }
+
+    /// Normalize the Content-Location announced in the FDT
+    pub fn set_normalize_content_location(&mut self, enable: bool) {
+        self.normalize_content_location = enable;
+    }
+
+    /// Report the per-block diagnostics of the object when its MD5 does not match
+    pub fn set_md5_failure_diagnostics(&mut self, enable: bool) {
+        self.md5_failure_diagnostics = enable;
+    }
+
+    /// Move the object to error when a source block that can not be decoded
+    /// has not received any symbol before the timeout
+    pub fn set_source_block_timeout(&mut self, timeout: Option<Duration>) {
+        self.source_block_timeout = timeout;
+    }
+
+    /// Algorithm of the ETag to check, if any
+    fn etag_check_algorithm(&self) -> Option<ETagAlgorithm> {
+        if !self.enable_etag_check {
+            return None;
+        }
+        self.file_etag.as_deref().and_then(ETagAlgorithm::from_etag)
+    }
+
+    /// Size of the packets cached before the OTI of this object is known
+    pub fn cache_size(&self) -> usize {
+        self.cache_size
+    }
+
+    /// Number of packets dropped because the cache was full
+    pub fn cache_dropped(&self) -> u64 {
+        self.cache_dropped
+    }
+
+    /// Total number of source blocks allocated to decode this object
+    pub fn blocks_allocated(&self) -> u64 {
+        self.blocks_allocated
+    }
+
     pub fn push(&mut self, pkt: &alc::AlcPkt, now: std::time::SystemTime) {
         self.last_timestamp = now;
         if self.state != State::Receiving {
@@ -142,17 +280,27 @@ impl ObjectReceiver {
         }
 
         self.last_activity = Instant::now();
+        if self.stats.first_packet_time.is_none() {
+            self.stats.first_packet_time = Some(now);
+        }
+        self.stats.packets_received += 1;
+        self.stats.bytes_received += pkt.data.len() as u64;
+        if pkt.server_time.is_some() {
+            self.stats.sender_current_time = pkt.server_time;
+        }
         self.set_fdt_id_from_pkt(pkt);
         self.set_cenc_from_pkt(pkt);
         self.set_oti_from_pkt(pkt, now);
+        if !self.check_transfer_length(now) {
+            return;
+        }
 
         self.init_blocks_partitioning();
         self.init_object_writer(now);
         self.push_from_cache(now);
 
         if self.oti.is_none() {
-            self.cache(pkt)
-                .unwrap_or_else(|_| self.error("Fail to push pkt to cache", now, false));
+            self.cache(pkt);
             return;
         }
 
@@ -163,8 +311,8 @@ impl ObjectReceiver {
     fn push_to_block(&mut self, pkt: &alc::AlcPkt, now: std::time::SystemTime) -> Result<()> {
         self.push_to_block2(pkt, now)?;
         if pkt.lct.close_object {
-            if self.state == State::Receiving {
-                self.error("No more packet for this object", now, true);
+            if self.state == State::Receiving && !self.is_decoded_waiting_writer() {
+                self.incomplete("No more packet for this object", now, true);
             }
         }
         Ok(())
@@ -182,8 +330,13 @@ impl ObjectReceiver {
             return Ok(());
         }
 
+        if pkt.data.len() <= pkt.data_payload_offset {
+            // Packet without encoding symbol (ex: close object flag sent on a transfer failure)
+            return Ok(());
+        }
+
         if payload_id.sbn as usize >= self.blocks.len() {
-            if !self.blocks_variable_size {
+            if !self.blocks_variable_size || payload_id.sbn as usize >= self.max_blocks {
                 return Err(FluteError::new(format!(
                     "SBN {} > max SBN {}",
                     payload_id.sbn,
@@ -194,11 +347,21 @@ impl ObjectReceiver {
                 .resize_with(payload_id.sbn as usize + 1, BlockDecoder::new);
         }
 
+        if !self.check_symbol_length(&payload_id, pkt.data.len() - pkt.data_payload_offset) {
+            return Ok(());
+        }
+
         let block = &mut self.blocks[payload_id.sbn as usize];
+        self.stats.symbols_received += 1;
         if block.completed {
             return Ok(());
         }
 
+        if !block.register_symbol(payload_id.esi) {
+            self.stats.symbols_duplicate += 1;
+            return Ok(());
+        }
+
         if !block.initialized {
             let source_block_length = payload_id.source_block_length.unwrap_or(
                 match payload_id.sbn < self.nb_a_large as u32 {
@@ -247,19 +410,131 @@ impl ObjectReceiver {
                     return Err(FluteError::new("Fail to init source block decoder"));
                 }
             }
+            block.received_before_fdt = self.fdt_instance_id.is_none();
             self.nb_allocated_blocks += 1;
+            self.blocks_allocated += 1;
             self.total_allocated_blocks_size += block_length;
         }
 
-        block.push(pkt, &payload_id);
+        block.push(pkt, &payload_id, now);
         if block.completed {
             log::debug!("block {} is completed", payload_id.sbn);
-            self.write_blocks(payload_id.sbn, now)?;
+            if block.is_decoded_with_fec() {
+                self.stats.blocks_decoded_with_fec += 1;
+                self.stats.repair_symbols_used += block.nb_repair_symbols_received() as u64;
+            }
+            self.stats.max_burst_loss = self.stats.max_burst_loss.max(block.max_burst_loss());
+            self.write_blocks(now)?;
         }
 
+        self.check_source_block_timeout(now);
         Ok(())
     }
 
+    /// Move the object to error if a source block that is not decoded
+    /// has not received any symbol for longer than `source_block_timeout`
+    /// Return `true` if the object has been moved to error
+    pub fn check_source_block_timeout(&mut self, now: std::time::SystemTime) -> bool {
+        let timeout = match self.source_block_timeout {
+            Some(timeout) => timeout,
+            None => return false,
+        };
+
+        if self.state != State::Receiving {
+            return false;
+        }
+
+        let expired_sbn = self.blocks.iter().position(|block| {
+            !block.completed
+                && block
+                    .last_symbol_received()
+                    .and_then(|last| now.duration_since(last).ok())
+                    .map(|duration| duration > timeout)
+                    .unwrap_or(false)
+        });
+
+        if let Some(sbn) = expired_sbn {
+            log::warn!(
+                "TSI={} TOI={} SBN={} No symbol received for more than {:?}, the block can not be decoded",
+                self.tsi,
+                self.toi,
+                sbn,
+                timeout
+            );
+            self.error("Source block timeout", now, false);
+            return true;
+        }
+        false
+    }
+
+    /// Return `false` if the length of the encoding symbol does not match the OTI
+    ///
+    /// All the symbols have the encoding symbol length, except the last source symbol of the object
+    /// that can be shorter, the rejected symbols are counted in the reception statistics
+    fn check_symbol_length(&mut self, payload_id: &alc::PayloadID, symbol_length: usize) -> bool {
+        let oti = self.oti.as_ref().unwrap();
+        // Raptor splits each source block in symbols of its own size, checked by the decoder
+        if oti.fec_encoding_id == oti::FECEncodingID::Raptor {
+            return true;
+        }
+
+        let encoding_symbol_length = oti.encoding_symbol_length as usize;
+        if symbol_length == encoding_symbol_length {
+            return true;
+        }
+
+        let transfer_length = self.transfer_length.unwrap();
+        let last_symbol_length =
+            ((transfer_length - 1) % encoding_symbol_length as u64) as usize + 1;
+        let source_block_length = payload_id.source_block_length.unwrap_or(
+            match payload_id.sbn < self.nb_a_large as u32 {
+                true => self.a_large as u32,
+                _ => self.a_small as u32,
+            },
+        );
+        let is_last_symbol = (self.blocks_variable_size
+            || payload_id.sbn as usize + 1 == self.blocks.len())
+            && payload_id.esi + 1 == source_block_length;
+        let expected_length = match is_last_symbol {
+            true => last_symbol_length,
+            false => encoding_symbol_length,
+        };
+        if symbol_length == expected_length {
+            return true;
+        }
+
+        self.stats.symbols_invalid_length += 1;
+        self.stats.last_invalid_symbol_length = Some((expected_length, symbol_length));
+        // Warn once per object, the following symbols would flood the logs
+        if self.stats.symbols_invalid_length == 1 {
+            log::warn!(
+                "TSI={} TOI={} SBN={} ESI={} Reject encoding symbol of {} bytes, expected {} bytes",
+                self.tsi,
+                self.toi,
+                payload_id.sbn,
+                payload_id.esi,
+                symbol_length,
+                expected_length
+            );
+        } else {
+            log::debug!(
+                "TSI={} TOI={} SBN={} ESI={} Reject encoding symbol of {} bytes, expected {} bytes",
+                self.tsi,
+                self.toi,
+                payload_id.sbn,
+                payload_id.esi,
+                symbol_length,
+                expected_length
+            );
+        }
+        false
+    }
+
+    /// Number of encoding symbols rejected because of their length
+    pub fn symbols_invalid_length(&self) -> u64 {
+        self.stats.symbols_invalid_length
+    }
+
     #[cfg(feature = "opentelemetry")]
     fn init_logger(&mut self, propagator: Option<&std::collections::HashMap<String, String>>) {
         if self.logger.is_some() {
@@ -292,6 +567,8 @@ impl ObjectReceiver {
             None => return false,
         };
 
+        self.fdt_transfer_length = Some(file.get_transfer_length());
+
         #[cfg(feature = "opentelemetry")]
         if self.logger.is_none() {
             let propagator = file.get_optel_propagator();
@@ -299,18 +576,60 @@ impl ObjectReceiver {
         }
 
         if self.cenc.is_none() {
-            self.cenc = match &file.content_encoding {
+            self.cenc = match &fdt.get_effective_content_encoding(file) {
                 Some(str) => Some(str.as_str().try_into().unwrap_or(lct::Cenc::Null)),
                 None => Some(lct::Cenc::Null),
             };
             log::debug!("Set cenc from FDT {:?}", self.cenc);
         }
 
-        if self.oti.is_none() {
-            self.oti = fdt.get_oti_for_file(file);
-            if self.oti.is_some() {
-                debug_assert!(self.transfer_length.is_none());
-                self.transfer_length = Some(file.get_transfer_length());
+        // The Content-Length describes the decoded content, it can not replace the Transfer-Length of an encoded object
+        let cenc = self.cenc.unwrap_or(lct::Cenc::Null);
+        if self.transfer_length.is_none()
+            && file.transfer_length.is_none()
+            && cenc != lct::Cenc::Null
+        {
+            log::error!(
+                "TSI={} TOI={} Transfer-Length is missing in the FDT for an object encoded with {:?}",
+                self.tsi,
+                self.toi,
+                cenc
+            );
+            self.error(
+                &format!(
+                    "Transfer-Length is missing for an object encoded with {:?}",
+                    cenc
+                ),
+                now,
+                false,
+            );
+            return true;
+        }
+
+        match self.oti.as_ref() {
+            None => {
+                self.oti = fdt
+                    .get_oti_for_file(file)
+                    .or_else(|| self.out_of_band_oti.clone());
+                if self.oti.is_some() {
+                    debug_assert!(self.transfer_length.is_none());
+                    self.oti_from_fdt = true;
+                    self.transfer_length = Some(file.get_transfer_length());
+                }
+            }
+            Some(fti_oti) => {
+                if let Some(fdt_oti) = fdt.get_oti_for_file(file) {
+                    let transfer_length = self.transfer_length.unwrap_or_default();
+                    if Self::is_oti_conflicting(&fdt_oti, fti_oti, transfer_length) {
+                        log::error!(
+                            "TSI={} TOI={} OTI of the FDT {:?} does not match the inband FTI {:?}, keep the FTI",
+                            self.tsi,
+                            self.toi,
+                            fdt_oti,
+                            fti_oti
+                        );
+                    }
+                }
             }
         }
 
@@ -327,28 +646,26 @@ impl ObjectReceiver {
             }
         }
 
-        self.content_location = match url::Url::parse(&file.content_location) {
+        if !self.check_transfer_length(now) {
+            return true;
+        }
+
+        self.content_location = match contentlocation::to_url(
+            &file.content_location,
+            self.normalize_content_location,
+        ) {
             Ok(val) => Some(val),
-            Err(_) => {
-                let base_url = url::Url::parse("file:///").unwrap();
-                match base_url.join(&file.content_location) {
-                    Ok(val) => Some(val),
-                    Err(_) => {
-                        log::error!(
-                            "Fail to parse content-location {} to URL",
-                            file.content_location
-                        );
-                        self.error(
-                            &format!(
-                                "Fail to parse content-location {} to URL",
-                                file.content_location
-                            ),
-                            now,
-                            false,
-                        );
-                        return false;
-                    }
-                }
+            Err(e) => {
+                log::error!("{:?}", e);
+                self.error(
+                    &format!(
+                        "Fail to parse content-location {} to URL",
+                        file.content_location
+                    ),
+                    now,
+                    false,
+                );
+                return false;
             }
         };
 
@@ -368,26 +685,80 @@ impl ObjectReceiver {
             self.content_md5 = file.content_md5.clone();
         }
         self.fdt_instance_id = Some(fdt_instance_id);
+        self.stats.fdt_attach_time = Some(now);
 
         self.cache_duration = file.get_cache_duration(fdt.get_expiration_date(), server_time);
+        self.cache_control = file.cache_control.as_ref().map(|cc| cc.value.clone());
         self.cache_expiration_date = self.cache_duration.map(|v| {
             now.checked_add(v)
                 .unwrap_or(now + std::time::Duration::from_secs(3600 * 24 * 360 * 10))
         });
 
         self.content_length = file.content_length.map(|c| c as usize);
-        self.content_type = file.content_type.clone();
+        self.content_type = fdt.get_effective_content_type(file);
         self.groups = groups;
+        self.alternate_locations = file.get_alternate_content_locations();
+        self.independent_unit_positions = file.get_independent_unit_positions();
+        self.file_etag = file.file_etag.clone();
+        self.base_urls = fdt.get_base_urls();
+        self.decryption_key_uri = file.decryption_key_uri.clone();
+        self.content_encoding = file.content_coding.clone();
+
+        if !self.filter(now) {
+            return true;
+        }
 
         self.init_blocks_partitioning();
         self.init_object_writer(now);
         self.push_from_cache(now);
-        self.write_blocks(0, now)
+        self.write_blocks(now)
             .unwrap_or_else(|_| self.error("Fail to write blocks to storage", now, false));
         self.push_from_cache(now);
         true
     }
 
+    /// Ask the writer builder if the object must be received
+    /// Return `false` if the object is skipped
+    fn filter(&mut self, now: SystemTime) -> bool {
+        let meta = self.create_meta();
+        let decision = self.object_writer_builder.object_filter(
+            &self.endpoint,
+            &self.tsi,
+            &self.toi,
+            &meta,
+            now,
+        );
+
+        match decision {
+            ObjectFilterDecision::Receive => return true,
+            ObjectFilterDecision::Discard => {}
+            ObjectFilterDecision::MetadataOnly => self.object_writer_builder.on_object_metadata(
+                &self.endpoint,
+                &self.tsi,
+                &self.toi,
+                &meta,
+                now,
+            ),
+        }
+
+        log::debug!(
+            "TSI={} TOI={} Object is skipped ({:?})",
+            self.tsi,
+            self.toi,
+            decision
+        );
+        self.state = State::Skipped;
+        self.blocks.clear();
+        self.cache.clear();
+        self.cache_size = 0;
+        false
+    }
+
+    /// A content declaring a content-coding is delivered as-is, it must not be detected as a transfer coding
+    fn autodetect_cenc(&self) -> bool {
+        self.autodetect_cenc && self.content_encoding.is_none()
+    }
+
     pub fn create_meta(&self) -> ObjectMetadata {
         ObjectMetadata {
             content_location: self
@@ -409,12 +780,37 @@ impl ObjectReceiver {
             oti: self.oti.clone(),
             transfer_length: self.transfer_length.map(|s| s as usize),
             cenc: self.cenc.clone(),
+            content_encoding: self.content_encoding.clone(),
+            alternate_locations: self.alternate_locations.clone(),
+            base_urls: self.base_urls.clone(),
+            independent_unit_positions: self.independent_unit_positions.clone(),
+            partial: self.partial,
+            max_burst_loss: self.stats.max_burst_loss,
+            md5_mismatch: self.md5_mismatch,
+        }
+    }
+
+    /// Fraction of the object written to the `ObjectWriter`, `None` if the writer is not initialized yet
+    pub fn write_progress(&self) -> Option<f64> {
+        self.block_writer.as_ref().map(|w| w.progress_fraction())
+    }
+
+    /// Progress of the reception of this object
+    pub fn progress_info(&self) -> ObjectProgressInfo {
+        ObjectProgressInfo {
+            content_location: self.content_location.as_ref().map(|u| u.to_string()),
+            transfer_length: self.transfer_length,
+            write_progress: self.write_progress().unwrap_or(0.0),
+            blocks_total: self.nb_block(),
+            blocks_done: self.nb_block_completed(),
+            elapsed: self.start_time.elapsed(),
+            content_type: self.content_type.clone(),
         }
     }
 
     pub fn byte_left(&self) -> usize {
         if let Some(w) = self.block_writer.as_ref() {
-            return w.left();
+            return w.bytes_remaining();
         }
         usize::MAX
     }
@@ -432,20 +828,38 @@ impl ObjectReceiver {
             return;
         }
 
+        let meta = self.create_meta();
         let object_writer = self.object_writer_builder.new_object_writer(
             &self.endpoint,
             &self.tsi,
             &self.toi,
-            &self.create_meta(),
+            &meta,
             now,
         );
 
+        if self.content_md5.is_some() {
+            self.md5_policy = match object_writer.enable_md5_check() {
+                true => self.object_writer_builder.md5_policy(
+                    &self.endpoint,
+                    &self.tsi,
+                    &self.toi,
+                    &meta,
+                ),
+                false => Md5Policy::Skip,
+            };
+        }
+
+        if self.md5_policy == Md5Policy::Skip {
+            self.content_md5 = None;
+        }
+
         debug_assert!(self.block_writer.is_none());
         self.object_writer = Some(ObjectWriterSession {
             writer: object_writer,
             state: ObjectWriterSessionState::Idle,
         });
 
+        let etag_check = self.etag_check_algorithm();
         let object_writer = self.object_writer.as_mut().unwrap();
 
         if object_writer.writer.open(now).is_err() {
@@ -453,20 +867,61 @@ impl ObjectReceiver {
             return;
         };
 
+        if let Some(key_uri) = self.decryption_key_uri.as_ref() {
+            self.decipher =
+                self.object_writer_builder
+                    .decipher(&self.endpoint, &self.tsi, &self.toi, key_uri);
+            if self.decipher.is_none() {
+                log::error!(
+                    "TSI={} TOI={} No decryption key registered for {}",
+                    self.tsi,
+                    self.toi,
+                    key_uri
+                );
+                object_writer.writer.decryption_key_missing(now, key_uri);
+                self.error("No decryption key", now, false);
+                return;
+            }
+        }
+
+        // The encrypted content is decrypted as a whole, it is kept in RAM until the last block is received
         let transfer_length = self.transfer_length.unwrap();
+        if self.decipher.is_some() && transfer_length > self.max_size_allocated as u64 {
+            log::error!(
+                "TSI={} TOI={} Encrypted object of {} bytes is larger than the maximum cache size {}",
+                self.tsi,
+                self.toi,
+                transfer_length,
+                self.max_size_allocated
+            );
+            self.error(
+                "Encrypted object is larger than the maximum cache size",
+                now,
+                false,
+            );
+            return;
+        }
+
         if transfer_length != 0 {
             self.block_writer = Some(BlockWriter::new(
                 transfer_length as usize,
                 self.content_length.clone(),
                 self.cenc.unwrap(),
                 self.content_md5.is_some(),
+                etag_check,
+                self.autodetect_cenc(),
             ));
         }
 
-        object_writer.state = ObjectWriterSessionState::Opened;
+        if let Some(block_writer) = self.block_writer.as_mut() {
+            block_writer.set_block_crcs(self.md5_failure_diagnostics);
+            block_writer.set_decipher(self.decipher.clone());
+        }
+
+        self.object_writer.as_mut().unwrap().state = ObjectWriterSessionState::Opened;
     }
 
-    fn write_blocks(&mut self, sbn_start: u32, now: std::time::SystemTime) -> Result<()> {
+    fn write_blocks(&mut self, now: std::time::SystemTime) -> Result<()> {
         if self.object_writer.is_none() {
             return Ok(());
         }
@@ -480,8 +935,9 @@ impl ObjectReceiver {
         }
 
         debug_assert!(self.block_writer.is_some());
-        let mut sbn = sbn_start as usize;
         let writer = self.block_writer.as_mut().unwrap();
+        // Blocks are written in order, from the first block not written yet
+        let mut sbn = writer.next_sbn() as usize;
         while sbn < self.blocks.len() {
             let block = &mut self.blocks[sbn];
             if !block.completed {
@@ -510,8 +966,43 @@ impl ObjectReceiver {
                     .map(|md5| writer.check_md5(md5))
                     .unwrap_or(true);
 
-                if md5_valid {
+                let etag_valid = self
+                    .file_etag
+                    .as_ref()
+                    .map(|etag| writer.check_etag(etag))
+                    .unwrap_or(true);
+
+                if md5_valid && etag_valid {
                     self.complete(now);
+                } else if etag_valid && self.md5_policy == Md5Policy::VerifyButWarnOnly {
+                    log::warn!(
+                        "MD5 does not match expects {:?} received {:?} {:?}, object is completed anyway",
+                        self.content_md5,
+                        writer.get_md5(),
+                        self.content_location
+                    );
+                    self.md5_mismatch = true;
+                    if let Some(object_writer) = self.object_writer.as_ref() {
+                        object_writer.writer.md5_mismatch(now);
+                    }
+                    self.complete(now);
+                } else if !etag_valid {
+                    let etag = writer.get_etag().map(|f| f.to_owned());
+                    log::error!(
+                        "ETag does not match expects {:?} received {:?} {:?}",
+                        self.file_etag,
+                        &etag,
+                        self.content_location
+                    );
+
+                    self.error(
+                        &format!(
+                            "ETag does not match expects {:?} received {:?}",
+                            self.file_etag, &etag
+                        ),
+                        now,
+                        false,
+                    );
                 } else {
                     let md5 = writer.get_md5().map(|f| f.to_owned());
                     log::error!(
@@ -521,6 +1012,14 @@ impl ObjectReceiver {
                         self.content_location
                     );
 
+                    if self.md5_failure_diagnostics {
+                        let report = self.md5_failure_report(md5.clone());
+                        log::error!("{:?}", report);
+                        if let Some(object_writer) = self.object_writer.as_ref() {
+                            object_writer.writer.md5_failure(now, &report);
+                        }
+                    }
+
                     self.error(
                         &format!(
                             "MD5 does not match expects {:?} received {:?}",
@@ -536,15 +1035,49 @@ impl ObjectReceiver {
         Ok(())
     }
 
+    fn md5_failure_report(&self, received_md5: Option<String>) -> Md5FailureReport {
+        let block_crcs = self
+            .block_writer
+            .as_ref()
+            .and_then(|writer| writer.block_crcs())
+            .unwrap_or_default();
+
+        let blocks = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(sbn, block)| BlockDiagnostics {
+                sbn: sbn as u32,
+                source_block_length: block.nb_source_symbols(),
+                source_symbols_received: block.nb_source_symbols_received(),
+                repair_symbols_received: block.nb_repair_symbols_received(),
+                fec_decoded: block.is_decoded_with_fec(),
+                crc32: block_crcs.get(sbn).copied(),
+                received_before_fdt: block.received_before_fdt,
+            })
+            .collect();
+
+        Md5FailureReport {
+            expected_md5: self.content_md5.clone(),
+            received_md5,
+            cenc: self.cenc,
+            oti: self.oti.clone(),
+            fdt_transfer_length: self.fdt_transfer_length,
+            fti_transfer_length: self.fti_transfer_length,
+            blocks,
+        }
+    }
+
     fn complete(&mut self, now: std::time::SystemTime) {
         #[cfg(feature = "opentelemetry")]
         let _span = self.logger.as_mut().map(|l| l.complete());
 
         self.state = State::Completed;
+        self.stats.completion_time = Some(now);
 
         if let Some(object_writer) = self.object_writer.as_mut() {
             object_writer.state = ObjectWriterSessionState::Closed;
-            object_writer.writer.complete(now);
+            object_writer.writer.complete_with_stats(now, &self.stats);
         }
 
         // Free space by removing blocks
@@ -583,19 +1116,158 @@ impl ObjectReceiver {
         self.cache_size = 0;
     }
 
+    /// The reception of the object stops with missing data
+    /// The data already written is delivered partially when allowed, otherwise the object is in error
+    fn incomplete(&mut self, description: &str, now: SystemTime, interrupted: bool) {
+        let partial_delivery = self.enable_partial_delivery
+            && self
+                .object_writer
+                .as_ref()
+                .map(|object_writer| {
+                    object_writer.state == ObjectWriterSessionState::Opened
+                        && object_writer.writer.allow_partial_delivery()
+                })
+                .unwrap_or(false);
+
+        if !partial_delivery {
+            self.error(description, now, interrupted);
+            return;
+        }
+
+        self.deliver_partial(description, now, interrupted);
+    }
+
+    /// Stop the reception of the object and deliver the data already written with `ObjectWriter::partial_complete()`
+    fn deliver_partial(&mut self, description: &str, now: SystemTime, interrupted: bool) {
+        let bytes_output = self
+            .block_writer
+            .as_ref()
+            .map(|writer| writer.bytes_output() as u64)
+            .unwrap_or_default();
+        // Blocks are written in order, the data received is the beginning of the object
+        let mut received_byte_ranges = Vec::new();
+        if bytes_output > 0 {
+            received_byte_ranges.push(0..bytes_output);
+        }
+
+        log::warn!(
+            "TSI={} TOI={} {}, deliver {} bytes partially",
+            self.tsi,
+            self.toi,
+            description,
+            bytes_output
+        );
+
+        self.state = match interrupted {
+            true => State::Interrupted,
+            false => State::Error,
+        };
+        self.partial = true;
+
+        let meta = self.create_meta();
+        let object_writer = self.object_writer.as_mut().unwrap();
+        object_writer.state = ObjectWriterSessionState::Closed;
+        object_writer
+            .writer
+            .partial_complete(now, &meta, &received_byte_ranges);
+
+        self.blocks.clear();
+        self.cache.clear();
+        self.cache_size = 0;
+    }
+
+    ///
+    /// Stop the reception of the object because its session is closed
+    ///
+    /// The data already written is delivered partially when allowed, otherwise `ObjectWriter::interrupted()` is called.
+    /// Returns `false` if the object is not in `Receiving` state.
+    ///
+    pub fn interrupt(&mut self, now: SystemTime) -> bool {
+        if self.state != State::Receiving {
+            return false;
+        }
+        self.incomplete("Session is closed", now, true);
+        true
+    }
+
+    /// `true` when decoded blocks are waiting for the `ObjectWriter` to be ready, see `ObjectWriter::ready()`
+    pub fn is_write_blocked(&self) -> bool {
+        self.state == State::Receiving
+            && self
+                .block_writer
+                .as_ref()
+                .map(|writer| writer.is_blocked())
+                .unwrap_or(false)
+    }
+
+    /// All the blocks are decoded, some are still waiting for the `ObjectWriter` to be ready
+    fn is_decoded_waiting_writer(&self) -> bool {
+        self.is_write_blocked() && self.blocks.iter().all(|block| block.completed)
+    }
+
+    /// Write again the decoded blocks that were waiting for the `ObjectWriter` to be ready
+    pub fn retry_write(&mut self, now: SystemTime) {
+        if self.write_blocks(now).is_err() {
+            self.error("Fail to write blocks to storage", now, false);
+        }
+    }
+
+    ///
+    /// Stop waiting for the missing blocks of the object and deliver the data already decoded
+    ///
+    /// The completed blocks are written in order, until the first missing block,
+    /// then the object is delivered with `ObjectWriter::partial_complete()`.
+    /// Returns `false` if the object is not in `Receiving` state or its `ObjectWriter` is not opened yet.
+    ///
+    pub fn force_complete(&mut self, now: SystemTime) -> bool {
+        if self.state != State::Receiving {
+            return false;
+        }
+
+        let writer_opened = self
+            .object_writer
+            .as_ref()
+            .map(|object_writer| object_writer.state == ObjectWriterSessionState::Opened)
+            .unwrap_or(false);
+        if !writer_opened || self.block_writer.is_none() {
+            return false;
+        }
+
+        if self.write_blocks(now).is_err() {
+            self.error("Fail to write blocks to storage", now, false);
+            return false;
+        }
+
+        // All the blocks were already decoded, the object is complete
+        if self.state != State::Receiving {
+            return self.state == State::Completed;
+        }
+
+        self.deliver_partial("Reception is forced to complete", now, true);
+        true
+    }
+
     fn push_from_cache(&mut self, now: std::time::SystemTime) {
         if self.blocks.is_empty() {
             return;
         }
 
-        while let Some(item) = self.cache.pop() {
+        // Packets are replayed in their order of arrival,
+        // a close object flag is processed once all the cached packets are pushed
+        let mut close_object = false;
+        while let Some(item) = self.cache.pop_front() {
             let pkt = item.to_pkt();
-            if self.push_to_block(&pkt, now).is_err() {
+            close_object |= pkt.lct.close_object;
+            if self.push_to_block2(&pkt, now).is_err() {
                 self.error("Fail to push block", now, false);
                 break;
             }
         }
         self.cache_size = 0;
+
+        if close_object && self.state == State::Receiving && !self.is_decoded_waiting_writer() {
+            self.incomplete("No more packet for this object", now, true);
+        }
     }
 
     fn set_cenc_from_pkt(&mut self, pkt: &alc::AlcPkt) {
@@ -619,11 +1291,26 @@ impl ObjectReceiver {
     }
 
     fn set_oti_from_pkt(&mut self, pkt: &alc::AlcPkt, now: SystemTime) {
-        if self.oti.is_some() {
+        if pkt.oti.is_none() {
             return;
         }
 
-        if pkt.oti.is_none() {
+        if self.fti_transfer_length.is_none() {
+            self.fti_transfer_length = pkt.transfer_length;
+        }
+
+        if self.oti.is_some() {
+            if self.oti_from_fdt {
+                self.oti_from_fdt = false;
+                let transfer_length = pkt.transfer_length.unwrap_or_default();
+                if Self::is_oti_conflicting(
+                    self.oti.as_ref().unwrap(),
+                    pkt.oti.as_ref().unwrap(),
+                    transfer_length,
+                ) {
+                    self.reset_oti_from_pkt(pkt, now);
+                }
+            }
             return;
         }
 
@@ -654,7 +1341,199 @@ impl ObjectReceiver {
         }
     }
 
-    fn cache(&mut self, pkt: &alc::AlcPkt) -> Result<()> {
+    /// Compare the OTI announced in the FDT with the one received in the inband FTI
+    ///
+    /// Raptor and RaptorQ do not transmit the maximum source block length and the number
+    /// of parity symbols in the FTI, the resulting block partitioning is compared instead
+    fn is_oti_conflicting(fdt_oti: &oti::Oti, fti_oti: &oti::Oti, transfer_length: u64) -> bool {
+        if fdt_oti.fec_encoding_id != fti_oti.fec_encoding_id
+            || fdt_oti.fec_instance_id != fti_oti.fec_instance_id
+            || fdt_oti.encoding_symbol_length != fti_oti.encoding_symbol_length
+        {
+            return true;
+        }
+
+        let sub_blocks = |oti: &oti::Oti| match oti.scheme_specific.as_ref() {
+            Some(oti::SchemeSpecific::Raptor(scheme)) => {
+                Some((scheme.sub_blocks_length as u16, scheme.symbol_alignment))
+            }
+            Some(oti::SchemeSpecific::RaptorQ(scheme)) => {
+                Some((scheme.sub_blocks_length, scheme.symbol_alignment))
+            }
+            _ => None,
+        };
+
+        match fti_oti.fec_encoding_id {
+            oti::FECEncodingID::Raptor | oti::FECEncodingID::RaptorQ => {
+                let partitioning = |oti: &oti::Oti| {
+                    partition::block_partitioning(
+                        oti.maximum_source_block_length as u64,
+                        transfer_length,
+                        oti.encoding_symbol_length as u64,
+                    )
+                };
+                sub_blocks(fdt_oti) != sub_blocks(fti_oti)
+                    || partitioning(fdt_oti) != partitioning(fti_oti)
+            }
+            _ => {
+                fdt_oti.scheme_specific != fti_oti.scheme_specific
+                    || fdt_oti.maximum_source_block_length != fti_oti.maximum_source_block_length
+                    || fdt_oti.max_number_of_parity_symbols != fti_oti.max_number_of_parity_symbols
+            }
+        }
+    }
+
+    /// The inband FTI is authoritative (RFC 6726), replace the OTI taken from the FDT
+    /// and restart the reception of the blocks with the new parameters
+    fn reset_oti_from_pkt(&mut self, pkt: &alc::AlcPkt, now: SystemTime) {
+        log::error!(
+            "TSI={} TOI={} OTI of the FDT {:?} does not match the inband FTI {:?}, use the FTI",
+            self.tsi,
+            self.toi,
+            self.oti,
+            pkt.oti
+        );
+
+        let transfer_length = match pkt.transfer_length {
+            Some(transfer_length) => transfer_length,
+            None => {
+                self.error("Bug? Pkt contains OTI without transfer length", now, false);
+                return;
+            }
+        };
+
+        if let Some(block_writer) = self.block_writer.as_ref() {
+            if block_writer.bytes_remaining() as u64 != self.transfer_length.unwrap_or_default() {
+                self.error(
+                    "OTI of the FDT does not match the inband FTI and data is already written",
+                    now,
+                    false,
+                );
+                return;
+            }
+        }
+
+        if self.nb_allocated_blocks > 0 {
+            log::warn!(
+                "TSI={} TOI={} Drop {} blocks received with the OTI of the FDT",
+                self.tsi,
+                self.toi,
+                self.nb_allocated_blocks
+            );
+        }
+
+        self.oti = pkt.oti.clone();
+        self.transfer_length = Some(transfer_length);
+        self.blocks.clear();
+        self.nb_allocated_blocks = 0;
+        self.total_allocated_blocks_size = 0;
+
+        // Packets still in the cache are replayed once the blocks are partitioned again
+        self.block_writer = match self.object_writer.is_some() && transfer_length != 0 {
+            true => Some(BlockWriter::new(
+                transfer_length as usize,
+                self.content_length,
+                self.cenc.unwrap_or(lct::Cenc::Null),
+                self.content_md5.is_some(),
+                self.etag_check_algorithm(),
+                self.autodetect_cenc(),
+            )),
+            false => None,
+        };
+
+        if let Some(block_writer) = self.block_writer.as_mut() {
+            block_writer.set_block_crcs(self.md5_failure_diagnostics);
+            block_writer.set_decipher(self.decipher.clone());
+        }
+    }
+
+    fn check_transfer_length(&mut self, now: SystemTime) -> bool {
+        self.check_max_transfer_length(now) && self.check_max_blocks(now)
+    }
+
+    fn check_max_transfer_length(&mut self, now: SystemTime) -> bool {
+        if self.state != State::Receiving {
+            return false;
+        }
+
+        let (max_transfer_length, transfer_length) =
+            match (self.max_transfer_length, self.transfer_length) {
+                (Some(max), Some(transfer_length)) => (max, transfer_length),
+                _ => return true,
+            };
+
+        if transfer_length > max_transfer_length {
+            log::error!(
+                "TSI={} TOI={} Transfer length {} is bigger than the maximum allowed {}",
+                self.tsi,
+                self.toi,
+                transfer_length,
+                max_transfer_length
+            );
+            self.object_writer_builder.transfer_length_exceeded(
+                &self.endpoint,
+                &self.tsi,
+                &self.toi,
+                transfer_length,
+                max_transfer_length,
+                now,
+            );
+            self.error(
+                &format!(
+                    "Transfer length {} is bigger than the maximum allowed {}",
+                    transfer_length, max_transfer_length
+                ),
+                now,
+                false,
+            );
+            return false;
+        }
+
+        true
+    }
+
+    /// Reject objects whose OTI and transfer length imply an absurd number of source blocks,
+    /// before the blocks are partitioned and allocated
+    fn check_max_blocks(&mut self, now: SystemTime) -> bool {
+        if self.state != State::Receiving {
+            return false;
+        }
+
+        let (oti, transfer_length) = match (self.oti.as_ref(), self.transfer_length) {
+            (Some(oti), Some(transfer_length)) => (oti, transfer_length),
+            _ => return true,
+        };
+
+        let (_, _, _, nb_blocks) = partition::block_partitioning(
+            oti.maximum_source_block_length as u64,
+            transfer_length,
+            oti.encoding_symbol_length as u64,
+        );
+
+        if nb_blocks > self.max_blocks as u64 {
+            log::error!(
+                "TSI={} TOI={} Transfer length {} requires {} blocks, the maximum allowed is {}",
+                self.tsi,
+                self.toi,
+                transfer_length,
+                nb_blocks,
+                self.max_blocks
+            );
+            self.error(
+                &format!(
+                    "Transfer length {} requires {} blocks, the maximum allowed is {}",
+                    transfer_length, nb_blocks, self.max_blocks
+                ),
+                now,
+                false,
+            );
+            return false;
+        }
+
+        true
+    }
+
+    fn cache(&mut self, pkt: &alc::AlcPkt) {
         if self.cache_size == 0 {
             log::warn!(
                 "TSI={} TOI={} Packet without FTI received before the FDT",
@@ -663,16 +1542,44 @@ impl ObjectReceiver {
             );
         }
 
-        if self.cache_size >= self.max_size_allocated {
-            return Err(FluteError::new("Pkt cache is full"));
+        // When the cache is full, the packets with the highest ESI are dropped first
+        // as they are the most likely to be repair symbols
+        let esi = |pkt: &alc::AlcPkt| {
+            alc::get_fec_inline_payload_id(pkt)
+                .map(|payload_id| payload_id.esi)
+                .unwrap_or(u32::MAX)
+        };
+
+        let pkt_esi = esi(pkt);
+        while self.cache_size + pkt.data.len() > self.max_cache_size {
+            let highest = self
+                .cache
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (esi(&item.to_pkt()), index))
+                .max();
+
+            match highest {
+                Some((highest_esi, index)) if highest_esi > pkt_esi => {
+                    let item = self.cache.remove(index).unwrap();
+                    self.cache_size -= item.data.len();
+                    self.cache_dropped += 1;
+                }
+                _ => {
+                    log::warn!(
+                        "TSI={} TOI={} Pkt cache is full, drop packet with ESI {}",
+                        self.tsi,
+                        self.toi,
+                        pkt_esi
+                    );
+                    self.cache_dropped += 1;
+                    return;
+                }
+            }
         }
 
-        match self.cache_size.checked_add(pkt.data.len()) {
-            Some(_) => Ok(()),
-            None => Err(FluteError::new("add overflow")),
-        }?;
-        self.cache.push(Box::new(pkt.to_cache()));
-        Ok(())
+        self.cache_size += pkt.data.len();
+        self.cache.push_back(Box::new(pkt.to_cache()));
     }
 
     ///  Block Partitioning Algorithm
@@ -741,7 +1648,7 @@ impl Drop for ObjectReceiver {
                     self.endpoint,
                     self.content_location.as_ref().map(|u| u.to_string())
                 );
-                self.error(
+                self.incomplete(
                     "Drop object in open state, pkt missing ?",
                     self.last_timestamp,
                     false,
@@ -767,3 +1674,666 @@ impl Drop for ObjectReceiver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::time::SystemTime;
+
+    use crate::common::{alc, fdtinstance::FdtInstance, lct, oti, pkt, Profile};
+    use crate::core::UDPEndpoint;
+    use crate::receiver::writer::ObjectWriterBufferBuilder;
+
+    const TRANSFER_LENGTH_100GB: u64 = 100 * 1024 * 1024 * 1024;
+    const MAX_TRANSFER_LENGTH_1GB: u64 = 1024 * 1024 * 1024;
+
+    fn create_object_receiver(writer: Rc<ObjectWriterBufferBuilder>) -> super::ObjectReceiver {
+        create_object_receiver_with_cache(writer, super::DEFAULT_PRE_FDT_CACHE_SIZE)
+    }
+
+    fn create_object_receiver_with_cache(
+        writer: Rc<ObjectWriterBufferBuilder>,
+        max_cache_size: usize,
+    ) -> super::ObjectReceiver {
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        super::ObjectReceiver::new(
+            &endpoint,
+            1,
+            &1,
+            None,
+            writer,
+            true,
+            false,
+            10 * 1024 * 1024,
+            Some(MAX_TRANSFER_LENGTH_1GB),
+            super::DEFAULT_MAX_BLOCKS_PER_OBJECT,
+            max_cache_size,
+            SystemTime::now(),
+        )
+    }
+
+    fn create_conflicting_oti_pkts(content: &[u8], now: SystemTime) -> Vec<Vec<u8>> {
+        let oti = oti::Oti::new_no_code(1424, 64);
+        content
+            .chunks(oti.encoding_symbol_length as usize)
+            .enumerate()
+            .map(|(esi, payload)| {
+                let pkt = pkt::Pkt {
+                    payload: payload.to_vec(),
+                    transfer_length: content.len() as u64,
+                    esi: esi as u32,
+                    sbn: 0,
+                    toi: 1,
+                    fdt_id: None,
+                    cenc: lct::Cenc::Null,
+                    inband_cenc: true,
+                    close_object: false,
+                    source_block_length: 64,
+                    sender_current_time: false,
+                };
+                alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap()
+            })
+            .collect()
+    }
+
+    fn create_conflicting_oti_fdt(content: &[u8]) -> FdtInstance {
+        use base64::Engine;
+        let md5 = base64::engine::general_purpose::STANDARD.encode(md5::compute(content).0);
+        // Stale FDT announcing a symbol length of 1400 while the sender uses 1424
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="64" FEC-OTI-Encoding-Symbol-Length="1400">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}" Content-MD5="{}"/>
+</FDT-Instance>"#,
+            content.len(),
+            md5
+        );
+        FdtInstance::parse(fdt.as_bytes()).unwrap()
+    }
+
+    fn check_conflicting_oti_completed(
+        obj: &super::ObjectReceiver,
+        writer: &ObjectWriterBufferBuilder,
+        content: &[u8],
+    ) {
+        assert!(obj.state == super::State::Completed);
+        assert!(obj.oti.as_ref().unwrap().encoding_symbol_length == 1424);
+
+        let objects = writer.objects.borrow();
+        assert!(objects.len() == 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(!object.error);
+        assert!(object.data == content);
+    }
+
+    #[test]
+    pub fn test_conflicting_oti_fdt_first() {
+        crate::tests::init();
+        let content: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let now = SystemTime::now();
+        let fdt = create_conflicting_oti_fdt(&content);
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+        assert!(obj.oti.as_ref().unwrap().encoding_symbol_length == 1400);
+
+        for data in create_conflicting_oti_pkts(&content, now) {
+            let alc_pkt = alc::parse_alc_pkt(&data).unwrap();
+            obj.push(&alc_pkt, now);
+        }
+
+        check_conflicting_oti_completed(&obj, &writer, &content);
+    }
+
+    #[test]
+    pub fn test_conflicting_oti_fti_first() {
+        crate::tests::init();
+        let content: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let now = SystemTime::now();
+        let fdt = create_conflicting_oti_fdt(&content);
+        let pkts = create_conflicting_oti_pkts(&content, now);
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        let alc_pkt = alc::parse_alc_pkt(&pkts[0]).unwrap();
+        obj.push(&alc_pkt, now);
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        for data in &pkts[1..] {
+            let alc_pkt = alc::parse_alc_pkt(data).unwrap();
+            obj.push(&alc_pkt, now);
+        }
+
+        check_conflicting_oti_completed(&obj, &writer, &content);
+    }
+
+    #[test]
+    pub fn test_missing_transfer_length_with_cenc() {
+        crate::tests::init();
+        let now = SystemTime::now();
+        // Content-Length is the length of the uncompressed content
+        let fdt = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="64" FEC-OTI-Encoding-Symbol-Length="1400">
+  <File Content-Location="file:///object" TOI="1" Content-Length="100000" Content-Encoding="gzip"/>
+</FDT-Instance>"#;
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+        assert!(obj.state == super::State::Error);
+        assert!(obj.transfer_length.is_none());
+        assert!(writer.objects.borrow().is_empty());
+
+        // Without Content-Encoding, the Content-Length is the Transfer-Length
+        let fdt = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="64" FEC-OTI-Encoding-Symbol-Length="1400">
+  <File Content-Location="file:///object" TOI="1" Content-Length="100000"/>
+</FDT-Instance>"#;
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+        assert!(obj.state == super::State::Receiving);
+        assert_eq!(obj.transfer_length, Some(100000));
+    }
+
+    #[test]
+    pub fn test_blocks_received_in_reverse_order() {
+        crate::tests::init();
+        // 36 symbols split in 9 blocks of 4 symbols
+        let content: Vec<u8> = (0..50000).map(|i| (i % 251) as u8).collect();
+        let oti = oti::Oti::new_no_code(1424, 4);
+        let now = SystemTime::now();
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="4" FEC-OTI-Encoding-Symbol-Length="1424">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}"/>
+</FDT-Instance>"#,
+            content.len()
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        let symbols: Vec<&[u8]> = content
+            .chunks(oti.encoding_symbol_length as usize)
+            .collect();
+        for (index, payload) in symbols.iter().enumerate().rev() {
+            let pkt = pkt::Pkt {
+                payload: payload.to_vec(),
+                transfer_length: content.len() as u64,
+                esi: index as u32 % 4,
+                sbn: index as u32 / 4,
+                toi: 1,
+                fdt_id: None,
+                cenc: lct::Cenc::Null,
+                inband_cenc: true,
+                close_object: false,
+                source_block_length: 4,
+                sender_current_time: false,
+            };
+            let data = alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+            obj.push(&alc::parse_alc_pkt(&data).unwrap(), now);
+        }
+
+        assert!(obj.state == super::State::Completed);
+        let objects = writer.objects.borrow();
+        assert!(objects.len() == 1);
+        assert!(objects[0].borrow().ordered_data() == content);
+    }
+
+    #[test]
+    pub fn test_carousel_symbols_of_completed_block() {
+        crate::tests::init();
+        // 2 blocks of 4 symbols, only the first block is received
+        let content: Vec<u8> = (0..8 * 1424).map(|i| (i % 251) as u8).collect();
+        let oti = oti::Oti::new_no_code(1424, 4);
+        let now = SystemTime::now();
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="4" FEC-OTI-Encoding-Symbol-Length="1424">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}"/>
+</FDT-Instance>"#,
+            content.len()
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        let symbols: Vec<&[u8]> = content
+            .chunks(oti.encoding_symbol_length as usize)
+            .take(4)
+            .collect();
+        // Same block repeated by the carousel
+        for _ in 0..3 {
+            for (esi, payload) in symbols.iter().enumerate() {
+                let pkt = pkt::Pkt {
+                    payload: payload.to_vec(),
+                    transfer_length: content.len() as u64,
+                    esi: esi as u32,
+                    sbn: 0,
+                    toi: 1,
+                    fdt_id: None,
+                    cenc: lct::Cenc::Null,
+                    inband_cenc: true,
+                    close_object: false,
+                    source_block_length: 4,
+                    sender_current_time: false,
+                };
+                let data = alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+                obj.push(&alc::parse_alc_pkt(&data).unwrap(), now);
+            }
+        }
+
+        assert!(obj.state == super::State::Receiving);
+        assert!(obj.blocks[0].completed);
+        assert_eq!(obj.blocks[0].max_burst_loss(), 0);
+        assert_eq!(obj.stats.symbols_received, 12);
+        // Symbols of a completed block are not tracked anymore
+        assert_eq!(obj.stats.symbols_duplicate, 0);
+    }
+
+    #[test]
+    pub fn test_md5_failure_diagnostics() {
+        use base64::Engine;
+        crate::tests::init();
+        // 36 symbols split in 9 blocks of 4 symbols
+        let content: Vec<u8> = (0..50000).map(|i| (i % 251) as u8).collect();
+        let oti = oti::Oti::new_no_code(1424, 4);
+        let now = SystemTime::now();
+        let md5 = base64::engine::general_purpose::STANDARD.encode(md5::compute(&content).0);
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="4" FEC-OTI-Encoding-Symbol-Length="1424">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}" Content-MD5="{}"/>
+</FDT-Instance>"#,
+            content.len(),
+            md5
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        obj.set_md5_failure_diagnostics(true);
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        // Corrupt the second symbol of the block 3
+        let corrupted_index = 13;
+        for (index, payload) in content
+            .chunks(oti.encoding_symbol_length as usize)
+            .enumerate()
+        {
+            let mut payload = payload.to_vec();
+            if index == corrupted_index {
+                payload[10] ^= 0xFF;
+            }
+            let pkt = pkt::Pkt {
+                payload,
+                transfer_length: content.len() as u64,
+                esi: index as u32 % 4,
+                sbn: index as u32 / 4,
+                toi: 1,
+                fdt_id: None,
+                cenc: lct::Cenc::Null,
+                inband_cenc: true,
+                close_object: false,
+                source_block_length: 4,
+                sender_current_time: false,
+            };
+            let data = alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+            obj.push(&alc::parse_alc_pkt(&data).unwrap(), now);
+        }
+
+        assert!(obj.state == super::State::Error);
+        let objects = writer.objects.borrow();
+        assert!(objects.len() == 1);
+        let object = objects[0].borrow();
+        assert!(object.error);
+        let report = object.md5_failure.as_ref().unwrap();
+        log::info!("{:?}", report);
+
+        assert_eq!(report.expected_md5, Some(md5));
+        assert_eq!(report.fdt_transfer_length, Some(content.len() as u64));
+        assert_eq!(report.fti_transfer_length, Some(content.len() as u64));
+        let report_oti = report.oti.as_ref().unwrap();
+        assert_eq!(
+            report_oti.encoding_symbol_length,
+            oti.encoding_symbol_length
+        );
+        assert_eq!(
+            report_oti.maximum_source_block_length,
+            oti.maximum_source_block_length
+        );
+        assert_eq!(report.blocks.len(), 9);
+
+        let block_size = 4 * oti.encoding_symbol_length as usize;
+        let corrupted_blocks: Vec<u32> = report
+            .blocks
+            .iter()
+            .zip(content.chunks(block_size))
+            .filter(|(block, data)| block.crc32 != Some(crate::tools::crc32::crc32(data)))
+            .map(|(block, _)| block.sbn)
+            .collect();
+        assert_eq!(corrupted_blocks, vec![corrupted_index as u32 / 4]);
+
+        assert!(report.blocks.iter().all(|block| !block.fec_decoded
+            && !block.received_before_fdt
+            && block.repair_symbols_received == 0));
+        assert_eq!(report.blocks[0].source_block_length, 4);
+        assert_eq!(report.blocks[0].source_symbols_received, 4);
+    }
+
+    fn receive_corrupted_object(writer: Rc<ObjectWriterBufferBuilder>) -> super::State {
+        use base64::Engine;
+        let content: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let oti = oti::Oti::new_no_code(1424, 4);
+        let now = SystemTime::now();
+        let md5 = base64::engine::general_purpose::STANDARD.encode(md5::compute(&content).0);
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="4" FEC-OTI-Encoding-Symbol-Length="1424">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}" Content-MD5="{}"/>
+</FDT-Instance>"#,
+            content.len(),
+            md5
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let mut obj = create_object_receiver(writer);
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        for (index, payload) in content
+            .chunks(oti.encoding_symbol_length as usize)
+            .enumerate()
+        {
+            let mut payload = payload.to_vec();
+            if index == 2 {
+                payload[0] ^= 0xFF;
+            }
+            let pkt = pkt::Pkt {
+                payload,
+                transfer_length: content.len() as u64,
+                esi: index as u32 % 4,
+                sbn: index as u32 / 4,
+                toi: 1,
+                fdt_id: None,
+                cenc: lct::Cenc::Null,
+                inband_cenc: true,
+                close_object: false,
+                source_block_length: 4,
+                sender_current_time: false,
+            };
+            let data = alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+            obj.push(&alc::parse_alc_pkt(&data).unwrap(), now);
+        }
+        obj.state
+    }
+
+    #[test]
+    pub fn test_md5_policy() {
+        use crate::receiver::writer::Md5Policy;
+        crate::tests::init();
+
+        for policy in [
+            Md5Policy::Verify,
+            Md5Policy::Skip,
+            Md5Policy::VerifyButWarnOnly,
+        ] {
+            let mut writer = ObjectWriterBufferBuilder::new();
+            writer.set_md5_policy(policy);
+            let writer = Rc::new(writer);
+            let state = receive_corrupted_object(writer.clone());
+
+            let objects = writer.objects.borrow();
+            assert!(objects.len() == 1);
+            let object = objects[0].borrow();
+            match policy {
+                Md5Policy::Verify => {
+                    assert!(state == super::State::Error);
+                    assert!(object.error);
+                    assert!(!object.meta.md5_mismatch);
+                }
+                Md5Policy::Skip => {
+                    assert!(state == super::State::Completed);
+                    assert!(object.complete);
+                    assert!(!object.meta.md5_mismatch);
+                }
+                Md5Policy::VerifyButWarnOnly => {
+                    assert!(state == super::State::Completed);
+                    assert!(object.complete);
+                    assert!(object.meta.md5_mismatch);
+                }
+            }
+        }
+
+        // The policy selected from the metadata overrides the default policy
+        let mut writer = ObjectWriterBufferBuilder::new();
+        writer.set_md5_policy(Md5Policy::Verify);
+        writer.set_md5_policy_filter(|meta| match meta.content_location.path() {
+            "/object" => Md5Policy::VerifyButWarnOnly,
+            _ => Md5Policy::Verify,
+        });
+        let writer = Rc::new(writer);
+        let state = receive_corrupted_object(writer.clone());
+        assert!(state == super::State::Completed);
+        assert!(writer.objects.borrow()[0].borrow().meta.md5_mismatch);
+    }
+
+    #[test]
+    pub fn test_reject_oversized_object_from_fdt() {
+        crate::tests::init();
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance Expires="4294967295" FEC-OTI-FEC-Encoding-ID="0" FEC-OTI-Maximum-Source-Block-Length="64" FEC-OTI-Encoding-Symbol-Length="1424">
+  <File Content-Location="file:///large" TOI="1" Transfer-Length="{}"/>
+</FDT-Instance>"#,
+            TRANSFER_LENGTH_100GB
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        let now = SystemTime::now();
+        obj.attach_fdt(1, &fdt, now, now);
+
+        assert!(obj.state == super::State::Error);
+        assert!(obj.nb_block() == 0);
+        assert!(writer.objects.borrow().is_empty());
+    }
+
+    #[test]
+    pub fn test_reject_oversized_object_from_inband_fti() {
+        crate::tests::init();
+        let oti = oti::Oti::new_no_code(1424, 64);
+        let pkt = pkt::Pkt {
+            payload: vec![0u8; 1424],
+            transfer_length: TRANSFER_LENGTH_100GB,
+            esi: 0,
+            sbn: 0,
+            toi: 1,
+            fdt_id: None,
+            cenc: lct::Cenc::Null,
+            inband_cenc: true,
+            close_object: false,
+            source_block_length: 64,
+            sender_current_time: false,
+        };
+        let now = SystemTime::now();
+        let data = alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap();
+        let alc_pkt = alc::parse_alc_pkt(&data).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        obj.push(&alc_pkt, now);
+
+        assert!(obj.state == super::State::Error);
+        assert!(obj.nb_block() == 0);
+        assert!(writer.objects.borrow().is_empty());
+    }
+
+    #[test]
+    pub fn test_reject_pathological_block_count() {
+        crate::tests::init();
+        // 512MB transferred with symbols of 1 byte and blocks of 1 symbol
+        let oti = oti::Oti::new_reed_solomon_rs28_under_specified(1, 1, 0).unwrap();
+        let now = SystemTime::now();
+        let create_pkt = |sbn: u32| {
+            let pkt = pkt::Pkt {
+                payload: vec![0u8; 1],
+                transfer_length: 512 * 1024 * 1024,
+                esi: 0,
+                sbn,
+                toi: 1,
+                fdt_id: None,
+                cenc: lct::Cenc::Null,
+                inband_cenc: true,
+                close_object: false,
+                source_block_length: 1,
+                sender_current_time: false,
+            };
+            alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap()
+        };
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        for sbn in 0..1000 {
+            let data = create_pkt(sbn);
+            let alc_pkt = alc::parse_alc_pkt(&data).unwrap();
+            obj.push(&alc_pkt, now);
+
+            assert!(obj.state == super::State::Error);
+            assert!(obj.nb_block() == 0);
+        }
+        assert!(writer.objects.borrow().is_empty());
+    }
+
+    /// Packets of a NoCode object without FTI, the last packet closes the object
+    fn create_pre_fdt_pkts(content: &[u8], now: SystemTime) -> Vec<Vec<u8>> {
+        let mut oti = oti::Oti::new_no_code(1400, 64);
+        oti.inband_fti = false;
+        let nb_pkts = num_integer::div_ceil(content.len(), 1400);
+        content
+            .chunks(oti.encoding_symbol_length as usize)
+            .enumerate()
+            .map(|(esi, payload)| {
+                let pkt = pkt::Pkt {
+                    payload: payload.to_vec(),
+                    transfer_length: content.len() as u64,
+                    esi: esi as u32,
+                    sbn: 0,
+                    toi: 1,
+                    fdt_id: None,
+                    cenc: lct::Cenc::Null,
+                    inband_cenc: true,
+                    close_object: esi + 1 == nb_pkts,
+                    source_block_length: 64,
+                    sender_current_time: false,
+                };
+                alc::new_alc_pkt(&oti, &0u128, 1, &pkt, Profile::RFC6726, now).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn test_pre_fdt_cache_close_object() {
+        crate::tests::init();
+        let content: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+        let now = SystemTime::now();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        for data in create_pre_fdt_pkts(&content, now) {
+            let alc_pkt = alc::parse_alc_pkt(&data).unwrap();
+            obj.push(&alc_pkt, now);
+        }
+        assert!(obj.state == super::State::Receiving);
+        assert!(obj.cache_size() > content.len());
+
+        let mut fdt = create_conflicting_oti_fdt(&content);
+        fdt.fec_oti_encoding_symbol_length = Some(1400);
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        assert!(obj.state == super::State::Completed);
+        assert!(obj.cache_size() == 0);
+        let objects = writer.objects.borrow();
+        assert!(objects.len() == 1);
+        assert!(objects[0].borrow().data == content);
+    }
+
+    #[test]
+    pub fn test_pre_fdt_cache_drops_highest_esi() {
+        crate::tests::init();
+        let content: Vec<u8> = (0..14000).map(|i| (i % 251) as u8).collect();
+        let now = SystemTime::now();
+        let pkts = create_pre_fdt_pkts(&content, now);
+        let pkt_size = pkts[0].len();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver_with_cache(writer, 3 * pkt_size);
+        for esi in [7, 8, 9, 0, 4, 1, 2, 3] {
+            let alc_pkt = alc::parse_alc_pkt(&pkts[esi]).unwrap();
+            obj.push(&alc_pkt, now);
+        }
+
+        assert!(obj.state == super::State::Receiving);
+        assert!(obj.cache_dropped() == 5);
+        let cached: Vec<u32> = obj
+            .cache
+            .iter()
+            .map(|item| alc::get_fec_inline_payload_id(&item.to_pkt()).unwrap().esi)
+            .collect();
+        assert!(cached == vec![0, 1, 2]);
+    }
+
+    #[test]
+    pub fn test_alternate_content_locations() {
+        crate::tests::init();
+        let content: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let now = SystemTime::now();
+        let fdt = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<FDT-Instance xmlns:mbms2012="urn:3GPP:metadata:2012:MBMS:FLUTE:FDT" Expires="4294967295">
+  <File Content-Location="file:///object" TOI="1" Transfer-Length="{}">
+    <mbms2012:Alternate-Content-Location-1>
+      <mbms2012:Alternate-Content-Location>https://mirror1.example.com/object</mbms2012:Alternate-Content-Location>
+    </mbms2012:Alternate-Content-Location-1>
+    <mbms2012:Alternate-Content-Location-2>
+      <mbms2012:Alternate-Content-Location>https://mirror2.example.com/object</mbms2012:Alternate-Content-Location>
+    </mbms2012:Alternate-Content-Location-2>
+  </File>
+</FDT-Instance>"#,
+            content.len()
+        );
+        let fdt = FdtInstance::parse(fdt.as_bytes()).unwrap();
+
+        let writer = Rc::new(ObjectWriterBufferBuilder::new());
+        let mut obj = create_object_receiver(writer.clone());
+        assert!(obj.attach_fdt(1, &fdt, now, now));
+
+        let data = crate::common::alcbuilder::PacketBuilder::new()
+            .toi(1)
+            .fti(content.len() as u64)
+            .payload(content.clone())
+            .build()
+            .unwrap();
+        obj.push(&alc::parse_alc_pkt(&data).unwrap(), now);
+        assert!(obj.state == super::State::Completed);
+
+        let objects = writer.objects.borrow();
+        let object = objects[0].borrow();
+        assert!(object.data == content);
+        assert_eq!(
+            object.meta.alternate_locations,
+            Some(vec![
+                "https://mirror1.example.com/object".to_owned(),
+                "https://mirror2.example.com/object".to_owned()
+            ])
+        );
+    }
+}
@@ -1,18 +1,35 @@
+use super::fdtdiff::FdtDiff;
 use super::fdtreceiver;
 use super::fdtreceiver::FdtReceiver;
 use super::objectreceiver;
 use super::objectreceiver::ObjectReceiver;
-use super::writer::ObjectWriterBuilder;
+use super::senderclock::SenderClock;
+use super::writer::{ObjectMetadata, ObjectWriterBuilder};
+use crate::common::fdtinstance::{CacheControlChoice, FdtInstance, File};
 use crate::common::udpendpoint::UDPEndpoint;
-use crate::common::{alc, lct};
+use crate::common::{alc, contentlocation, lct, oti};
 use crate::tools::error::FluteError;
 use crate::tools::error::Result;
-use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 use std::time::Duration;
 use std::time::Instant;
 use std::time::SystemTime;
 
+/// Clock used by the receiver to evaluate the expiration of the FDT and of the objects
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ClockSource {
+    /// Use the local clock of the receiver
+    Local,
+    /// Use the Sender Current Time (SCT) transmitted inside the EXT_TIME extension of the ALC/LCT packets.
+    /// The offset between the sender and the local clock is estimated for each session.
+    /// Falls back to the local clock as long as no SCT has been received.
+    SenderSct {
+        /// SCT values deviating from the local clock by more than this duration are ignored
+        max_skew: Duration,
+    },
+}
+
 /// Configuration of the FLUTE Receiver
 ///
 /// The FLUTE receiver uses the `Config` struct to specify various settings and timeouts for the FLUTE session.
@@ -20,43 +37,315 @@ use std::time::SystemTime;
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Config {
     /// Max number of objects with error that the receiver is keeping track of.
-    /// Packets received for an object in error state are discarded
+    /// Packets received for an object in error state are discarded, until the first packet (SBN 0, ESI 0)
+    /// of a new transfer of the object is received and its reception restarts.
+    /// Objects in quarantine (see `quarantine_errored_toi`) are kept even if this limit is exceeded
     pub max_objects_error: usize,
+    /// Duration during which all the packets of an object moved to error are discarded,
+    /// the reception of the object can not restart before the end of this cooldown.
+    /// `Duration::ZERO` disables the quarantine. Default `Duration::ZERO`
+    pub quarantine_errored_toi: Duration,
     /// The receiver expires if no data has been received before this timeout
     /// `None` the receiver never expires except if a close session packet is received
     pub session_timeout: Option<Duration>,
     /// Objects expire if no data has been received before this timeout
     /// `None` Objects never expires, not recommended as object that are not fully reconstructed might continue to consume memory for an finite amount of time.
     pub object_timeout: Option<Duration>,
+    /// Objects are moved to error when a source block that can not be decoded yet has not received any symbol
+    /// before this timeout, ex: the loss exceeded the capacity of the FEC and the repair symbols will never arrive.
+    /// Frees the memory of the undecodable blocks without waiting for `object_timeout`.
+    /// The blocks are checked when a packet of the object is received and by `cleanup()`.
+    /// `None` the source blocks never expire. Default `None`
+    pub source_block_timeout: Option<Duration>,
+    /// FDT instances that are still being received are dropped if no packet has been received for them before this timeout,
+    /// the FDT is received again from the next repetition of the carousel.
+    /// `None` an incomplete FDT instance is kept until it is replaced by a new one. Default `None`
+    pub fdt_timeout: Option<Duration>,
     /// Maximum cache size that can be allocated to received an object. Default is 10MB.
+    /// Encrypted objects are decrypted as a whole once received, those with a larger `Transfer-Length` are rejected.
     pub object_max_cache_size: Option<usize>,
+    /// Maximum size of the packets cached for an object received before its FDT (or its FTI).
+    /// When the cache is full, the packets with the highest ESI are dropped first. Default is 10MB.
+    pub pre_fdt_cache_size: usize,
+    /// Maximum transfer length accepted for an object.
+    /// Objects announcing a larger `Transfer-Length` (FDT or inband FTI) are rejected before any block is allocated.
+    /// `None` no limit is applied. Default is `None`.
+    pub max_object_transfer_length: Option<u64>,
+    /// Maximum number of source blocks accepted for an object.
+    /// Objects whose OTI and `Transfer-Length` imply more blocks are moved to error before any block is allocated,
+    /// and their following packets are discarded as long as the object is tracked in the `max_objects_error` list.
+    /// Default is `1 << 20`.
+    pub max_blocks_per_object: usize,
     /// Enable MD5 check of the received objects. Default `true`
+    /// The check can be skipped for some objects with `ObjectWriter::enable_md5_check()`,
+    /// or completed despite an MD5 mismatch with `ObjectWriterBuilder::md5_policy()`
     pub enable_md5_check: bool,
+    /// Check the `File-ETag` of the received objects against the hash of their content.
+    /// Only the ETags computed by the sender with `ObjectDesc::compute_e_tag()` are checked,
+    /// an object whose content does not match is moved to error. Default `false`
+    pub enable_etag_check: bool,
+    /// Deliver the data already written of the objects that time out or are interrupted with missing blocks,
+    /// to the `ObjectWriter` that allow it with `ObjectWriter::allow_partial_delivery()`. Default `false`
+    pub enable_partial_delivery: bool,
+    /// Normalize the Content-Location of the objects announced in the FDT,
+    /// see `core::content_location::normalize()`, so the same resource always maps to the same URL.
+    /// Default `true`
+    pub normalize_content_location: bool,
+    /// When the MD5 of an object does not match, report per source block how it was decoded
+    /// (symbols received, FEC decoding, CRC-32 of the data written) with `ObjectWriter::md5_failure()` and in the logs.
+    /// Costs 4 bytes per source block of the objects being received. Default `false`
+    pub md5_failure_diagnostics: bool,
+    /// When the content encoding (CENC) of an object is `Null`, detect Gzip or Zlib compressed content
+    /// from its magic number and decompress it.
+    /// Content-Length and Content-MD5 are then considered to describe the compressed content.
+    /// Can lead to false positives on binary content. Default `false`
+    pub autodetect_cenc: bool,
     /// When set to `true`, the receiver will only reconstruct each object once.
     /// If the same object is transferred again, it will be automatically discarded.
     pub object_receive_once: bool,
+    /// When set to `true`, the receiver keeps track of the `File-ETag` of the completed objects.
+    /// An object announced in the FDT with a new TOI but with an already received `File-ETag` is not received again.
+    /// Default `false`
+    pub dedup_by_etag: bool,
+    /// When set to `true`, a completed object (or an object in error) whose TOI is announced again in the FDT
+    /// with a different `Content-MD5`, `File-ETag` or `Transfer-Length` is considered as a new content
+    /// and its reception restarts from the first packet received, whatever its ESI.
+    /// Default `true`
+    pub detect_toi_reuse: bool,
     /// When set to `true`, the receiver will check the expiration date of the FDT.
     pub enable_fdt_expiration_check: bool,
+    /// When set to `true`, an FDT with an instance ID lower than the current one is processed as a new instance
+    /// (ex: the sender has restarted).
+    /// When set to `false`, such FDT is skipped, unless its ID is lower by more than `fdt_version_rollback_threshold`.
+    /// Default `false`
+    pub allow_fdt_version_rollback: bool,
+    /// An FDT instance ID lower than the current one by more than this threshold is considered as a rollback
+    /// of the sender (restart or wraparound of the FDT instance ID) and is always processed. Default `1000`
+    pub fdt_version_rollback_threshold: u32,
+    /// Clock used to evaluate the expiration of the FDT and the cache-control of the objects.
+    pub clock_source: ClockSource,
+    /// Number of the last packets pushed to the `MultiReceiver` that are retained for post-mortem debugging,
+    /// see `MultiReceiver::last_packets()`.
+    /// `0` disables the capture. Default `0`
+    pub packet_capture_size: usize,
+    /// Minimum interval between two full scans of the objects, of the FDTs and of the sessions done by `cleanup()`.
+    /// The calls of `cleanup()` in between are cheap no-ops,
+    /// so the timeouts and expiration dates are honored with this granularity.
+    /// The interval is elapsed when either the `now` passed to `cleanup()` or the monotonic clock has advanced by this duration.
+    /// `Duration::ZERO` scans at each call. Default 100ms
+    pub cleanup_interval: Duration,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             max_objects_error: 0,
+            quarantine_errored_toi: Duration::ZERO,
             session_timeout: None,
             object_timeout: Some(Duration::from_secs(10)),
+            source_block_timeout: None,
+            fdt_timeout: None,
             object_max_cache_size: None,
+            pre_fdt_cache_size: objectreceiver::DEFAULT_PRE_FDT_CACHE_SIZE,
+            max_object_transfer_length: None,
+            max_blocks_per_object: objectreceiver::DEFAULT_MAX_BLOCKS_PER_OBJECT,
             enable_md5_check: true,
+            enable_etag_check: false,
+            enable_partial_delivery: false,
+            normalize_content_location: true,
+            md5_failure_diagnostics: false,
+            autodetect_cenc: false,
             object_receive_once: true,
+            dedup_by_etag: false,
+            detect_toi_reuse: true,
             enable_fdt_expiration_check: true,
+            allow_fdt_version_rollback: false,
+            fdt_version_rollback_threshold: 1000,
+            clock_source: ClockSource::SenderSct {
+                max_skew: Duration::from_secs(3600 * 24),
+            },
+            packet_capture_size: 0,
+            cleanup_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+///
+/// Rate-limit the full scans done by `cleanup()` to one per `Config::cleanup_interval`
+///
+#[derive(Debug)]
+pub(crate) struct CleanupSchedule {
+    last_scan: Option<(Instant, SystemTime)>,
+}
+
+impl CleanupSchedule {
+    pub(crate) fn new() -> Self {
+        CleanupSchedule { last_scan: None }
+    }
+
+    /// Return `true` and record the scan when `interval` is elapsed since the last scan
+    pub(crate) fn is_due(&mut self, interval: Duration, now: SystemTime) -> bool {
+        if let Some((instant, time)) = self.last_scan {
+            let time_elapsed = now
+                .duration_since(time)
+                .map(|elapsed| elapsed >= interval)
+                .unwrap_or(true);
+            if !time_elapsed && instant.elapsed() < interval {
+                return false;
+            }
         }
+        self.last_scan = Some((Instant::now(), now));
+        true
     }
 }
 
+/// Statistics of a FLUTE session
+#[derive(Clone, Debug)]
+pub struct SessionStats {
+    /// Number of objects that have been completed and are still referenced by the FDT
+    pub objects_completed: usize,
+    /// Number of objects being received
+    pub objects_in_progress: usize,
+    /// Number of objects in error state
+    pub objects_error: usize,
+    /// Number of FDT instances that have been received
+    pub fdt_instances_received: usize,
+    /// ID of the current FDT instance
+    pub current_fdt_id: Option<u32>,
+    /// Time of the last received packet
+    pub last_activity: SystemTime,
+    /// Total number of bytes (ALC/LCT packets) received
+    pub total_bytes_received: u64,
+    /// Duration since the creation of the session
+    pub session_age: Duration,
+    /// Number of objects skipped by `ObjectWriterBuilder::object_filter()`
+    pub objects_skipped: usize,
+    /// Total number of source blocks allocated to decode objects
+    pub blocks_allocated: u64,
+    /// Size of the packets currently cached for objects received before their FDT
+    pub pre_fdt_cache_size: usize,
+    /// Number of packets dropped because the cache of an object received before its FDT was full
+    pub pre_fdt_cache_dropped: u64,
+    /// Number of encoding symbols rejected because their length does not match the encoding symbol length of the OTI,
+    /// see `ObjectReceptionStats::last_invalid_symbol_length`
+    pub symbols_invalid_length: u64,
+    /// Number of FDT instances being received
+    pub fdt_instances_in_progress: usize,
+    /// Number of incomplete FDT instances dropped by `Config::fdt_timeout`
+    pub fdt_instances_timed_out: u64,
+}
+
+/// Description of a FLUTE session discovered by the receiver
+///
+/// The FDT related fields are updated each time an FDT instance is completely received
+#[derive(Clone, Debug)]
+pub struct SessionInfo {
+    /// UDP endpoint of the session
+    pub endpoint: UDPEndpoint,
+    /// TSI of the session
+    pub tsi: u64,
+    /// Time of the first packet received
+    pub first_seen: SystemTime,
+    /// Time of the last packet received
+    pub last_activity: SystemTime,
+    /// ID of the latest FDT instance received, `None` if no FDT has been received yet
+    pub latest_fdt_instance_id: Option<u32>,
+    /// Expiration date of the latest FDT instance
+    pub fdt_expires: Option<SystemTime>,
+    /// Number of files announced in the latest FDT instance
+    pub announced_files: usize,
+    /// Sum of the Content-Length (or Transfer-Length) of the files announced in the latest FDT instance
+    pub announced_bytes: u64,
+    /// Number of objects that have been completed and are still referenced by the FDT
+    pub objects_completed: usize,
+    /// `true` if the latest FDT instance has the `Complete` attribute set
+    pub complete: bool,
+    /// MBMS Session Identities of the latest FDT instance, with their expiry in seconds since NTP epoch
+    pub mbms_session_identity_expiry: Vec<(u8, u32)>,
+}
+
+/// Summary of the latest FDT instance received
+#[derive(Clone, Debug)]
+struct FdtSummary {
+    fdt_instance_id: u32,
+    expires: Option<SystemTime>,
+    files: usize,
+    bytes: u64,
+    complete: bool,
+    mbms_session_identity_expiry: Vec<(u8, u32)>,
+}
+
+impl FdtSummary {
+    fn new(fdt_instance_id: u32, instance: &FdtInstance) -> Self {
+        let files = instance.file.as_deref().unwrap_or_default();
+        FdtSummary {
+            fdt_instance_id,
+            expires: instance.get_expiration_date(),
+            files: files.len(),
+            bytes: files
+                .iter()
+                .map(|file| file.content_length.or(file.transfer_length).unwrap_or(0))
+                .sum(),
+            complete: instance.complete == Some(true),
+            mbms_session_identity_expiry: instance
+                .get_mbms_session_identity_expiry()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Progress of an object being received
+#[derive(Clone, Debug)]
+pub struct ObjectProgressInfo {
+    /// Content-Location of the object, `None` if the FDT describing the object is not received yet
+    pub content_location: Option<String>,
+    /// Transfer-Length of the object, `None` if unknown yet
+    pub transfer_length: Option<u64>,
+    /// Fraction of the object written to its `ObjectWriter`, in `[0.0, 1.0]`
+    pub write_progress: f64,
+    /// Number of source blocks of the object, 0 if the OTI is unknown yet
+    pub blocks_total: usize,
+    /// Number of source blocks decoded
+    pub blocks_done: usize,
+    /// Duration since the reception of the first packet of the object
+    pub elapsed: Duration,
+    /// Content-Type of the object, `None` if the FDT describing the object is not received yet
+    pub content_type: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ObjectCompletedMeta {
     expiration_date: SystemTime,
+    /// Cache-Control of the object, only the `Expires` cache is evicted by the receiver
+    cache_control: Option<CacheControlChoice>,
     content_location: url::Url,
+    meta: ObjectMetadata,
+    etag: Option<String>,
+}
+
+/// Attributes of an FDT entry identifying the content transferred under a TOI
+#[derive(Debug)]
+struct ContentSignature {
+    md5: Option<String>,
+    etag: Option<String>,
+    transfer_length: Option<u64>,
+}
+
+impl ContentSignature {
+    fn from_file(file: &File) -> Self {
+        ContentSignature {
+            md5: file.content_md5.clone(),
+            etag: file.file_etag.clone(),
+            transfer_length: file.transfer_length.or(file.content_length),
+        }
+    }
+
+    /// Return `true` if an attribute known in `self` is different in `other`
+    fn has_changed(&self, other: &ContentSignature) -> bool {
+        (self.md5.is_some() && self.md5 != other.md5)
+            || (self.etag.is_some() && self.etag != other.etag)
+            || (self.transfer_length.is_some() && self.transfer_length != other.transfer_length)
+    }
 }
 
 ///
@@ -67,7 +356,12 @@ pub struct Receiver {
     tsi: u64,
     objects: HashMap<u128, Box<ObjectReceiver>>,
     objects_completed: BTreeMap<u128, ObjectCompletedMeta>,
-    objects_error: BTreeSet<u128>,
+    /// Objects in error, with the time of the error
+    objects_error: BTreeMap<u128, SystemTime>,
+    objects_skipped: HashSet<u128>,
+    objects_deduplicated: BTreeMap<u128, String>,
+    etags_completed: HashSet<String>,
+    out_of_band_oti: Option<oti::Oti>,
     fdt_receivers: BTreeMap<u32, Box<FdtReceiver>>,
     fdt_current: VecDeque<Box<FdtReceiver>>,
     writer: Rc<dyn ObjectWriterBuilder>,
@@ -76,6 +370,22 @@ pub struct Receiver {
     closed_is_imminent: bool,
     endpoint: UDPEndpoint,
     last_timestamp: Option<SystemTime>,
+    sender_clock: SenderClock,
+    fdt_received: Option<String>,
+    report_objects_completed: bool,
+    objects_completed_received: Vec<(u128, Option<url::Url>)>,
+    fdt_diff: Option<FdtDiff>,
+    fdt_instances_received: usize,
+    total_bytes_received: u64,
+    blocks_allocated: u64,
+    pre_fdt_cache_dropped: u64,
+    symbols_invalid_length: u64,
+    fdt_instances_timed_out: u64,
+    last_pkt_timestamp: Option<SystemTime>,
+    first_pkt_timestamp: Option<SystemTime>,
+    fdt_summary: Option<FdtSummary>,
+    creation_time: Instant,
+    cleanup_schedule: CleanupSchedule,
 }
 
 impl Receiver {
@@ -98,6 +408,7 @@ impl Receiver {
         writer: Rc<dyn ObjectWriterBuilder>,
         config: Option<Config>,
     ) -> Self {
+        let config = config.unwrap_or_default();
         Self {
             tsi,
             objects: HashMap::new(),
@@ -105,15 +416,43 @@ impl Receiver {
             fdt_current: VecDeque::new(),
             writer,
             objects_completed: BTreeMap::new(),
-            objects_error: BTreeSet::new(),
-            config: config.unwrap_or_default(),
+            objects_error: BTreeMap::new(),
+            objects_skipped: HashSet::new(),
+            objects_deduplicated: BTreeMap::new(),
+            etags_completed: HashSet::new(),
+            out_of_band_oti: None,
+            config,
             last_activity: Instant::now(),
             closed_is_imminent: false,
             endpoint: endpoint.clone(),
             last_timestamp: None,
+            sender_clock: SenderClock::new(config.clock_source),
+            fdt_received: None,
+            report_objects_completed: false,
+            objects_completed_received: Vec::new(),
+            fdt_diff: None,
+            fdt_instances_received: 0,
+            total_bytes_received: 0,
+            blocks_allocated: 0,
+            pre_fdt_cache_dropped: 0,
+            symbols_invalid_length: 0,
+            fdt_instances_timed_out: 0,
+            last_pkt_timestamp: None,
+            first_pkt_timestamp: None,
+            fdt_summary: None,
+            creation_time: Instant::now(),
+            cleanup_schedule: CleanupSchedule::new(),
         }
     }
 
+    /// Set the Object Transmission Information (OTI) delivered out-of-band (ex: SDP).
+    ///
+    /// This OTI is used for the objects that are neither described by the FDT nor by an inband FTI.
+    ///
+    pub fn set_out_of_band_oti(&mut self, oti: Option<oti::Oti>) {
+        self.out_of_band_oti = oti;
+    }
+
     /// Check if the receiver is expired.
     ///
     /// This method checks whether the receiver is expired and returns `true` if it is.
@@ -159,10 +498,62 @@ impl Receiver {
         self.objects_error.len()
     }
 
+    /// Get the statistics of this session.
+    ///
+    /// # Returns
+    ///
+    /// The `SessionStats` of the `Receiver`.
+    ///
+    pub fn stats(&self) -> SessionStats {
+        SessionStats {
+            objects_completed: self.objects_completed.len(),
+            objects_in_progress: self.objects.len(),
+            objects_error: self.objects_error.len(),
+            fdt_instances_received: self.fdt_instances_received,
+            current_fdt_id: self.fdt_current.front().map(|fdt| fdt.fdt_id),
+            last_activity: self.last_pkt_timestamp.unwrap_or_else(SystemTime::now),
+            total_bytes_received: self.total_bytes_received,
+            session_age: self.creation_time.elapsed(),
+            objects_skipped: self.objects_skipped.len(),
+            blocks_allocated: self.blocks_allocated,
+            pre_fdt_cache_size: self.objects.values().map(|obj| obj.cache_size()).sum(),
+            pre_fdt_cache_dropped: self.pre_fdt_cache_dropped,
+            symbols_invalid_length: self.symbols_invalid_length,
+            fdt_instances_in_progress: self
+                .fdt_receivers
+                .values()
+                .filter(|fdt| fdt.state() == fdtreceiver::FDTState::Receiving)
+                .count(),
+            fdt_instances_timed_out: self.fdt_instances_timed_out,
+        }
+    }
+
+    /// Get the description of this session and of its latest FDT instance
+    pub fn session_info(&self) -> SessionInfo {
+        let last_activity = self.last_pkt_timestamp.unwrap_or_else(SystemTime::now);
+        let fdt = self.fdt_summary.as_ref();
+        SessionInfo {
+            endpoint: self.endpoint.clone(),
+            tsi: self.tsi,
+            first_seen: self.first_pkt_timestamp.unwrap_or(last_activity),
+            last_activity,
+            latest_fdt_instance_id: fdt.map(|fdt| fdt.fdt_instance_id),
+            fdt_expires: fdt.and_then(|fdt| fdt.expires),
+            announced_files: fdt.map(|fdt| fdt.files).unwrap_or(0),
+            announced_bytes: fdt.map(|fdt| fdt.bytes).unwrap_or(0),
+            objects_completed: self.objects_completed.len(),
+            complete: fdt.map(|fdt| fdt.complete).unwrap_or(false),
+            mbms_session_identity_expiry: fdt
+                .map(|fdt| fdt.mbms_session_identity_expiry.clone())
+                .unwrap_or_default(),
+        }
+    }
+
     /// Free objects that timed out.
     ///
     /// This method performs cleanup operations on the `Receiver`, freeing objects that
-    /// have timed out.
+    /// have timed out, and writing again the decoded blocks whose `ObjectWriter` was not ready.
+    /// The objects are scanned at most once per `Config::cleanup_interval`, the other calls return immediately.
     ///
     /// # Arguments
     ///
@@ -170,19 +561,111 @@ impl Receiver {
     ///
     pub fn cleanup(&mut self, now: std::time::SystemTime) {
         self.last_timestamp = Some(now);
+        if !self
+            .cleanup_schedule
+            .is_due(self.config.cleanup_interval, now)
+        {
+            return;
+        }
+        self.retry_blocked_writes(now);
+        self.cleanup_source_blocks(now);
         self.cleanup_objects();
+        self.gc_object_error(now);
+        self.cleanup_objects_completed(now);
         self.cleanup_fdt(now);
     }
 
+    /// Write the decoded blocks of the objects whose `ObjectWriter` was not ready
+    fn retry_blocked_writes(&mut self, now: std::time::SystemTime) {
+        let blocked: Vec<u128> = self
+            .objects
+            .iter()
+            .filter(|(_, object)| object.is_write_blocked())
+            .map(|(toi, _)| *toi)
+            .collect();
+
+        for toi in blocked {
+            if let Some(object) = self.objects.get_mut(&toi) {
+                object.retry_write(now);
+            }
+            self.check_object_state(toi, now);
+        }
+    }
+
+    /// Move to error the objects with a source block that has not received any symbol before `Config::source_block_timeout`
+    fn cleanup_source_blocks(&mut self, now: std::time::SystemTime) {
+        if self.config.source_block_timeout.is_none() {
+            return;
+        }
+
+        let expired: Vec<u128> = self
+            .objects
+            .iter_mut()
+            .filter_map(|(toi, object)| object.check_source_block_timeout(now).then_some(*toi))
+            .collect();
+
+        for toi in expired {
+            self.check_object_state(toi, now);
+        }
+    }
+
+    fn cleanup_objects_completed(&mut self, now: std::time::SystemTime) {
+        let expired_objects_toi: Vec<u128> = self
+            .objects_completed
+            .iter()
+            .filter(|(_, meta)| {
+                matches!(meta.cache_control, Some(CacheControlChoice::Expires(_)))
+                    && meta.expiration_date < now
+            })
+            .map(|(toi, _)| *toi)
+            .collect();
+
+        for toi in expired_objects_toi {
+            if let Some(obj) = self.objects_completed.remove(&toi) {
+                log::debug!(
+                    "Cache expired tsi={} toi={} location={}",
+                    self.tsi,
+                    toi,
+                    obj.content_location
+                );
+                self.writer
+                    .on_cache_expired(&self.endpoint, &self.tsi, &toi, &obj.meta, now);
+            }
+        }
+
+        self.gc_etags_completed();
+    }
+
     fn cleanup_fdt(&mut self, now: std::time::SystemTime) {
+        let server_time = self.sender_clock.server_time(now);
         self.fdt_receivers.iter_mut().for_each(|fdt| {
-            fdt.1.update_expired_state(now);
+            fdt.1.update_expired_state(server_time);
         });
 
+        let fdt_timeout = self.config.fdt_timeout;
+        let tsi = self.tsi;
+        let mut timed_out = 0;
         self.fdt_receivers.retain(|_, fdt| {
             let state = fdt.state();
+            if state == fdtreceiver::FDTState::Receiving {
+                if let Some(fdt_timeout) = fdt_timeout {
+                    let duration = fdt.last_activity_duration_since(now);
+                    if duration > fdt_timeout {
+                        log::warn!(
+                            "TSI={} FDT instance {} expired during its reception, last activity={:?} max={:?}",
+                            tsi,
+                            fdt.fdt_id,
+                            duration,
+                            fdt_timeout
+                        );
+                        timed_out += 1;
+                        return false;
+                    }
+                }
+            }
             state == fdtreceiver::FDTState::Complete || state == fdtreceiver::FDTState::Receiving
         });
+        self.fdt_instances_timed_out += timed_out;
     }
 
     fn cleanup_objects(&mut self) {
@@ -254,6 +737,39 @@ impl Receiver {
         self.push(&alc, now)
     }
 
+    /// Push a batch of ALC/LCT packets to the `Receiver`.
+    ///
+    /// Same as calling `push_data()` for each packet, for receivers reading several UDP packets
+    /// per system call (ex: `recvmmsg`). All the packets share the same `now` timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// * `packets` - The payloads of the UDP/IP packets, in order of reception.
+    /// * `now` - The current `SystemTime` to use for time-related operations.
+    ///
+    /// # Returns
+    ///
+    /// The `Result` of each packet, in the same order as `packets`.
+    ///
+    pub fn push_data_batch(
+        &mut self,
+        packets: &[&[u8]],
+        now: std::time::SystemTime,
+    ) -> Vec<Result<()>> {
+        self.last_activity = Instant::now();
+        packets
+            .iter()
+            .map(|data| {
+                self.last_timestamp = Some(now);
+                let alc = alc::parse_alc_pkt(data)?;
+                if alc.lct.tsi != self.tsi {
+                    return Ok(());
+                }
+                self.push_alc(&alc, now)
+            })
+            .collect()
+    }
+
     /// Push ALC/LCT packets to the `Receiver`.
     ///
     /// This method is used to push ALC/LCT packets to the `Receiver`.
@@ -268,27 +784,263 @@ impl Receiver {
     /// A `Result` indicating success (`Ok`) or an error (`Err`).
     ///
     pub fn push(&mut self, alc_pkt: &alc::AlcPkt, now: std::time::SystemTime) -> Result<()> {
-        debug_assert!(self.tsi == alc_pkt.lct.tsi);
         self.last_activity = Instant::now();
+        self.push_alc(alc_pkt, now)
+    }
+
+    fn push_alc(&mut self, alc_pkt: &alc::AlcPkt, now: std::time::SystemTime) -> Result<()> {
+        debug_assert!(self.tsi == alc_pkt.lct.tsi);
         self.last_timestamp = Some(now);
+        self.last_pkt_timestamp = Some(now);
+        self.first_pkt_timestamp.get_or_insert(now);
+        self.total_bytes_received += alc_pkt.data.len() as u64;
 
         if alc_pkt.lct.close_session {
             log::info!("Close session");
             self.closed_is_imminent = true;
         }
 
+        if self.sender_clock.is_sender_sct() {
+            if let Some(sct) = alc_pkt.server_time {
+                self.sender_clock.update(sct, now);
+            }
+        }
+
         match alc_pkt.lct.toi {
             toi if toi == lct::TOI_FDT => self.push_fdt_obj(alc_pkt, now),
             _ => self.push_obj(alc_pkt, now),
         }
     }
 
+    ///
+    /// Stop the reception of an object and deliver the blocks already decoded, see `MultiReceiver::force_complete()`
+    ///
+    /// Returns `false` if the object is unknown, is not being received or can not be delivered yet
+    ///
+    pub fn force_complete(&mut self, toi: u128, now: SystemTime) -> bool {
+        let ret = match self.objects.get_mut(&toi) {
+            Some(obj) => obj.force_complete(now),
+            None => return false,
+        };
+        self.check_object_state(toi, now);
+        ret
+    }
+
+    ///
+    /// Close the session as if a packet with the Close Session flag (A) was received,
+    /// see `MultiReceiver::inject_close_session()`
+    ///
+    /// The reception of the objects in progress is interrupted.
+    ///
+    pub fn handle_close_session(&mut self, now: SystemTime) {
+        log::info!("TSI={} Close session", self.tsi);
+        self.closed_is_imminent = true;
+        self.last_timestamp = Some(now);
+        let tois: Vec<u128> = self.objects.keys().copied().collect();
+        for toi in tois {
+            if let Some(obj) = self.objects.get_mut(&toi) {
+                obj.interrupt(now);
+            }
+            self.check_object_state(toi, now);
+        }
+    }
+
+    /// Return the fraction of an object that has been written to its `ObjectWriter`
+    ///
+    /// # Arguments
+    ///
+    /// * `toi` - TOI of the object
+    ///
+    /// # Returns
+    ///
+    /// A value in `[0.0, 1.0]`, or `None` if the object is not being received
+    /// or if its writer is not created yet (ex: FDT not received)
+    ///
+    pub fn object_write_progress(&self, toi: u128) -> Option<f64> {
+        self.objects.get(&toi).and_then(|obj| obj.write_progress())
+    }
+
+    /// Return the TOI of the completed objects that belong to `group`
+    ///
+    /// Only the objects kept in the cache of the completed objects are listed,
+    /// until their cache duration expires.
+    ///
+    /// # Returns
+    ///
+    /// The TOI of the objects whose FDT entry (or FDT-Instance) includes `group`, sorted by TOI
+    ///
+    pub fn objects_in_group(&self, group: &str) -> Vec<u128> {
+        self.objects_completed
+            .iter()
+            .filter(|(_, obj)| {
+                obj.meta
+                    .groups
+                    .as_ref()
+                    .map(|groups| groups.iter().any(|g| g == group))
+                    .unwrap_or(false)
+            })
+            .map(|(toi, _)| *toi)
+            .collect()
+    }
+
+    /// Return the progress of all the objects being received
+    ///
+    /// # Returns
+    ///
+    /// The TOI and the progress of each object in `Receiving` state, sorted by TOI
+    ///
+    pub fn objects_in_progress(&self) -> Vec<(u128, ObjectProgressInfo)> {
+        let mut objects: Vec<(u128, ObjectProgressInfo)> = self
+            .objects
+            .iter()
+            .filter(|(_, obj)| obj.state == objectreceiver::State::Receiving)
+            .map(|(toi, obj)| (*toi, obj.progress_info()))
+            .collect();
+        objects.sort_by_key(|(toi, _)| *toi);
+        objects
+    }
+
+    /// Return the difference between the current FDT and the previous one
+    ///
+    /// The difference is computed each time a new FDT instance becomes current.
+    /// When the first FDT is received, all its files are reported as added.
+    ///
+    /// # Returns
+    ///
+    /// `None` if no FDT has been received yet
+    ///
+    pub fn fdt_diff(&self) -> Option<FdtDiff> {
+        self.fdt_diff.clone()
+    }
+
+    /// Return the metadata of all the files announced in the current FDT
+    ///
+    /// The list is available as soon as the FDT is received, before any data of the objects.
+    ///
+    /// # Returns
+    ///
+    /// The TOI and the metadata of each file, an empty list if no FDT has been received yet
+    ///
+    pub fn fdt_files(&mut self, now: SystemTime) -> Vec<(u128, ObjectMetadata)> {
+        let server_time = self.sender_clock.server_time(now);
+        let normalize = self.config.normalize_content_location;
+        let fdt_instance = match self
+            .fdt_current
+            .front_mut()
+            .and_then(|fdt| fdt.fdt_instance())
+        {
+            Some(instance) => instance,
+            None => return Vec::new(),
+        };
+
+        fdt_instance
+            .file
+            .iter()
+            .flatten()
+            .map(|file| {
+                (
+                    file.toi.parse().unwrap_or_default(),
+                    Self::file_metadata(fdt_instance, file, server_time, normalize),
+                )
+            })
+            .collect()
+    }
+
+    fn file_metadata(
+        fdt: &FdtInstance,
+        file: &File,
+        server_time: SystemTime,
+        normalize: bool,
+    ) -> ObjectMetadata {
+        let content_location = contentlocation::to_url(&file.content_location, normalize)
+            .unwrap_or_else(|_| url::Url::parse("file:///").unwrap());
+
+        let mut groups = fdt.group.clone().unwrap_or_default();
+        if let Some(group) = file.group.as_ref() {
+            groups.extend(group.iter().cloned());
+        }
+
+        ObjectMetadata {
+            content_location,
+            content_length: file.content_length.map(|c| c as usize),
+            transfer_length: Some(file.get_transfer_length() as usize),
+            content_type: fdt.get_effective_content_type(file),
+            cache_duration: file.get_cache_duration(fdt.get_expiration_date(), server_time),
+            groups: match groups.is_empty() {
+                true => None,
+                false => Some(groups),
+            },
+            md5: file.content_md5.clone(),
+            #[cfg(feature = "opentelemetry")]
+            optel_propagator: file.get_optel_propagator(),
+            #[cfg(not(feature = "opentelemetry"))]
+            optel_propagator: None,
+            oti: fdt.get_oti_for_file(file),
+            cenc: Some(
+                fdt.get_effective_content_encoding(file)
+                    .and_then(|cenc| cenc.as_str().try_into().ok())
+                    .unwrap_or(lct::Cenc::Null),
+            ),
+            content_encoding: file.content_coding.clone(),
+            alternate_locations: file.get_alternate_content_locations(),
+            base_urls: fdt.get_base_urls(),
+            independent_unit_positions: file.get_independent_unit_positions(),
+            partial: false,
+            max_burst_loss: 0,
+            md5_mismatch: false,
+        }
+    }
+
+    /// Return the XML of the FDT received during the last push, if any
+    pub(crate) fn take_fdt_received(&mut self) -> Option<String> {
+        self.fdt_received.take()
+    }
+
+    /// Keep the TOI and Content-Location of the objects completed, until taken with `take_objects_completed()`
+    pub(crate) fn set_report_objects_completed(&mut self, enable: bool) {
+        self.report_objects_completed = enable;
+        if !enable {
+            self.objects_completed_received.clear();
+        }
+    }
+
+    /// Return the TOI and Content-Location of the objects completed since the last call
+    pub(crate) fn take_objects_completed(&mut self) -> Vec<(u128, Option<url::Url>)> {
+        std::mem::take(&mut self.objects_completed_received)
+    }
+
     fn is_fdt_received(&self, fdt_instance_id: u32) -> bool {
         self.fdt_current
             .iter()
             .any(|fdt| fdt.fdt_id == fdt_instance_id)
     }
 
+    /// Return `false` if the FDT instance ID is lower than the current one and the FDT must be skipped
+    fn check_fdt_version_rollback(&self, fdt_instance_id: u32) -> bool {
+        let current_fdt_id = match self.fdt_current.front() {
+            Some(fdt) => fdt.fdt_id,
+            None => return true,
+        };
+
+        if fdt_instance_id >= current_fdt_id {
+            return true;
+        }
+
+        let is_rollback =
+            current_fdt_id - fdt_instance_id > self.config.fdt_version_rollback_threshold;
+        if !is_rollback && !self.config.allow_fdt_version_rollback {
+            log::warn!(
+                "TSI={} FDT ID {} is lower than the current FDT ID {}, skip the FDT",
+                self.tsi,
+                fdt_instance_id,
+                current_fdt_id
+            );
+            return false;
+        }
+
+        true
+    }
+
     fn push_fdt_obj(&mut self, alc_pkt: &alc::AlcPkt, now: std::time::SystemTime) -> Result<()> {
         if alc_pkt.fdt_info.is_none() {
             if alc_pkt.lct.close_object {
@@ -311,6 +1063,11 @@ impl Receiver {
             return Ok(());
         }
 
+        if !self.check_fdt_version_rollback(fdt_instance_id) {
+            return Ok(());
+        }
+
+        let server_time = self.sender_clock.server_time(now);
         {
             let fdt_receiver = self
                 .fdt_receivers
@@ -335,7 +1092,7 @@ impl Receiver {
             fdt_receiver.push(alc_pkt, now);
 
             if fdt_receiver.state() == fdtreceiver::FDTState::Complete {
-                fdt_receiver.update_expired_state(now);
+                fdt_receiver.update_expired_state(server_time);
             }
 
             match fdt_receiver.state() {
@@ -344,7 +1101,6 @@ impl Receiver {
                 fdtreceiver::FDTState::Error => return Err(FluteError::new("Fail to decode FDT")),
                 fdtreceiver::FDTState::Expired => {
                     let expiration = fdt_receiver.get_expiration_time().unwrap_or(now);
-                    let server_time = fdt_receiver.get_server_time(now);
 
                     let expiration: chrono::DateTime<chrono::Utc> = expiration.into();
                     let server_time: chrono::DateTime<chrono::Utc> = server_time.into();
@@ -361,7 +1117,17 @@ impl Receiver {
         }
 
         if let Some(previous_fdt) = self.fdt_current.front() {
-            if previous_fdt.fdt_id + 1 != fdt_instance_id && previous_fdt.fdt_id != fdt_instance_id
+            if fdt_instance_id < previous_fdt.fdt_id {
+                // Previous instances belong to a previous session of the sender
+                log::info!(
+                    "TSI={} FDT ID has rolled back from {} to {}",
+                    self.tsi,
+                    previous_fdt.fdt_id,
+                    fdt_instance_id
+                );
+                self.fdt_current.clear();
+            } else if previous_fdt.fdt_id + 1 != fdt_instance_id
+                && previous_fdt.fdt_id != fdt_instance_id
             {
                 log::warn!(
                     "TSI={} Previous FDT ID {} was current is {} is there an FDT missing ?",
@@ -374,6 +1140,7 @@ impl Receiver {
 
         let fdt_current = self.fdt_receivers.remove(&fdt_instance_id);
         if let Some(mut fdt_current) = fdt_current {
+            self.update_fdt_diff(&mut fdt_current, now);
             if let Some(xml) = fdt_current.fdt_xml_str() {
                 let expiration_date = fdt_current
                     .fdt_instance()
@@ -395,11 +1162,30 @@ impl Receiver {
                     now,
                     fdt_current.ext_time,
                 );
+                if let Some(entries) = fdt_current
+                    .fdt_instance()
+                    .and_then(|inst| inst.get_mbms_session_identity_expiry())
+                {
+                    self.writer.fdt_mbms_session_identity_expiry(
+                        &self.endpoint,
+                        &self.tsi,
+                        &entries,
+                        now,
+                    );
+                }
+                self.fdt_received = Some(xml);
+            }
+            self.fdt_instances_received += 1;
+            if let Some(instance) = fdt_current.fdt_instance() {
+                self.fdt_summary = Some(FdtSummary::new(fdt_instance_id, instance));
             }
             self.fdt_current.push_front(fdt_current);
+            self.deduplicate_objects_using_latest_fdt();
             self.attach_latest_fdt_to_objects(now);
             self.gc_object_completed();
+            self.invalidate_completed_objects_using_latest_fdt();
             self.update_expiration_date_of_completed_objects_using_latest_fdt(now);
+            self.gc_etags_completed();
 
             if self.fdt_current.len() > 10 {
                 self.fdt_current.pop_back();
@@ -409,15 +1195,52 @@ impl Receiver {
         Ok(())
     }
 
+    fn update_fdt_diff(&mut self, fdt_current: &mut FdtReceiver, now: std::time::SystemTime) {
+        let previous_fdt = self.fdt_current.front_mut();
+        if previous_fdt
+            .as_ref()
+            .map(|fdt| fdt.fdt_id == fdt_current.fdt_id)
+            .unwrap_or(false)
+        {
+            return;
+        }
+
+        let current = match fdt_current.fdt_instance() {
+            Some(instance) => instance,
+            None => return,
+        };
+
+        let diff = FdtDiff::new(previous_fdt.and_then(|fdt| fdt.fdt_instance()), current);
+
+        // Skipped objects whose FDT entry has changed are filtered again
+        for file in diff
+            .removed
+            .iter()
+            .chain(diff.modified.iter().flat_map(|(old, new)| [old, new]))
+        {
+            self.objects_skipped.remove(&file.toi);
+        }
+
+        if !diff.is_empty() {
+            self.writer
+                .fdt_changed(&self.endpoint, &self.tsi, &diff, now);
+        }
+        self.fdt_diff = Some(diff);
+    }
+
     fn attach_latest_fdt_to_objects(&mut self, now: std::time::SystemTime) -> Option<()> {
+        let server_time = self.sender_clock.server_time(now);
         let fdt = self.fdt_current.front_mut()?;
         let fdt_id = fdt.fdt_id;
-        let server_time = fdt.get_server_time(now);
         let fdt_instance = fdt.fdt_instance()?;
         log::debug!("TSI={} Attach FDT id {}", self.tsi, fdt_id);
         let mut check_state = Vec::new();
         for obj in &mut self.objects {
+            let blocks_allocated = obj.1.blocks_allocated();
+            let symbols_invalid_length = obj.1.symbols_invalid_length();
             let success = obj.1.attach_fdt(fdt_id, fdt_instance, now, server_time);
+            self.blocks_allocated += obj.1.blocks_allocated() - blocks_allocated;
+            self.symbols_invalid_length += obj.1.symbols_invalid_length() - symbols_invalid_length;
             if success {
                 check_state.push(*obj.0);
             }
@@ -430,12 +1253,140 @@ impl Receiver {
         Some(())
     }
 
+    /// Objects announced with the `File-ETag` of an already completed object are not received
+    ///
+    /// A TOI stays deduplicated as long as the latest FDT announces it with the same `File-ETag`
+    fn deduplicate_objects_using_latest_fdt(&mut self) -> Option<()> {
+        if !self.config.dedup_by_etag {
+            return None;
+        }
+
+        let fdt = self.fdt_current.front_mut()?;
+        let files = fdt.fdt_instance()?.file.as_ref()?;
+        let mut objects_deduplicated = BTreeMap::new();
+        for file in files {
+            let toi: u128 = file.toi.parse().unwrap_or_default();
+            let etag = match file.file_etag.as_ref() {
+                Some(etag) => etag,
+                None => continue,
+            };
+
+            let already_received = !self.objects_completed.contains_key(&toi)
+                && (self.etags_completed.contains(etag)
+                    || self.objects_deduplicated.get(&toi) == Some(etag));
+            if !already_received {
+                continue;
+            }
+
+            if !self.objects_deduplicated.contains_key(&toi) {
+                log::debug!(
+                    "TSI={} TOI={} ETag {:?} has already been received, skip the object",
+                    self.tsi,
+                    toi,
+                    etag
+                );
+                self.objects.remove(&toi);
+            }
+            objects_deduplicated.insert(toi, etag.clone());
+        }
+
+        self.objects_deduplicated = objects_deduplicated;
+        Some(())
+    }
+
+    /// Forget the `File-ETag` of objects that are no longer in the cache of completed objects
+    fn gc_etags_completed(&mut self) {
+        if self.etags_completed.is_empty() {
+            return;
+        }
+
+        let etags: HashSet<&String> = self
+            .objects_completed
+            .values()
+            .filter_map(|meta| meta.etag.as_ref())
+            .collect();
+        self.etags_completed.retain(|etag| etags.contains(etag));
+    }
+
+    /// Objects whose content has changed in the latest FDT (new MD5) can be received again
+    ///
+    /// When `detect_toi_reuse` is enabled, a change of `File-ETag` or `Transfer-Length` is also detected
+    /// and objects in error whose FDT entry has changed are received again
+    fn invalidate_completed_objects_using_latest_fdt(&mut self) -> Option<()> {
+        if self.config.object_receive_once && !self.config.detect_toi_reuse {
+            return None;
+        }
+
+        let previous_files: HashMap<u128, ContentSignature> = match self.config.detect_toi_reuse {
+            true => self
+                .fdt_current
+                .get_mut(1)
+                .and_then(|fdt| fdt.fdt_instance())
+                .and_then(|instance| instance.file.as_ref())
+                .map(|files| {
+                    files
+                        .iter()
+                        .map(|file| {
+                            (
+                                file.toi.parse().unwrap_or_default(),
+                                ContentSignature::from_file(file),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            false => HashMap::new(),
+        };
+
+        let fdt = self.fdt_current.front_mut()?;
+        let files = fdt.fdt_instance()?.file.as_ref()?;
+        for file in files {
+            let toi: u128 = file.toi.parse().unwrap_or_default();
+            let content_changed = match self.objects_completed.get(&toi) {
+                Some(obj) if self.config.detect_toi_reuse => ContentSignature {
+                    md5: obj.meta.md5.clone(),
+                    etag: obj.etag.clone(),
+                    transfer_length: obj.meta.transfer_length.map(|l| l as u64),
+                }
+                .has_changed(&ContentSignature::from_file(file)),
+                Some(obj) => obj.meta.md5.is_some() && obj.meta.md5 != file.content_md5,
+                None => false,
+            };
+
+            if content_changed {
+                log::info!(
+                    "TSI={} TOI={} content has changed, object can be received again",
+                    self.tsi,
+                    toi
+                );
+                self.objects_completed.remove(&toi);
+            }
+
+            let error_changed = self.objects_error.contains_key(&toi)
+                && previous_files
+                    .get(&toi)
+                    .map(|previous| previous.has_changed(&ContentSignature::from_file(file)))
+                    .unwrap_or(false);
+
+            if error_changed {
+                log::info!(
+                    "TSI={} TOI={} content has changed, object in error can be received again",
+                    self.tsi,
+                    toi
+                );
+                self.objects_error.remove(&toi);
+            }
+        }
+
+        Some(())
+    }
+
     fn update_expiration_date_of_completed_objects_using_latest_fdt(
         &mut self,
         now: std::time::SystemTime,
     ) -> Option<()> {
+        let server_time = self.sender_clock.server_time(now);
         let fdt = self.fdt_current.front_mut()?;
-        let server_time = fdt.get_server_time(now);
         let fdt_instance = fdt.fdt_instance()?;
         let files = fdt_instance.file.as_ref()?;
         let expiration_date = fdt_instance.get_expiration_date();
@@ -455,12 +1406,19 @@ impl Receiver {
                 .collect();
 
             if !remove_candidates.is_empty() {
-                let content_locations: std::collections::HashSet<&str> =
-                    files.iter().map(|f| f.content_location.as_str()).collect();
+                let normalize = self.config.normalize_content_location;
+                let content_locations: std::collections::HashSet<String> = files
+                    .iter()
+                    .map(|f| {
+                        contentlocation::to_url(&f.content_location, normalize)
+                            .map(|u| u.to_string())
+                            .unwrap_or_else(|_| f.content_location.clone())
+                    })
+                    .collect();
                 let duration = std::time::Duration::from_secs(4);
                 for (toi, meta) in &remove_candidates {
                     let content_location = meta.content_location.to_string();
-                    if !content_locations.contains(content_location.as_str())
+                    if !content_locations.contains(&content_location)
                         && meta.expiration_date > now + duration
                     {
                         self.writer.set_cache_duration(
@@ -482,6 +1440,7 @@ impl Receiver {
             let toi: u128 = file.toi.parse().unwrap_or_default();
             let cache_duration = file.get_cache_duration(expiration_date, server_time);
             if let Some(obj) = self.objects_completed.get_mut(&toi) {
+                obj.cache_control = file.cache_control.as_ref().map(|cc| cc.value.clone());
                 if let Some(cache_duration) = cache_duration {
                     let new_duration = now
                         .checked_add(cache_duration)
@@ -512,6 +1471,12 @@ impl Receiver {
     }
 
     fn push_obj(&mut self, pkt: &alc::AlcPkt, now: SystemTime) -> Result<()> {
+        if self.objects_skipped.contains(&pkt.lct.toi)
+            || self.objects_deduplicated.contains_key(&pkt.lct.toi)
+        {
+            return Ok(());
+        }
+
         if self.objects_completed.contains_key(&pkt.lct.toi) {
             if self.config.object_receive_once {
                 return Ok(());
@@ -525,7 +1490,11 @@ impl Receiver {
                 return Ok(());
             }
         }
-        if self.objects_error.contains(&pkt.lct.toi) {
+        if let Some(error_time) = self.objects_error.get(&pkt.lct.toi) {
+            if self.is_in_quarantine(*error_time, now) {
+                return Ok(());
+            }
+
             let payload_id = alc::get_fec_inline_payload_id(pkt)?;
             if payload_id.sbn == 0 && payload_id.esi == 0 {
                 log::warn!("Re-download object after errors");
@@ -538,6 +1507,9 @@ impl Receiver {
         let mut obj = self.objects.get_mut(&pkt.lct.toi);
         if obj.is_none() {
             self.create_obj(&pkt.lct.toi, now);
+            if self.objects_skipped.contains(&pkt.lct.toi) {
+                return Ok(());
+            }
             obj = self.objects.get_mut(&pkt.lct.toi);
         }
 
@@ -546,7 +1518,13 @@ impl Receiver {
             None => return Err(FluteError::new("Bug ? Object not found")),
         };
 
+        let blocks_allocated = obj.blocks_allocated();
+        let cache_dropped = obj.cache_dropped();
+        let symbols_invalid_length = obj.symbols_invalid_length();
         obj.push(pkt, now);
+        self.blocks_allocated += obj.blocks_allocated() - blocks_allocated;
+        self.pre_fdt_cache_dropped += obj.cache_dropped() - cache_dropped;
+        self.symbols_invalid_length += obj.symbols_invalid_length() - symbols_invalid_length;
         self.check_object_state(pkt.lct.toi, now);
 
         Ok(())
@@ -573,6 +1551,17 @@ impl Receiver {
                         obj.toi
                     );
 
+                    if self.config.dedup_by_etag {
+                        if let Some(etag) = obj.file_etag.as_ref() {
+                            self.etags_completed.insert(etag.clone());
+                        }
+                    }
+
+                    if self.report_objects_completed {
+                        self.objects_completed_received
+                            .push((obj.toi, obj.content_location.clone()));
+                    }
+
                     if obj.cache_expiration_date.is_some() {
                         debug_assert!(obj.content_location.is_some());
                         log::debug!(
@@ -584,7 +1573,10 @@ impl Receiver {
                             obj.toi,
                             ObjectCompletedMeta {
                                 expiration_date: obj.cache_expiration_date.unwrap(),
+                                cache_control: obj.cache_control.clone(),
                                 content_location: obj.content_location.as_ref().unwrap().clone(),
+                                meta: obj.create_meta(),
+                                etag: obj.file_etag.clone(),
                             },
                         );
                     } else {
@@ -598,14 +1590,19 @@ impl Receiver {
                         obj.toi
                     );
                     remove_object = true;
-                    self.objects_error.insert(toi);
-                    self.gc_object_error();
+                    self.objects_error.insert(toi, now);
+                    self.gc_object_error(now);
                 }
                 objectreceiver::State::Error => {
                     log::error!("Object in error state tsi={} toi={}", self.tsi, obj.toi);
                     remove_object = true;
-                    self.objects_error.insert(toi);
-                    self.gc_object_error();
+                    self.objects_error.insert(toi, now);
+                    self.gc_object_error(now);
+                }
+                objectreceiver::State::Skipped => {
+                    log::debug!("Object is skipped tsi={} toi={}", self.tsi, obj.toi);
+                    remove_object = true;
+                    self.objects_skipped.insert(toi);
                 }
             }
         }
@@ -651,13 +1648,35 @@ impl Receiver {
         }
     }
 
-    fn gc_object_error(&mut self) {
-        while self.objects_error.len() > self.config.max_objects_error {
-            let toi = self.objects_error.pop_first().unwrap();
+    fn gc_object_error(&mut self, now: SystemTime) {
+        if self.objects_error.len() <= self.config.max_objects_error {
+            return;
+        }
+
+        let nb_to_remove = self.objects_error.len() - self.config.max_objects_error;
+        let expired: Vec<u128> = self
+            .objects_error
+            .iter()
+            .filter(|(_, error_time)| !self.is_in_quarantine(**error_time, now))
+            .map(|(toi, _)| *toi)
+            .take(nb_to_remove)
+            .collect();
+
+        for toi in expired {
+            self.objects_error.remove(&toi);
             self.objects.remove(&toi);
         }
     }
 
+    /// Return `true` when the packets of an object moved to error at `error_time` must be discarded
+    fn is_in_quarantine(&self, error_time: SystemTime, now: SystemTime) -> bool {
+        if self.config.quarantine_errored_toi.is_zero() {
+            return false;
+        }
+
+        now.duration_since(error_time).unwrap_or_default() < self.config.quarantine_errored_toi
+    }
+
     fn create_obj(&mut self, toi: &u128, now: SystemTime) {
         let mut obj = Box::new(ObjectReceiver::new(
             &self.endpoint,
@@ -666,17 +1685,28 @@ impl Receiver {
             None,
             self.writer.clone(),
             self.config.enable_md5_check,
+            self.config.autodetect_cenc,
             self.config
                 .object_max_cache_size
                 .unwrap_or(10 * 1024 * 1024),
+            self.config.max_object_transfer_length,
+            self.config.max_blocks_per_object,
+            self.config.pre_fdt_cache_size,
             now,
         ));
 
+        obj.set_out_of_band_oti(self.out_of_band_oti.clone());
+        obj.set_etag_check(self.config.enable_etag_check);
+        obj.set_partial_delivery(self.config.enable_partial_delivery);
+        obj.set_normalize_content_location(self.config.normalize_content_location);
+        obj.set_md5_failure_diagnostics(self.config.md5_failure_diagnostics);
+        obj.set_source_block_timeout(self.config.source_block_timeout);
+
         let mut is_attached = false;
+        let server_time = self.sender_clock.server_time(now);
         for (fdt_index, fdt) in (&mut self.fdt_current.iter_mut()).enumerate() {
             let fdt_id = fdt.fdt_id;
-            let server_time = fdt.get_server_time(now);
-            fdt.update_expired_state(now);
+            fdt.update_expired_state(server_time);
             if fdt.state() == fdtreceiver::FDTState::Complete {
                 if let Some(fdt_instance) = fdt.fdt_instance() {
                     let success = obj.attach_fdt(fdt_id, fdt_instance, now, server_time);
@@ -706,6 +1736,12 @@ impl Receiver {
             );
         }
 
+        self.blocks_allocated += obj.blocks_allocated();
+        if obj.state == objectreceiver::State::Skipped {
+            self.objects_skipped.insert(*toi);
+            return;
+        }
+
         self.objects.insert(*toi, obj);
     }
 }
@@ -0,0 +1,102 @@
+use super::receiver::ClockSource;
+use std::time::{Duration, SystemTime};
+
+/// A sample that deviates from the current estimate by more than this value is an outlier
+const OUTLIER_THRESHOLD_US: i64 = 2_000_000;
+/// Re-synchronize the estimate after this number of consecutive outliers (sender clock jump)
+const MAX_CONSECUTIVE_OUTLIERS: u32 = 8;
+
+///
+/// Estimate the offset between the sender clock and the local clock
+/// from the Sender Current Time (SCT) transmitted inside the EXT_TIME extension
+///
+#[derive(Debug)]
+pub struct SenderClock {
+    source: ClockSource,
+    offset_us: Option<i64>,
+    nb_consecutive_outliers: u32,
+}
+
+impl SenderClock {
+    pub fn new(source: ClockSource) -> Self {
+        Self {
+            source,
+            offset_us: None,
+            nb_consecutive_outliers: 0,
+        }
+    }
+
+    pub fn is_sender_sct(&self) -> bool {
+        matches!(self.source, ClockSource::SenderSct { .. })
+    }
+
+    pub fn update(&mut self, sct: SystemTime, now: SystemTime) {
+        let max_skew = match self.source {
+            ClockSource::Local => return,
+            ClockSource::SenderSct { max_skew } => max_skew,
+        };
+
+        let offset_us = Self::signed_diff_us(sct, now);
+        if offset_us.unsigned_abs() > max_skew.as_micros() as u64 {
+            log::warn!(
+                "Sender clock offset of {}us is above the maximum skew {:?}, SCT is ignored",
+                offset_us,
+                max_skew
+            );
+            return;
+        }
+
+        let estimate = match self.offset_us {
+            Some(estimate) => estimate,
+            None => {
+                log::debug!("Sender clock offset initialized to {}us", offset_us);
+                self.offset_us = Some(offset_us);
+                return;
+            }
+        };
+
+        let deviation = offset_us - estimate;
+        if deviation.abs() > OUTLIER_THRESHOLD_US {
+            self.nb_consecutive_outliers += 1;
+            if self.nb_consecutive_outliers < MAX_CONSECUTIVE_OUTLIERS {
+                log::debug!("Reject SCT outlier, deviation={}us", deviation);
+                return;
+            }
+
+            log::warn!(
+                "Sender clock has jumped from an offset of {}us to {}us",
+                estimate,
+                offset_us
+            );
+            self.offset_us = Some(offset_us);
+            self.nb_consecutive_outliers = 0;
+            return;
+        }
+
+        self.nb_consecutive_outliers = 0;
+        // Exponential smoothing with a gain of 1/8
+        self.offset_us = Some(estimate + deviation / 8);
+    }
+
+    /// Return the time of the sender, or the local time when no SCT has been received
+    pub fn server_time(&self, now: SystemTime) -> SystemTime {
+        let offset_us = match self.offset_us {
+            Some(offset) if self.is_sender_sct() => offset,
+            _ => return now,
+        };
+
+        let offset = Duration::from_micros(offset_us.unsigned_abs());
+        let server_time = match offset_us < 0 {
+            true => now.checked_sub(offset),
+            false => now.checked_add(offset),
+        };
+        server_time.unwrap_or(now)
+    }
+
+    fn signed_diff_us(a: SystemTime, b: SystemTime) -> i64 {
+        match a.duration_since(b) {
+            Ok(diff) => diff.as_micros() as i64,
+            Err(e) => -(e.duration().as_micros() as i64),
+        }
+    }
+}
@@ -1,8 +1,19 @@
+use crate::common::lct;
 use crate::tools::ringbuffer::RingBuffer;
 use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
 use std::io::Read;
 use std::io::Write;
 
+/// Detect the content encoding of a buffer from its magic number
+/// Only Gzip and Zlib can be detected, raw Deflate streams have no header
+pub fn detect_cenc(data: &[u8]) -> Option<lct::Cenc> {
+    match data {
+        [0x1f, 0x8b, ..] => Some(lct::Cenc::Gzip),
+        [0x78, flg, ..] if ((0x78u16 << 8) | *flg as u16) % 31 == 0 => Some(lct::Cenc::Zlib),
+        _ => None,
+    }
+}
+
 pub trait Decompress {
     fn write(&mut self, data: &[u8]) -> std::io::Result<usize>;
     fn read(&mut self, data: &mut [u8]) -> std::io::Result<usize>;
@@ -101,6 +112,17 @@ impl Decompress for DecompressZlib {
 
 #[cfg(test)]
 mod tests {
+    use crate::common::lct;
+
     #[test]
     pub fn test_gzip() {}
+
+    #[test]
+    pub fn test_detect_cenc() {
+        assert!(super::detect_cenc(&[0x1f, 0x8b, 0x08]) == Some(lct::Cenc::Gzip));
+        assert!(super::detect_cenc(&[0x78, 0x9c, 0x00]) == Some(lct::Cenc::Zlib));
+        assert!(super::detect_cenc(&[0x78, 0x00, 0x00]).is_none());
+        assert!(super::detect_cenc(b"hello").is_none());
+        assert!(super::detect_cenc(&[]).is_none());
+    }
 }
@@ -0,0 +1,473 @@
+use super::objectwriterfs::{sync_file, FsyncPolicy};
+use crate::error::{FluteError, Result};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::Write,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::JoinHandle,
+};
+
+/// I/O operation on a file, executed by a worker thread
+#[derive(Debug)]
+enum Action {
+    /// Write the data, then sync the file if `sync` is set
+    Write {
+        data: Vec<u8>,
+        sync: bool,
+    },
+    /// Close the file, synced with the folder `parent` if `sync` is set
+    Close {
+        sync: bool,
+        parent: Option<std::path::PathBuf>,
+    },
+    Remove(std::path::PathBuf),
+}
+
+/// First I/O error of a file, reported to the receiver by `FsFileWriter::try_flush()`,
+/// or of the files closed by the workers, reported by `FsWorkers::flush()`
+type FileError = Arc<Mutex<Option<std::io::Error>>>;
+
+#[derive(Debug)]
+struct Job {
+    id: u64,
+    /// File opened by the receiver, handed over to the worker with the first job of the object
+    file: Option<std::fs::File>,
+    action: Action,
+    error: FileError,
+}
+
+impl Job {
+    fn set_error(&self, error: std::io::Error) {
+        let mut file_error = self.error.lock().unwrap_or_else(|e| e.into_inner());
+        if file_error.is_none() {
+            *file_error = Some(error);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    jobs: VecDeque<Job>,
+    busy: bool,
+    closed: bool,
+    max_queued: usize,
+}
+
+/// Bounded queue of jobs of a worker thread
+#[derive(Debug)]
+struct Queue {
+    capacity: usize,
+    state: Mutex<QueueState>,
+    condvar: Condvar,
+}
+
+impl Queue {
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, QueueState>) -> MutexGuard<'a, QueueState> {
+        self.condvar.wait(state).unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn enqueue(&self, mut state: MutexGuard<'_, QueueState>, job: Job) {
+        state.jobs.push_back(job);
+        state.max_queued = state.max_queued.max(state.jobs.len());
+        drop(state);
+        self.condvar.notify_all();
+    }
+
+    /// Queue `job`, or give it back if the queue is full
+    fn try_push(&self, job: Job) -> std::result::Result<(), Job> {
+        let state = self.lock();
+        if state.closed || state.jobs.len() >= self.capacity {
+            return Err(job);
+        }
+        self.enqueue(state, job);
+        Ok(())
+    }
+
+    /// Queue `job`, wait until the queue has room for it
+    fn push(&self, job: Job) {
+        let mut state = self.lock();
+        while !state.closed && state.jobs.len() >= self.capacity {
+            state = self.wait(state);
+        }
+        if state.closed {
+            log::error!("File writer is stopped, drop {:?}", job.action);
+            return;
+        }
+        self.enqueue(state, job);
+    }
+
+    fn pop(&self) -> Option<Job> {
+        let mut state = self.lock();
+        state.busy = false;
+        self.condvar.notify_all();
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                state.busy = true;
+                drop(state);
+                self.condvar.notify_all();
+                return Some(job);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.wait(state);
+        }
+    }
+
+    fn wait_idle(&self) {
+        let mut state = self.lock();
+        while !state.jobs.is_empty() || state.busy {
+            state = self.wait(state);
+        }
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.condvar.notify_all();
+    }
+}
+
+fn run(queue: Arc<Queue>) {
+    let mut files: HashMap<u64, std::io::BufWriter<std::fs::File>> = HashMap::new();
+    while let Some(mut job) = queue.pop() {
+        if let Some(file) = job.file.take() {
+            files.insert(job.id, std::io::BufWriter::new(file));
+        }
+
+        match &job.action {
+            Action::Write { data, sync } => {
+                if let Some(writer) = files.get_mut(&job.id) {
+                    let result = writer.write_all(data).and_then(|_| match sync {
+                        true => sync_file(writer, None),
+                        false => Ok(()),
+                    });
+                    if let Err(e) = result {
+                        log::error!("Fail to write file {:?}", e);
+                        // The next writes of the file are dropped
+                        files.remove(&job.id);
+                        job.set_error(e);
+                    }
+                }
+            }
+            Action::Close { sync, parent } => {
+                if let Some(mut writer) = files.remove(&job.id) {
+                    let result = match sync {
+                        true => sync_file(&mut writer, parent.as_deref()),
+                        false => writer.flush(),
+                    };
+                    if let Err(e) = result {
+                        log::error!("Fail to close file {:?}", e);
+                        job.set_error(e);
+                    }
+                }
+            }
+            Action::Remove(path) => {
+                files.remove(&job.id);
+                log::debug!("Remove file {:?}", path);
+                std::fs::remove_file(path).ok();
+            }
+        }
+    }
+}
+
+///
+/// Pool of worker threads writing the files of `ObjectWriterFS`
+///
+/// All the I/O of an object is done by the same worker, in order.
+/// The queue of each worker holds at most `queue_size` jobs.
+///
+pub(crate) struct FsWorkers {
+    queues: Vec<Arc<Queue>>,
+    threads: Vec<JoinHandle<()>>,
+    next_id: std::cell::Cell<u64>,
+    /// First I/O error of the files closed by the workers
+    error: FileError,
+}
+
+impl std::fmt::Debug for FsWorkers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FsWorkers")
+            .field("nb_threads", &self.threads.len())
+            .field("queue_size", &self.queues.first().map(|q| q.capacity))
+            .finish()
+    }
+}
+
+impl FsWorkers {
+    pub fn new(nb_threads: usize, queue_size: usize) -> Self {
+        let queues: Vec<Arc<Queue>> = (0..nb_threads.max(1))
+            .map(|_| {
+                Arc::new(Queue {
+                    capacity: queue_size.max(1),
+                    state: Mutex::new(QueueState::default()),
+                    condvar: Condvar::new(),
+                })
+            })
+            .collect();
+
+        let threads = queues
+            .iter()
+            .enumerate()
+            .map(|(index, queue)| {
+                let queue = queue.clone();
+                std::thread::Builder::new()
+                    .name(format!("flute-fs-{}", index))
+                    .spawn(move || run(queue))
+                    .expect("Fail to spawn file writer thread")
+            })
+            .collect();
+
+        Self {
+            queues,
+            threads,
+            next_id: std::cell::Cell::new(0),
+            error: FileError::default(),
+        }
+    }
+
+    /// Return the writer of `file`, bound to one of the workers
+    pub fn file_writer(
+        &self,
+        file: std::fs::File,
+        fsync: FsyncPolicy,
+        parent: Option<&std::path::Path>,
+    ) -> FsFileWriter {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        FsFileWriter {
+            queue: self.queues[(id % self.queues.len() as u64) as usize].clone(),
+            id,
+            file: Some(file),
+            pending: Vec::new(),
+            closed: false,
+            fsync,
+            parent: parent.map(|parent| parent.to_path_buf()),
+            error: FileError::default(),
+            close_error: self.error.clone(),
+        }
+    }
+
+    /// Block until all the queued jobs are done,
+    /// return the first I/O error of the files closed since the previous call, if any
+    pub fn flush(&self) -> Result<()> {
+        for queue in &self.queues {
+            queue.wait_idle();
+        }
+        match self.error.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(e) => Err(FluteError::from(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Highest number of jobs waiting in the queue of a worker
+    pub fn max_queued_jobs(&self) -> usize {
+        self.queues
+            .iter()
+            .map(|queue| queue.lock().max_queued)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for FsWorkers {
+    fn drop(&mut self) {
+        // The workers finish the queued jobs before exiting
+        for queue in &self.queues {
+            queue.close();
+        }
+        for thread in self.threads.drain(..) {
+            thread.join().ok();
+        }
+    }
+}
+
+///
+/// Write a file through the queue of a worker
+///
+/// The data is buffered until `try_flush()` or `close()`.
+/// The first I/O error of the worker is returned by the next `try_flush()` or by `close()`,
+/// the errors that occur once the file is closed are returned by `FsWorkers::flush()`.
+///
+#[derive(Debug)]
+pub(crate) struct FsFileWriter {
+    queue: Arc<Queue>,
+    id: u64,
+    file: Option<std::fs::File>,
+    pending: Vec<u8>,
+    closed: bool,
+    fsync: FsyncPolicy,
+    parent: Option<std::path::PathBuf>,
+    error: FileError,
+    close_error: FileError,
+}
+
+impl FsFileWriter {
+    fn job(&mut self, action: Action) -> Job {
+        Job {
+            id: self.id,
+            file: self.file.take(),
+            action,
+            error: self.error.clone(),
+        }
+    }
+
+    /// Take the I/O error of the worker, if any
+    fn take_error(&self) -> Result<()> {
+        match self.error.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(e) => Err(FluteError::from(e)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn write(&mut self, data: &[u8]) {
+        self.pending.extend_from_slice(data);
+    }
+
+    /// Queue the buffered data, return a `WouldBlock` error if the queue is full
+    /// or the I/O error of a previous write
+    pub fn try_flush(&mut self) -> Result<()> {
+        self.take_error()?;
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let data = std::mem::take(&mut self.pending);
+        let sync = self.fsync == FsyncPolicy::PerBlock;
+        let job = self.job(Action::Write { data, sync });
+        if let Err(job) = self.queue.try_push(job) {
+            self.file = job.file;
+            if let Action::Write { data, .. } = job.action {
+                self.pending = data;
+            }
+            return Err(FluteError::would_block("File writer queue is full"));
+        }
+        Ok(())
+    }
+
+    /// Queue the buffered data and close the file, wait for room in the queue if needed
+    ///
+    /// The file is synced by the worker when `fsync` is `FsyncPolicy::OnComplete` or `FsyncPolicy::PerBlock`,
+    /// without waiting for it. Return the I/O error of a previous write, if any
+    pub fn close(mut self) -> Result<()> {
+        self.queue_close();
+        self.take_error()
+    }
+
+    /// Drop the buffered data and remove the file, wait for room in the queue if needed
+    pub fn remove(mut self, path: std::path::PathBuf) {
+        self.pending.clear();
+        let job = self.job(Action::Remove(path));
+        self.queue.push(job);
+        self.closed = true;
+    }
+
+    /// Queue the buffered data and the closing of the file,
+    /// their errors are reported by `FsWorkers::flush()`
+    fn queue_close(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        if !self.pending.is_empty() {
+            let data = std::mem::take(&mut self.pending);
+            let mut job = self.job(Action::Write { data, sync: false });
+            job.error = self.close_error.clone();
+            self.queue.push(job);
+        }
+        let action = Action::Close {
+            sync: self.fsync != FsyncPolicy::None,
+            parent: self.parent.take(),
+        };
+        let mut job = self.job(action);
+        job.error = self.close_error.clone();
+        self.queue.push(job);
+        self.closed = true;
+    }
+}
+
+impl Drop for FsFileWriter {
+    fn drop(&mut self) {
+        self.queue_close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FsFileWriter, FsWorkers, FsyncPolicy, Queue, QueueState};
+    use std::sync::{Arc, Condvar, Mutex};
+
+    #[test]
+    pub fn test_fs_queue_bound() {
+        crate::tests::init();
+        let queue = Arc::new(Queue {
+            capacity: 1,
+            state: Mutex::new(QueueState::default()),
+            condvar: Condvar::new(),
+        });
+        let mut writer = FsFileWriter {
+            queue: queue.clone(),
+            id: 0,
+            file: Some(tempfile::tempfile().unwrap()),
+            pending: Vec::new(),
+            closed: false,
+            fsync: FsyncPolicy::None,
+            parent: None,
+            error: Default::default(),
+            close_error: Default::default(),
+        };
+
+        writer.write(b"hello");
+        assert!(writer.try_flush().is_ok());
+        writer.write(b"world");
+        let err = writer.try_flush().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+        assert_eq!(writer.pending, b"world");
+        assert_eq!(queue.lock().max_queued, 1);
+
+        // The worker takes the first job
+        let job = queue.pop().unwrap();
+        assert!(job.file.is_some());
+        assert!(writer.try_flush().is_ok());
+        assert!(writer.pending.is_empty());
+        assert_eq!(queue.lock().max_queued, 1);
+
+        queue.close();
+    }
+
+    #[test]
+    pub fn test_fs_worker_error() {
+        crate::tests::init();
+        let workers = FsWorkers::new(1, 4);
+        // The files are opened read-only, the writes fail
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = workers.file_writer(
+            std::fs::File::open(file.path()).unwrap(),
+            FsyncPolicy::None,
+            None,
+        );
+        writer.write(&vec![0u8; 100000]);
+        assert!(writer.try_flush().is_ok());
+        workers.flush().unwrap();
+        let err = writer.try_flush().unwrap_err();
+        assert_ne!(err.kind(), std::io::ErrorKind::WouldBlock);
+        writer.remove(file.path().to_path_buf());
+        workers.flush().unwrap();
+        assert!(!file.path().exists());
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer = workers.file_writer(
+            std::fs::File::open(file.path()).unwrap(),
+            FsyncPolicy::OnComplete,
+            None,
+        );
+        writer.write(b"hello");
+        // The write and the sync fail once the file is closed
+        assert!(writer.close().is_ok());
+        assert!(workers.flush().is_err());
+        assert!(workers.flush().is_ok());
+    }
+}
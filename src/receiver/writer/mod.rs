@@ -11,12 +11,16 @@
 //!
 
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::SystemTime;
 
+use crate::common::cipher::ObjectDecipher;
 use crate::common::udpendpoint::UDPEndpoint;
 use crate::core::lct::Cenc;
 use crate::core::Oti;
+use crate::receiver::FdtDiff;
 use crate::tools::error::Result;
 
 ///
@@ -46,6 +50,168 @@ pub struct ObjectMetadata {
     pub oti: Option<Oti>,
     /// CENC information
     pub cenc: Option<Cenc>,
+    /// Content-coding of the object (`X-Content-Coding` of the FDT), not reversed by the receiver:
+    /// the object is delivered with this coding (e.g. a pre-compressed `gzip` content)
+    pub content_encoding: Option<String>,
+    /// Alternate locations where the object can be retrieved (Alternate-Content-Location-1 and -2 of the FDT)
+    pub alternate_locations: Option<Vec<String>>,
+    /// Base URLs of the FDT (Base-URL-1 and -2), used to resolve a relative content location
+    pub base_urls: Option<Vec<String>>,
+    /// Byte offsets of the independent units of the object (IndependentUnitPositions of the FDT),
+    /// a player can start the playback from any of these positions
+    pub independent_unit_positions: Option<Vec<u64>>,
+    /// `true` when the object is delivered partially, see `ObjectWriter::partial_complete()`
+    pub partial: bool,
+    /// Longest run of consecutive encoding symbols lost in a source block of the object,
+    /// known once the object is completed.
+    /// A value larger than the number of repair symbols of a block indicates that interleaving would help
+    pub max_burst_loss: u32,
+    /// `true` when the object is completed although its MD5 does not match the `Content-MD5` of the FDT,
+    /// see `Md5Policy::VerifyButWarnOnly`
+    pub md5_mismatch: bool,
+}
+
+///
+/// Reception statistics of an object
+///
+#[derive(Debug, Clone, Default)]
+pub struct ObjectReceptionStats {
+    /// Time when the first ALC packet of this object was received
+    pub first_packet_time: Option<SystemTime>,
+    /// Time when the object was attached to its FDT entry
+    pub fdt_attach_time: Option<SystemTime>,
+    /// Time when the object was fully reconstructed
+    pub completion_time: Option<SystemTime>,
+    /// Number of ALC packets received for this object
+    pub packets_received: u64,
+    /// Number of bytes (ALC headers included) received for this object
+    pub bytes_received: u64,
+    /// Number of encoding symbols received
+    pub symbols_received: u64,
+    /// Number of encoding symbols discarded because they were already received
+    pub symbols_duplicate: u64,
+    /// Number of repair symbols used to recover the source blocks
+    pub repair_symbols_used: u64,
+    /// Number of source blocks that required FEC decoding
+    pub blocks_decoded_with_fec: u64,
+    /// Longest run of consecutive encoding symbols lost in a source block
+    pub max_burst_loss: u32,
+    /// Most recent Sender Current Time (SCT) received in the ALC/LCT packets of this object
+    pub sender_current_time: Option<SystemTime>,
+    /// Number of encoding symbols rejected because their length does not match the encoding symbol length of the OTI
+    pub symbols_invalid_length: u64,
+    /// Expected and received length of the last encoding symbol rejected because of its length.
+    /// Symbols split across several ALC packets by the sender show up here
+    pub last_invalid_symbol_length: Option<(usize, usize)>,
+}
+
+///
+/// Diagnostics of a source block of an object whose MD5 does not match
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDiagnostics {
+    /// Source Block Number
+    pub sbn: u32,
+    /// Number of source symbols of the block, from the block partitioning
+    pub source_block_length: u32,
+    /// Number of source symbols received before the block was decoded
+    pub source_symbols_received: u32,
+    /// Number of repair symbols received before the block was decoded
+    pub repair_symbols_received: u32,
+    /// `true` when missing source symbols have been recovered by FEC decoding
+    pub fec_decoded: bool,
+    /// CRC-32 of the data of the block, as written (before decompression)
+    pub crc32: Option<u32>,
+    /// `true` when the decoding of the block started before the object was attached to its FDT entry,
+    /// with the OTI of the inband FTI
+    pub received_before_fdt: bool,
+}
+
+///
+/// Report of an object whose MD5 does not match the `Content-MD5` of the FDT
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Md5FailureReport {
+    /// `Content-MD5` announced in the FDT
+    pub expected_md5: Option<String>,
+    /// MD5 of the received content
+    pub received_md5: Option<String>,
+    /// Content encoding used to decode the object
+    pub cenc: Option<Cenc>,
+    /// OTI used to decode the object
+    pub oti: Option<Oti>,
+    /// Transfer length announced in the FDT
+    pub fdt_transfer_length: Option<u64>,
+    /// Transfer length received in the inband FTI
+    pub fti_transfer_length: Option<u64>,
+    /// Diagnostics of the source blocks, ordered by SBN
+    pub blocks: Vec<BlockDiagnostics>,
+}
+
+///
+/// Decision returned by `ObjectWriterBuilder::object_filter()`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFilterDecision {
+    /// Receive and decode the object
+    Receive,
+    /// Ignore the object, its packets are dropped without being decoded
+    Discard,
+    /// Report the FDT entry of the object with `ObjectWriterBuilder::on_object_metadata()`,
+    /// its packets are dropped without being decoded
+    MetadataOnly,
+}
+
+///
+/// MD5 check of an object, returned by `ObjectWriterBuilder::md5_policy()`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Md5Policy {
+    /// Check the MD5 of the object, the object is in error when it does not match
+    #[default]
+    Verify,
+    /// Do not check the MD5 of the object
+    Skip,
+    /// Check the MD5 of the object, the object is completed when it does not match
+    /// with `ObjectMetadata::md5_mismatch` set to `true`
+    VerifyButWarnOnly,
+}
+
+type Md5PolicyFilter = Rc<dyn Fn(&ObjectMetadata) -> Md5Policy>;
+
+///
+/// Default `Md5Policy` of the objects, optionally selected from their metadata
+///
+#[derive(Clone, Default)]
+pub(crate) struct Md5PolicySelector {
+    default: Md5Policy,
+    filter: Option<Md5PolicyFilter>,
+}
+
+impl Md5PolicySelector {
+    pub fn set_default(&mut self, policy: Md5Policy) {
+        self.default = policy;
+    }
+
+    pub fn set_filter(&mut self, filter: Md5PolicyFilter) {
+        self.filter = Some(filter);
+    }
+
+    pub fn policy(&self, meta: &ObjectMetadata) -> Md5Policy {
+        self.filter
+            .as_ref()
+            .map(|filter| filter(meta))
+            .unwrap_or(self.default)
+    }
+}
+
+impl std::fmt::Debug for Md5PolicySelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Md5PolicySelector")
+            .field("default", &self.default)
+            .field("filter", &self.filter.is_some())
+            .finish()
+    }
 }
 
 ///
@@ -83,6 +249,98 @@ pub trait ObjectWriterBuilder {
         now: std::time::SystemTime,
         ext_time: Option<std::time::SystemTime>,
     );
+    /// Called after `fdt_received()` when the FDT announces MBMS Session Identities (3GPP TS 26.346 Section 7.2.10)
+    /// `entries` lists the MBMS Session Identities and their expiry in seconds since NTP epoch
+    fn fdt_mbms_session_identity_expiry(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _entries: &[(u8, u32)],
+        _now: std::time::SystemTime,
+    ) {
+    }
+    /// Called when a new FDT instance adds, removes or modifies files compared to the previous one
+    fn fdt_changed(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _diff: &FdtDiff,
+        _now: std::time::SystemTime,
+    ) {
+    }
+    /// Called when the FDT entry of an object is attached, before any data is decoded
+    ///
+    /// Objects that are not received are skipped until a new FDT modifies their entry.
+    /// Default implementation receives all the objects
+    fn object_filter(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) -> ObjectFilterDecision {
+        ObjectFilterDecision::Receive
+    }
+    /// Called when the FDT entry of an object filtered with `ObjectFilterDecision::MetadataOnly` is attached
+    fn on_object_metadata(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) {
+    }
+    /// Called when the writer of an object is created, select how the MD5 of this object is checked
+    ///
+    /// Only used when MD5 check is enabled in the receiver configuration and the FDT announces a `Content-MD5`.
+    /// Default implementation returns `Md5Policy::Verify`
+    fn md5_policy(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _meta: &ObjectMetadata,
+    ) -> Md5Policy {
+        Md5Policy::Verify
+    }
+    /// Called when the writer of an encrypted object is created,
+    /// return the decipher of the key `key_uri` announced as `Decryption-KEY-URI` in the FDT
+    ///
+    /// The object is decrypted once all its blocks are received, before its decompression (`Cenc`) and its MD5 check.
+    /// Default implementation returns `None`: the object is moved to error, see `ObjectWriter::decryption_key_missing()`
+    fn decipher(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _key_uri: &str,
+    ) -> Option<Arc<dyn ObjectDecipher>> {
+        None
+    }
+    /// Called when an object is rejected because its `Transfer-Length` (FDT or inband FTI) exceeds
+    /// `Config::max_object_transfer_length`, no writer is created for this object
+    fn transfer_length_exceeded(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _transfer_length: u64,
+        _max_transfer_length: u64,
+        _now: std::time::SystemTime,
+    ) {
+    }
+    /// Called when the cache of a completed object has expired
+    fn on_cache_expired(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) {
+    }
 }
 
 ///
@@ -92,13 +350,58 @@ pub trait ObjectWriter {
     /// Open the destination
     fn open(&self, now: SystemTime) -> Result<()>;
     /// Write data
+    /// Data is written in order, once decoded (and uncompressed), whatever the order the source blocks are received
     fn write(&self, data: &[u8], now: SystemTime);
+    /// Called before the data of a source block is written
+    /// Return an error of kind `std::io::ErrorKind::WouldBlock` when the writer can not accept more data for now
+    /// (e.g. its I/O queue is full): the block is kept by the receiver and written again later.
+    /// Any other error moves the object to error. Default implementation returns `Ok(())`
+    fn ready(&self, _now: SystemTime) -> Result<()> {
+        Ok(())
+    }
     /// Called when all the data has been written
     fn complete(&self, now: SystemTime);
+    /// Called when all the data has been written, with the reception statistics of the object
+    /// Default implementation calls `complete()`
+    fn complete_with_stats(&self, now: SystemTime, _stats: &ObjectReceptionStats) {
+        self.complete(now)
+    }
     /// Called when an error occurred during the reception of this object
     fn error(&self, now: SystemTime);
+    /// Called before `error()` when the MD5 of the object does not match the `Content-MD5` of the FDT
+    /// Only used when MD5 failure diagnostics are enabled in the receiver configuration
+    fn md5_failure(&self, _now: SystemTime, _report: &Md5FailureReport) {}
+    /// Called before `complete()` when the MD5 of the object does not match the `Content-MD5` of the FDT
+    /// and the object is checked with `Md5Policy::VerifyButWarnOnly`
+    fn md5_mismatch(&self, _now: SystemTime) {}
+    /// Called before `error()` when the object is encrypted with the key `key_uri`
+    /// and `ObjectWriterBuilder::decipher()` returns no decipher for it
+    fn decryption_key_missing(&self, _now: SystemTime, _key_uri: &str) {}
     /// Called when the sender has interrupted the transmission of this object
     fn interrupted(&self, now: SystemTime);
+    /// Return `false` to skip the MD5 check of this object
+    /// Only used when MD5 check is enabled in the receiver configuration. Default implementation returns `true`
+    fn enable_md5_check(&self) -> bool {
+        true
+    }
+    /// Return `true` to keep the data already written when the reception of this object times out
+    /// or is interrupted with missing blocks, `partial_complete()` is then called instead of `error()` or `interrupted()`.
+    /// Only used when partial delivery is enabled in the receiver configuration. Default implementation returns `false`
+    fn allow_partial_delivery(&self) -> bool {
+        false
+    }
+    /// Called when an incomplete object is delivered partially, the MD5 of the object is not checked
+    /// `meta` is the metadata of the object, flagged `partial`.
+    /// `received_byte_ranges` lists the contiguous byte ranges of the object that have been written
+    /// Default implementation calls `error()`
+    fn partial_complete(
+        &self,
+        now: SystemTime,
+        _meta: &ObjectMetadata,
+        _received_byte_ranges: &[std::ops::Range<u64>],
+    ) {
+        self.error(now)
+    }
 }
 
 impl std::fmt::Debug for dyn ObjectWriterBuilder {
@@ -113,11 +416,28 @@ impl std::fmt::Debug for dyn ObjectWriter {
     }
 }
 
+#[cfg(feature = "fs-workers")]
+mod fsworkers;
 mod objectwriterbuffer;
+#[cfg(feature = "async")]
+mod objectwriterchannel;
 mod objectwriterfs;
+mod objectwritervalidate;
 
 pub use objectwriterbuffer::ObjectWriterBuffer;
 pub use objectwriterbuffer::ObjectWriterBufferBuilder;
 
+#[cfg(feature = "async")]
+pub use objectwriterchannel::ObjectChannel;
+#[cfg(feature = "async")]
+pub use objectwriterchannel::ObjectChannelStatus;
+#[cfg(feature = "async")]
+pub use objectwriterchannel::ObjectWriterChannelBuilder;
+
+pub use objectwriterfs::FileNamePolicy;
+pub use objectwriterfs::FsyncPolicy;
 pub use objectwriterfs::ObjectWriterFS;
 pub use objectwriterfs::ObjectWriterFSBuilder;
+
+pub use objectwritervalidate::ObjectValidation;
+pub use objectwritervalidate::ObjectWriterValidateBuilder;
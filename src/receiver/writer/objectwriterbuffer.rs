@@ -1,14 +1,60 @@
-use super::{ObjectMetadata, ObjectWriter, ObjectWriterBuilder};
-use crate::{common::udpendpoint::UDPEndpoint, tools::error::Result};
-use std::{cell::RefCell, rc::Rc, time::SystemTime};
+use super::{
+    Md5FailureReport, Md5Policy, Md5PolicySelector, ObjectMetadata, ObjectReceptionStats,
+    ObjectWriter, ObjectWriterBuilder,
+};
+use crate::{
+    common::{
+        cipher::{DecipherRegistry, ObjectDecipher},
+        udpendpoint::UDPEndpoint,
+    },
+    tools::error::Result,
+};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+type ObjectList = RefCell<Vec<Rc<RefCell<ObjectWriterBuffer>>>>;
 
 ///
 /// Write objects received by the `receiver` to a buffers
 ///
 #[derive(Debug)]
 pub struct ObjectWriterBufferBuilder {
-    /// List of all objects received
-    pub objects: RefCell<Vec<Rc<RefCell<ObjectWriterBuffer>>>>,
+    /// List of the objects received, until they are evicted by the retention policy
+    /// or taken with `take_completed()`
+    pub objects: Rc<ObjectList>,
+    /// TOI of the objects rejected because their `Transfer-Length` exceeds `Config::max_object_transfer_length`
+    pub transfer_length_exceeded: RefCell<Vec<u128>>,
+    md5_check_filter: Option<fn(&ObjectMetadata) -> bool>,
+    md5_policy: Md5PolicySelector,
+    partial_delivery: bool,
+    retention: Rc<RetentionPolicy>,
+    deciphers: DecipherRegistry,
+}
+
+///
+/// Limits of the objects kept in memory by an `ObjectWriterBufferBuilder`
+///
+#[derive(Clone, Default)]
+struct RetentionPolicy {
+    max_objects: Option<usize>,
+    max_total_bytes: Option<usize>,
+    duration: Option<Duration>,
+    on_evict: Option<Rc<dyn Fn(ObjectWriterBuffer)>>,
+}
+
+impl std::fmt::Debug for RetentionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetentionPolicy")
+            .field("max_objects", &self.max_objects)
+            .field("max_total_bytes", &self.max_total_bytes)
+            .field("duration", &self.duration)
+            .field("on_evict", &self.on_evict.is_some())
+            .finish()
+    }
 }
 
 ///
@@ -17,6 +63,10 @@ pub struct ObjectWriterBufferBuilder {
 #[derive(Debug)]
 struct ObjectWriterBufferWrapper {
     inner: Rc<RefCell<ObjectWriterBuffer>>,
+    md5_check: bool,
+    partial_delivery: bool,
+    objects: Rc<ObjectList>,
+    retention: Rc<RetentionPolicy>,
 }
 
 #[derive(Debug)]
@@ -34,15 +84,107 @@ pub struct ObjectWriterBuffer {
     pub start_time: SystemTime,
     /// Time when the object reception ended
     pub end_time: Option<SystemTime>,
+    /// Reception statistics, available when the object is completed
+    pub stats: Option<ObjectReceptionStats>,
+    /// Byte ranges of `data` received, available when the object is delivered partially
+    pub received_byte_ranges: Option<Vec<std::ops::Range<u64>>>,
+    /// Diagnostics of the object, available when its MD5 does not match and MD5 failure diagnostics are enabled
+    pub md5_failure: Option<Md5FailureReport>,
+    /// `Decryption-KEY-URI` of the object, when it is encrypted with a key that has no registered decipher
+    pub decryption_key_missing: Option<String>,
+}
+
+impl ObjectWriterBuffer {
+    /// Return a copy of the content of the object
+    ///
+    /// The receiver writes the source blocks in SBN order, once decoded (and uncompressed),
+    /// whatever the order they are received, so the content is always assembled in order.
+    pub fn ordered_data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
 }
 
 impl ObjectWriterBufferBuilder {
     /// Return a new `ObjectWriterBuffer`
     pub fn new() -> ObjectWriterBufferBuilder {
         ObjectWriterBufferBuilder {
-            objects: RefCell::new(Vec::new()),
+            objects: Rc::new(RefCell::new(Vec::new())),
+            transfer_length_exceeded: RefCell::new(Vec::new()),
+            md5_check_filter: None,
+            md5_policy: Md5PolicySelector::default(),
+            partial_delivery: false,
+            retention: Rc::new(RetentionPolicy::default()),
+            deciphers: DecipherRegistry::new(),
         }
     }
+
+    /// Keep at most `max_objects` objects in memory, the oldest objects whose reception has ended are evicted first
+    pub fn with_max_objects(mut self, max_objects: usize) -> Self {
+        Rc::make_mut(&mut self.retention).max_objects = Some(max_objects);
+        self
+    }
+
+    /// Keep at most `max_total_bytes` bytes of data in memory, the oldest objects whose reception has ended are evicted first
+    pub fn with_max_total_bytes(mut self, max_total_bytes: usize) -> Self {
+        Rc::make_mut(&mut self.retention).max_total_bytes = Some(max_total_bytes);
+        self
+    }
+
+    /// Evict the objects whose reception has ended for more than `duration`
+    pub fn with_retention(mut self, duration: Duration) -> Self {
+        Rc::make_mut(&mut self.retention).duration = Some(duration);
+        self
+    }
+
+    /// Called with each object evicted by the retention policy, before its data is dropped
+    pub fn on_evict(mut self, callback: impl Fn(ObjectWriterBuffer) + 'static) -> Self {
+        Rc::make_mut(&mut self.retention).on_evict = Some(Rc::new(callback));
+        self
+    }
+
+    /// Remove and return the objects whose reception has ended (completed, in error or delivered partially),
+    /// in order of creation
+    ///
+    /// The objects still being written are kept.
+    pub fn take_completed(&self) -> Vec<ObjectWriterBuffer> {
+        let mut objects = self.objects.borrow_mut();
+        let (completed, receiving): (Vec<_>, Vec<_>) = objects
+            .drain(..)
+            .partition(|obj| obj.borrow().end_time.is_some());
+        *objects = receiving;
+        completed.into_iter().map(take_object).collect()
+    }
+
+    /// Select the objects whose MD5 is checked, from their metadata
+    /// By default, the MD5 of all the objects is checked when MD5 check is enabled in the receiver configuration
+    pub fn set_md5_check_filter(&mut self, filter: fn(&ObjectMetadata) -> bool) {
+        self.md5_check_filter = Some(filter);
+    }
+
+    /// Set how the MD5 of the objects is checked. Default `Md5Policy::Verify`
+    pub fn set_md5_policy(&mut self, policy: Md5Policy) {
+        self.md5_policy.set_default(policy);
+    }
+
+    /// Select how the MD5 of each object is checked, from its metadata.
+    /// Overrides the policy set with `set_md5_policy()`
+    pub fn set_md5_policy_filter(
+        &mut self,
+        filter: impl Fn(&ObjectMetadata) -> Md5Policy + 'static,
+    ) {
+        self.md5_policy.set_filter(Rc::new(filter));
+    }
+
+    /// Keep the objects whose reception times out or is interrupted, with the data received so far.
+    /// Only used when partial delivery is enabled in the receiver configuration. Default `false`
+    pub fn set_partial_delivery(&mut self, enable: bool) {
+        self.partial_delivery = enable;
+    }
+
+    /// Register the decipher of the objects encrypted with the key `key_uri`
+    pub fn add_decipher(&mut self, key_uri: &str, decipher: Arc<dyn ObjectDecipher>) {
+        self.deciphers.insert(key_uri, decipher);
+    }
 }
 
 impl Default for ObjectWriterBufferBuilder {
@@ -60,6 +202,8 @@ impl ObjectWriterBuilder for ObjectWriterBufferBuilder {
         meta: &ObjectMetadata,
         now: std::time::SystemTime,
     ) -> Box<dyn ObjectWriter> {
+        evict(&self.objects, &self.retention, now);
+
         let obj = Rc::new(RefCell::new(ObjectWriterBuffer {
             complete: false,
             error: false,
@@ -67,13 +211,58 @@ impl ObjectWriterBuilder for ObjectWriterBufferBuilder {
             meta: meta.clone(),
             start_time: now,
             end_time: None,
+            stats: None,
+            received_byte_ranges: None,
+            md5_failure: None,
+            decryption_key_missing: None,
         }));
 
-        let obj_wrapper = Box::new(ObjectWriterBufferWrapper { inner: obj.clone() });
+        let obj_wrapper = Box::new(ObjectWriterBufferWrapper {
+            inner: obj.clone(),
+            md5_check: self
+                .md5_check_filter
+                .map(|filter| filter(meta))
+                .unwrap_or(true),
+            partial_delivery: self.partial_delivery,
+            objects: self.objects.clone(),
+            retention: self.retention.clone(),
+        });
         self.objects.borrow_mut().push(obj);
         obj_wrapper
     }
 
+    fn md5_policy(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        meta: &ObjectMetadata,
+    ) -> Md5Policy {
+        self.md5_policy.policy(meta)
+    }
+
+    fn decipher(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        key_uri: &str,
+    ) -> Option<Arc<dyn ObjectDecipher>> {
+        self.deciphers.get(key_uri)
+    }
+
+    fn transfer_length_exceeded(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        toi: &u128,
+        _transfer_length: u64,
+        _max_transfer_length: u64,
+        _now: std::time::SystemTime,
+    ) {
+        self.transfer_length_exceeded.borrow_mut().push(*toi);
+    }
+
     fn set_cache_duration(
         &self,
         _endpoint: &UDPEndpoint,
@@ -99,6 +288,101 @@ impl ObjectWriterBuilder for ObjectWriterBufferBuilder {
     }
 }
 
+/// Move the object out of its handle, without copying its data
+fn take_object(obj: Rc<RefCell<ObjectWriterBuffer>>) -> ObjectWriterBuffer {
+    match Rc::try_unwrap(obj) {
+        Ok(obj) => obj.into_inner(),
+        Err(obj) => {
+            // The `ObjectWriter` of the object is not dropped yet
+            let mut obj = obj.borrow_mut();
+            ObjectWriterBuffer {
+                complete: obj.complete,
+                error: obj.error,
+                data: std::mem::take(&mut obj.data),
+                meta: obj.meta.clone(),
+                start_time: obj.start_time,
+                end_time: obj.end_time,
+                stats: obj.stats.take(),
+                received_byte_ranges: obj.received_byte_ranges.take(),
+                md5_failure: obj.md5_failure.take(),
+                decryption_key_missing: obj.decryption_key_missing.take(),
+            }
+        }
+    }
+}
+
+/// Evict the oldest objects whose reception has ended until the retention policy is satisfied
+fn evict(objects: &ObjectList, retention: &RetentionPolicy, now: SystemTime) {
+    if retention.max_objects.is_none()
+        && retention.max_total_bytes.is_none()
+        && retention.duration.is_none()
+    {
+        return;
+    }
+
+    let evicted = {
+        let mut objects = objects.borrow_mut();
+        let mut nb_objects = objects.len();
+        let mut total_bytes: usize = objects.iter().map(|obj| obj.borrow().data.len()).sum();
+
+        let mut ended: Vec<(SystemTime, usize)> = objects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, obj)| obj.borrow().end_time.map(|end_time| (end_time, index)))
+            .collect();
+        ended.sort();
+
+        let mut evict_index = vec![false; objects.len()];
+        for (end_time, index) in ended {
+            let expired = retention
+                .duration
+                .map(|duration| {
+                    now.duration_since(end_time)
+                        .map(|elapsed| elapsed >= duration)
+                        .unwrap_or(false)
+                })
+                .unwrap_or(false);
+            let too_many = retention
+                .max_objects
+                .map(|max| nb_objects > max)
+                .unwrap_or(false);
+            let too_large = retention
+                .max_total_bytes
+                .map(|max| total_bytes > max)
+                .unwrap_or(false);
+
+            if !expired && !too_many && !too_large {
+                break;
+            }
+
+            nb_objects -= 1;
+            total_bytes -= objects[index].borrow().data.len();
+            evict_index[index] = true;
+        }
+
+        let (evicted, kept): (Vec<_>, Vec<_>) = objects
+            .drain(..)
+            .zip(evict_index)
+            .partition(|(_, evict)| *evict);
+        *objects = kept.into_iter().map(|(obj, _)| obj).collect();
+        evicted
+    };
+
+    for (obj, _) in evicted {
+        let obj = take_object(obj);
+        log::debug!("Evict object {}", obj.meta.content_location);
+        if let Some(on_evict) = retention.on_evict.as_ref() {
+            on_evict(obj);
+        }
+    }
+}
+
+impl ObjectWriterBufferWrapper {
+    fn evict(&self, now: SystemTime) {
+        evict(&self.objects, &self.retention, now);
+    }
+}
+
 impl ObjectWriter for ObjectWriterBufferWrapper {
     fn open(&self, _now: SystemTime) -> Result<()> {
         Ok(())
@@ -114,6 +398,14 @@ impl ObjectWriter for ObjectWriterBufferWrapper {
         log::info!("Object complete !");
         inner.complete = true;
         inner.end_time = Some(now);
+        drop(inner);
+        self.evict(now);
+    }
+
+    fn complete_with_stats(&self, now: SystemTime, stats: &ObjectReceptionStats) {
+        self.inner.borrow_mut().meta.max_burst_loss = stats.max_burst_loss;
+        self.inner.borrow_mut().stats = Some(stats.clone());
+        self.complete(now);
     }
 
     fn error(&self, now: SystemTime) {
@@ -121,6 +413,20 @@ impl ObjectWriter for ObjectWriterBufferWrapper {
         log::error!("Object received with error");
         inner.error = true;
         inner.end_time = Some(now);
+        drop(inner);
+        self.evict(now);
+    }
+
+    fn md5_failure(&self, _now: SystemTime, report: &Md5FailureReport) {
+        self.inner.borrow_mut().md5_failure = Some(report.clone());
+    }
+
+    fn md5_mismatch(&self, _now: SystemTime) {
+        self.inner.borrow_mut().meta.md5_mismatch = true;
+    }
+
+    fn decryption_key_missing(&self, _now: SystemTime, key_uri: &str) {
+        self.inner.borrow_mut().decryption_key_missing = Some(key_uri.to_owned());
     }
 
     fn interrupted(&self, now: SystemTime) {
@@ -128,5 +434,30 @@ impl ObjectWriter for ObjectWriterBufferWrapper {
         log::error!("Object reception interrupted");
         inner.error = true;
         inner.end_time = Some(now);
+        drop(inner);
+        self.evict(now);
+    }
+
+    fn enable_md5_check(&self) -> bool {
+        self.md5_check
+    }
+
+    fn allow_partial_delivery(&self) -> bool {
+        self.partial_delivery
+    }
+
+    fn partial_complete(
+        &self,
+        now: SystemTime,
+        meta: &ObjectMetadata,
+        received_byte_ranges: &[std::ops::Range<u64>],
+    ) {
+        let mut inner = self.inner.borrow_mut();
+        log::warn!("Object partially received {:?}", received_byte_ranges);
+        inner.meta = meta.clone();
+        inner.received_byte_ranges = Some(received_byte_ranges.to_vec());
+        inner.end_time = Some(now);
+        drop(inner);
+        self.evict(now);
     }
 }
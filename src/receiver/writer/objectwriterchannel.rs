@@ -0,0 +1,189 @@
+use super::{ObjectMetadata, ObjectWriter, ObjectWriterBuilder};
+use crate::{
+    common::udpendpoint::UDPEndpoint,
+    tools::error::{FluteError, Result},
+};
+use bytes::Bytes;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tokio::sync::mpsc;
+
+///
+/// Write objects received by the `receiver` to Tokio channels
+///
+/// A channel is created for each object when its reception starts,
+/// the receiving end is available with `ObjectWriterChannelBuilder::pop_object()`
+///
+#[derive(Debug)]
+pub struct ObjectWriterChannelBuilder {
+    capacity: usize,
+    objects: Rc<RefCell<VecDeque<ObjectChannel>>>,
+}
+
+/// Reception status of an object written to a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectChannelStatus {
+    /// Object is being received
+    Receiving,
+    /// All the data of the object has been sent to the channel
+    Complete,
+    /// An error occurred during the reception or the channel was full,
+    /// the data sent to the channel is incomplete
+    Error,
+}
+
+///
+/// Receiving end of an object written to a channel
+///
+/// The channel is closed once the object is complete or in error
+///
+#[derive(Debug)]
+pub struct ObjectChannel {
+    /// Metadata of the object
+    pub meta: ObjectMetadata,
+    /// Data of the object, in order
+    pub receiver: mpsc::Receiver<Bytes>,
+    status: Arc<Mutex<ObjectChannelStatus>>,
+}
+
+#[derive(Debug)]
+struct ObjectWriterChannel {
+    meta: ObjectMetadata,
+    capacity: usize,
+    objects: Rc<RefCell<VecDeque<ObjectChannel>>>,
+    sender: RefCell<Option<mpsc::Sender<Bytes>>>,
+    status: Arc<Mutex<ObjectChannelStatus>>,
+}
+
+impl ObjectWriterChannelBuilder {
+    /// Return a new `ObjectWriterChannelBuilder`
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Capacity of the channel of each object, in number of writes.
+    ///   The object is moved to error if its channel is full
+    pub fn new(capacity: usize) -> ObjectWriterChannelBuilder {
+        ObjectWriterChannelBuilder {
+            capacity,
+            objects: Rc::new(RefCell::new(VecDeque::new())),
+        }
+    }
+
+    /// Return the oldest object whose reception has started, `None` if there is no new object
+    pub fn pop_object(&self) -> Option<ObjectChannel> {
+        self.objects.borrow_mut().pop_front()
+    }
+}
+
+impl ObjectChannel {
+    /// Reception status of the object
+    ///
+    /// Once the channel is closed, `ObjectChannelStatus::Complete` indicates that all the data has been received
+    pub fn status(&self) -> ObjectChannelStatus {
+        *self.status.lock().unwrap()
+    }
+}
+
+impl ObjectWriterBuilder for ObjectWriterChannelBuilder {
+    fn new_object_writer(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) -> Box<dyn ObjectWriter> {
+        Box::new(ObjectWriterChannel {
+            meta: meta.clone(),
+            capacity: self.capacity,
+            objects: self.objects.clone(),
+            sender: RefCell::new(None),
+            status: Arc::new(Mutex::new(ObjectChannelStatus::Receiving)),
+        })
+    }
+
+    fn set_cache_duration(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _content_location: &url::Url,
+        _duration: &std::time::Duration,
+        _now: std::time::SystemTime,
+    ) {
+    }
+
+    fn fdt_received(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _fdt_xml: &str,
+        _expires: std::time::SystemTime,
+        _meta: &ObjectMetadata,
+        _transfer_duration: std::time::Duration,
+        _now: std::time::SystemTime,
+        _ext_time: Option<std::time::SystemTime>,
+    ) {
+    }
+}
+
+impl ObjectWriterChannel {
+    fn close(&self, status: ObjectChannelStatus) {
+        *self.status.lock().unwrap() = status;
+        self.sender.borrow_mut().take();
+    }
+}
+
+impl ObjectWriter for ObjectWriterChannel {
+    fn open(&self, _now: SystemTime) -> Result<()> {
+        if self.capacity == 0 {
+            return Err(FluteError::new(
+                "Capacity of the channel must be greater than 0",
+            ));
+        }
+
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        self.sender.replace(Some(sender));
+        self.objects.borrow_mut().push_back(ObjectChannel {
+            meta: self.meta.clone(),
+            receiver,
+            status: self.status.clone(),
+        });
+        Ok(())
+    }
+
+    fn write(&self, data: &[u8], _now: SystemTime) {
+        let result = match self.sender.borrow().as_ref() {
+            Some(sender) => sender.try_send(Bytes::copy_from_slice(data)),
+            None => return,
+        };
+
+        if let Err(e) = result {
+            log::error!(
+                "Fail to write {} to channel: {}",
+                self.meta.content_location,
+                e
+            );
+            self.close(ObjectChannelStatus::Error);
+        }
+    }
+
+    fn complete(&self, _now: SystemTime) {
+        if self.sender.borrow().is_some() {
+            self.close(ObjectChannelStatus::Complete);
+        }
+    }
+
+    fn error(&self, _now: SystemTime) {
+        self.close(ObjectChannelStatus::Error);
+    }
+
+    fn interrupted(&self, _now: SystemTime) {
+        self.close(ObjectChannelStatus::Error);
+    }
+}
@@ -1,9 +1,46 @@
-use super::{ObjectMetadata, ObjectWriter, ObjectWriterBuilder};
+#[cfg(feature = "fs-workers")]
+use super::fsworkers::{FsFileWriter, FsWorkers};
+use super::{Md5Policy, Md5PolicySelector, ObjectMetadata, ObjectWriter, ObjectWriterBuilder};
 use crate::{
-    common::udpendpoint::UDPEndpoint,
+    common::{
+        cipher::{DecipherRegistry, ObjectDecipher},
+        udpendpoint::UDPEndpoint,
+    },
     error::{FluteError, Result},
 };
-use std::{cell::RefCell, io::Write, time::SystemTime};
+use std::{cell::RefCell, io::Write, rc::Rc, sync::Arc, time::SystemTime};
+
+///
+/// Handling of the file names that are not valid on every platform
+///
+/// Concerns the characters `< > : " | ? *`, the trailing dots and spaces
+/// and the names reserved by Windows (`CON`, `NUL`, `AUX.txt`...).
+/// The file names are converted the same way on every platform.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileNamePolicy {
+    /// Replace the invalid characters with `_`, prefix the reserved names with `_`
+    #[default]
+    Sanitize,
+    /// Percent-encode the invalid characters and the first character of the reserved names
+    PercentEncode,
+    /// Reject the objects with an invalid file name
+    Reject,
+}
+
+///
+/// Durability of the files written by `ObjectWriterFS`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// The files are flushed to the operating system only
+    #[default]
+    None,
+    /// The file (and its parent folder on Unix) is synced to the storage device before the object is reported as completed
+    OnComplete,
+    /// Same as `OnComplete`, the file is also synced each time a source block has been written
+    PerBlock,
+}
 
 ///
 /// Write objects received by the `receiver` to a filesystem
@@ -11,6 +48,15 @@ use std::{cell::RefCell, io::Write, time::SystemTime};
 #[derive(Debug)]
 pub struct ObjectWriterFSBuilder {
     dest: std::path::PathBuf,
+    md5_check_filter: Option<fn(&ObjectMetadata) -> bool>,
+    md5_policy: Md5PolicySelector,
+    partial_delivery: bool,
+    preserve_directories: bool,
+    file_name_policy: FileNamePolicy,
+    fsync_policy: FsyncPolicy,
+    deciphers: DecipherRegistry,
+    #[cfg(feature = "fs-workers")]
+    workers: Option<Rc<FsWorkers>>,
 }
 
 impl ObjectWriterFSBuilder {
@@ -22,8 +68,97 @@ impl ObjectWriterFSBuilder {
 
         Ok(ObjectWriterFSBuilder {
             dest: dest.to_path_buf(),
+            md5_check_filter: None,
+            md5_policy: Md5PolicySelector::default(),
+            partial_delivery: false,
+            preserve_directories: true,
+            file_name_policy: FileNamePolicy::default(),
+            fsync_policy: FsyncPolicy::default(),
+            deciphers: DecipherRegistry::new(),
+            #[cfg(feature = "fs-workers")]
+            workers: None,
         })
     }
+
+    /// Select the objects whose MD5 is checked, from their metadata
+    /// By default, the MD5 of all the objects is checked when MD5 check is enabled in the receiver configuration
+    pub fn set_md5_check_filter(&mut self, filter: fn(&ObjectMetadata) -> bool) {
+        self.md5_check_filter = Some(filter);
+    }
+
+    /// Set how the MD5 of the objects is checked. Default `Md5Policy::Verify`
+    pub fn set_md5_policy(&mut self, policy: Md5Policy) {
+        self.md5_policy.set_default(policy);
+    }
+
+    /// Select how the MD5 of each object is checked, from its metadata.
+    /// Overrides the policy set with `set_md5_policy()`
+    pub fn set_md5_policy_filter(
+        &mut self,
+        filter: impl Fn(&ObjectMetadata) -> Md5Policy + 'static,
+    ) {
+        self.md5_policy.set_filter(Rc::new(filter));
+    }
+
+    /// Keep the file of the objects whose reception times out or is interrupted,
+    /// with the data received so far.
+    /// Only used when partial delivery is enabled in the receiver configuration. Default `false`
+    pub fn set_partial_delivery(&mut self, enable: bool) {
+        self.partial_delivery = enable;
+    }
+
+    /// Keep the folder hierarchy of the content-location in the destination path.
+    /// When disabled, objects whose content-location contains folders are rejected. Default `true`
+    pub fn set_preserve_directories(&mut self, enable: bool) {
+        self.preserve_directories = enable;
+    }
+
+    /// Set how the file names that are not valid on every platform are handled. Default `FileNamePolicy::Sanitize`
+    pub fn set_file_name_policy(&mut self, policy: FileNamePolicy) {
+        self.file_name_policy = policy;
+    }
+
+    /// Set when the files are synced to the storage device. Default `FsyncPolicy::None`
+    pub fn set_fsync_policy(&mut self, policy: FsyncPolicy) {
+        self.fsync_policy = policy;
+    }
+
+    /// Write the files from `nb_threads` worker threads instead of the thread of the receiver.
+    /// The queue of each worker holds at most `queue_size` writes, when it is full
+    /// the receiver keeps the decoded blocks and writes them again later (see `ObjectWriter::ready()`).
+    /// When the files are synced (see `set_fsync_policy()`), the workers sync each file once the object is completed,
+    /// `flush()` waits for the pending syncs
+    #[cfg(feature = "fs-workers")]
+    pub fn set_worker_threads(&mut self, nb_threads: usize, queue_size: usize) {
+        self.workers = Some(Rc::new(FsWorkers::new(nb_threads, queue_size)));
+    }
+
+    /// Block until the worker threads have written, synced and closed all the queued files
+    ///
+    /// # Errors
+    ///
+    /// Returns the first I/O error of the files completed since the previous call
+    #[cfg(feature = "fs-workers")]
+    pub fn flush(&self) -> Result<()> {
+        match self.workers.as_ref() {
+            Some(workers) => workers.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Highest number of writes that have been waiting in the queue of a worker thread
+    #[cfg(feature = "fs-workers")]
+    pub fn max_queued_writes(&self) -> usize {
+        self.workers
+            .as_ref()
+            .map(|workers| workers.max_queued_jobs())
+            .unwrap_or_default()
+    }
+
+    /// Register the decipher of the objects encrypted with the key `key_uri`
+    pub fn add_decipher(&mut self, key_uri: &str, decipher: Arc<dyn ObjectDecipher>) {
+        self.deciphers.insert(key_uri, decipher);
+    }
 }
 
 impl ObjectWriterBuilder for ObjectWriterFSBuilder {
@@ -40,11 +175,43 @@ impl ObjectWriterBuilder for ObjectWriterFSBuilder {
             inner: RefCell::new(ObjectWriterFSInner {
                 destination: None,
                 writer: None,
+                dirty: false,
+                error: None,
             }),
             meta: meta.clone(),
+            md5_check: self
+                .md5_check_filter
+                .map(|filter| filter(meta))
+                .unwrap_or(true),
+            partial_delivery: self.partial_delivery,
+            preserve_directories: self.preserve_directories,
+            file_name_policy: self.file_name_policy,
+            fsync_policy: self.fsync_policy,
+            #[cfg(feature = "fs-workers")]
+            workers: self.workers.clone(),
         })
     }
 
+    fn md5_policy(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        meta: &ObjectMetadata,
+    ) -> Md5Policy {
+        self.md5_policy.policy(meta)
+    }
+
+    fn decipher(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        key_uri: &str,
+    ) -> Option<Arc<dyn ObjectDecipher>> {
+        self.deciphers.get(key_uri)
+    }
+
     fn set_cache_duration(
         &self,
         _endpoint: &UDPEndpoint,
@@ -68,6 +235,178 @@ impl ObjectWriterBuilder for ObjectWriterFSBuilder {
         _ext_time: Option<std::time::SystemTime>,
     ) {
     }
+
+    fn on_cache_expired(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) {
+        let destination = match destination_path(
+            &self.dest,
+            &meta.content_location,
+            self.preserve_directories,
+            self.file_name_policy,
+        ) {
+            Some(destination) => destination,
+            None => return,
+        };
+        if destination.is_file() {
+            log::info!("Cache expired, remove file {:?}", destination);
+            std::fs::remove_file(&destination).ok();
+        }
+    }
+}
+
+/// Characters that are not allowed in a Windows file name
+const INVALID_CHARACTERS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names reserved by Windows, with or without extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Convert a segment of the content-location to a file name valid on every platform
+/// Return `None` if the segment is not valid and `policy` is `FileNamePolicy::Reject`
+fn file_name(segment: &str, policy: FileNamePolicy) -> Option<String> {
+    let trailing = segment.len() - segment.trim_end_matches(&['.', ' '][..]).len();
+    let reserved = is_reserved_name(segment);
+    if trailing == 0 && !reserved && !segment.contains(INVALID_CHARACTERS) {
+        return Some(segment.to_owned());
+    }
+
+    let escape = |c: char| -> String {
+        match policy {
+            FileNamePolicy::PercentEncode => format!("%{:02X}", c as u32),
+            _ => "_".to_owned(),
+        }
+    };
+
+    let mut name = String::with_capacity(segment.len());
+    let end = segment.len() - trailing;
+    for (index, c) in segment.char_indices() {
+        if INVALID_CHARACTERS.contains(&c) || index >= end {
+            name.push_str(&escape(c));
+        } else {
+            name.push(c);
+        }
+    }
+
+    match policy {
+        FileNamePolicy::Reject => None,
+        FileNamePolicy::Sanitize if reserved => Some(format!("_{}", name)),
+        FileNamePolicy::PercentEncode if reserved => {
+            let mut chars = name.chars();
+            let first = chars.next()?;
+            Some(format!("{}{}", escape(first), chars.as_str()))
+        }
+        _ => Some(name),
+    }
+}
+
+/// Prefix the long absolute paths with `\\?\` so they are not limited to `MAX_PATH`
+#[cfg(windows)]
+fn extended_length_path(path: std::path::PathBuf) -> std::path::PathBuf {
+    const MAX_PATH: usize = 260;
+    let path = match path.is_absolute() {
+        true => path,
+        false => match std::env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(_) => return path,
+        },
+    };
+
+    let str = path.to_string_lossy();
+    if str.len() < MAX_PATH || str.starts_with(r"\\?\") {
+        return path;
+    }
+
+    match str.strip_prefix(r"\\") {
+        Some(unc) => std::path::PathBuf::from(format!(r"\\?\UNC\{}", unc.replace('/', "\\"))),
+        None => std::path::PathBuf::from(format!(r"\\?\{}", str.replace('/', "\\"))),
+    }
+}
+
+/// Write the buffered data of `writer` and sync the file to the storage device,
+/// with its parent folder on Unix when `parent` is set
+pub(super) fn sync_file(
+    writer: &mut std::io::BufWriter<std::fs::File>,
+    parent: Option<&std::path::Path>,
+) -> std::io::Result<()> {
+    writer.flush()?;
+    writer.get_ref().sync_all()?;
+    #[cfg(unix)]
+    if let Some(parent) = parent {
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = parent;
+    Ok(())
+}
+
+/// Convert the content-location to a path inside `dest`
+///
+/// The segments of the content-location are percent-decoded, then converted with `file_name_policy`.
+/// Return `None` if a segment contains control characters, path separators or refers to a parent folder,
+/// if the content-location contains folders and `preserve_directories` is disabled,
+/// or if a segment is not a valid file name and `file_name_policy` is `FileNamePolicy::Reject`.
+fn destination_path(
+    dest: &std::path::Path,
+    content_location: &url::Url,
+    preserve_directories: bool,
+    file_name_policy: FileNamePolicy,
+) -> Option<std::path::PathBuf> {
+    let mut segments = Vec::new();
+    for segment in content_location.path_segments()? {
+        let segment = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+        if segment.is_empty() || segment == "." {
+            continue;
+        }
+
+        if segment == ".."
+            || segment
+                .chars()
+                .any(|c| c.is_control() || c == '/' || c == '\\')
+        {
+            log::error!("Content-Location {} is not a safe path", content_location);
+            return None;
+        }
+
+        match file_name(&segment, file_name_policy) {
+            Some(name) => segments.push(name),
+            None => {
+                log::error!(
+                    "Content-Location {} is not a valid file name on every platform",
+                    content_location
+                );
+                return None;
+            }
+        }
+    }
+
+    if segments.is_empty() || (!preserve_directories && segments.len() > 1) {
+        log::error!(
+            "Content-Location {} can not be converted to a file name",
+            content_location
+        );
+        return None;
+    }
+
+    let mut destination = dest.to_path_buf();
+    destination.extend(segments);
+    #[cfg(windows)]
+    let destination = extended_length_path(destination);
+    Some(destination)
 }
 
 ///
@@ -82,6 +421,13 @@ pub struct ObjectWriterFS {
     dest: std::path::PathBuf,
     inner: RefCell<ObjectWriterFSInner>,
     meta: ObjectMetadata,
+    md5_check: bool,
+    partial_delivery: bool,
+    preserve_directories: bool,
+    file_name_policy: FileNamePolicy,
+    fsync_policy: FsyncPolicy,
+    #[cfg(feature = "fs-workers")]
+    workers: Option<Rc<FsWorkers>>,
 }
 
 ///
@@ -89,22 +435,38 @@ pub struct ObjectWriterFS {
 #[derive(Debug)]
 pub struct ObjectWriterFSInner {
     destination: Option<std::path::PathBuf>,
-    writer: Option<std::io::BufWriter<std::fs::File>>,
+    writer: Option<FileWriter>,
+    /// Data written since the last sync
+    dirty: bool,
+    /// First error of `write()`, returned by the next `ready()`
+    error: Option<std::io::Error>,
+}
+
+#[derive(Debug)]
+enum FileWriter {
+    Direct(std::io::BufWriter<std::fs::File>),
+    #[cfg(feature = "fs-workers")]
+    Queued(FsFileWriter),
 }
 
 impl ObjectWriter for ObjectWriterFS {
     fn open(&self, _now: SystemTime) -> Result<()> {
-        let content_location_path = self.meta.content_location.path();
-        let relative_path = content_location_path
-            .strip_prefix('/')
-            .unwrap_or(content_location_path);
-        let destination = self.dest.join(relative_path);
-        log::info!(
-            "Create destination {:?} {:?} {:?}",
-            self.dest,
-            relative_path,
-            destination
-        );
+        let destination = destination_path(
+            &self.dest,
+            &self.meta.content_location,
+            self.preserve_directories,
+            self.file_name_policy,
+        )
+        .ok_or_else(|| {
+            FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Content-Location {} is not a valid file name",
+                    self.meta.content_location
+                ),
+            )
+        })?;
+        log::info!("Create destination {:?} {:?}", self.dest, destination);
         let parent = destination.parent();
         if parent.is_some() {
             let parent = parent.unwrap();
@@ -115,20 +477,55 @@ impl ObjectWriter for ObjectWriterFS {
 
         let file = std::fs::File::create(&destination)?;
         let mut inner = self.inner.borrow_mut();
-        inner.writer = Some(std::io::BufWriter::new(file));
+        #[cfg(feature = "fs-workers")]
+        if let Some(workers) = self.workers.as_ref() {
+            inner.writer = Some(FileWriter::Queued(workers.file_writer(
+                file,
+                self.fsync_policy,
+                destination.parent(),
+            )));
+            inner.destination = Some(destination.to_path_buf());
+            return Ok(());
+        }
+        inner.writer = Some(FileWriter::Direct(std::io::BufWriter::new(file)));
         inner.destination = Some(destination.to_path_buf());
         Ok(())
     }
 
+    fn ready(&self, _now: SystemTime) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(e) = inner.error.take() {
+            return Err(FluteError::from(e));
+        }
+        let dirty = std::mem::take(&mut inner.dirty);
+        match inner.writer.as_mut() {
+            // The previous block is written
+            Some(FileWriter::Direct(writer))
+                if dirty && self.fsync_policy == FsyncPolicy::PerBlock =>
+            {
+                sync_file(writer, None).map_err(FluteError::from)
+            }
+            #[cfg(feature = "fs-workers")]
+            Some(FileWriter::Queued(writer)) => writer.try_flush(),
+            _ => Ok(()),
+        }
+    }
+
     fn write(&self, data: &[u8], _now: SystemTime) {
         let mut inner = self.inner.borrow_mut();
-        if inner.writer.is_none() {
-            return;
+        inner.dirty = true;
+        match inner.writer.as_mut() {
+            Some(FileWriter::Direct(writer)) => match writer.write_all(data) {
+                Ok(_) => {}
+                Err(e) => {
+                    log::error!("Fail to write file {:?}", e);
+                    inner.error.get_or_insert(e);
+                }
+            },
+            #[cfg(feature = "fs-workers")]
+            Some(FileWriter::Queued(writer)) => writer.write(data),
+            None => {}
         }
-        match inner.writer.as_mut().unwrap().write_all(data) {
-            Ok(_) => {}
-            Err(e) => log::error!("Fail to write file {:?}", e),
-        };
     }
 
     fn complete(&self, _now: SystemTime) {
@@ -138,22 +535,85 @@ impl ObjectWriter for ObjectWriterFS {
         }
 
         println!("File {:?} is completed !", inner.destination);
-        inner.writer.as_mut().unwrap().flush().ok();
-        inner.writer = None;
-        inner.destination = None
+        let destination = inner.destination.take();
+        inner
+            .writer
+            .take()
+            .unwrap()
+            .close(self.fsync_policy, destination.as_deref());
     }
 
     fn error(&self, _now: SystemTime) {
         let mut inner = self.inner.borrow_mut();
-        inner.writer = None;
-        if inner.destination.is_some() {
-            log::error!("Remove file {:?}", inner.destination);
-            std::fs::remove_file(inner.destination.as_ref().unwrap()).ok();
-            inner.destination = None;
+        let writer = inner.writer.take();
+        if let Some(destination) = inner.destination.take() {
+            match writer {
+                #[cfg(feature = "fs-workers")]
+                Some(FileWriter::Queued(writer)) => writer.remove(destination),
+                _ => {
+                    drop(writer);
+                    log::error!("Remove file {:?}", destination);
+                    std::fs::remove_file(destination).ok();
+                }
+            }
         }
     }
 
     fn interrupted(&self, now: SystemTime) {
         self.error(now);
     }
+
+    fn enable_md5_check(&self) -> bool {
+        self.md5_check
+    }
+
+    fn allow_partial_delivery(&self) -> bool {
+        self.partial_delivery
+    }
+
+    fn partial_complete(
+        &self,
+        _now: SystemTime,
+        _meta: &ObjectMetadata,
+        received_byte_ranges: &[std::ops::Range<u64>],
+    ) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.writer.is_none() {
+            return;
+        }
+
+        log::warn!(
+            "File {:?} is partially received {:?}",
+            inner.destination,
+            received_byte_ranges
+        );
+        let destination = inner.destination.take();
+        inner
+            .writer
+            .take()
+            .unwrap()
+            .close(self.fsync_policy, destination.as_deref());
+    }
+}
+
+impl FileWriter {
+    /// Flush the data and close the file, synced to the storage device unless `fsync` is `FsyncPolicy::None`
+    fn close(self, fsync: FsyncPolicy, destination: Option<&std::path::Path>) {
+        match self {
+            FileWriter::Direct(mut writer) if fsync != FsyncPolicy::None => {
+                if let Err(e) = sync_file(&mut writer, destination.and_then(|d| d.parent())) {
+                    log::error!("Fail to sync file {:?} {:?}", destination, e);
+                }
+            }
+            FileWriter::Direct(mut writer) => {
+                writer.flush().ok();
+            }
+            #[cfg(feature = "fs-workers")]
+            FileWriter::Queued(writer) => {
+                if let Err(e) = writer.close() {
+                    log::error!("Fail to write file {:?} {:?}", destination, e);
+                }
+            }
+        }
+    }
 }
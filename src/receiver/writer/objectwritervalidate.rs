@@ -0,0 +1,150 @@
+use super::{ObjectMetadata, ObjectWriter, ObjectWriterBuilder};
+use crate::{common::udpendpoint::UDPEndpoint, tools::error::Result};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::SystemTime,
+};
+
+///
+/// Verify the objects received by the `receiver` against their FDT entry, without storing their content
+///
+/// The data of the objects is discarded. The MD5 is still computed and checked by the receiver,
+/// when MD5 check is enabled in the receiver configuration.
+///
+#[derive(Debug)]
+pub struct ObjectWriterValidateBuilder {
+    results: Rc<RefCell<Vec<ObjectValidation>>>,
+}
+
+///
+/// Result of the verification of a received object
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectValidation {
+    /// Content-Location of the object
+    pub content_location: url::Url,
+    /// `true` when the MD5 of the object matches the `Content-MD5` of the FDT, or when the FDT has no `Content-MD5`.
+    /// `false` when the object is received with error or its reception is interrupted
+    pub md5_ok: bool,
+    /// `true` when the number of bytes received matches the `Content-Length` of the FDT, or when it is unknown
+    pub length_ok: bool,
+}
+
+#[derive(Debug)]
+struct ObjectWriterValidate {
+    meta: ObjectMetadata,
+    bytes_written: Cell<usize>,
+    results: Rc<RefCell<Vec<ObjectValidation>>>,
+}
+
+impl ObjectWriterValidateBuilder {
+    /// Return a new `ObjectWriterValidateBuilder`
+    pub fn new() -> ObjectWriterValidateBuilder {
+        ObjectWriterValidateBuilder {
+            results: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Return the result of the objects received so far, in order of completion
+    pub fn results(&self) -> Vec<ObjectValidation> {
+        self.results.borrow().clone()
+    }
+
+    /// Remove and return the result of the objects received so far, in order of completion
+    pub fn take_results(&self) -> Vec<ObjectValidation> {
+        std::mem::take(&mut *self.results.borrow_mut())
+    }
+}
+
+impl Default for ObjectWriterValidateBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjectWriterBuilder for ObjectWriterValidateBuilder {
+    fn new_object_writer(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        meta: &ObjectMetadata,
+        _now: std::time::SystemTime,
+    ) -> Box<dyn ObjectWriter> {
+        Box::new(ObjectWriterValidate {
+            meta: meta.clone(),
+            bytes_written: Cell::new(0),
+            results: self.results.clone(),
+        })
+    }
+
+    fn set_cache_duration(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _toi: &u128,
+        _content_location: &url::Url,
+        _duration: &std::time::Duration,
+        _now: std::time::SystemTime,
+    ) {
+    }
+
+    fn fdt_received(
+        &self,
+        _endpoint: &UDPEndpoint,
+        _tsi: &u64,
+        _fdt_xml: &str,
+        _expires: std::time::SystemTime,
+        _meta: &ObjectMetadata,
+        _transfer_duration: std::time::Duration,
+        _now: std::time::SystemTime,
+        _ext_time: Option<std::time::SystemTime>,
+    ) {
+    }
+}
+
+impl ObjectWriterValidate {
+    fn push_result(&self, md5_ok: bool) {
+        let length_ok = self
+            .meta
+            .content_length
+            .map(|length| length == self.bytes_written.get())
+            .unwrap_or(true);
+
+        self.results.borrow_mut().push(ObjectValidation {
+            content_location: self.meta.content_location.clone(),
+            md5_ok,
+            length_ok,
+        });
+    }
+}
+
+impl ObjectWriter for ObjectWriterValidate {
+    fn open(&self, _now: SystemTime) -> Result<()> {
+        Ok(())
+    }
+
+    fn write(&self, data: &[u8], _now: SystemTime) {
+        self.bytes_written
+            .set(self.bytes_written.get() + data.len());
+    }
+
+    fn complete(&self, _now: SystemTime) {
+        log::info!("Object {} is valid", self.meta.content_location);
+        self.push_result(true);
+    }
+
+    fn error(&self, _now: SystemTime) {
+        log::error!("Object {} received with error", self.meta.content_location);
+        self.push_result(false);
+    }
+
+    fn interrupted(&self, _now: SystemTime) {
+        log::error!(
+            "Object {} reception interrupted",
+            self.meta.content_location
+        );
+        self.push_result(false);
+    }
+}
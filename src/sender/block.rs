@@ -69,7 +69,7 @@ impl Block {
         self.read_index as usize == self.shards.len()
     }
 
-    pub fn read(&mut self) -> Option<(EncodingSymbol, bool)> {
+    pub fn read(&mut self) -> Option<(EncodingSymbol<'_>, bool)> {
         if self.is_empty() {
             return None;
         }
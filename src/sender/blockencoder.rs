@@ -2,6 +2,7 @@ use std::io::Read;
 use std::sync::Arc;
 
 use super::filedesc;
+use super::observer::{ErrorKind, SenderError};
 use crate::common::{partition, pkt};
 use crate::tools::error::Result;
 
@@ -23,6 +24,8 @@ pub struct BlockEncoder {
     fd: Option<std::fs::File>,
     stopped: bool,
     closabled_object: bool,
+    pad_last_symbol: bool,
+    error: Option<SenderError>,
 }
 
 use super::block::Block;
@@ -32,6 +35,7 @@ impl BlockEncoder {
         file: Arc<filedesc::FileDesc>,
         block_multiplex_windows: usize,
         closabled_object: bool,
+        pad_last_symbol: bool,
     ) -> Result<BlockEncoder> {
         let mut fd = None;
         if let (None, Some(path)) = (file.object.content.as_ref(), file.object.path.as_ref()) {
@@ -56,6 +60,8 @@ impl BlockEncoder {
             fd,
             stopped: false,
             closabled_object,
+            pad_last_symbol,
+            error: None,
         };
         block.block_partitioning();
         Ok(block)
@@ -73,6 +79,28 @@ impl BlockEncoder {
         loop {
             self.read_window();
 
+            if self.error.is_some() {
+                log::warn!(
+                    "Stop the transfer of toi={} and send a close object flag",
+                    self.file.toi
+                );
+                self.stopped = true;
+                self.blocks.clear();
+                return Some(pkt::Pkt {
+                    payload: Vec::new(),
+                    transfer_length: self.file.object.transfer_length,
+                    esi: 0,
+                    sbn: self.curr_sbn,
+                    toi: self.file.toi,
+                    fdt_id: self.file.fdt_id,
+                    cenc: self.file.object.cenc,
+                    inband_cenc: self.file.object.inband_cenc,
+                    close_object: true,
+                    source_block_length: 0,
+                    sender_current_time: self.file.sender_current_time,
+                });
+            }
+
             if self.blocks.is_empty() {
                 if self.nb_pkt_sent == 0 {
                     log::debug!("Empty file ? Send a pkt containing close object flag");
@@ -121,8 +149,14 @@ impl BlockEncoder {
                 >= self.file.object.transfer_length as usize)
                 && *is_last_symbol;
 
+            let mut payload = symbol.symbols.to_vec();
+            let encoding_symbol_length = self.file.oti.encoding_symbol_length as usize;
+            if self.pad_last_symbol && payload.len() < encoding_symbol_length {
+                payload.resize(encoding_symbol_length, 0);
+            }
+
             return Some(pkt::Pkt {
-                payload: symbol.symbols.to_vec(),
+                payload,
                 transfer_length: self.file.object.transfer_length,
                 esi: symbol.esi,
                 sbn: symbol.sbn,
@@ -137,6 +171,20 @@ impl BlockEncoder {
         }
     }
 
+    /// Error that has interrupted the reading of the object, if any
+    pub fn error(&self) -> Option<&SenderError> {
+        self.error.as_ref()
+    }
+
+    fn new_error(&self, kind: ErrorKind, message: String) -> SenderError {
+        SenderError {
+            toi: self.file.toi,
+            sbn: self.curr_sbn,
+            kind,
+            message,
+        }
+    }
+
     fn block_partitioning(&mut self) {
         let oti = &self.file.oti;
         (self.a_large, self.a_small, self.nb_a_large, self.nb_blocks) =
@@ -147,13 +195,17 @@ impl BlockEncoder {
             );
     }
 
-    fn read_block(&mut self) -> Result<()> {
+    fn read_block(&mut self) -> std::result::Result<(), SenderError> {
         debug_assert!(!self.read_end);
 
         if self.fd.is_some() {
             return self.read_fd_block();
         }
 
+        if self.file.object.generator.is_some() {
+            return self.read_generator_block();
+        }
+
         if self.file.object.content.is_none() {
             self.read_end = true;
             return Ok(());
@@ -175,7 +227,8 @@ impl BlockEncoder {
         }
 
         let buffer = &content.as_slice()[offset_start..offset_end];
-        let block = Block::new_from_buffer(self.curr_sbn, buffer, block_length, oti)?;
+        let block = Block::new_from_buffer(self.curr_sbn, buffer, block_length, oti)
+            .map_err(|e| self.new_error(ErrorKind::Encoding, e.0.to_string()))?;
         self.blocks.push(block);
         self.curr_sbn += 1;
         self.read_end = offset_end == content.len();
@@ -190,7 +243,7 @@ impl BlockEncoder {
         Ok(())
     }
 
-    fn read_fd_block(&mut self) -> Result<()> {
+    fn read_fd_block(&mut self) -> std::result::Result<(), SenderError> {
         let fd = self.fd.as_mut().unwrap();
 
         log::info!("Read block nb {}", self.curr_sbn);
@@ -199,37 +252,78 @@ impl BlockEncoder {
             value if value < self.nb_a_large => self.a_large,
             _ => self.a_small,
         };
-        let mut buffer: Vec<u8> =
-            vec![0; block_length as usize * oti.encoding_symbol_length as usize];
-        let result = match fd.read(&mut buffer) {
-            Ok(s) => s,
-            Err(e) => {
-                log::error!("Fail to read file {:?}", e.to_string());
-                self.read_end = true;
-                return Ok(());
-            }
-        };
+        let remaining = self.file.object.transfer_length - self.curr_content_offset;
+        if remaining == 0 {
+            self.read_end = true;
+            return Ok(());
+        }
 
-        if result == 0 {
+        let block_size = block_length * oti.encoding_symbol_length as u64;
+        let mut buffer: Vec<u8> = vec![0; block_size.min(remaining) as usize];
+        if let Err(e) = fd.read_exact(&mut buffer) {
+            return Err(self.new_error(
+                ErrorKind::Io,
+                format!(
+                    "Fail to read block {} of {:?} at offset {}: {}",
+                    self.curr_sbn, self.file.object.path, self.curr_content_offset, e
+                ),
+            ));
+        }
+
+        let block = Block::new_from_buffer(self.curr_sbn, &buffer, block_length, oti)
+            .map_err(|e| self.new_error(ErrorKind::Encoding, e.0.to_string()))?;
+        self.blocks.push(block);
+        self.curr_sbn += 1;
+        self.curr_content_offset += buffer.len() as u64;
+        Ok(())
+    }
+
+    fn read_generator_block(&mut self) -> std::result::Result<(), SenderError> {
+        let generator = self.file.object.generator.as_ref().unwrap();
+
+        log::debug!("Generate block nb {}", self.curr_sbn);
+        let oti = &self.file.oti;
+        let block_length = match self.curr_sbn as u64 {
+            value if value < self.nb_a_large => self.a_large,
+            _ => self.a_small,
+        };
+        let remaining = self.file.object.transfer_length - self.curr_content_offset;
+        if remaining == 0 {
             self.read_end = true;
             return Ok(());
         }
 
-        buffer.truncate(result);
+        let block_size = block_length * oti.encoding_symbol_length as u64;
+        let length = block_size.min(remaining) as usize;
+        let buffer = generator.generate(self.curr_content_offset, length);
+        if buffer.len() != length {
+            return Err(self.new_error(
+                ErrorKind::Io,
+                format!(
+                    "Generator returned {} bytes instead of {} for block {} at offset {}",
+                    buffer.len(),
+                    length,
+                    self.curr_sbn,
+                    self.curr_content_offset
+                ),
+            ));
+        }
 
-        let block = Block::new_from_buffer(self.curr_sbn, &buffer, block_length, oti)?;
+        let block = Block::new_from_buffer(self.curr_sbn, &buffer, block_length, oti)
+            .map_err(|e| self.new_error(ErrorKind::Encoding, e.0.to_string()))?;
         self.blocks.push(block);
         self.curr_sbn += 1;
         self.curr_content_offset += buffer.len() as u64;
+        self.read_end = self.curr_content_offset == self.file.object.transfer_length;
         Ok(())
     }
 
     fn read_window(&mut self) {
         while !self.read_end && (self.blocks.len() < self.block_multiplex_windows) {
-            match self.read_block() {
-                Ok(_) => {}
-                Err(_) => self.read_end = true, // TODO handle error
-            };
+            if let Err(e) = self.read_block() {
+                self.read_end = true;
+                self.error = Some(e);
+            }
         }
     }
 }
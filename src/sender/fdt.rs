@@ -2,24 +2,33 @@ use super::filedesc::FileDesc;
 use super::observer::ObserverList;
 use super::toiallocator::{Toi, ToiAllocator};
 use super::{objectdesc, ObjectDesc};
-use crate::common::{fdtinstance::FdtInstance, lct, oti};
+use crate::common::{
+    contentlocation,
+    fdtinstance::{self, FdtInstance},
+    lct, oti,
+    udpendpoint::UDPEndpoint,
+    Profile,
+};
 use crate::sender::observer;
-use crate::sender::TOIMaxLength;
+use crate::sender::Config;
 use crate::tools;
-use crate::tools::error::{FluteError, Result};
+use crate::tools::error::{FluteError, LogError, Result};
 use serde::Serialize;
 use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use std::time::SystemTime;
 
 #[derive(Debug)]
 pub struct Fdt {
     _tsi: u64,
+    endpoint: UDPEndpoint,
     fdtid: u32,
     oti: oti::Oti,
     files_transfer_queue: VecDeque<Arc<FileDesc>>,
     fdt_transfer_queue: VecDeque<Arc<FileDesc>>,
     files: std::collections::HashMap<u128, Arc<FileDesc>>,
+    files_pending_replacement: std::collections::HashMap<u128, Arc<FileDesc>>,
     current_fdt_transfer: Option<Arc<FileDesc>>,
     complete: Option<bool>,
     cenc: lct::Cenc,
@@ -27,113 +36,108 @@ pub struct Fdt {
     carousel: std::time::Duration,
     inband_sct: bool,
     last_publish: Option<SystemTime>,
+    min_republish_interval: std::time::Duration,
+    publish_pending: bool,
+    suppress_duplicate_fdt: bool,
+    normalize_content_location: bool,
+    last_fdt_hash: Option<u64>,
     observers: ObserverList,
     groups: Option<Vec<String>>,
+    base_urls: Option<Vec<String>>,
+    content_type: Option<String>,
+    mbms_session_identity_expiry: Option<Vec<(u8, u32)>>,
     toi_allocator: Arc<ToiAllocator>,
+    profile: Profile,
 }
 
 impl Fdt {
+    /// Create the FDT of a sender, configured with the `fdt_*`, `toi_*` and FDT attributes of `config`
     pub fn new(
         tsi: u64,
-        fdtid: u32,
+        endpoint: UDPEndpoint,
         default_oti: &oti::Oti,
-        cenc: lct::Cenc,
-        duration: std::time::Duration,
-        carousel: std::time::Duration,
-        inband_sct: bool,
+        config: &Config,
         observers: ObserverList,
-        toi_max_length: TOIMaxLength,
-        toi_initial_value: Option<u128>,
-        groups: Option<Vec<String>>,
     ) -> Fdt {
         Fdt {
             _tsi: tsi,
-            fdtid,
+            endpoint,
+            fdtid: config.fdt_start_id,
             oti: default_oti.clone(),
             files_transfer_queue: VecDeque::new(),
             fdt_transfer_queue: VecDeque::new(),
             files: std::collections::HashMap::new(),
+            files_pending_replacement: std::collections::HashMap::new(),
             current_fdt_transfer: None,
             complete: None,
-            cenc,
-            duration,
-            carousel,
-            inband_sct,
+            cenc: config.fdt_cenc,
+            duration: config.fdt_duration,
+            carousel: config.fdt_carousel,
+            inband_sct: config.fdt_inband_sct,
             last_publish: None,
+            min_republish_interval: config.fdt_min_republish_interval,
+            publish_pending: false,
+            suppress_duplicate_fdt: config.suppress_duplicate_fdt,
+            normalize_content_location: config.normalize_content_location,
+            last_fdt_hash: None,
             observers,
-            groups,
-            toi_allocator: ToiAllocator::new(toi_max_length, toi_initial_value),
+            groups: config.groups.clone(),
+            base_urls: config.base_urls.clone(),
+            content_type: config.fdt_content_type.clone(),
+            mbms_session_identity_expiry: None,
+            toi_allocator: ToiAllocator::new(config.toi_max_length, config.toi_initial_value),
+            profile: config.profile,
         }
     }
 
     fn get_fdt_instance(&self, now: SystemTime) -> FdtInstance {
         let ntp = tools::system_time_to_ntp(now).unwrap_or(0);
         let expires_ntp = (ntp >> 32) + self.duration.as_secs();
+        let files = self
+            .files
+            .values()
+            .map(|desc| {
+                let mut file = desc.to_file_xml(now, self.content_type.as_deref());
+                if self.normalize_content_location {
+                    normalize_content_location(&mut file);
+                }
+                file
+            })
+            .collect();
 
-        let oti_attributes = match self.oti.fec_encoding_id {
-            oti::FECEncodingID::RaptorQ => None, // RaptorA scheme parameters is object dependent
-            _ => Some(self.oti.get_attributes()),
-        };
-
-        FdtInstance {
-            xmlns: None,
-            xmlns_xsi: None,
-            expires: expires_ntp.to_string(),
-            complete: self.complete,
-            content_type: None,
-            content_encoding: None,
-            fec_oti_fec_encoding_id: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_fec_encoding_id,
-            },
-            fec_oti_encoding_symbol_length: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_encoding_symbol_length,
-            },
-            fec_oti_fec_instance_id: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_fec_instance_id,
-            },
-            fec_oti_max_number_of_encoding_symbols: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_max_number_of_encoding_symbols,
-            },
-            fec_oti_maximum_source_block_length: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_maximum_source_block_length,
-            },
-            fec_oti_scheme_specific_info: match &oti_attributes {
-                None => None,
-                Some(attr) => attr.fec_oti_scheme_specific_info.clone(),
-            },
+        let mut instance = new_fdt_instance(
+            &self.oti,
+            files,
+            expires_ntp,
+            self.complete,
+            self.groups.clone(),
+            self.base_urls.clone(),
+            self.content_type.clone(),
+        );
+        instance.mbms_session_identity_expiry =
+            self.mbms_session_identity_expiry.as_ref().map(|entries| {
+                entries
+                    .iter()
+                    .map(|(identity, expiry)| {
+                        fdtinstance::MBMSSessionIdentityExpiry::new(*identity, *expiry)
+                    })
+                    .collect()
+            });
+        instance
+    }
 
-            file: Some(
-                self.files
-                    .values()
-                    .map(|desc| desc.to_file_xml(now))
-                    .collect(),
-            ),
-            xmlns_mbms_2005: None,
-            xmlns_mbms_2007: None,
-            xmlns_mbms_2008: None,
-            xmlns_mbms_2009: None,
-            xmlns_mbms_2012: None,
-            xmlns_mbms_2015: None,
-            xmlns_sv: None,
-            full_fdt: None,
-            base_url_1: None,
-            base_url_2: None,
-            group: self.groups.clone(),
-            mbms_session_identity_expiry: None,
-            schema_version: Some(4),
-            delimiter: Some(0),
-        }
+    pub fn set_mbms_session_identity_expiry(&mut self, entries: Option<Vec<(u8, u32)>>) {
+        self.mbms_session_identity_expiry = entries;
     }
 
     pub fn allocate_toi(&mut self) -> Box<Toi> {
         ToiAllocator::allocate(&self.toi_allocator)
     }
 
+    pub fn allocate_toi_range(&mut self, count: usize) -> Result<Vec<Toi>> {
+        ToiAllocator::allocate_range(&self.toi_allocator, count)
+    }
+
     pub fn add_object(
         &mut self,
         priority: u32,
@@ -149,7 +153,15 @@ impl Fdt {
             obj.set_toi(self.allocate_toi());
         }
 
-        let filedesc = Arc::new(FileDesc::new(priority, obj, &self.oti, None, false)?);
+        let inband_sct = obj.inband_sct;
+        let filedesc = Arc::new(FileDesc::new(
+            priority,
+            obj,
+            &self.oti,
+            None,
+            inband_sct,
+            self.profile,
+        )?);
         let ret = filedesc.toi;
         debug_assert!(!self.files.contains_key(&filedesc.toi));
         self.files.insert(filedesc.toi, filedesc.clone());
@@ -157,6 +169,30 @@ impl Fdt {
         Ok(ret)
     }
 
+    pub fn add_object_with_toi(
+        &mut self,
+        priority: u32,
+        mut obj: Box<objectdesc::ObjectDesc>,
+        toi: u128,
+    ) -> Result<u128> {
+        if obj.toi.is_some() {
+            return Err(FluteError::new("A TOI is already assigned to the object"));
+        }
+
+        let toi = match ToiAllocator::reserve(&self.toi_allocator, toi) {
+            Some(toi) => toi,
+            None => {
+                return Err(FluteError::new(format!(
+                    "TOI {} is reserved, invalid or already used by another object",
+                    toi
+                )))
+            }
+        };
+
+        obj.set_toi(toi);
+        self.add_object(priority, obj)
+    }
+
     pub fn get_objects_in_fdt(&self) -> std::collections::HashMap<u128, &ObjectDesc> {
         self.files
             .iter()
@@ -164,6 +200,15 @@ impl Fdt {
             .collect()
     }
 
+    #[cfg(feature = "sdp")]
+    pub fn get_oti(&self) -> &oti::Oti {
+        &self.oti
+    }
+
+    pub fn get_file(&self, toi: u128) -> Option<Arc<FileDesc>> {
+        self.files.get(&toi).cloned()
+    }
+
     pub fn is_added(&self, toi: u128) -> bool {
         self.files.contains_key(&toi)
     }
@@ -174,9 +219,193 @@ impl Fdt {
             None => return false,
         };
         self.files_transfer_queue.retain(|obj| obj.toi != toi);
+        self.files_pending_replacement.remove(&toi);
         true
     }
 
+    /// Remove the objects whose `Expires` announced in the last published FDT has passed
+    ///
+    /// `CacheControl::Expires` is relative to the publication of the FDT, so an object expires
+    /// when no FDT has been published during its cache duration.
+    /// Return the number of objects removed
+    pub fn remove_expired_objects(&mut self, now: SystemTime) -> usize {
+        let last_publish = match self.last_publish {
+            Some(last_publish) => last_publish,
+            None => return 0,
+        };
+
+        let mut instance = self.get_fdt_instance(last_publish);
+        if instance.remove_expired_files(now) == 0 {
+            return 0;
+        }
+
+        let remaining: std::collections::HashSet<&str> = instance
+            .file
+            .iter()
+            .flatten()
+            .map(|file| file.toi.as_str())
+            .collect();
+
+        let expired: Vec<u128> = self
+            .files
+            .values()
+            .filter(|file| {
+                file.is_published() && !remaining.contains(file.toi.to_string().as_str())
+            })
+            .map(|file| file.toi)
+            .collect();
+
+        for toi in &expired {
+            log::info!("TSI={} Remove expired object TOI={}", self._tsi, toi);
+            self.remove_object(*toi);
+        }
+        expired.len()
+    }
+
+    pub fn replace_object_content(
+        &mut self,
+        toi: u128,
+        source: objectdesc::ObjectDataSource,
+        md5: Option<String>,
+        now: SystemTime,
+    ) -> Result<()> {
+        let file = match self.files.get(&toi) {
+            Some(file) => file.clone(),
+            None => {
+                return Err(FluteError::new(format!(
+                    "Object with TOI {} is not in the FDT",
+                    toi
+                )))
+            }
+        };
+
+        let mut obj = file.object.with_content(source, md5)?;
+        obj.set_toi(ToiAllocator::retain(file.object.toi.as_ref().unwrap()));
+        let inband_sct = obj.inband_sct;
+        let filedesc = Arc::new(FileDesc::new(
+            file.priority,
+            obj,
+            &self.oti,
+            None,
+            inband_sct,
+            self.profile,
+        )?);
+
+        if file.is_transferring() {
+            log::debug!(
+                "TOI {} is being transferred, content is replaced at the end of the transfer",
+                toi
+            );
+            self.files_pending_replacement.insert(toi, filedesc);
+            return Ok(());
+        }
+
+        self.replace_file(filedesc, now)
+    }
+
+    pub fn set_oti_for_object(
+        &mut self,
+        toi: u128,
+        oti: oti::Oti,
+        now: SystemTime,
+    ) -> Result<bool> {
+        let file = match self.files.get(&toi) {
+            Some(file) => file.clone(),
+            None => return Ok(false),
+        };
+
+        if file.is_transferring() {
+            return Err(FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Object with TOI {} is being transferred, its OTI can not be changed",
+                    toi
+                ),
+            ));
+        }
+
+        let mut obj = file.object.with_oti(Some(oti));
+        obj.set_toi(ToiAllocator::retain(file.object.toi.as_ref().unwrap()));
+        let inband_sct = obj.inband_sct;
+        let filedesc = Arc::new(FileDesc::new(
+            file.priority,
+            obj,
+            &self.oti,
+            None,
+            inband_sct,
+            self.profile,
+        )?);
+
+        self.replace_file(filedesc, now)?;
+        Ok(true)
+    }
+
+    fn replace_file(&mut self, filedesc: Arc<FileDesc>, now: SystemTime) -> Result<()> {
+        log::info!(
+            "Replace content of {} toi={}",
+            filedesc.object.content_location.as_str(),
+            filedesc.toi
+        );
+        self.files_transfer_queue
+            .retain(|obj| obj.toi != filedesc.toi);
+        self.files.insert(filedesc.toi, filedesc.clone());
+        self.files_transfer_queue.push_back(filedesc);
+        self.publish(now)
+    }
+
+    pub fn rewind(&mut self) {
+        for file in self.files.values() {
+            file.reset_last_transfer(None);
+        }
+    }
+
+    pub fn rewind_object(&mut self, toi: u128) -> bool {
+        match self.files.get(&toi) {
+            Some(file) => {
+                file.reset_last_transfer(None);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shift the timers of the objects selected by `filter`, see `FileDesc::shift_timers()`
+    pub fn shift_timers<F>(&self, duration: std::time::Duration, filter: F)
+    where
+        F: Fn(&FileDesc) -> bool,
+    {
+        self.files
+            .values()
+            .filter(|file| filter(file))
+            .for_each(|file| file.shift_timers(duration));
+    }
+
+    /// Resume the transfer of the FDT after a pause of `duration`
+    ///
+    /// A new version is published if the current FDT has expired during the pause,
+    /// otherwise the carousel of the current FDT is shifted by `duration`
+    pub fn resume(&mut self, duration: std::time::Duration, now: SystemTime) -> Result<()> {
+        let expired = self
+            .last_publish
+            .map(|last_publish| {
+                now.duration_since(last_publish).unwrap_or_default() >= self.duration
+            })
+            .unwrap_or(false);
+
+        if expired {
+            log::info!(
+                "TSI={} FDT has expired during the pause, publish new version",
+                self._tsi
+            );
+            return self.publish_now(now);
+        }
+
+        if let Some(current_fdt_transfer) = self.current_fdt_transfer.as_ref() {
+            current_fdt_transfer.shift_timers(duration);
+        }
+        Ok(())
+    }
+
     pub fn nb_transfers(&mut self, toi: u128) -> Option<u64> {
         let obj = self.files.get(&toi)?;
         Some(obj.total_nb_transfer())
@@ -195,39 +424,102 @@ impl Fdt {
         self.files.len()
     }
 
+    pub fn nb_queued_objects(&self, priority: u32) -> usize {
+        self.files_transfer_queue
+            .iter()
+            .filter(|file| file.priority == priority)
+            .count()
+    }
+
     pub fn publish(&mut self, now: SystemTime) -> Result<()> {
+        if !self.is_republish_interval_elapsed(now) {
+            log::debug!(
+                "TSI={} FDT published less than {:?} ago, new version is delayed",
+                self._tsi,
+                self.min_republish_interval
+            );
+            self.publish_pending = true;
+            return Ok(());
+        }
+
+        self.publish_now(now)
+    }
+
+    fn is_republish_interval_elapsed(&self, now: SystemTime) -> bool {
+        let last_publish = match self.last_publish {
+            Some(last_publish) => last_publish,
+            None => return true,
+        };
+
+        now.duration_since(last_publish).unwrap_or_default() >= self.min_republish_interval
+    }
+
+    fn publish_now(&mut self, now: SystemTime) -> Result<()> {
+        let hash = match self.suppress_duplicate_fdt {
+            true => Some(self.content_hash(now)?),
+            false => None,
+        };
+
+        if hash.is_some() && hash == self.last_fdt_hash && !self.current_fdt_will_expire(now) {
+            log::debug!(
+                "TSI={} FDT content is unchanged, retransmit the current FDT",
+                self._tsi
+            );
+            if let Some(current_fdt_transfer) = self.current_fdt_transfer.as_ref() {
+                current_fdt_transfer.reset_last_transfer(None);
+            }
+            self.publish_pending = false;
+            self.files.iter().for_each(|(_, file)| file.set_published());
+            return Ok(());
+        }
+
         log::info!("TSI={} Publish new FDT", self._tsi);
         let content = self.to_xml(now)?;
-        let mut obj = objectdesc::ObjectDesc::create_from_buffer(
+        let obj = new_fdt_object(
             &content,
-            "text/xml",
-            &url::Url::parse("file:///").unwrap(),
-            1,
             Some(self.carousel),
-            None,
-            None,
             self.groups.clone(),
             self.cenc,
-            true,
-            None,
-            true,
+            ToiAllocator::allocate_toi_fdt(&self.toi_allocator),
         )?;
-        obj.toi = Some(ToiAllocator::allocate_toi_fdt(&self.toi_allocator));
         let filedesc = Arc::new(FileDesc::new(
             0,
             obj,
             &self.oti,
             Some(self.fdtid),
             self.inband_sct,
+            self.profile,
         )?);
         filedesc.set_published();
         self.fdt_transfer_queue.push_back(filedesc);
+        if self
+            .observers
+            .is_subscribed(observer::EventKindMask::FDT_PUBLISHED)
+        {
+            let evt = observer::Event::FdtPublished {
+                instance_id: self.fdtid,
+                bytes: content.len(),
+            };
+            self.observers.dispatch(&evt, now);
+        }
         self.fdtid = (self.fdtid + 1) & 0xFFFFF;
+        self.last_fdt_hash = hash;
         self.last_publish = Some(now);
+        self.publish_pending = false;
         self.files.iter().for_each(|(_, file)| file.set_published());
         Ok(())
     }
 
+    /// Hash of the content of the FDT, its expiration date excluded
+    fn content_hash(&self, now: SystemTime) -> Result<u64> {
+        let mut instance = self.get_fdt_instance(now);
+        instance.expires.clear();
+        let content = fdt_instance_to_xml(&instance)?;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
     fn current_fdt_will_expire(&self, now: SystemTime) -> bool {
         if !self.fdt_transfer_queue.is_empty() {
             return false;
@@ -244,6 +536,55 @@ impl Fdt {
         self.duration < duration + std::time::Duration::from_secs(5)
     }
 
+    /// Earliest time when `get_next_fdt_transfer()` returns an FDT,
+    /// `None` while the current FDT is being transferred
+    pub fn next_fdt_transfer_time(&self) -> Option<SystemTime> {
+        if let Some(current_fdt_transfer) = self.current_fdt_transfer.as_ref() {
+            if current_fdt_transfer.is_transferring() {
+                return None;
+            }
+        }
+
+        if !self.fdt_transfer_queue.is_empty() {
+            return Some(SystemTime::UNIX_EPOCH);
+        }
+
+        let (current_fdt_transfer, last_publish) =
+            match (self.current_fdt_transfer.as_ref(), self.last_publish) {
+                (Some(current_fdt_transfer), Some(last_publish)) => {
+                    (current_fdt_transfer, last_publish)
+                }
+                _ => return Some(SystemTime::UNIX_EPOCH),
+            };
+
+        // See current_fdt_will_expire()
+        let mut next = last_publish
+            + self
+                .duration
+                .saturating_sub(std::time::Duration::from_secs(5))
+            + std::time::Duration::from_nanos(1);
+
+        if self.publish_pending {
+            next = next.min(last_publish + self.min_republish_interval);
+        }
+
+        if let Some(transfer_time) = current_fdt_transfer.next_transfer_time() {
+            next = next.min(transfer_time);
+        }
+
+        Some(next)
+    }
+
+    /// Earliest time when `get_next_file_transfer()` returns a file of the priority queue,
+    /// `None` if no published file is waiting for a transfer
+    pub fn next_file_transfer_time(&self, priority: u32) -> Option<SystemTime> {
+        self.files_transfer_queue
+            .iter()
+            .filter(|file| file.priority == priority)
+            .filter_map(|file| file.next_transfer_time())
+            .min()
+    }
+
     pub fn get_next_fdt_transfer(&mut self, now: SystemTime) -> Option<Arc<FileDesc>> {
         if let Some(current_fdt_transfer) = self.current_fdt_transfer.as_ref() {
             if current_fdt_transfer.is_transferring() {
@@ -251,9 +592,12 @@ impl Fdt {
             }
         }
 
-        if self.current_fdt_will_expire(now) {
+        if self.publish_pending && self.is_republish_interval_elapsed(now) {
+            log::debug!("Publish delayed FDT modifications");
+            self.publish_now(now).log_error("Fail to publish the FDT");
+        } else if self.current_fdt_will_expire(now) {
             log::debug!("FDT will expire soon, publish new version");
-            self.publish(now).ok();
+            self.publish_now(now).log_error("Fail to publish the FDT");
         }
 
         if !self.fdt_transfer_queue.is_empty() {
@@ -270,6 +614,49 @@ impl Fdt {
         Some(current_fdt_transfer.clone())
     }
 
+    /// Estimate the number of packets left to transfer the objects of the FDT and the pending FDT instances
+    pub fn packets_left(&self) -> u64 {
+        let mut packets: u64 = 0;
+        for file in self.files.values() {
+            match file.packets_left() {
+                Some(file_packets) => packets = packets.saturating_add(file_packets),
+                None => return u64::MAX,
+            }
+        }
+
+        let fdt_packets = |fdt: &FileDesc| fdt.oti.packets_per_object(fdt.object.transfer_length);
+        for fdt in &self.fdt_transfer_queue {
+            packets = packets.saturating_add(fdt_packets(fdt));
+        }
+
+        if let Some(fdt) = self.current_fdt_transfer.as_ref() {
+            if fdt.is_transferring() {
+                packets =
+                    packets.saturating_add(fdt_packets(fdt).saturating_sub(fdt.symbols_sent()));
+            } else if fdt.total_nb_transfer() == 0 {
+                packets = packets.saturating_add(fdt_packets(fdt));
+            }
+        }
+
+        packets
+    }
+
+    pub fn is_fdt_pending(&self) -> bool {
+        if !self.fdt_transfer_queue.is_empty() {
+            return true;
+        }
+
+        match self.current_fdt_transfer.as_ref() {
+            Some(fdt) => fdt.is_transferring() || fdt.total_nb_transfer() == 0,
+            None => false,
+        }
+    }
+
+    /// Return `true` if the publication of an FDT is delayed by the minimum republish interval
+    pub fn is_publish_pending(&self) -> bool {
+        self.publish_pending
+    }
+
     pub fn get_next_file_transfer(
         &mut self,
         priority: u32,
@@ -288,14 +675,31 @@ impl Fdt {
             file.toi
         );
 
-        let evt = observer::Event::StartTransfer(observer::FileInfo { toi: file.toi });
+        let evt = observer::Event::StartTransfer(self.file_info(&file));
         self.observers.dispatch(&evt, now);
 
         file.transfer_started(now);
         Some(file.clone())
     }
 
-    pub fn transfer_done(&mut self, file: Arc<FileDesc>, now: SystemTime) {
+    fn file_info(&self, file: &FileDesc) -> observer::FileInfo {
+        observer::FileInfo {
+            toi: file.toi,
+            content_location: file.object.content_location.to_string(),
+            content_length: file.object.content_length,
+            transfer_length: file.object.transfer_length,
+            priority: file.priority,
+            endpoint: self.endpoint.clone(),
+            tsi: self._tsi,
+        }
+    }
+
+    pub fn transfer_done(
+        &mut self,
+        file: Arc<FileDesc>,
+        stats: observer::TransferStats,
+        now: SystemTime,
+    ) {
         file.transfer_done(now);
 
         if file.toi == lct::TOI_FDT {
@@ -305,14 +709,29 @@ impl Fdt {
                 self.current_fdt_transfer = None;
             }
         } else {
-            let evt = observer::Event::StopTransfer(observer::FileInfo { toi: file.toi });
+            let evt = observer::Event::StopTransfer(self.file_info(&file));
             self.observers.dispatch(&evt, now);
+            self.observers
+                .dispatch(&observer::Event::TransferStats(stats), now);
 
             if !self.files.contains_key(&file.toi) {
                 log::debug!("Transfer is finished and file has been removed from FDT");
                 return;
             }
 
+            if let Some(filedesc) = self.files_pending_replacement.remove(&file.toi) {
+                if let Err(e) = self.replace_file(filedesc, now) {
+                    log::error!("TOI {} Fail to replace the content {:?}", file.toi, e);
+                    self.report_error(&observer::SenderError {
+                        toi: file.toi,
+                        sbn: 0,
+                        kind: observer::ErrorKind::Fdt,
+                        message: e.0.to_string(),
+                    });
+                }
+                return;
+            }
+
             log::info!(
                 "Stop transmission of {} toi={}",
                 file.object.content_location.as_str(),
@@ -328,34 +747,176 @@ impl Fdt {
         }
     }
 
+    pub fn transfer_failed(
+        &mut self,
+        file: Arc<FileDesc>,
+        error: &str,
+        stats: observer::TransferStats,
+        now: SystemTime,
+    ) {
+        debug_assert!(file.toi != lct::TOI_FDT);
+        file.transfer_done(now);
+
+        log::error!(
+            "Transfer of {} toi={} has failed, remove it from the FDT: {}",
+            file.object.content_location.as_str(),
+            file.toi,
+            error
+        );
+
+        let evt = observer::Event::StopTransfer(self.file_info(&file));
+        self.observers.dispatch(&evt, now);
+        self.observers
+            .dispatch(&observer::Event::TransferStats(stats), now);
+        let evt = observer::Event::ObjectTransferFailed {
+            toi: file.toi,
+            error: error.to_owned(),
+        };
+        self.observers.dispatch(&evt, now);
+
+        self.remove_object(file.toi);
+    }
+
+    /// Send the error to the channels of `Sender::subscribe_error_events()`
+    pub fn report_error(&self, error: &observer::SenderError) {
+        self.observers.dispatch_error(error);
+    }
+
     pub fn set_complete(&mut self) {
         self.complete = Some(true)
     }
 
     pub fn to_xml(&self, now: SystemTime) -> Result<Vec<u8>> {
-        let mut buffer = ToFmtWrite(Vec::new());
-        let mut writer = quick_xml::Writer::new_with_indent(&mut buffer, b' ', 2);
-
-        match writer.write_event(quick_xml::events::Event::Decl(
-            quick_xml::events::BytesDecl::new("1.0", Some("UTF-8"), None),
-        )) {
-            Ok(_) => {}
-            Err(e) => return Err(FluteError::new(e.to_string())),
-        };
+        fdt_instance_to_xml(&self.get_fdt_instance(now))
+    }
+}
 
-        let ser = match quick_xml::se::Serializer::with_root(&mut buffer, Some("FDT-Instance")) {
-            Ok(ser) => ser,
-            Err(e) => return Err(FluteError::new(e.to_string())),
-        };
-        match self.get_fdt_instance(now).serialize(ser) {
-            Ok(_) => {}
-            Err(e) => return Err(FluteError::new(e.to_string())),
-        };
+/// Create an FDT Instance announcing `files`
+/// `expires_ntp` is the expiration date of the FDT in seconds since NTP epoch
+/// `content_type` is the default Content-Type of the files
+/// Normalize the Content-Location of a file announced in the FDT,
+/// the Content-Location is kept unchanged if it can not be parsed
+pub(crate) fn normalize_content_location(file: &mut fdtinstance::File) {
+    if let Ok(normalized) = contentlocation::normalize(&file.content_location) {
+        file.content_location = normalized;
+    }
+}
 
-        Ok(buffer.0)
+pub(crate) fn new_fdt_instance(
+    oti: &oti::Oti,
+    files: Vec<fdtinstance::File>,
+    expires_ntp: u64,
+    complete: Option<bool>,
+    groups: Option<Vec<String>>,
+    base_urls: Option<Vec<String>>,
+    content_type: Option<String>,
+) -> FdtInstance {
+    let oti_attributes = match oti.fec_encoding_id {
+        oti::FECEncodingID::RaptorQ => None, // RaptorA scheme parameters is object dependent
+        _ => Some(oti.to_fdt_attributes()),
+    };
+
+    FdtInstance {
+        xmlns: None,
+        xmlns_xsi: None,
+        expires: expires_ntp.to_string(),
+        complete,
+        content_type,
+        content_encoding: None,
+        fec_oti_fec_encoding_id: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_fec_encoding_id,
+        },
+        fec_oti_encoding_symbol_length: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_encoding_symbol_length,
+        },
+        fec_oti_fec_instance_id: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_fec_instance_id,
+        },
+        fec_oti_max_number_of_encoding_symbols: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_max_number_of_encoding_symbols,
+        },
+        fec_oti_maximum_source_block_length: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_maximum_source_block_length,
+        },
+        fec_oti_scheme_specific_info: match &oti_attributes {
+            None => None,
+            Some(attr) => attr.fec_oti_scheme_specific_info.clone(),
+        },
+
+        file: Some(files),
+        xmlns_mbms_2005: None,
+        xmlns_mbms_2007: None,
+        xmlns_mbms_2008: None,
+        xmlns_mbms_2009: None,
+        xmlns_mbms_2012: None,
+        xmlns_mbms_2015: None,
+        xmlns_sv: None,
+        full_fdt: None,
+        base_url_1: base_urls,
+        base_url_2: None,
+        group: groups,
+        mbms_session_identity_expiry: None,
+        schema_version: Some(4),
+        delimiter: Some(0),
     }
 }
 
+/// Create the object transporting the FDT Instance `content` on the TOI of the FDT
+pub(crate) fn new_fdt_object(
+    content: &[u8],
+    carousel: Option<std::time::Duration>,
+    groups: Option<Vec<String>>,
+    cenc: lct::Cenc,
+    toi: Box<Toi>,
+) -> Result<Box<ObjectDesc>> {
+    debug_assert!(toi.get() == lct::TOI_FDT);
+    let mut obj = objectdesc::ObjectDesc::create_from_buffer(
+        content,
+        "text/xml",
+        &url::Url::parse("file:///").unwrap(),
+        1,
+        carousel,
+        None,
+        None,
+        groups,
+        cenc,
+        true,
+        None,
+        true,
+    )?;
+    obj.toi = Some(toi);
+    Ok(obj)
+}
+
+/// Serialize an FDT Instance to XML
+pub(crate) fn fdt_instance_to_xml(instance: &FdtInstance) -> Result<Vec<u8>> {
+    let mut buffer = ToFmtWrite(Vec::new());
+    let mut writer = quick_xml::Writer::new_with_indent(&mut buffer, b' ', 2);
+
+    match writer.write_event(quick_xml::events::Event::Decl(
+        quick_xml::events::BytesDecl::new("1.0", Some("UTF-8"), None),
+    )) {
+        Ok(_) => {}
+        Err(e) => return Err(FluteError::from(e)),
+    };
+
+    let ser = match quick_xml::se::Serializer::with_root(&mut buffer, Some("FDT-Instance")) {
+        Ok(ser) => ser,
+        Err(e) => return Err(FluteError::from(e)),
+    };
+    match instance.serialize(ser) {
+        Ok(_) => {}
+        Err(e) => return Err(FluteError::from(e)),
+    };
+
+    Ok(buffer.0)
+}
+
 struct ToFmtWrite<T>(pub T);
 
 impl<T> std::fmt::Write for ToFmtWrite<T>
@@ -383,30 +944,47 @@ where
 #[cfg(test)]
 mod tests {
 
+    use std::sync::Arc;
     use std::time::SystemTime;
 
-    use crate::common::lct;
-    use crate::sender::observer::ObserverList;
+    use crate::common::{fdtinstance::FdtInstance, lct, Profile};
+    use crate::core::UDPEndpoint;
+    use crate::sender::observer::{self, ObserverList};
 
     use super::objectdesc;
     use super::oti;
 
-    fn create_fdt() -> super::Fdt {
+    fn create_empty_fdt(profile: Profile) -> super::Fdt {
         let oti: oti::Oti = Default::default();
-        let mut fdt = super::Fdt::new(
+        let config = crate::sender::Config {
+            fdt_start_id: 1,
+            fdt_cenc: lct::Cenc::Null,
+            fdt_duration: std::time::Duration::from_secs(3600),
+            fdt_carousel: std::time::Duration::from_secs(1),
+            fdt_inband_sct: true,
+            fdt_min_republish_interval: std::time::Duration::ZERO,
+            suppress_duplicate_fdt: false,
+            normalize_content_location: true,
+            toi_max_length: crate::sender::TOIMaxLength::ToiMax112,
+            toi_initial_value: Some(1),
+            groups: Some(vec!["Group1".to_owned()]),
+            base_urls: Some(vec!["https://example.com/".to_owned()]),
+            fdt_content_type: None,
+            profile,
+            ..Default::default()
+        };
+        super::Fdt::new(
             10,
-            1,
+            UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3400),
             &oti,
-            lct::Cenc::Null,
-            std::time::Duration::from_secs(3600),
-            std::time::Duration::from_secs(1),
-            true,
+            &config,
             ObserverList::new(),
-            crate::sender::TOIMaxLength::ToiMax112,
-            Some(1),
-            Some(vec!["Group1".to_owned()]),
-        );
-        let obj1 = objectdesc::ObjectDesc::create_from_buffer(
+        )
+    }
+
+    fn create_fdt() -> super::Fdt {
+        let mut fdt = create_empty_fdt(Profile::RFC6726);
+        let mut obj1 = objectdesc::ObjectDesc::create_from_buffer(
             &Vec::new(),
             "plain/txt",
             &url::Url::parse("file:///object1").unwrap(),
@@ -421,6 +999,10 @@ mod tests {
             true,
         )
         .unwrap();
+        obj1.alternate_content_locations = Some(vec![
+            url::Url::parse("https://example.com/object1").unwrap(),
+            url::Url::parse("https://example.org/object1").unwrap(),
+        ]);
 
         let obj2 = objectdesc::ObjectDesc::create_from_buffer(
             &Vec::new(),
@@ -444,6 +1026,207 @@ mod tests {
         fdt
     }
 
+    fn add_object_with_oti(
+        profile: Profile,
+        oti: oti::Oti,
+        content_length: usize,
+    ) -> crate::error::Result<u128> {
+        let mut fdt = create_empty_fdt(profile);
+        let obj = objectdesc::ObjectDesc::create_from_buffer(
+            &vec![0u8; content_length],
+            "plain/txt",
+            &url::Url::parse("file:///object").unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            lct::Cenc::Null,
+            true,
+            Some(oti),
+            true,
+        )
+        .unwrap();
+        fdt.add_object(0, obj)
+    }
+
+    fn assert_invalid_input(result: crate::error::Result<u128>) {
+        let err = result.unwrap_err();
+        log::info!("{}", err);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    pub fn test_fdt_mbms_session_identity_expiry() {
+        crate::tests::init();
+        let mut fdt = create_empty_fdt(Profile::RFC6726);
+        let now = SystemTime::now();
+        let xml = fdt.to_xml(now).unwrap();
+        let instance = FdtInstance::parse(&xml).unwrap();
+        assert!(instance.mbms_session_identity_expiry.is_none());
+
+        let entries = vec![(1, 3900000000), (2, 3900003600), (255, u32::MAX)];
+        fdt.set_mbms_session_identity_expiry(Some(entries.clone()));
+        let xml = fdt.to_xml(now).unwrap();
+        let instance = FdtInstance::parse(&xml).unwrap();
+        assert_eq!(instance.get_mbms_session_identity_expiry(), Some(entries));
+    }
+
+    #[test]
+    pub fn test_fdt_object_oti_profile() {
+        crate::tests::init();
+        let oti = oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        assert!(oti.is_compatible_with(&Profile::RFC6726));
+        assert!(!oti.is_compatible_with(&Profile::RFC3926));
+        assert_invalid_input(add_object_with_oti(Profile::RFC3926, oti.clone(), 1000));
+        assert!(add_object_with_oti(Profile::RFC6726, oti, 1000).is_ok());
+    }
+
+    #[test]
+    pub fn test_fdt_object_oti_max_transfer_length() {
+        crate::tests::init();
+        let oti = oti::Oti::new_reed_solomon_rs28(4, 2, 1).unwrap();
+        let max_transfer_length = oti.max_transfer_length();
+        assert!(add_object_with_oti(Profile::RFC6726, oti.clone(), max_transfer_length).is_ok());
+        assert_invalid_input(add_object_with_oti(
+            Profile::RFC6726,
+            oti,
+            max_transfer_length + 1,
+        ));
+    }
+
+    #[test]
+    pub fn test_fdt_object_oti_symbol_alignment() {
+        crate::tests::init();
+        let mut oti = oti::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        if let Some(oti::SchemeSpecific::Raptor(scheme)) = oti.scheme_specific.as_mut() {
+            scheme.symbol_alignment = 3;
+        }
+        assert_invalid_input(add_object_with_oti(Profile::RFC6726, oti, 1000));
+
+        let mut oti = oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        if let Some(oti::SchemeSpecific::RaptorQ(scheme)) = oti.scheme_specific.as_mut() {
+            scheme.symbol_alignment = 0;
+        }
+        assert_invalid_input(add_object_with_oti(Profile::RFC6726, oti, 1000));
+    }
+
+    #[test]
+    pub fn test_fdt_object_oti_scheme_specific() {
+        crate::tests::init();
+        // Raptor parameters with a RaptorQ encoding
+        let mut oti = oti::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        oti.fec_encoding_id = oti::FECEncodingID::RaptorQ;
+        assert_invalid_input(add_object_with_oti(Profile::RFC6726, oti, 1000));
+
+        let mut oti = oti::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        oti.scheme_specific = None;
+        assert_invalid_input(add_object_with_oti(Profile::RFC6726, oti, 1000));
+    }
+
+    #[test]
+    pub fn test_fdt_object_oti_outband_fti() {
+        crate::tests::init();
+        let mut oti = oti::Oti::new_no_code(1400, 64);
+        oti.fec_encoding_id = oti::FECEncodingID::ReedSolomonGF2M;
+        oti.inband_fti = false;
+        assert_invalid_input(add_object_with_oti(Profile::RFC6726, oti.clone(), 1000));
+
+        oti.scheme_specific = Some(oti::SchemeSpecific::ReedSolomon(Default::default()));
+        assert!(add_object_with_oti(Profile::RFC6726, oti, 1000).is_ok());
+    }
+
+    #[test]
+    pub fn test_fdt_replace_object_content_during_transfer() {
+        crate::tests::init();
+        let mut fdt = create_fdt();
+        let now = SystemTime::now();
+        fdt.publish(now).unwrap();
+
+        let file = fdt.get_next_file_transfer(0, now).unwrap();
+        let toi = file.toi;
+        let md5 = file.object.md5.clone();
+        fdt.replace_object_content(
+            toi,
+            objectdesc::ObjectDataSource::Buffer(vec![1, 2, 3]),
+            None,
+            now,
+        )
+        .unwrap();
+
+        // Content is replaced only once the transfer of the old content is finished
+        assert!(fdt.files.get(&toi).unwrap().object.md5 == md5);
+        fdt.transfer_done(
+            file,
+            observer::TransferStats {
+                toi,
+                ..Default::default()
+            },
+            now,
+        );
+
+        let file = fdt.files.get(&toi).unwrap();
+        assert!(file.object.md5.is_some());
+        assert!(file.object.md5 != md5);
+        assert!(file.object.content_length == 3);
+        assert!(fdt
+            .files_transfer_queue
+            .iter()
+            .any(|f| Arc::ptr_eq(f, file)));
+    }
+
+    #[test]
+    pub fn test_fdt_replace_object_content_etag() {
+        crate::tests::init();
+        let mut fdt = create_empty_fdt(Profile::RFC6726);
+        let now = SystemTime::now();
+
+        let mut obj1 = objectdesc::ObjectDesc::create_from_buffer_with_etag(
+            vec![1, 2, 3],
+            "plain/txt",
+            &url::Url::parse("file:///object1").unwrap(),
+            objectdesc::ObjectParams {
+                md5: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        obj1.independent_unit_positions = Some(vec![0, 2]);
+        let mut obj2 = objectdesc::ObjectDesc::create_from_buffer(
+            &[1, 2, 3],
+            "plain/txt",
+            &url::Url::parse("file:///object2").unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            lct::Cenc::Null,
+            true,
+            None,
+            false,
+        )
+        .unwrap();
+        obj2.file_etag = Some("\"v1\"".to_owned());
+        let toi1 = fdt.add_object(0, obj1).unwrap();
+        let toi2 = fdt.add_object(0, obj2).unwrap();
+
+        let content = vec![4, 5, 6, 7];
+        for toi in [toi1, toi2] {
+            let source = objectdesc::ObjectDataSource::Buffer(content.clone());
+            fdt.replace_object_content(toi, source, None, now).unwrap();
+        }
+
+        // The ETag computed from the content follows the new content, the other ETag is removed
+        let source = objectdesc::ObjectDataSource::Buffer(content);
+        let expected = objectdesc::ObjectDesc::compute_e_tag(&source).unwrap();
+        let object1 = &fdt.get_file(toi1).unwrap().object;
+        assert_eq!(object1.file_etag.as_ref(), Some(&expected));
+        assert!(object1.independent_unit_positions.is_none());
+        let object2 = &fdt.get_file(toi2).unwrap().object;
+        assert!(object2.file_etag.is_none());
+    }
+
     #[test]
     pub fn test_fdt() {
         use std::{io::Write, process::Command};
@@ -2,7 +2,7 @@ use base64::Engine;
 
 use super::objectdesc::{create_fdt_cache_control, ObjectDesc};
 use crate::common::oti::SchemeSpecific;
-use crate::common::{fdtinstance, oti, partition};
+use crate::common::{fdtinstance, oti, partition, Profile};
 use crate::error::{FluteError, Result};
 use std::sync::atomic::AtomicBool;
 use std::sync::RwLock;
@@ -16,11 +16,13 @@ struct TransferInfo {
     last_transfer: Option<SystemTime>,
     next_transfer_timestamp: Option<SystemTime>,
     packet_transmission_tick: Option<std::time::Duration>,
+    symbols_sent: u64,
 }
 
 impl TransferInfo {
     fn init(&mut self, object: &ObjectDesc, oti: &oti::Oti, now: SystemTime) {
         self.transferring = true;
+        self.symbols_sent = 0;
         let mut packet_transmission_tick = None;
         if let Some(target_acquisition_latency) = object.target_acquisition.as_ref() {
             packet_transmission_tick = match target_acquisition_latency {
@@ -92,6 +94,7 @@ impl FileDesc {
         default_oti: &oti::Oti,
         fdt_id: Option<u32>,
         sender_current_time: bool,
+        profile: Profile,
     ) -> Result<FileDesc> {
         assert!(object.toi.is_some());
         let mut oti = match &object.oti {
@@ -99,12 +102,25 @@ impl FileDesc {
             None => default_oti.clone(),
         };
 
+        if !oti.is_compatible_with(&profile) {
+            return Err(FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "FEC {:?} is not supported by the FLUTE profile {:?}",
+                    oti.fec_encoding_id, profile
+                ),
+            ));
+        }
+
         let max_transfer_length = oti.max_transfer_length();
         if object.transfer_length as usize > max_transfer_length {
-            return Err(FluteError::new(format!(
-                "Object transfer length of {} is bigger than {}, so is incompatible with the parameters of your OTI",
-                object.transfer_length, max_transfer_length
-            )));
+            return Err(FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Object transfer length of {} is bigger than {}, so is incompatible with the parameters of your OTI",
+                    object.transfer_length, max_transfer_length
+                ),
+            ));
         }
 
         if oti.fec_encoding_id == oti::FECEncodingID::RaptorQ
@@ -118,45 +134,64 @@ impl FileDesc {
                 oti.encoding_symbol_length as u64,
             );
 
-            if oti.fec_encoding_id == oti::FECEncodingID::RaptorQ {
-                if oti.scheme_specific.is_none() {
-                    return Err(FluteError::new(
-                        "FEC RaptorQ is selected, however scheme parameters are not defined",
-                    ));
+            let encoding_symbol_length = oti.encoding_symbol_length;
+            match (oti.fec_encoding_id, oti.scheme_specific.as_mut()) {
+                (oti::FECEncodingID::RaptorQ, Some(SchemeSpecific::RaptorQ(scheme))) => {
+                    check_symbol_alignment(encoding_symbol_length, scheme.symbol_alignment)?;
+                    scheme.source_blocks_length = nb_blocks.try_into().map_err(|_| {
+                        FluteError::new_kind(std::io::ErrorKind::InvalidInput, format!(
+                            "Object transfer length of {} requires the transmission of {} source blocks, the maximum is {}, your object is incompatible with the FEC parameters of your OTI",
+                            object.transfer_length,
+                            nb_blocks, u8::MAX
+                        ))
+                    })?;
                 }
-
-                let nb_blocks:u8 = nb_blocks.try_into().map_err(|_| {
-                    FluteError::new(format!(
-                        "Object transfer length of {} requires the transmission of {} source blocks, the maximum is {}, your object is incompatible with the FEC parameters of your OTI",
-                        object.transfer_length,
-                        nb_blocks, u8::MAX
-                    ))
-                })?;
-
-                if let SchemeSpecific::RaptorQ(scheme) = oti.scheme_specific.as_mut().unwrap() {
-                    scheme.source_blocks_length = nb_blocks;
+                (oti::FECEncodingID::Raptor, Some(SchemeSpecific::Raptor(scheme))) => {
+                    check_symbol_alignment(encoding_symbol_length, scheme.symbol_alignment)?;
+                    scheme.source_blocks_length = nb_blocks.try_into().map_err(|_| {
+                        FluteError::new_kind(std::io::ErrorKind::InvalidInput, format!(
+                            "Object transfer length of {} requires the transmission of {} source blocks, the maximum is {}, your object is incompatible with the FEC parameters of your OTI",
+                            object.transfer_length,
+                            nb_blocks, u16::MAX
+                        ))
+                    })?;
                 }
-            } else if oti.fec_encoding_id == oti::FECEncodingID::Raptor {
-                if oti.scheme_specific.is_none() {
-                    return Err(FluteError::new(
-                        "FEC Raptor is selected, however scheme parameters are not defined",
+                (fec_encoding_id, _) => {
+                    return Err(FluteError::new_kind(
+                        std::io::ErrorKind::InvalidInput,
+                        format!(
+                            "FEC {:?} is selected, however scheme parameters are not defined",
+                            fec_encoding_id
+                        ),
                     ));
                 }
-
-                let nb_blocks:u16 = nb_blocks.try_into().map_err(|_| {
-                    FluteError::new(format!(
-                        "Object transfer length of {} requires the transmission of {} source blocks, the maximum is {}, your object is incompatible with the FEC parameters of your OTI",
-                        object.transfer_length,
-                        nb_blocks, u8::MAX
-                    ))
-                })?;
-
-                if let SchemeSpecific::Raptor(scheme) = oti.scheme_specific.as_mut().unwrap() {
-                    scheme.source_blocks_length = nb_blocks;
-                }
             }
         }
 
+        // Without FTI in the ALC/LCT packets, the receiver gets the scheme parameters from the FDT only
+        let scheme_specific_required = matches!(
+            oti.fec_encoding_id,
+            oti::FECEncodingID::ReedSolomonGF2M
+                | oti::FECEncodingID::RaptorQ
+                | oti::FECEncodingID::Raptor
+        );
+        if fdt_id.is_none()
+            && !oti.inband_fti
+            && scheme_specific_required
+            && oti
+                .to_fdt_attributes()
+                .fec_oti_scheme_specific_info
+                .is_none()
+        {
+            return Err(FluteError::new_kind(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "FEC {:?} is selected without in-band FTI, however its scheme parameters can not be expressed in the FDT",
+                    oti.fec_encoding_id
+                ),
+            ));
+        }
+
         let toi = object.toi.as_ref().unwrap().get();
         Ok(FileDesc {
             priority,
@@ -171,6 +206,7 @@ impl FileDesc {
                 total_nb_transfer: 0,
                 next_transfer_timestamp: None,
                 packet_transmission_tick: None,
+                symbols_sent: 0,
             }),
             published: AtomicBool::new(false),
             toi,
@@ -192,6 +228,51 @@ impl FileDesc {
         info.done(now);
     }
 
+    /// Override the time of the last transfer, `None` restarts the carousel delay of the file
+    pub fn reset_last_transfer(&self, last_transfer: Option<SystemTime>) {
+        let mut info = self.transfer_info.write().unwrap();
+        info.last_transfer = last_transfer;
+    }
+
+    /// Delay the carousel and the pacing of the transfers by `duration`, ex: after a pause of the sender
+    pub fn shift_timers(&self, duration: std::time::Duration) {
+        let mut info = self.transfer_info.write().unwrap();
+        let shift = |t: SystemTime| t.checked_add(duration).unwrap_or(t);
+        info.last_transfer = info.last_transfer.map(shift);
+        info.next_transfer_timestamp = info.next_transfer_timestamp.map(shift);
+    }
+
+    /// Number of encoding symbols sent during the current transfer
+    pub fn symbols_sent(&self) -> u64 {
+        let info = self.transfer_info.read().unwrap();
+        info.symbols_sent
+    }
+
+    pub fn inc_symbols_sent(&self) {
+        let mut info = self.transfer_info.write().unwrap();
+        info.symbols_sent += 1;
+    }
+
+    /// Estimate the number of packets left to transfer this file
+    /// `None` if the file is empty or transferred in a carousel
+    pub fn packets_left(&self) -> Option<u64> {
+        if self.object.transfer_length == 0 || self.object.carousel_delay.is_some() {
+            return None;
+        }
+
+        let packets_per_transfer = self.oti.packets_per_object(self.object.transfer_length);
+        let info = self.transfer_info.read().unwrap();
+        let transfers_left = self
+            .object
+            .max_transfer_count
+            .saturating_sub(info.transfer_count) as u64;
+        let symbols_sent = match info.transferring {
+            true => info.symbols_sent,
+            false => 0,
+        };
+        Some((packets_per_transfer * transfers_left).saturating_sub(symbols_sent))
+    }
+
     pub fn is_expired(&self) -> bool {
         let info = self.transfer_info.read().unwrap();
         if self.object.max_transfer_count > info.transfer_count {
@@ -248,6 +329,26 @@ impl FileDesc {
         now.duration_since(*last_transfer).unwrap_or_default() > *delay
     }
 
+    /// Earliest time when `should_transfer_now()` returns `true`, `None` if the file is not published
+    pub fn next_transfer_time(&self) -> Option<SystemTime> {
+        if !self.is_published() {
+            return None;
+        }
+
+        let info = self.transfer_info.read().unwrap();
+        if self.object.max_transfer_count > info.transfer_count {
+            return Some(SystemTime::UNIX_EPOCH);
+        }
+
+        match (self.object.carousel_delay, info.last_transfer) {
+            // The carousel delay must be strictly exceeded
+            (Some(delay), Some(last_transfer)) => {
+                Some(last_transfer + delay + std::time::Duration::from_nanos(1))
+            }
+            _ => Some(SystemTime::UNIX_EPOCH),
+        }
+    }
+
     pub fn is_published(&self) -> bool {
         self.published.load(std::sync::atomic::Ordering::Relaxed)
     }
@@ -257,10 +358,17 @@ impl FileDesc {
             .store(true, std::sync::atomic::Ordering::Relaxed);
     }
 
-    pub fn to_file_xml(&self, now: SystemTime) -> fdtinstance::File {
+    /// Convert to an FDT File entry
+    /// `Content-Type` is omitted when it is equal to `fdt_content_type`, the default Content-Type of the FDT
+    pub fn to_file_xml(
+        &self,
+        now: SystemTime,
+        fdt_content_type: Option<&str>,
+    ) -> fdtinstance::File {
         let oti_attributes = match self.oti.fec_encoding_id {
-            oti::FECEncodingID::RaptorQ => Some(self.oti.get_attributes()), // for RaptorQ we need to add OTI for each object
-            _ => self.object.oti.as_ref().map(|oti| oti.get_attributes()),
+            oti::FECEncodingID::RaptorQ => Some(self.oti.to_fdt_attributes()), // for RaptorQ we need to add OTI for each object
+            _ if self.object.oti.is_some() => Some(self.oti.to_fdt_attributes()),
+            _ => None,
         };
 
         let optel_propagator = self.object.optel_propagator.as_ref().map(|propagator| {
@@ -273,7 +381,10 @@ impl FileDesc {
             toi: self.toi.to_string(),
             content_length: Some(self.object.content_length),
             transfer_length: Some(self.object.transfer_length),
-            content_type: Some(self.object.content_type.clone()),
+            content_type: match fdt_content_type == Some(self.object.content_type.as_str()) {
+                true => None,
+                false => Some(self.object.content_type.clone()),
+            },
             content_encoding: Some(self.object.cenc.to_str().to_string()),
             content_md5: self.object.md5.clone(),
             fec_oti_fec_encoding_id: oti_attributes
@@ -299,17 +410,54 @@ impl FileDesc {
                 .cache_control
                 .as_ref()
                 .map(|cc| create_fdt_cache_control(cc, now)),
-            alternate_content_location_1: None,
+            alternate_content_location_1: self.object.alternate_content_locations.as_ref().map(
+                |locations| {
+                    vec![fdtinstance::AlternateContentLocation {
+                        alternate_content_location: locations
+                            .iter()
+                            .map(|location| location.to_string())
+                            .collect(),
+                        availability_time: None,
+                    }]
+                },
+            ),
             alternate_content_location_2: None,
             mbms_session_identity: None,
-            decryption_key_uri: None,
+            decryption_key_uri: self
+                .object
+                .encryption
+                .as_ref()
+                .map(|cipher| cipher.key_uri()),
             fec_redundancy_level: None,
-            file_etag: None,
-            independent_unit_positions: None,
+            file_etag: self.object.file_etag.clone(),
+            independent_unit_positions: self.object.independent_unit_positions.as_ref().map(
+                |positions| {
+                    positions
+                        .iter()
+                        .map(|position| position.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                },
+            ),
             delimiter: Some(0),
             delimiter2: Some(0),
-            group: None,
+            group: self.object.groups.clone(),
             optel_propagator,
+            content_coding: self.object.content_encoding.clone(),
         }
     }
 }
+
+/// Raptor and RaptorQ require the encoding symbol length to be a multiple of the symbol alignment (Al)
+fn check_symbol_alignment(encoding_symbol_length: u16, symbol_alignment: u8) -> Result<()> {
+    if symbol_alignment == 0 || encoding_symbol_length % symbol_alignment as u16 != 0 {
+        return Err(FluteError::new_kind(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Encoding symbol length of {} is not a multiple of the symbol alignment {}",
+                encoding_symbol_length, symbol_alignment
+            ),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use crate::common::{lct, pkt};
+use crate::error::FluteError;
+use crate::tools::error::Result;
+
+///
+/// Policy assigning the ALC/LCT packets of the objects to the layers of a layered coding session
+/// as defined in [rfc5775](https://www.rfc-editor.org/rfc/rfc5775)
+///
+/// The FDT is always sent on layer 0
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayerPolicy {
+    /// Source symbols are sent on layer 0, repair symbols are spread across the upper layers.
+    /// A receiver listening to layer 0 only receives the objects without FEC protection
+    RepairOnUpperLayers,
+    /// Source blocks are assigned to the layers in a round-robin way
+    RoundRobinBlocks,
+}
+
+/// Layers of the session and their Congestion Control Information (CCI)
+#[derive(Debug)]
+pub struct Layering {
+    policy: LayerPolicy,
+    cci: Vec<u128>,
+}
+
+impl Layering {
+    pub fn new(nb_layers: usize, policy: LayerPolicy) -> Self {
+        Layering {
+            policy,
+            cci: vec![0; nb_layers.max(1)],
+        }
+    }
+
+    pub fn nb_layers(&self) -> usize {
+        self.cci.len()
+    }
+
+    pub fn set_cci(&mut self, layer: usize, cci: u128) -> Result<()> {
+        let nb_layers = self.nb_layers();
+        let value = self.cci.get_mut(layer).ok_or_else(|| {
+            FluteError::new(format!(
+                "Layer {} does not exist ({} layers)",
+                layer, nb_layers
+            ))
+        })?;
+        *value = cci;
+        Ok(())
+    }
+
+    /// Return the layer of the packet and its CCI
+    pub fn select(&self, pkt: &pkt::Pkt) -> (usize, u128) {
+        let layer = self.select_layer(pkt);
+        (layer, self.cci[layer])
+    }
+
+    fn select_layer(&self, pkt: &pkt::Pkt) -> usize {
+        let nb_layers = self.nb_layers();
+        if nb_layers == 1 || pkt.toi == lct::TOI_FDT {
+            return 0;
+        }
+
+        match self.policy {
+            LayerPolicy::RepairOnUpperLayers => {
+                if pkt.source_block_length == 0 || pkt.esi < pkt.source_block_length {
+                    return 0;
+                }
+                let repair_index = (pkt.esi - pkt.source_block_length) as usize;
+                1 + repair_index % (nb_layers - 1)
+            }
+            LayerPolicy::RoundRobinBlocks => pkt.sbn as usize % nb_layers,
+        }
+    }
+}
@@ -7,25 +7,42 @@ mod blockencoder;
 mod compress;
 mod fdt;
 mod filedesc;
+mod layer;
 mod objectdesc;
 mod observer;
 mod sender;
+mod senderbuilder;
 mod sendersession;
 mod toiallocator;
 
+pub mod offline;
+
 #[cfg(feature = "opentelemetry")]
 mod objectsenderlogger;
 
 pub use crate::common::Profile;
+pub use layer::LayerPolicy;
 pub use objectdesc::CacheControl;
+pub use objectdesc::ContentGenerator;
+pub use objectdesc::ObjectDataSource;
 pub use objectdesc::ObjectDesc;
+pub use objectdesc::ObjectGenerator;
+pub use objectdesc::ObjectParams;
 pub use objectdesc::TargetAcquisition;
+pub use observer::ErrorKind;
 pub use observer::Event;
+pub use observer::EventKindMask;
 pub use observer::FileInfo;
+pub use observer::SenderError;
 pub use observer::Subscriber;
+pub use observer::TransferStats;
+pub use sender::BackpressureState;
 pub use sender::Config;
+pub use sender::EncodingParams;
 pub use sender::PriorityQueue;
 pub use sender::Sender;
+pub use sender::SenderActivity;
+pub use senderbuilder::SenderBuilder;
 pub use sender::TOIMaxLength;
 pub use toiallocator::Toi;
 
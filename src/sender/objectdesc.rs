@@ -2,13 +2,17 @@ use base64::Engine;
 
 use super::compress;
 use super::toiallocator::Toi;
+use crate::common::cipher::ObjectCipher;
 use crate::common::{fdtinstance, lct, oti};
 use crate::error::FluteError;
 use crate::tools;
 use crate::tools::error::Result;
+use crate::tools::etag::{ETagAlgorithm, ETagHasher};
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::io::{BufReader, Read};
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// Cache Control
@@ -50,7 +54,7 @@ pub fn create_fdt_cache_control(cc: &CacheControl, now: SystemTime) -> fdtinstan
 
 ///
 /// Target Acquisition for Object
-/// 
+///
 #[derive(Debug, Clone)]
 pub enum TargetAcquisition {
     /// Transfer the object as fast as possible
@@ -61,6 +65,158 @@ pub enum TargetAcquisition {
     WithinTime(std::time::SystemTime),
 }
 
+///
+/// Transfer parameters of an object, see `ObjectDesc::create_from_buffer_with_etag()`
+/// and `ObjectDesc::create_from_generator()`
+///
+#[derive(Debug, Clone)]
+pub struct ObjectParams {
+    /// Repeat the transfer the same object multiple times
+    pub max_transfer_count: u32,
+    /// If defined, object is transmitted in a carousel every `carousel_delay`
+    pub carousel_delay: Option<std::time::Duration>,
+    /// Specifies the desired duration for transferring the object to the receiver
+    pub target_acquisition: Option<TargetAcquisition>,
+    /// Define object cache control
+    pub cache_control: Option<CacheControl>,
+    /// Add file to a list of groups
+    pub groups: Option<Vec<String>>,
+    /// Content Encoding (compression)
+    pub cenc: lct::Cenc,
+    /// If `true`, Cenc extension are added to ALC/LCT packet
+    pub inband_cenc: bool,
+    /// If defined, FEC Object Transmission Information (OTI) overload the default OTI defined in the FDT
+    pub oti: Option<oti::Oti>,
+    /// Compute the MD5 of the object
+    pub md5: bool,
+}
+
+impl Default for ObjectParams {
+    fn default() -> Self {
+        Self {
+            max_transfer_count: 1,
+            carousel_delay: None,
+            target_acquisition: None,
+            cache_control: None,
+            groups: None,
+            cenc: lct::Cenc::Null,
+            inband_cenc: true,
+            oti: None,
+            md5: true,
+        }
+    }
+}
+
+/// Function generating the content of an object
+///
+/// Called with `(offset, length)`, returns `length` bytes of content starting at `offset`
+pub type ContentGenerator = Arc<dyn Fn(u64, usize) -> Vec<u8> + Send + Sync>;
+
+///
+/// Source of the content of an object
+///
+pub enum ObjectDataSource {
+    /// Content of the object is stored in a buffer
+    Buffer(Vec<u8>),
+    /// Content of the object is read from a file
+    File(std::path::PathBuf),
+    /// Content of the object is generated on the fly, without being stored in RAM
+    Generator {
+        /// Function generating the content
+        generator: ContentGenerator,
+        /// Size of the object
+        total_length: u64,
+        /// Offset of the next byte returned by `Read::read()`
+        position: AtomicU64,
+    },
+}
+
+impl ObjectDataSource {
+    /// Return a source generating `total_length` bytes of content with `generator`
+    pub fn from_generator(generator: ContentGenerator, total_length: u64) -> Self {
+        ObjectDataSource::Generator {
+            generator,
+            total_length,
+            position: AtomicU64::new(0),
+        }
+    }
+}
+
+impl std::fmt::Debug for ObjectDataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectDataSource::Buffer(content) => f.debug_tuple("Buffer").field(content).finish(),
+            ObjectDataSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            ObjectDataSource::Generator {
+                total_length,
+                position,
+                ..
+            } => f
+                .debug_struct("Generator")
+                .field("total_length", total_length)
+                .field("position", position)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Only the `Generator` source can be read
+impl Read for ObjectDataSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let (generator, total_length, position) = match self {
+            ObjectDataSource::Generator {
+                generator,
+                total_length,
+                position,
+            } => (generator, *total_length, position.get_mut()),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "Only a generated content can be read",
+                ))
+            }
+        };
+
+        let length = total_length.saturating_sub(*position).min(buf.len() as u64) as usize;
+        if length == 0 {
+            return Ok(0);
+        }
+
+        let data = generator(*position, length);
+        if data.len() != length {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Generator returned {} bytes instead of {}",
+                    data.len(),
+                    length
+                ),
+            ));
+        }
+
+        buf[..length].copy_from_slice(&data);
+        *position += length as u64;
+        Ok(length)
+    }
+}
+
+/// Content generator of an object created with `ObjectDesc::create_from_generator()`
+#[derive(Clone)]
+pub struct ObjectGenerator(pub ContentGenerator);
+
+impl ObjectGenerator {
+    /// Generate `length` bytes of content starting at `offset`
+    pub fn generate(&self, offset: u64, length: usize) -> Vec<u8> {
+        (self.0)(offset, length)
+    }
+}
+
+impl std::fmt::Debug for ObjectGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ObjectGenerator")
+    }
+}
+
 ///
 /// Object (file) that can be send over FLUTE
 ///
@@ -73,6 +229,8 @@ pub struct ObjectDesc {
     pub path: Option<std::path::PathBuf>,
     /// Optional buffer contening the content of this object
     pub content: Option<Vec<u8>>,
+    /// Optional generator of the content of this object
+    pub generator: Option<ObjectGenerator>,
     /// Media type of the object
     /// as defined in [rfc2616 14.17](https://www.rfc-editor.org/rfc/rfc2616#section-14.17)
     pub content_type: String,
@@ -84,6 +242,8 @@ pub struct ObjectDesc {
     pub transfer_length: u64,
     /// Content Encoding (compression)
     pub cenc: lct::Cenc,
+    /// Encryption of the content, applied after the compression, see `ObjectDesc::set_encryption()`
+    pub encryption: Option<Arc<dyn ObjectCipher>>,
     /// If `true`, Cenc extension are added to ALC/LCT packet
     /// Else Cenc is defined only inside the FDT
     pub inband_cenc: bool,
@@ -107,6 +267,21 @@ pub struct ObjectDesc {
     pub toi: Option<Box<Toi>>,
     /// Optional Opentelemetry propagator (only available with the `opentelemetry` feature)
     pub optel_propagator: Option<HashMap<String, String>>,
+    /// Optional list of alternate locations (ex: HTTPS fallback) where the object can be retrieved,
+    /// advertised as `Alternate-Content-Location-1` in the FDT
+    pub alternate_content_locations: Option<Vec<url::Url>>,
+    /// Optional entity tag of the object, advertised as `File-ETag` in the FDT
+    pub file_etag: Option<String>,
+    /// Optional byte offsets of the independent units (e.g. DASH segments start points) inside the object,
+    /// advertised as `IndependentUnitPositions` in the FDT
+    pub independent_unit_positions: Option<Vec<u64>>,
+    /// If `true`, the Sender Current Time (SCT) is added to the ALC/LCT packets of this object (EXT_TIME)
+    pub inband_sct: bool,
+    /// Optional content-coding of the content (e.g. `gzip` for a pre-compressed content), advertised as `X-Content-Coding` in the FDT
+    ///
+    /// Unlike `cenc`, the transfer coding applied by the sender and reversed by the receiver,
+    /// the content-coding is kept as-is: the receiver delivers the encoded content and reports the coding in `ObjectMetadata`
+    pub content_encoding: Option<String>,
 }
 
 impl ObjectDesc {
@@ -216,6 +391,241 @@ impl ObjectDesc {
         )
     }
 
+    /// Return an `ObjectDesc` from a buffer, with its `File-ETag` computed from the content
+    ///
+    /// See `ObjectDesc::compute_e_tag()`
+    pub fn create_from_buffer_with_etag(
+        content: Vec<u8>,
+        content_type: &str,
+        content_location: &url::Url,
+        params: ObjectParams,
+    ) -> Result<Box<ObjectDesc>> {
+        let mut hasher = ETagHasher::new(ETagAlgorithm::preferred());
+        hasher.consume(&content);
+        let file_etag = hasher.compute();
+
+        let mut obj = ObjectDesc::create_with_content(
+            content,
+            None,
+            content_type.to_string(),
+            content_location.clone(),
+            params.max_transfer_count,
+            params.carousel_delay,
+            params.target_acquisition,
+            params.cache_control,
+            params.groups,
+            params.cenc,
+            params.inband_cenc,
+            params.oti,
+            params.md5,
+        )?;
+        obj.file_etag = Some(file_etag);
+        Ok(obj)
+    }
+
+    /// Return an `ObjectDesc` whose content is generated on the fly by `generator`
+    ///
+    /// The content is never stored in RAM: `generator` is called with `(offset, length)`
+    /// each time a source block is encoded and must return `length` bytes of content starting at `offset`.
+    /// The same bytes must be returned for the same offset, as the content is generated again at each transfer.
+    /// The content is not compressed, `params.cenc` must be `Cenc::Null`.
+    pub fn create_from_generator(
+        generator: ContentGenerator,
+        total_length: u64,
+        content_type: &str,
+        content_location: &url::Url,
+        params: ObjectParams,
+    ) -> Result<Box<ObjectDesc>> {
+        Self::create_with_generator(
+            ObjectGenerator(generator),
+            total_length,
+            content_type.to_string(),
+            content_location.clone(),
+            params,
+        )
+    }
+
+    /// Compute an ETag from the content of an object
+    ///
+    /// The ETag is the hash of the content, before compression, formatted as `<algorithm>-<hex>`.
+    /// BLAKE3 is used when the `blake3` feature is enabled, MD5 otherwise.
+    /// Receivers can validate it with `receiver::Config::enable_etag_check`
+    pub fn compute_e_tag(source: &ObjectDataSource) -> Result<String> {
+        let mut hasher = ETagHasher::new(ETagAlgorithm::preferred());
+        match source {
+            ObjectDataSource::Buffer(content) => hasher.consume(content),
+            ObjectDataSource::File(path) => {
+                let file = std::fs::File::open(path)?;
+                let mut reader = BufReader::new(file);
+                let mut buffer = vec![0; 102400];
+                loop {
+                    let count = reader.read(&mut buffer)?;
+                    if count == 0 {
+                        break;
+                    }
+                    hasher.consume(&buffer[..count]);
+                }
+            }
+            ObjectDataSource::Generator {
+                generator,
+                total_length,
+                ..
+            } => {
+                let generator = ObjectGenerator(generator.clone());
+                Self::consume_generator(&generator, *total_length, |data| hasher.consume(data))?;
+            }
+        }
+        Ok(hasher.compute())
+    }
+
+    /// Encrypt the content of the object with `cipher`
+    ///
+    /// The content, already compressed with `Cenc`, is replaced by its encrypted version
+    /// and the `Transfer-Length` is updated. The MD5 of the object is kept, so the receiver checks the decrypted content.
+    /// The key URI of the cipher is advertised as `Decryption-KEY-URI` in the FDT.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is not stored in RAM (file not cached in RAM or generated content),
+    /// if the object is already encrypted or if the encryption fails
+    pub fn set_encryption(&mut self, cipher: Arc<dyn ObjectCipher>) -> Result<()> {
+        if self.encryption.is_some() {
+            return Err(FluteError::new("Object is already encrypted"));
+        }
+
+        let content = match self.content.as_ref() {
+            Some(content) => content,
+            None => {
+                return Err(FluteError::new(
+                    "Encryption requires the content of the object to be stored in RAM",
+                ))
+            }
+        };
+
+        let content = cipher.transform(content)?;
+        self.transfer_length = content.len() as u64;
+        self.content = Some(content);
+        self.encryption = Some(cipher);
+        Ok(())
+    }
+
+    /// Return a copy of this object description with a new content
+    ///
+    /// If `md5` is `None` and this object has an MD5, the MD5 of the new content is computed.
+    /// A `File-ETag` computed from the content is computed again, any other `File-ETag` is removed,
+    /// as well as the positions of the independent units.
+    /// The TOI is not copied.
+    pub(crate) fn with_content(
+        &self,
+        source: ObjectDataSource,
+        md5: Option<String>,
+    ) -> Result<Box<ObjectDesc>> {
+        let compute_md5 = md5.is_none() && self.md5.is_some();
+        let file_etag = match self.file_etag.as_deref().and_then(ETagAlgorithm::from_etag) {
+            Some(_) => Some(Self::compute_e_tag(&source)?),
+            None => None,
+        };
+        let mut obj = match source {
+            ObjectDataSource::Buffer(content) => Self::create_with_content(
+                content,
+                None,
+                self.content_type.clone(),
+                self.content_location.clone(),
+                self.max_transfer_count,
+                self.carousel_delay,
+                self.target_acquisition.clone(),
+                self.cache_control,
+                self.groups.clone(),
+                self.cenc,
+                self.inband_cenc,
+                self.oti.clone(),
+                compute_md5,
+            )?,
+            ObjectDataSource::File(path) => Self::create_with_path(
+                path,
+                self.content_type.clone(),
+                self.content_location.clone(),
+                self.max_transfer_count,
+                self.carousel_delay,
+                self.target_acquisition.clone(),
+                self.cache_control,
+                self.groups.clone(),
+                self.cenc,
+                self.inband_cenc,
+                self.oti.clone(),
+                compute_md5,
+            )?,
+            ObjectDataSource::Generator {
+                generator,
+                total_length,
+                ..
+            } => Self::create_with_generator(
+                ObjectGenerator(generator),
+                total_length,
+                self.content_type.clone(),
+                self.content_location.clone(),
+                ObjectParams {
+                    max_transfer_count: self.max_transfer_count,
+                    carousel_delay: self.carousel_delay,
+                    target_acquisition: self.target_acquisition.clone(),
+                    cache_control: self.cache_control,
+                    groups: self.groups.clone(),
+                    cenc: lct::Cenc::Null,
+                    inband_cenc: self.inband_cenc,
+                    oti: self.oti.clone(),
+                    md5: compute_md5,
+                },
+            )?,
+        };
+
+        if md5.is_some() {
+            obj.md5 = md5;
+        }
+        if let Some(cipher) = self.encryption.as_ref() {
+            obj.set_encryption(cipher.clone())?;
+        }
+        obj.attributes = self.attributes.clone();
+        obj.optel_propagator = self.optel_propagator.clone();
+        obj.alternate_content_locations = self.alternate_content_locations.clone();
+        obj.file_etag = file_etag;
+        obj.content_encoding = self.content_encoding.clone();
+        obj.inband_sct = self.inband_sct;
+        Ok(obj)
+    }
+
+    /// Return a copy of this object description with new FEC parameters
+    ///
+    /// The TOI is not copied.
+    pub(crate) fn with_oti(&self, oti: Option<oti::Oti>) -> Box<ObjectDesc> {
+        Box::new(ObjectDesc {
+            content_location: self.content_location.clone(),
+            path: self.path.clone(),
+            content: self.content.clone(),
+            generator: self.generator.clone(),
+            content_type: self.content_type.clone(),
+            content_length: self.content_length,
+            transfer_length: self.transfer_length,
+            cenc: self.cenc,
+            encryption: self.encryption.clone(),
+            inband_cenc: self.inband_cenc,
+            md5: self.md5.clone(),
+            attributes: self.attributes.clone(),
+            oti,
+            max_transfer_count: self.max_transfer_count,
+            target_acquisition: self.target_acquisition.clone(),
+            carousel_delay: self.carousel_delay,
+            cache_control: self.cache_control,
+            groups: self.groups.clone(),
+            toi: None,
+            optel_propagator: self.optel_propagator.clone(),
+            alternate_content_locations: self.alternate_content_locations.clone(),
+            file_etag: self.file_etag.clone(),
+            independent_unit_positions: self.independent_unit_positions.clone(),
+            content_encoding: self.content_encoding.clone(),
+            inband_sct: self.inband_sct,
+        })
+    }
+
     fn create_with_content(
         mut content: Vec<u8>,
         path: Option<std::path::PathBuf>,
@@ -256,10 +666,12 @@ impl ObjectDesc {
             content_location,
             path,
             content: Some(content),
+            generator: None,
             content_type,
             content_length: content_length as u64,
             transfer_length: transfer_length as u64,
             cenc,
+            encryption: None,
             inband_cenc,
             md5,
             attributes: None,
@@ -271,6 +683,11 @@ impl ObjectDesc {
             groups,
             toi: None,
             optel_propagator: None,
+            alternate_content_locations: None,
+            file_etag: None,
+            independent_unit_positions: None,
+            content_encoding: None,
+            inband_sct: false,
         }))
     }
 
@@ -308,10 +725,12 @@ impl ObjectDesc {
             content_location,
             path: Some(path.to_path_buf()),
             content: None,
+            generator: None,
             content_type,
             content_length: transfer_length,
             transfer_length,
             cenc,
+            encryption: None,
             inband_cenc,
             md5,
             attributes: None,
@@ -323,9 +742,94 @@ impl ObjectDesc {
             groups,
             toi: None,
             optel_propagator: None,
+            alternate_content_locations: None,
+            file_etag: None,
+            independent_unit_positions: None,
+            content_encoding: None,
+            inband_sct: false,
         }))
     }
 
+    fn create_with_generator(
+        generator: ObjectGenerator,
+        total_length: u64,
+        content_type: String,
+        content_location: url::Url,
+        params: ObjectParams,
+    ) -> Result<Box<ObjectDesc>> {
+        if params.cenc != lct::Cenc::Null {
+            return Err(FluteError::new(
+                "Compressed object is not compatible with generated content",
+            ));
+        }
+
+        let md5 = match params.md5 {
+            // https://www.rfc-editor.org/rfc/rfc2616#section-14.15
+            true => {
+                let mut context = md5::Context::new();
+                Self::consume_generator(&generator, total_length, |data| context.consume(data))?;
+                Some(base64::engine::general_purpose::STANDARD.encode(context.compute().0))
+            }
+            false => None,
+        };
+
+        Ok(Box::new(ObjectDesc {
+            content_location,
+            path: None,
+            content: None,
+            generator: Some(generator),
+            content_type,
+            content_length: total_length,
+            transfer_length: total_length,
+            cenc: lct::Cenc::Null,
+            encryption: None,
+            inband_cenc: params.inband_cenc,
+            md5,
+            attributes: None,
+            oti: params.oti,
+            max_transfer_count: params.max_transfer_count,
+            carousel_delay: params.carousel_delay,
+            target_acquisition: params.target_acquisition,
+            cache_control: params.cache_control,
+            groups: params.groups,
+            toi: None,
+            optel_propagator: None,
+            alternate_content_locations: None,
+            file_etag: None,
+            independent_unit_positions: None,
+            content_encoding: None,
+            inband_sct: false,
+        }))
+    }
+
+    /// Pass the whole generated content to `consume`, chunk by chunk
+    fn consume_generator<F>(
+        generator: &ObjectGenerator,
+        total_length: u64,
+        mut consume: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[u8]),
+    {
+        const CHUNK_SIZE: u64 = 102400;
+        let mut offset = 0;
+        while offset < total_length {
+            let length = (total_length - offset).min(CHUNK_SIZE) as usize;
+            let data = generator.generate(offset, length);
+            if data.len() != length {
+                return Err(FluteError::new(format!(
+                    "Generator returned {} bytes instead of {} at offset {}",
+                    data.len(),
+                    length,
+                    offset
+                )));
+            }
+            consume(&data);
+            offset += length as u64;
+        }
+        Ok(())
+    }
+
     fn compute_file_md5(file: &std::fs::File) -> md5::Digest {
         let mut reader = BufReader::new(file);
         let mut context = md5::Context::new();
@@ -1,19 +1,165 @@
-use std::sync::{Arc, RwLock};
+use crate::common::udpendpoint::UDPEndpoint;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Max number of `SenderError` waiting in the channel returned by `Sender::subscribe_error_events()`
+const ERROR_CHANNEL_CAPACITY: usize = 64;
 
 /// File State Changed event
+///
+/// Identifies the object and the `Sender` transferring it, so a `Subscriber` can serve several senders
+/// without keeping its own TOI to Content-Location map.
+/// Fields can be added in minor releases, do not build this struct outside of the crate.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct FileInfo {
     /// Object TOI
     pub toi: u128,
+    /// Content-Location of the object, as announced in the FDT
+    pub content_location: String,
+    /// Size of the object, before content encoding
+    pub content_length: u64,
+    /// Size of the object transferred, after content encoding
+    pub transfer_length: u64,
+    /// Priority queue of the object
+    pub priority: u32,
+    /// UDP endpoint of the `Sender`
+    pub endpoint: UDPEndpoint,
+    /// TSI of the `Sender`
+    pub tsi: u64,
+}
+
+/// Statistics of an object transfer
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct TransferStats {
+    /// Object TOI
+    pub toi: u128,
+    /// Number of ALC/LCT packets sent during the transfer, including the repair symbols
+    pub packets_sent: u64,
+    /// Number of bytes of the ALC/LCT packets sent during the transfer, IP/UDP headers excluded
+    pub bytes_on_wire: u64,
 }
 
 /// Event
+///
+/// Events can be added in minor releases, `Subscriber` implementations should `match` with a wildcard arm.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Event {
     /// Transfer is started
     StartTransfer(FileInfo),
     /// Transfer has stopped
     StopTransfer(FileInfo),
+    /// Statistics of a transfer that has stopped, dispatched after `StopTransfer`
+    /// An object transferred multiple times (carousel, `max_transfer_count`) reports one event per transfer
+    TransferStats(TransferStats),
+    /// Transfer of an object has failed because its content could not be read
+    /// The object is removed from the FDT, dispatched after `TransferStats`
+    ObjectTransferFailed {
+        /// Object TOI
+        toi: u128,
+        /// Description of the error
+        error: String,
+    },
+    /// A new version of the FDT is published
+    FdtPublished {
+        /// FDT Instance ID
+        instance_id: u32,
+        /// Size of the FDT-Instance XML, before content encoding
+        bytes: usize,
+    },
+}
+
+impl Event {
+    /// Kind of the event, to match against an `EventKindMask`
+    pub fn kind(&self) -> EventKindMask {
+        match self {
+            Event::StartTransfer(_) => EventKindMask::START_TRANSFER,
+            Event::StopTransfer(_) => EventKindMask::STOP_TRANSFER,
+            Event::TransferStats(_) => EventKindMask::TRANSFER_STATS,
+            Event::ObjectTransferFailed { .. } => EventKindMask::OBJECT_TRANSFER_FAILED,
+            Event::FdtPublished { .. } => EventKindMask::FDT_PUBLISHED,
+        }
+    }
+}
+
+///
+/// Set of `Event` kinds dispatched to a `Subscriber`, see `Sender::subscribe_filtered()`
+///
+/// # Example
+///
+/// ```
+/// use flute::sender::EventKindMask;
+/// let mask = EventKindMask::START_TRANSFER | EventKindMask::STOP_TRANSFER;
+/// assert!(mask.contains(EventKindMask::STOP_TRANSFER));
+/// assert!(!mask.contains(EventKindMask::TRANSFER_STATS));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventKindMask(u32);
+
+impl EventKindMask {
+    /// `Event::StartTransfer`
+    pub const START_TRANSFER: EventKindMask = EventKindMask(1 << 0);
+    /// `Event::StopTransfer`
+    pub const STOP_TRANSFER: EventKindMask = EventKindMask(1 << 1);
+    /// `Event::TransferStats`
+    pub const TRANSFER_STATS: EventKindMask = EventKindMask(1 << 2);
+    /// `Event::ObjectTransferFailed`
+    pub const OBJECT_TRANSFER_FAILED: EventKindMask = EventKindMask(1 << 3);
+    /// `Event::FdtPublished`
+    pub const FDT_PUBLISHED: EventKindMask = EventKindMask(1 << 4);
+    /// All the events, including the ones added in future releases
+    pub const ALL: EventKindMask = EventKindMask(u32::MAX);
+
+    /// Return `true` if all the kinds of `other` are in this mask
+    pub fn contains(&self, other: EventKindMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for EventKindMask {
+    type Output = EventKindMask;
+
+    fn bitor(self, rhs: EventKindMask) -> EventKindMask {
+        EventKindMask(self.0 | rhs.0)
+    }
+}
+
+/// Kind of `SenderError`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ErrorKind {
+    /// FEC encoding of a source block has failed
+    Encoding,
+    /// Content of the object could not be read (ex: file removed or truncated during the transfer)
+    Io,
+    /// An ALC/LCT packet of the object exceeds `Config::enforce_mtu`
+    Mtu,
+    /// An ALC/LCT packet of the object could not be built (ex: LCT header length overflow)
+    Packet,
+    /// The FDT announcing the new content of the object could not be published,
+    /// see `Sender::replace_object_content()`
+    Fdt,
+}
+
+/// Error that occurred during the transfer of an object, see `Sender::subscribe_error_events()`
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SenderError {
+    /// Object TOI
+    pub toi: u128,
+    /// Source Block Number of the block being read or encoded
+    pub sbn: u32,
+    /// Kind of error
+    pub kind: ErrorKind,
+    /// Description of the error
+    pub message: String,
+}
+
+impl std::fmt::Display for SenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} error toi={} sbn={}: {}",
+            self.kind, self.toi, self.sbn, self.message
+        )
+    }
 }
 
 /// Subscribe to events
@@ -22,32 +168,74 @@ pub trait Subscriber: Send + Sync {
     fn on_sender_event(&self, evt: &Event, now: std::time::SystemTime);
 }
 
+type SubscriberList = Vec<(Arc<dyn Subscriber>, EventKindMask)>;
+
 #[derive(Clone)]
-pub struct ObserverList(Arc<RwLock<Vec<Arc<dyn Subscriber>>>>);
+pub struct ObserverList {
+    subscribers: Arc<RwLock<SubscriberList>>,
+    error_channels: Arc<Mutex<Vec<mpsc::SyncSender<SenderError>>>>,
+}
 
 impl ObserverList {
     pub fn new() -> Self {
-        ObserverList(Arc::new(RwLock::new(Vec::new())))
+        ObserverList {
+            subscribers: Arc::new(RwLock::new(Vec::new())),
+            error_channels: Arc::new(Mutex::new(Vec::new())),
+        }
     }
 
-    pub fn subscribe(&mut self, s: Arc<dyn Subscriber>) {
-        self.0.write().unwrap().push(s);
+    pub fn subscribe(&mut self, s: Arc<dyn Subscriber>, mask: EventKindMask) {
+        self.subscribers.write().unwrap().push((s, mask));
     }
 
     pub fn unsubscribe(&mut self, s: Arc<dyn Subscriber>) {
-        self.0
+        self.subscribers
             .write()
             .unwrap()
-            .retain(|a| !std::ptr::eq(a.as_ref() as *const _, s.as_ref() as *const _))
+            .retain(|(a, _)| !std::ptr::eq(a.as_ref() as *const _, s.as_ref() as *const _))
+    }
+
+    /// Return a new channel receiving the `SenderError`
+    pub fn subscribe_errors(&mut self) -> mpsc::Receiver<SenderError> {
+        let (tx, rx) = mpsc::sync_channel(ERROR_CHANNEL_CAPACITY);
+        self.error_channels.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Return `true` if at least one subscriber receives the events of this kind
+    /// Permits to skip building events that nobody listens to
+    pub fn is_subscribed(&self, kind: EventKindMask) -> bool {
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(_, mask)| mask.contains(kind))
     }
 
     pub fn dispatch(&self, event: &Event, now: std::time::SystemTime) {
-        let lock = self.0.read().unwrap();
+        let lock = self.subscribers.read().unwrap();
+        let kind = event.kind();
 
-        for subscriber in lock.iter() {
-            subscriber.on_sender_event(event, now);
+        for (subscriber, mask) in lock.iter() {
+            if mask.contains(kind) {
+                subscriber.on_sender_event(event, now);
+            }
         }
     }
+
+    /// Send the error to the channels without blocking
+    /// The error is only logged for a channel that is full, the disconnected channels are removed
+    pub fn dispatch_error(&self, error: &SenderError) {
+        let mut channels = self.error_channels.lock().unwrap();
+        channels.retain(|tx| match tx.try_send(error.clone()) {
+            Ok(_) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                log::error!("Error channel is full, drop {}", error);
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        });
+    }
 }
 
 impl std::fmt::Debug for ObserverList {
@@ -0,0 +1,258 @@
+//!
+//! Packetization of objects and FDT Instances to ALC/LCT packets, without a `Sender`
+//!
+//! Packets are generated in sequence, independently of any transmission schedule or wall clock.
+//! This is useful to pre-generate a stream of packets that is stored and replayed later.
+//!
+
+use super::blockencoder::BlockEncoder;
+use super::fdt;
+use super::filedesc::FileDesc;
+use super::toiallocator::ToiAllocator;
+use super::{ObjectDesc, Profile, TOIMaxLength};
+use crate::common::{alc, lct, oti};
+use crate::error::{FluteError, Result};
+use crate::tools;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+///
+/// Encode an object to ALC/LCT packets
+///
+/// Packets are returned by the `Iterator` implementation, in transmission order
+///
+#[derive(Debug)]
+pub struct ObjectEncoder {
+    file: Arc<FileDesc>,
+    encoder: BlockEncoder,
+    tsi: u64,
+    profile: Profile,
+    sender_current_time: Option<SystemTime>,
+}
+
+///
+/// Configuration of the `FdtEncoder`
+///
+#[derive(Debug, Clone)]
+pub struct FdtConfig {
+    /// FEC parameters used to transfer the FDT, and default FEC parameters of the objects announced in the FDT
+    pub oti: oti::Oti,
+    /// Content Encoding of the FDT
+    pub cenc: lct::Cenc,
+    /// FLUTE profile of the packets
+    pub profile: Profile,
+    /// Validity duration of the FDT, starting from its publication time
+    pub duration: std::time::Duration,
+    /// Set the `Complete` attribute of the FDT
+    pub complete: Option<bool>,
+    /// Groups of the FDT
+    pub groups: Option<Vec<String>>,
+    /// Base URLs of the FDT
+    pub base_urls: Option<Vec<String>>,
+    /// Default Content-Type of the objects announced in the FDT
+    pub content_type: Option<String>,
+    /// Normalize the Content-Location of the objects announced in the FDT,
+    /// see `core::content_location::normalize()`
+    pub normalize_content_location: bool,
+}
+
+impl Default for FdtConfig {
+    fn default() -> Self {
+        Self {
+            oti: Default::default(),
+            cenc: lct::Cenc::Null,
+            profile: Profile::RFC6726,
+            duration: std::time::Duration::from_secs(3600),
+            complete: None,
+            groups: None,
+            base_urls: None,
+            content_type: None,
+            normalize_content_location: true,
+        }
+    }
+}
+
+///
+/// Encode an FDT Instance to ALC/LCT packets transferred on TOI 0
+///
+/// Packets are returned by the `Iterator` implementation, in transmission order
+///
+#[derive(Debug)]
+pub struct FdtEncoder {
+    inner: ObjectEncoder,
+}
+
+impl ObjectEncoder {
+    /// Return a new `ObjectEncoder`
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - Object to encode
+    /// * `oti` - Default FEC parameters, used when the object has no FEC parameters
+    /// * `tsi` - Transport Session Identifier
+    /// * `toi` - Transport Object Identifier of the object, must be > 0
+    /// * `profile` - FLUTE profile of the packets
+    /// * `interleave_blocks` - Max number of blocks that are interleaved.
+    ///   With 1, the blocks are transferred one after the other, the source symbols of a block followed by its repair symbols
+    pub fn new(
+        mut object: Box<ObjectDesc>,
+        oti: &oti::Oti,
+        tsi: u64,
+        toi: u128,
+        profile: Profile,
+        interleave_blocks: usize,
+    ) -> Result<ObjectEncoder> {
+        let toi_allocator = ToiAllocator::new(TOIMaxLength::ToiMax112, None);
+        let toi = ToiAllocator::reserve(&toi_allocator, toi)
+            .ok_or_else(|| FluteError::new(format!("TOI {} is not valid for an object", toi)))?;
+        object.toi = Some(toi);
+
+        let file = Arc::new(FileDesc::new(0, object, oti, None, false, profile)?);
+        Self::new_from_file(file, tsi, profile, interleave_blocks, true)
+    }
+
+    fn new_from_file(
+        file: Arc<FileDesc>,
+        tsi: u64,
+        profile: Profile,
+        interleave_blocks: usize,
+        closable_object: bool,
+    ) -> Result<ObjectEncoder> {
+        let encoder = BlockEncoder::new(
+            file.clone(),
+            interleave_blocks.max(1),
+            closable_object,
+            false,
+        )?;
+        Ok(ObjectEncoder {
+            file,
+            encoder,
+            tsi,
+            profile,
+            sender_current_time: None,
+        })
+    }
+
+    /// Insert the Sender Current Time (SCT) `time` to the next packets
+    /// `None` to remove the SCT from the next packets
+    pub fn set_sender_current_time(&mut self, time: Option<SystemTime>) {
+        self.sender_current_time = time;
+    }
+
+    /// Transport Object Identifier of the object
+    pub fn toi(&self) -> u128 {
+        self.file.toi
+    }
+
+    /// Number of ALC/LCT packets required to transfer the object once
+    pub fn nb_packets(&self) -> u64 {
+        self.file
+            .oti
+            .packets_per_object(self.file.object.transfer_length)
+    }
+}
+
+impl Iterator for ObjectEncoder {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pkt = self.encoder.read(false)?;
+        pkt.sender_current_time = self.sender_current_time.is_some();
+        alc::new_alc_pkt(
+            &self.file.oti,
+            &0u128,
+            self.tsi,
+            &pkt,
+            self.profile,
+            self.sender_current_time.unwrap_or(SystemTime::UNIX_EPOCH),
+        )
+        .map_err(|e| log::error!("{:?}", e))
+        .ok()
+    }
+}
+
+impl FdtEncoder {
+    /// Return a new `FdtEncoder`
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - Objects announced in the FDT
+    /// * `tsi` - Transport Session Identifier
+    /// * `fdt_id` - FDT Instance ID, on 20 bits
+    /// * `publish_time` - Publication time of the FDT, the FDT expires at `publish_time + config.duration`.
+    ///   Expiration of objects with `CacheControl::Expires` are computed from this time
+    /// * `config` - Configuration of the FDT
+    pub fn new(
+        files: &[&ObjectEncoder],
+        tsi: u64,
+        fdt_id: u32,
+        publish_time: SystemTime,
+        config: &FdtConfig,
+    ) -> Result<FdtEncoder> {
+        if fdt_id > 0xFFFFF {
+            return Err(FluteError::new(format!(
+                "FDT Instance ID {} does not fit on 20 bits",
+                fdt_id
+            )));
+        }
+
+        let ntp = tools::system_time_to_ntp(publish_time)?;
+        let expires_ntp = (ntp >> 32) + config.duration.as_secs();
+        let instance = fdt::new_fdt_instance(
+            &config.oti,
+            files
+                .iter()
+                .map(|encoder| {
+                    let mut file = encoder
+                        .file
+                        .to_file_xml(publish_time, config.content_type.as_deref());
+                    if config.normalize_content_location {
+                        fdt::normalize_content_location(&mut file);
+                    }
+                    file
+                })
+                .collect(),
+            expires_ntp,
+            config.complete,
+            config.groups.clone(),
+            config.base_urls.clone(),
+            config.content_type.clone(),
+        );
+        let content = fdt::fdt_instance_to_xml(&instance)?;
+
+        let toi_allocator = ToiAllocator::new(TOIMaxLength::ToiMax112, None);
+        let object = fdt::new_fdt_object(
+            &content,
+            None,
+            config.groups.clone(),
+            config.cenc,
+            ToiAllocator::allocate_toi_fdt(&toi_allocator),
+        )?;
+        let file = Arc::new(FileDesc::new(
+            0,
+            object,
+            &config.oti,
+            Some(fdt_id),
+            false,
+            config.profile,
+        )?);
+
+        Ok(FdtEncoder {
+            inner: ObjectEncoder::new_from_file(file, tsi, config.profile, 1, false)?,
+        })
+    }
+
+    /// Insert the Sender Current Time (SCT) `time` to the next packets
+    /// `None` to remove the SCT from the next packets
+    pub fn set_sender_current_time(&mut self, time: Option<SystemTime>) {
+        self.inner.set_sender_current_time(time);
+    }
+}
+
+impl Iterator for FdtEncoder {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
@@ -1,13 +1,17 @@
 use super::fdt::Fdt;
+use super::layer::{LayerPolicy, Layering};
 use super::observer::ObserverList;
 use super::sendersession::SenderSession;
-use super::{objectdesc, ObjectDesc, Subscriber, Toi};
+use super::{
+    objectdesc, EventKindMask, ObjectDataSource, ObjectDesc, SenderError, Subscriber, Toi,
+};
 use crate::common::{alc, lct, oti, Profile};
 use crate::core::UDPEndpoint;
 use crate::error::FluteError;
-use crate::tools::error::Result;
+use crate::tools::error::{LogError, Result};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 /// Maximum number of bits to encode the TOI
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -63,6 +67,60 @@ impl PriorityQueue {
     }
 }
 
+///
+/// Backpressure state of a priority queue
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackpressureState {
+    /// Number of objects waiting in the queue for their transfer
+    pub queue_depth: usize,
+    /// Max number of objects accepted in the queue (`Config::max_queue_depth_per_priority`)
+    pub max_depth: Option<usize>,
+    /// `true` when the queue is full, new objects are rejected by `Sender::add_object()`
+    pub is_blocked: bool,
+}
+
+///
+/// Activity of the `Sender`, returned by `Sender::next_activity()`
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderActivity {
+    /// A packet can be read now,
+    /// `Sender::read()` may still return `None` when the transfer of an object has just ended
+    PacketReady,
+    /// No packet is ready before this time
+    WaitUntil(SystemTime),
+    /// No packet is scheduled: no object is waiting for a transfer and no FDT is pending,
+    /// the session is paused or `Config::max_in_flight_bytes` is reached.
+    /// A call to the `Sender` (ex: `add_object()`, `resume()`, `notify_sent()`) is required to schedule new packets.
+    /// The FDT carousel alone does not keep the `Sender` active.
+    Idle,
+}
+
+///
+/// FEC parameters recommended by `Sender::get_encoding_params()`
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingParams {
+    /// Encoding symbol length, in bytes
+    pub recommended_encoding_symbol_length: u16,
+    /// Maximum source block length, in number of symbols
+    pub recommended_max_source_block_length: u32,
+    /// Maximum number of repair symbols per source block
+    pub recommended_max_parity_symbols: u32,
+    /// Estimated duration of one transfer of the object, see `Oti::estimate_transfer_time()`
+    pub estimated_transfer_duration: Duration,
+    /// Estimated number of ALC/LCT packets of one transfer of the object
+    pub estimated_total_packets: u64,
+}
+
+/// Packet rate targeted by `Sender::get_encoding_params()`, in packets per second
+const ADVISOR_TARGET_PACKET_RATE: u64 = 1000;
+/// Alignment of the encoding symbol length recommended by `Sender::get_encoding_params()`
+const ADVISOR_SYMBOL_ALIGNMENT: u64 = 4;
+/// Packet loss rate used by `Sender::get_encoding_params()` when `Config::expected_loss_rate` is not set
+const DEFAULT_EXPECTED_LOSS_RATE: f64 = 0.05;
+
 ///
 /// Configuration of the `Sender`
 ///
@@ -78,6 +136,18 @@ pub struct Config {
     pub fdt_cenc: lct::Cenc,
     /// Insert Sender Current Time inside ALC/LCT packets containing the FDT.
     pub fdt_inband_sct: bool,
+    /// Minimum interval between two versions of the FDT.
+    /// Modifications published before the end of the interval are batched inside the next FDT version.
+    /// Zero : a new FDT version is generated at each call to `publish()`
+    pub fdt_min_republish_interval: std::time::Duration,
+    /// Do not generate a new FDT version when `publish()` is called but the content of the FDT is unchanged.
+    /// The current FDT version is retransmitted instead, its carousel delay is restarted.
+    /// false : a new FDT version is generated at each publication
+    pub suppress_duplicate_fdt: bool,
+    /// Normalize the Content-Location of the objects announced in the FDT,
+    /// see `core::content_location::normalize()`.
+    /// true : the receivers get the same URL for the same resource, whatever its spelling
+    pub normalize_content_location: bool,
     /// A struct representing a set of priority queues for file transmission.
     /// Each priority queue is associated with a specific priority level determined by the key in the `BTreeMap`.
     /// A lower key indicates a higher priority.
@@ -97,6 +167,43 @@ pub struct Config {
     pub toi_initial_value: Option<u128>,
     /// List of groups added to the FDT-Instance
     pub groups: Option<Vec<String>>,
+    /// List of base URLs added to the FDT-Instance as `Base-URL-1`
+    /// Receivers can resolve the relative `Content-Location` of the objects against these URLs to retrieve them from another network
+    pub base_urls: Option<Vec<String>>,
+    /// Max number of bytes returned by `read()` that have not been notified as sent with `Sender::notify_sent()`.
+    /// When the limit is reached, `read()` returns `None` until the application notifies the packets sent.
+    /// None : no limit, the bytes returned by `read()` are not tracked
+    pub max_in_flight_bytes: Option<usize>,
+    /// Default Content-Type of the objects, added to the FDT-Instance.
+    /// Objects with this Content-Type do not repeat it in their FDT entry
+    pub fdt_content_type: Option<String>,
+    /// Endpoints of the LCT layers of a layered coding session, layer 0 is the base layer.
+    /// Packets are assigned to the layers according to `layer_policy` and read with `Sender::read_layered()`.
+    /// Empty : single layer
+    pub layers: Vec<UDPEndpoint>,
+    /// Policy assigning the packets to the `layers`
+    pub layer_policy: LayerPolicy,
+    /// Max number of objects waiting for their transfer in each priority queue.
+    /// When a queue is full, `Sender::add_object()` returns an error until objects leave the queue.
+    /// None : no limit
+    pub max_queue_depth_per_priority: Option<HashMap<u32, usize>>,
+    /// Zero-pad the last (short) source symbol of the objects to the full `encoding_symbol_length`.
+    /// Required by some receivers, the receivers recover the real size of the object from its transfer length.
+    /// false : the last symbol is truncated to the end of the object
+    pub pad_last_symbol: bool,
+    /// Check the size of each ALC/LCT packet against this MTU.
+    /// The transfer of an object whose packets exceed the MTU fails,
+    /// see `core::alc::alc_pkt_max_payload_size()` to size the `encoding_symbol_length`.
+    /// None : the size of the packets is not checked
+    pub enforce_mtu: Option<usize>,
+    /// Expected packet loss rate of the network (ex: 0.05 for 5%),
+    /// used by `Sender::get_encoding_params()` to recommend the number of repair symbols.
+    /// None : 5%
+    pub expected_loss_rate: Option<f64>,
+    /// MBMS Session Identities added to the FDT-Instance, with their expiry in seconds since NTP epoch
+    /// (3GPP TS 26.346 Section 7.2.10)
+    /// None : `MBMS-Session-Identity-Expiry` is not added to the FDT
+    pub mbms_session_identity_expiry: Option<Vec<(u8, u32)>>,
 }
 
 impl Config {
@@ -130,6 +237,9 @@ impl Default for Config {
             fdt_start_id: 1,
             fdt_cenc: lct::Cenc::Null,
             fdt_inband_sct: true,
+            fdt_min_republish_interval: std::time::Duration::ZERO,
+            suppress_duplicate_fdt: false,
+            normalize_content_location: true,
             priority_queues: std::collections::BTreeMap::from([(
                 0,
                 PriorityQueue { multiplex_files: 3 },
@@ -139,6 +249,16 @@ impl Default for Config {
             toi_max_length: TOIMaxLength::ToiMax112,
             toi_initial_value: Some(1),
             groups: None,
+            base_urls: None,
+            max_in_flight_bytes: None,
+            fdt_content_type: None,
+            layers: Vec::new(),
+            layer_policy: LayerPolicy::RepairOnUpperLayers,
+            max_queue_depth_per_priority: None,
+            pad_last_symbol: false,
+            enforce_mtu: None,
+            expected_loss_rate: None,
+            mbms_session_identity_expiry: None,
         }
     }
 }
@@ -161,6 +281,15 @@ pub struct Sender {
     observers: ObserverList,
     tsi: u64,
     endpoint: UDPEndpoint,
+    max_in_flight_bytes: Option<usize>,
+    in_flight_bytes: usize,
+    layers: Vec<UDPEndpoint>,
+    layering: Layering,
+    max_queue_depth_per_priority: Option<HashMap<u32, usize>>,
+    raw_packets: VecDeque<Vec<u8>>,
+    paused_since: Option<SystemTime>,
+    paused_queues: HashMap<u32, SystemTime>,
+    expected_loss_rate: f64,
 }
 
 impl Sender {
@@ -170,28 +299,19 @@ impl Sender {
     pub fn new(endpoint: UDPEndpoint, tsi: u64, oti: &oti::Oti, config: &Config) -> Sender {
         let observers = ObserverList::new();
 
-        let fdt = Fdt::new(
-            tsi,
-            config.fdt_start_id,
-            oti,
-            config.fdt_cenc,
-            config.fdt_duration,
-            config.fdt_carousel,
-            config.fdt_inband_sct,
-            observers.clone(),
-            config.toi_max_length,
-            config.toi_initial_value,
-            config.groups.clone(),
-        );
+        let mut fdt = Fdt::new(tsi, endpoint.clone(), oti, config, observers.clone());
+        fdt.set_mbms_session_identity_expiry(config.mbms_session_identity_expiry.clone());
 
-        let fdt_session = SenderSession::new(
+        let mut fdt_session = SenderSession::new(
             0,
             tsi,
             config.interleave_blocks as usize,
             true,
             config.profile,
+            config.pad_last_symbol,
             endpoint.clone(),
         );
+        fdt_session.set_enforce_mtu(config.enforce_mtu);
 
         let mut sessions = std::collections::BTreeMap::new();
 
@@ -203,14 +323,17 @@ impl Sender {
 
             let new_sessions = (0..multiplex_files)
                 .map(|_| {
-                    SenderSession::new(
+                    let mut session = SenderSession::new(
                         *priority,
                         tsi,
                         config.interleave_blocks as usize,
                         false,
                         config.profile,
+                        config.pad_last_symbol,
                         endpoint.clone(),
-                    )
+                    );
+                    session.set_enforce_mtu(config.enforce_mtu);
+                    session
                 })
                 .collect();
             sessions.insert(
@@ -229,12 +352,39 @@ impl Sender {
             observers,
             tsi,
             endpoint,
+            max_in_flight_bytes: config.max_in_flight_bytes,
+            in_flight_bytes: 0,
+            layers: config.layers.clone(),
+            layering: Layering::new(config.layers.len(), config.layer_policy),
+            max_queue_depth_per_priority: config.max_queue_depth_per_priority.clone(),
+            raw_packets: VecDeque::new(),
+            paused_since: None,
+            paused_queues: HashMap::new(),
+            expected_loss_rate: config
+                .expected_loss_rate
+                .unwrap_or(DEFAULT_EXPECTED_LOSS_RATE),
         }
     }
 
     /// Add an observer
     pub fn subscribe(&mut self, s: Arc<dyn Subscriber>) {
-        self.observers.subscribe(s);
+        self.observers.subscribe(s, EventKindMask::ALL);
+    }
+
+    /// Add an observer that receives only the events matching `mask`
+    pub fn subscribe_filtered(&mut self, s: Arc<dyn Subscriber>, mask: EventKindMask) {
+        self.observers.subscribe(s, mask);
+    }
+
+    ///
+    /// Return a channel receiving the errors that occur during the transfer of the objects
+    ///
+    /// The errors are sent without blocking the `Sender`, the channel keeps up to 64 errors.
+    /// When the channel is full, the new errors are logged but not sent.
+    /// The transfer of an object that fails is also reported with `Event::ObjectTransferFailed`.
+    ///
+    pub fn subscribe_error_events(&mut self) -> std::sync::mpsc::Receiver<SenderError> {
+        self.observers.subscribe_errors()
     }
 
     /// Remove an observer
@@ -252,6 +402,22 @@ impl Sender {
         self.tsi
     }
 
+    ///
+    /// Generate the Session Description (SDP) of this FLUTE session
+    ///
+    /// The SDP announces the UDP endpoint, the TSI and the default OTI of the sender,
+    /// it can be parsed by `MultiReceiver::from_sdp()` or `SessionDescription::from_sdp()`
+    ///
+    #[cfg(feature = "sdp")]
+    pub fn to_sdp(&self) -> String {
+        let session = crate::common::sdp::FluteSessionParams {
+            endpoint: self.endpoint.clone(),
+            tsi: self.tsi,
+            oti: Some(self.fdt.get_oti().clone()),
+        };
+        crate::common::sdp::SessionDescription::new("FLUTE session", vec![session]).to_sdp()
+    }
+
     /// Add an object to the FDT
     ///
     /// After calling this function, a call to `publish()` to publish your modifications
@@ -267,6 +433,10 @@ impl Sender {
     /// # Returns
     ///
     /// A `Result` containing an `u128` representing the unique identifier of the added object (TOI), if the operation was successful.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `std::io::ErrorKind::WouldBlock` if the priority queue is full (`Config::max_queue_depth_per_priority`)
     pub fn add_object(&mut self, priority: u32, obj: Box<objectdesc::ObjectDesc>) -> Result<u128> {
         if !self.sessions.contains_key(&priority) {
             return Err(FluteError::new(
@@ -274,9 +444,83 @@ impl Sender {
             ));
         }
 
+        self.check_queue_capacity(priority)?;
         self.fdt.add_object(priority, obj)
     }
 
+    /// Add an object to the FDT with a TOI chosen by the caller
+    ///
+    /// After calling this function, a call to `publish()` to publish your modifications
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - Selects the priority queue used to transfer the object.
+    /// * `obj` - The object description to be added to the FDT. A TOI must not be already assigned to it.
+    /// * `toi` - TOI of the object.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the TOI of the added object.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TOI is the TOI of the FDT (0), does not fit `Config::toi_max_length`,
+    /// or is already used by another object.
+    /// Returns an error of kind `std::io::ErrorKind::WouldBlock` if the priority queue is full.
+    pub fn add_object_with_toi(
+        &mut self,
+        priority: u32,
+        obj: Box<objectdesc::ObjectDesc>,
+        toi: u128,
+    ) -> Result<u128> {
+        if !self.sessions.contains_key(&priority) {
+            return Err(FluteError::new(
+                format! {"Priority queue {} does not exist", priority},
+            ));
+        }
+
+        self.check_queue_capacity(priority)?;
+        self.fdt.add_object_with_toi(priority, obj, toi)
+    }
+
+    /// Return the backpressure state of each priority queue
+    ///
+    /// A blocked queue accepts new objects once `read()` has completed the transfer of some of its objects,
+    /// poll the state between the calls to `read()`
+    pub fn priority_queue_backpressure(&self) -> HashMap<u32, BackpressureState> {
+        self.sessions
+            .keys()
+            .map(|priority| (*priority, self.backpressure_state(*priority)))
+            .collect()
+    }
+
+    fn backpressure_state(&self, priority: u32) -> BackpressureState {
+        let queue_depth = self.fdt.nb_queued_objects(priority);
+        let max_depth = self
+            .max_queue_depth_per_priority
+            .as_ref()
+            .and_then(|max| max.get(&priority).copied());
+        BackpressureState {
+            queue_depth,
+            max_depth,
+            is_blocked: max_depth.map(|max| queue_depth >= max).unwrap_or(false),
+        }
+    }
+
+    fn check_queue_capacity(&self, priority: u32) -> Result<()> {
+        let state = self.backpressure_state(priority);
+        if state.is_blocked {
+            return Err(FluteError::new_kind(
+                std::io::ErrorKind::WouldBlock,
+                format!(
+                    "Priority queue {} is full ({} objects)",
+                    priority, state.queue_depth
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     /// Check if the object is inside the FDT
     pub fn is_added(&self, toi: u128) -> bool {
         self.fdt.is_added(toi)
@@ -300,6 +544,105 @@ impl Sender {
         self.fdt.remove_object(toi)
     }
 
+    /// Remove the objects whose `CacheControl::Expires` has passed and publish the updated FDT
+    ///
+    /// The expiration of an object is evaluated from the `Expires` announced in the last published FDT,
+    /// so that the objects are removed once the receivers consider them as expired.
+    ///
+    /// # Returns
+    ///
+    /// The number of objects removed
+    ///
+    pub fn prune_expired_objects(&mut self, now: SystemTime) -> usize {
+        let nb_removed = self.fdt.remove_expired_objects(now);
+        if nb_removed > 0 {
+            self.fdt.publish(now).log_error("Fail to publish the FDT");
+        }
+        nb_removed
+    }
+
+    /// Replace the content of an object without changing its TOI
+    ///
+    /// The FDT is updated with the new Content-Length, Transfer-Length and Content-MD5 and is published.
+    /// If the object is being transferred, the transfer of the old content is finished first,
+    /// the new content is then published and transferred in the next carousel round,
+    /// a failure to publish it is then reported with `observer::ErrorKind::Fdt` (see `subscribe_error_events()`).
+    ///
+    /// # Arguments
+    ///
+    /// * `toi` - TOI of the Object.
+    /// * `source` - New content of the object.
+    /// * `md5` - MD5 of the new content. If `None`, the MD5 is computed when the object had one.
+    /// * `now` - Current time
+    ///
+    pub fn replace_object_content(
+        &mut self,
+        toi: u128,
+        source: ObjectDataSource,
+        md5: Option<String>,
+        now: SystemTime,
+    ) -> Result<()> {
+        self.fdt.replace_object_content(toi, source, md5, now)
+    }
+
+    /// Change the FEC parameters of an object that is not being transferred
+    ///
+    /// The new OTI overrides the default OTI of the session for this object,
+    /// the FDT is updated with the new FEC parameters and is published.
+    ///
+    /// # Arguments
+    ///
+    /// * `toi` - TOI of the Object.
+    /// * `oti` - New FEC parameters of the object.
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - The OTI of the object has been changed.
+    /// * `Ok(false)` - The object with the specified `toi` is not present in the FDT.
+    /// * `Err` - The object is being transferred (`std::io::ErrorKind::InvalidInput`),
+    ///   or the object is not compatible with the new OTI.
+    ///
+    pub fn set_oti_for_object(
+        &mut self,
+        toi: u128,
+        oti: oti::Oti,
+        now: SystemTime,
+    ) -> Result<bool> {
+        self.fdt.set_oti_for_object(toi, oti, now)
+    }
+
+    /// Restart the carousel of all the objects of the FDT
+    ///
+    /// The carousel delay of the objects is reset, objects waiting for their next carousel round
+    /// are transferred again immediately.
+    /// The FDT instance ID and the TOI of the objects are not modified, `publish()` is not called.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current time
+    ///
+    pub fn rewind(&mut self, _now: SystemTime) {
+        self.fdt.rewind()
+    }
+
+    /// Restart the carousel of an object
+    ///
+    /// See `rewind()`
+    ///
+    /// # Arguments
+    ///
+    /// * `toi` - TOI of the Object.
+    /// * `now` - Current time
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object is in the FDT
+    ///
+    pub fn rewind_object(&mut self, toi: u128, _now: SystemTime) -> bool {
+        self.fdt.rewind_object(toi)
+    }
+
     /// Return the number of times an object has been transferred,
     /// or None if the object is not in the FDT anymore.
     ///
@@ -324,6 +667,8 @@ impl Sender {
     /// Publish modification to the FDT
     /// An updated version of the FDT will be generated and transferred
     /// Multiple modification can be made (ex: several call to 'add_object()`) before publishing a new FDT version
+    /// If the previous version was published less than `Config::fdt_min_republish_interval` ago,
+    /// the new version is generated once the interval has elapsed
     pub fn publish(&mut self, now: SystemTime) -> Result<()> {
         self.fdt.publish(now)
     }
@@ -335,6 +680,88 @@ impl Sender {
         self.fdt.set_complete();
     }
 
+    /// Replace the MBMS Session Identities announced in the FDT, with their expiry in seconds since NTP epoch
+    /// An empty list removes `MBMS-Session-Identity-Expiry` from the FDT
+    /// After calling this function, a call to `publish()` is required to publish your modifications
+    pub fn set_mbms_session_identity_expiry(&mut self, entries: Vec<(u8, u32)>) {
+        self.fdt
+            .set_mbms_session_identity_expiry(match entries.is_empty() {
+                true => None,
+                false => Some(entries),
+            });
+    }
+
+    /// Enqueue a pre-built packet, returned by `read()` ahead of the FDT and of the objects
+    ///
+    /// The packet is neither parsed nor modified. It is assigned to layer 0
+    /// and is subject to `Config::max_in_flight_bytes` like any other packet.
+    pub fn inject_raw_packet(&mut self, data: Vec<u8>) {
+        self.raw_packets.push_back(data);
+    }
+
+    /// Pause the session, `read()` returns `None` until `resume()` is called
+    ///
+    /// The objects being transferred keep their state.
+    pub fn pause(&mut self, now: SystemTime) {
+        if self.paused_since.is_none() {
+            log::info!("TSI={} Pause the session", self.tsi);
+            self.paused_since = Some(now);
+        }
+    }
+
+    /// Resume the session after a call to `pause()`
+    ///
+    /// The carousel delays and the `TargetAcquisition` pacing of the objects are shifted by the duration of the pause.
+    /// If the FDT has expired during the pause, a new version is published and transferred before the objects.
+    pub fn resume(&mut self, now: SystemTime) -> Result<()> {
+        let paused_since = match self.paused_since.take() {
+            Some(paused_since) => paused_since,
+            None => return Ok(()),
+        };
+
+        let duration = now.duration_since(paused_since).unwrap_or_default();
+        log::info!("TSI={} Resume the session after {:?}", self.tsi, duration);
+        let paused_queues = &self.paused_queues;
+        self.fdt
+            .shift_timers(duration, |file| !paused_queues.contains_key(&file.priority));
+        self.fdt.resume(duration, now)
+    }
+
+    /// Return `true` if the session is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Pause the transfer of the objects of a priority queue until `resume_queue()` is called
+    ///
+    /// The FDT and the objects of the other queues are still transferred.
+    pub fn pause_queue(&mut self, priority: u32, now: SystemTime) {
+        let paused_since = self.paused_since.unwrap_or(now);
+        self.paused_queues.entry(priority).or_insert(paused_since);
+    }
+
+    /// Resume the transfer of the objects of a priority queue after a call to `pause_queue()`
+    ///
+    /// The carousel delays and the `TargetAcquisition` pacing of the objects of the queue
+    /// are shifted by the duration of the pause.
+    pub fn resume_queue(&mut self, priority: u32, now: SystemTime) {
+        let paused_since = match self.paused_queues.remove(&priority) {
+            Some(paused_since) => paused_since,
+            None => return,
+        };
+
+        // The part of the pause overlapping a pause of the session is shifted by `resume()`
+        let end = self.paused_since.unwrap_or(now);
+        let duration = end.duration_since(paused_since).unwrap_or_default();
+        self.fdt
+            .shift_timers(duration, |file| file.priority == priority);
+    }
+
+    /// Return `true` if the priority queue is paused
+    pub fn is_queue_paused(&self, priority: u32) -> bool {
+        self.paused_queues.contains_key(&priority)
+    }
+
     /// Generate a close_session packet
     pub fn read_close_session(&mut self, _now: SystemTime) -> Vec<u8> {
         alc::new_alc_pkt_close_session(&0u128, self.tsi)
@@ -346,6 +773,18 @@ impl Sender {
         self.fdt.allocate_toi()
     }
 
+    /// Allocate `count` consecutive TOIs at once, ex: to plan a carousel of many objects
+    /// The TOIs already used by other objects are skipped, the TOI wraps around when it reaches `Config::toi_max_length`.
+    /// Each TOI must be either released or assigned to an object and call `add_object()`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if less than `count` TOIs are free
+    pub fn allocate_toi_range(&mut self, count: usize) -> Result<Vec<Box<Toi>>> {
+        let tois = self.fdt.allocate_toi_range(count)?;
+        Ok(tois.into_iter().map(Box::new).collect())
+    }
+
     /// Convert current FDT to XML
     pub fn fdt_xml_data(&self, now: SystemTime) -> Result<Vec<u8>> {
         self.fdt.to_xml(now)
@@ -356,17 +795,290 @@ impl Sender {
         self.fdt.get_objects_in_fdt()
     }
 
+    /// Estimate the number of ALC/LCT packets left to transfer all the objects of the FDT
+    ///
+    /// The estimation includes the pending transfers of the FDT but not its future repetitions.
+    ///
+    /// # Returns
+    ///
+    /// The number of packets left, or `u64::MAX` if an object is empty or transferred in a carousel.
+    ///
+    pub fn read_symbol_count_estimate(&self, _now: SystemTime) -> u64 {
+        self.fdt.packets_left()
+    }
+
+    /// Recommend FEC parameters to transfer an object at `bitrate_bps`
+    ///
+    /// The encoding symbol length is reduced, if needed, so the object is sent at about 1000 packets per second.
+    /// The source block length and the number of repair symbols are sized to recover the
+    /// `Config::expected_loss_rate`, see `Oti::for_loss_rate()`.
+    /// The parameters of the object are not modified, see `set_oti_for_object()`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the object is not in the FDT or if `bitrate_bps` is 0
+    ///
+    pub fn get_encoding_params(&self, toi: u128, bitrate_bps: u64) -> Option<EncodingParams> {
+        if bitrate_bps == 0 {
+            return None;
+        }
+
+        let file = self.fdt.get_file(toi)?;
+        let transfer_length = file.object.transfer_length;
+        let alignment = match file.oti.scheme_specific.as_ref() {
+            Some(oti::SchemeSpecific::RaptorQ(raptorq)) => raptorq.symbol_alignment as u64,
+            Some(oti::SchemeSpecific::Raptor(raptor)) => raptor.symbol_alignment as u64,
+            _ => 1,
+        }
+        .max(ADVISOR_SYMBOL_ALIGNMENT);
+
+        let overhead = file.oti.estimated_packet_overhead_bytes() as u64;
+        let packet_size = bitrate_bps / 8 / ADVISOR_TARGET_PACKET_RATE;
+        let encoding_symbol_length = (packet_size.saturating_sub(overhead) / alignment * alignment)
+            .max(alignment)
+            .min(file.oti.encoding_symbol_length as u64);
+
+        let mut base = file.oti.clone();
+        base.encoding_symbol_length = encoding_symbol_length as u16;
+        let oti = Self::recommend_fec(&base, self.expected_loss_rate).unwrap_or(base);
+
+        Some(EncodingParams {
+            recommended_encoding_symbol_length: oti.encoding_symbol_length,
+            recommended_max_source_block_length: oti.maximum_source_block_length,
+            recommended_max_parity_symbols: oti.max_number_of_parity_symbols,
+            estimated_transfer_duration: oti.estimate_transfer_time(transfer_length, bitrate_bps),
+            estimated_total_packets: oti.packets_per_object(transfer_length),
+        })
+    }
+
+    /// Largest source block length, up to the one of `base`, that recovers `expected_loss`
+    fn recommend_fec(base: &oti::Oti, expected_loss: f64) -> Option<oti::Oti> {
+        if base.fec_encoding_id == oti::FECEncodingID::NoCode {
+            return None;
+        }
+
+        let mut block_len = base.maximum_source_block_length.min(u16::MAX as u32) as u16;
+        while block_len > 0 {
+            if let Ok(oti) = oti::Oti::for_loss_rate(base, expected_loss, block_len) {
+                return Some(oti);
+            }
+            block_len /= 2;
+        }
+        None
+    }
+
     /// Read the next ALC/LCT packet
     /// return None if there is no new packet to be transferred
     /// ALC/LCT packet should be encapsulated into a UDP/IP payload and transferred via UDP/multicast
+    ///
+    /// When `Config::max_in_flight_bytes` is set, None is also returned as long as the bytes
+    /// that have not been notified with `notify_sent()` exceed the limit
     pub fn read(&mut self, now: SystemTime) -> Option<Vec<u8>> {
-        if let Some(fdt_data) = self.fdt_session.run(&mut self.fdt, now) {
+        self.read_layered(now).map(|(_, data)| data)
+    }
+
+    /// Read the next ALC/LCT packet and the index of the layer it must be sent to
+    ///
+    /// The packet should be transferred to the endpoint of the layer, see `layer_endpoint()`.
+    /// When `Config::layers` is empty, all the packets are assigned to layer 0.
+    pub fn read_layered(&mut self, now: SystemTime) -> Option<(usize, Vec<u8>)> {
+        self.read_flow_controlled(now, Self::read_packet)
+    }
+
+    /// Read the next ALC/LCT packet of the FDT, the objects are not transferred
+    ///
+    /// Together with `read_data_packet()`, allows to schedule the transfer of the FDT
+    /// separately from the transfer of the objects (ex: a high-priority FDT channel and a best-effort data channel).
+    /// Packets enqueued with `inject_raw_packet()` are only returned by `read()`.
+    pub fn read_fdt_packet(&mut self, now: SystemTime) -> Option<Vec<u8>> {
+        self.read_flow_controlled(now, Self::read_fdt)
+            .map(|(_, data)| data)
+    }
+
+    /// Read the next ALC/LCT packet of the objects, the FDT is not transferred
+    ///
+    /// See `read_fdt_packet()`
+    pub fn read_data_packet(&mut self, now: SystemTime) -> Option<Vec<u8>> {
+        self.read_flow_controlled(now, Self::read_data)
+            .map(|(_, data)| data)
+    }
+
+    fn read_flow_controlled<F>(&mut self, now: SystemTime, read: F) -> Option<(usize, Vec<u8>)>
+    where
+        F: FnOnce(&mut Self, SystemTime) -> Option<(usize, Vec<u8>)>,
+    {
+        if self.paused_since.is_some() {
+            return None;
+        }
+
+        let max_in_flight_bytes = match self.max_in_flight_bytes {
+            Some(max) => max,
+            None => return read(self, now),
+        };
+
+        if self.in_flight_bytes >= max_in_flight_bytes {
+            log::debug!(
+                "TSI={} {} bytes in flight, wait for the packets to be sent",
+                self.tsi,
+                self.in_flight_bytes
+            );
+            return None;
+        }
+
+        let (layer, data) = read(self, now)?;
+        self.in_flight_bytes += data.len();
+        Some((layer, data))
+    }
+
+    /// Number of LCT layers of the session
+    pub fn nb_layers(&self) -> usize {
+        self.layering.nb_layers()
+    }
+
+    /// Endpoint of a layer
+    ///
+    /// When `Config::layers` is empty, layer 0 is the endpoint of the `Sender`
+    pub fn layer_endpoint(&self, layer: usize) -> Option<&UDPEndpoint> {
+        match self.layers.is_empty() {
+            true if layer == 0 => Some(&self.endpoint),
+            true => None,
+            false => self.layers.get(layer),
+        }
+    }
+
+    /// Set the Congestion Control Information (CCI) of the packets sent on a layer
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - Index of the layer
+    /// * `cci` - CCI value, default is 0
+    ///
+    pub fn set_layer_cci(&mut self, layer: usize, cci: u128) -> Result<()> {
+        self.layering.set_cci(layer, cci)
+    }
+
+    /// Notify that `bytes` returned by `read()` have been sent (ex: written to the UDP socket)
+    ///
+    /// Only used when `Config::max_in_flight_bytes` is set
+    pub fn notify_sent(&mut self, bytes: usize) {
+        self.in_flight_bytes = self.in_flight_bytes.saturating_sub(bytes);
+    }
+
+    /// Number of bytes returned by `read()` that have not been notified as sent with `notify_sent()`
+    pub fn in_flight_bytes(&self) -> usize {
+        self.in_flight_bytes
+    }
+
+    /// Return when the next packet can be read
+    ///
+    /// Allows to sleep until the returned `SenderActivity::WaitUntil` time instead of polling `read()`,
+    /// ex: between two transfers of an object in carousel or while a `TargetAcquisition` paces the transfer.
+    /// The activity is computed from the current state, it must be requested again after each call to the `Sender`,
+    /// including a `read()` that returned `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use flute::sender::{Sender, SenderActivity};
+    /// use std::time::SystemTime;
+    ///
+    /// # let endpoint = flute::core::UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3400);
+    /// # let mut sender = Sender::new(endpoint, 1, &Default::default(), &Default::default());
+    /// loop {
+    ///     let now = SystemTime::now();
+    ///     match sender.next_activity(now) {
+    ///         SenderActivity::PacketReady => {
+    ///             if let Some(pkt) = sender.read(now) {
+    ///                 // Send the packet
+    ///             }
+    ///         }
+    ///         SenderActivity::WaitUntil(time) => {
+    ///             std::thread::sleep(time.duration_since(now).unwrap_or_default())
+    ///         }
+    ///         SenderActivity::Idle => break,
+    ///     }
+    /// }
+    /// ```
+    pub fn next_activity(&self, now: SystemTime) -> SenderActivity {
+        if self.paused_since.is_some() {
+            return SenderActivity::Idle;
+        }
+
+        if let Some(max_in_flight_bytes) = self.max_in_flight_bytes {
+            if self.in_flight_bytes >= max_in_flight_bytes {
+                return SenderActivity::Idle;
+            }
+        }
+
+        if !self.raw_packets.is_empty() {
+            return SenderActivity::PacketReady;
+        }
+
+        fn earliest(a: Option<SystemTime>, b: Option<SystemTime>) -> Option<SystemTime> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        let mut next = None;
+        for (priority, sessions) in &self.sessions {
+            if self.paused_queues.contains_key(priority) {
+                continue;
+            }
+
+            let mut session_available = false;
+            for session in &sessions.sessions {
+                match session.next_packet_time() {
+                    Some(time) => next = earliest(next, Some(time)),
+                    None => session_available = true,
+                }
+            }
+
+            // Waiting files are only transferred by a session that is not busy
+            if session_available {
+                next = earliest(next, self.fdt.next_file_transfer_time(*priority));
+            }
+        }
+
+        if next.is_some() || self.fdt.is_fdt_pending() || self.fdt.is_publish_pending() {
+            let fdt_time = self
+                .fdt_session
+                .next_packet_time()
+                .or_else(|| self.fdt.next_fdt_transfer_time());
+            next = earliest(next, fdt_time);
+        }
+
+        match next {
+            None => SenderActivity::Idle,
+            Some(time) if time <= now => SenderActivity::PacketReady,
+            Some(time) => SenderActivity::WaitUntil(time),
+        }
+    }
+
+    fn read_packet(&mut self, now: SystemTime) -> Option<(usize, Vec<u8>)> {
+        if let Some(data) = self.raw_packets.pop_front() {
+            return Some((0, data));
+        }
+
+        if let Some(fdt_data) = self.read_fdt(now) {
             return Some(fdt_data);
         }
 
+        self.read_data(now)
+    }
+
+    fn read_fdt(&mut self, now: SystemTime) -> Option<(usize, Vec<u8>)> {
+        self.fdt_session.run(&mut self.fdt, &self.layering, now)
+    }
+
+    fn read_data(&mut self, now: SystemTime) -> Option<(usize, Vec<u8>)> {
         let fdt = &mut self.fdt;
         for session in &mut self.sessions {
-            let data = Self::read_priority_queue(fdt, session.1, now);
+            if self.paused_queues.contains_key(session.0) {
+                continue;
+            }
+            let data = Self::read_priority_queue(fdt, session.1, &self.layering, now);
             if data.is_some() {
                 return data;
             }
@@ -375,15 +1087,45 @@ impl Sender {
         None
     }
 
+    /// Return `true` if a published FDT has not been fully transferred yet
+    pub fn is_fdt_pending(&self) -> bool {
+        self.fdt.is_fdt_pending()
+    }
+
+    /// Read ALC/LCT packets until the pending FDT has been fully transferred
+    ///
+    /// Packets are read with `read()` until no FDT is pending,
+    /// until no packet is available at `now`, or after a safety limit of 10 000 packets.
+    ///
+    /// # Returns
+    ///
+    /// All the ALC/LCT packets that have been read
+    pub fn read_until_fdt_sent(&mut self, now: SystemTime) -> Vec<Vec<u8>> {
+        const MAX_ITERATIONS: usize = 10000;
+        let mut output = Vec::new();
+        for _ in 0..MAX_ITERATIONS {
+            if !self.is_fdt_pending() {
+                break;
+            }
+
+            match self.read(now) {
+                Some(pkt) => output.push(pkt),
+                None => break,
+            }
+        }
+        output
+    }
+
     fn read_priority_queue(
         fdt: &mut Fdt,
         sessions: &mut SenderSessionList,
+        layering: &Layering,
         now: SystemTime,
-    ) -> Option<Vec<u8>> {
+    ) -> Option<(usize, Vec<u8>)> {
         let session_index_orig = sessions.index;
         loop {
             let session = sessions.sessions.get_mut(sessions.index).unwrap();
-            let data = session.run(fdt, now);
+            let data = session.run(fdt, layering, now);
 
             sessions.index += 1;
             if sessions.index == sessions.sessions.len() {
@@ -407,6 +1149,7 @@ mod tests {
 
     use crate::common::lct;
     use crate::core::UDPEndpoint;
+    use crate::sender::observer;
 
     use super::objectdesc;
     use super::oti;
@@ -450,6 +1193,374 @@ mod tests {
         }
     }
 
+    struct TransferStatsSubscriber {
+        stats: std::sync::Mutex<Vec<observer::TransferStats>>,
+    }
+
+    impl observer::Subscriber for TransferStatsSubscriber {
+        fn on_sender_event(&self, evt: &observer::Event, _now: std::time::SystemTime) {
+            if let observer::Event::TransferStats(stats) = evt {
+                self.stats.lock().unwrap().push(stats.clone());
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_sender_transfer_stats() {
+        crate::tests::init();
+
+        let oti = oti::Oti::new_reed_solomon_rs28(1400, 10, 4).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+        let subscriber = std::sync::Arc::new(TransferStatsSubscriber {
+            stats: std::sync::Mutex::new(Vec::new()),
+        });
+        sender.subscribe(subscriber.clone());
+
+        let buffer = vec![0u8; 50000];
+        let object = objectdesc::ObjectDesc::create_from_buffer(
+            &buffer,
+            "text",
+            &url::Url::parse("file:///hello").unwrap(),
+            2,
+            None,
+            None,
+            None,
+            None,
+            lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+        let toi = sender.add_object(0, object).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let mut packets_sent = 0u64;
+        let mut bytes_on_wire = 0u64;
+        while let Some(data) = sender.read(std::time::SystemTime::now()) {
+            let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi == toi {
+                packets_sent += 1;
+                bytes_on_wire += data.len() as u64;
+            }
+        }
+
+        let stats = subscriber.stats.lock().unwrap();
+        // Object is transferred twice
+        assert!(stats.len() == 2);
+        assert!(stats.iter().all(|s| s.toi == toi));
+        assert!(stats.iter().map(|s| s.packets_sent).sum::<u64>() == packets_sent);
+        assert!(stats.iter().map(|s| s.bytes_on_wire).sum::<u64>() == bytes_on_wire);
+        // 36 source symbols split in 4 blocks of 9 source + 4 repair symbols, transferred twice
+        assert!(packets_sent == 2 * 4 * (9 + 4));
+    }
+
+    #[test]
+    pub fn test_sender_max_in_flight_bytes() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            max_in_flight_bytes: Some(3000),
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+        sender.add_object(0, create_obj(50000)).unwrap();
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+
+        // Packets are not notified as sent, read is throttled
+        let mut pkts = Vec::new();
+        while let Some(data) = sender.read(now) {
+            pkts.push(data);
+        }
+        let in_flight: usize = pkts.iter().map(|pkt| pkt.len()).sum();
+        assert!(!pkts.is_empty());
+        assert!(in_flight >= 3000);
+        assert!(in_flight - pkts.last().unwrap().len() < 3000);
+        assert_eq!(sender.in_flight_bytes(), in_flight);
+
+        // Read resumes once the packets are notified as sent
+        sender.notify_sent(in_flight);
+        assert_eq!(sender.in_flight_bytes(), 0);
+        let mut nb_pkts = pkts.len();
+        while let Some(data) = sender.read(now) {
+            sender.notify_sent(data.len());
+            nb_pkts += 1;
+        }
+        assert!(nb_pkts > pkts.len() + 30);
+        assert_eq!(sender.in_flight_bytes(), 0);
+    }
+
+    #[test]
+    pub fn test_sender_fdt_min_republish_interval() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            fdt_min_republish_interval: std::time::Duration::from_secs(1),
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+
+        let now = std::time::SystemTime::now();
+        for _ in 0..5 {
+            sender.add_object(0, create_obj(1024)).unwrap();
+            sender.publish(now).unwrap();
+        }
+
+        let mut fdt_ids = std::collections::BTreeSet::new();
+        for step in 0..30 {
+            let now = now + std::time::Duration::from_millis(step * 100);
+            while let Some(data) = sender.read(now) {
+                let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+                if let Some(fdt_info) = pkt.fdt_info.as_ref() {
+                    fdt_ids.insert(fdt_info.fdt_instance_id);
+                }
+            }
+        }
+
+        // First publish is sent immediately, the 4 others are batched in a single FDT version
+        assert!(fdt_ids.len() == 2);
+    }
+
+    fn run_publish_rounds(suppress_duplicate_fdt: bool) -> std::collections::BTreeSet<u32> {
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            suppress_duplicate_fdt,
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+        // Object stays in the FDT during the test
+        let obj = objectdesc::ObjectDesc::create_from_buffer(
+            &vec![0u8; 1024],
+            "text",
+            &url::Url::parse("file:///hello").unwrap(),
+            1,
+            Some(std::time::Duration::from_secs(3600)),
+            None,
+            None,
+            None,
+            lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+        sender.add_object(0, obj).unwrap();
+
+        let start = std::time::SystemTime::now();
+        let mut fdt_ids = std::collections::BTreeSet::new();
+        for round in 0..5 {
+            let now = start + std::time::Duration::from_millis(round * 100);
+            sender.publish(now).unwrap();
+            let mut fdt_sent = false;
+            while let Some(data) = sender.read(now) {
+                let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+                if let Some(fdt_info) = pkt.fdt_info.as_ref() {
+                    fdt_ids.insert(fdt_info.fdt_instance_id);
+                    fdt_sent = true;
+                }
+            }
+            // Each publication transfers the FDT, even when its content is unchanged
+            assert!(fdt_sent);
+        }
+        fdt_ids
+    }
+
+    #[test]
+    pub fn test_sender_suppress_duplicate_fdt() {
+        crate::tests::init();
+        assert_eq!(run_publish_rounds(true).len(), 1);
+        assert_eq!(run_publish_rounds(false).len(), 5);
+    }
+
+    fn run_enforce_mtu(encoding_symbol_length: u16, mtu: usize) -> (usize, usize) {
+        let oti = oti::Oti::new_no_code(encoding_symbol_length, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            enforce_mtu: Some(mtu),
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+        sender.add_object(0, create_obj(100 * 1024)).unwrap();
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+
+        let mut nb_object_pkts = 0;
+        let mut max_size = 0;
+        while let Some(data) = sender.read(now) {
+            let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != lct::TOI_FDT {
+                nb_object_pkts += 1;
+            }
+            max_size = max_size.max(data.len());
+        }
+        (nb_object_pkts, max_size)
+    }
+
+    #[test]
+    pub fn test_sender_enforce_mtu() {
+        crate::tests::init();
+        let mtu = 1500;
+        let oti = oti::Oti::new_no_code(1400, 64);
+        let encoding_symbol_length = [lct::TOI_FDT, 1]
+            .iter()
+            .map(|toi| crate::common::alc::alc_pkt_max_payload_size(&oti, 1, *toi, mtu))
+            .min()
+            .unwrap() as u16;
+
+        let (nb_object_pkts, max_size) = run_enforce_mtu(encoding_symbol_length, mtu);
+        assert!(nb_object_pkts > 0);
+        assert!(max_size <= mtu);
+
+        // Symbols are too large, the transfer of the object fails
+        let (nb_object_pkts, max_size) = run_enforce_mtu(encoding_symbol_length + 100, mtu);
+        assert_eq!(nb_object_pkts, 0);
+        assert!(max_size <= mtu);
+    }
+
+    #[test]
+    pub fn test_sender_pause_fdt_expired() {
+        crate::tests::init();
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            fdt_duration: std::time::Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+        sender.add_object(0, create_obj(1024)).unwrap();
+        let start = std::time::SystemTime::now();
+        sender.publish(start).unwrap();
+
+        let data = sender.read(start).unwrap();
+        let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+        let first_fdt_id = pkt.fdt_info.unwrap().fdt_instance_id;
+
+        sender.pause(start);
+        let now = start + std::time::Duration::from_secs(120);
+        assert!(sender.read(now).is_none());
+        sender.resume(now).unwrap();
+
+        // A new FDT version is sent before the data of the object
+        let data = sender.read(now).unwrap();
+        let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+        assert_eq!(pkt.lct.toi, lct::TOI_FDT);
+        assert!(pkt.fdt_info.unwrap().fdt_instance_id != first_fdt_id);
+    }
+
+    #[test]
+    pub fn test_sender_pause_queue() {
+        crate::tests::init();
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+        sender.add_object(0, create_obj(1024)).unwrap();
+        let now = std::time::SystemTime::now();
+        sender.pause_queue(0, now);
+        assert!(sender.is_queue_paused(0));
+        sender.publish(now).unwrap();
+
+        // Only the FDT is transferred
+        while let Some(data) = sender.read(now) {
+            let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+            assert_eq!(pkt.lct.toi, lct::TOI_FDT);
+        }
+
+        sender.resume_queue(0, now);
+        assert!(!sender.is_queue_paused(0));
+        let data = sender.read(now).unwrap();
+        let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+        assert!(pkt.lct.toi != lct::TOI_FDT);
+    }
+
+    #[test]
+    pub fn test_sender_get_encoding_params() {
+        crate::tests::init();
+        let oti = oti::Oti::new_reed_solomon_rs28(1400, 64, 2).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+        let transfer_length = 1024 * 1024;
+        let toi = sender.add_object(0, create_obj(transfer_length)).unwrap();
+        let bitrate = 8_000_000;
+
+        let params = sender.get_encoding_params(toi, bitrate).unwrap();
+        log::info!("{:?}", params);
+        // 8Mbps at 1000 packets per second, 1000 bytes per packet
+        assert!(params.recommended_encoding_symbol_length < 1000);
+        assert_eq!(params.recommended_encoding_symbol_length % 4, 0);
+        assert!(params.recommended_max_parity_symbols > 0);
+        assert!(
+            params.recommended_max_source_block_length + params.recommended_max_parity_symbols
+                <= 255
+        );
+
+        let recommended_oti = oti::Oti::new_reed_solomon_rs28(
+            params.recommended_encoding_symbol_length,
+            params.recommended_max_source_block_length as u8,
+            params.recommended_max_parity_symbols as u8,
+        )
+        .unwrap();
+        assert_eq!(
+            params.estimated_total_packets,
+            recommended_oti.packets_per_object(transfer_length as u64)
+        );
+        let expected = recommended_oti.estimate_transfer_time(transfer_length as u64, bitrate);
+        let diff =
+            (params.estimated_transfer_duration.as_secs_f64() - expected.as_secs_f64()).abs();
+        assert!(diff <= expected.as_secs_f64() * 0.01);
+
+        // The encoding symbol length is never increased
+        let params = sender.get_encoding_params(toi, bitrate * 100).unwrap();
+        assert_eq!(params.recommended_encoding_symbol_length, 1400);
+
+        assert!(sender.get_encoding_params(toi, 0).is_none());
+        assert!(sender.get_encoding_params(toi + 1, bitrate).is_none());
+    }
+
+    #[test]
+    pub fn test_sender_add_object_with_toi() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+
+        let toi = sender
+            .add_object_with_toi(0, create_obj(1024), 1234)
+            .unwrap();
+        assert!(toi == 1234);
+        assert!(sender.is_added(1234));
+
+        // TOI of the FDT and TOI already used are refused
+        assert!(sender.add_object_with_toi(0, create_obj(1024), 0).is_err());
+        assert!(sender
+            .add_object_with_toi(0, create_obj(1024), 1234)
+            .is_err());
+
+        // Allocated TOIs skip the TOIs chosen by the caller (first allocated TOI is 1)
+        sender.add_object_with_toi(0, create_obj(1024), 1).unwrap();
+        let allocated = sender.add_object(0, create_obj(1024)).unwrap();
+        assert!(allocated == 2);
+
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+        let fdt = String::from_utf8(sender.fdt_xml_data(now).unwrap()).unwrap();
+        assert!(fdt.contains("TOI=\"1234\""));
+
+        let mut found_on_wire = false;
+        while let Some(data) = sender.read(now) {
+            let pkt = crate::common::alc::parse_alc_pkt(&data).unwrap();
+            found_on_wire |= pkt.lct.toi == 1234;
+        }
+        assert!(found_on_wire);
+    }
+
     #[test]
     pub fn test_sender_file_too_large() {
         crate::tests::init();
@@ -480,6 +1591,204 @@ mod tests {
         assert!(sender.nb_objects() == 0);
     }
 
+    #[derive(Debug)]
+    struct FdtWriterBuilder {
+        fdt: std::cell::RefCell<Option<String>>,
+        inner: crate::receiver::writer::ObjectWriterBufferBuilder,
+    }
+
+    impl crate::receiver::writer::ObjectWriterBuilder for FdtWriterBuilder {
+        fn new_object_writer(
+            &self,
+            endpoint: &UDPEndpoint,
+            tsi: &u64,
+            toi: &u128,
+            meta: &crate::receiver::writer::ObjectMetadata,
+            now: std::time::SystemTime,
+        ) -> Box<dyn crate::receiver::writer::ObjectWriter> {
+            self.inner.new_object_writer(endpoint, tsi, toi, meta, now)
+        }
+
+        fn set_cache_duration(
+            &self,
+            _endpoint: &UDPEndpoint,
+            _tsi: &u64,
+            _toi: &u128,
+            _content_location: &url::Url,
+            _duration: &std::time::Duration,
+            _now: std::time::SystemTime,
+        ) {
+        }
+
+        fn fdt_received(
+            &self,
+            _endpoint: &UDPEndpoint,
+            _tsi: &u64,
+            fdt_xml: &str,
+            _expires: std::time::SystemTime,
+            _meta: &crate::receiver::writer::ObjectMetadata,
+            _transfer_duration: std::time::Duration,
+            _now: std::time::SystemTime,
+            _ext_time: Option<std::time::SystemTime>,
+        ) {
+            self.fdt.replace(Some(fdt_xml.to_owned()));
+        }
+    }
+
+    #[test]
+    pub fn test_sender_read_until_fdt_sent() {
+        crate::tests::init();
+
+        let oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint.clone(), 1, &oti, &Default::default());
+        assert!(!sender.is_fdt_pending());
+
+        let now = std::time::SystemTime::now();
+        let toi = sender.add_object(0, create_obj(100000)).unwrap();
+        sender.publish(now).unwrap();
+        assert!(sender.is_fdt_pending());
+
+        let pkts = sender.read_until_fdt_sent(now);
+        assert!(!pkts.is_empty());
+        assert!(!sender.is_fdt_pending());
+
+        let writer = std::rc::Rc::new(FdtWriterBuilder {
+            fdt: std::cell::RefCell::new(None),
+            inner: crate::receiver::writer::ObjectWriterBufferBuilder::new(),
+        });
+        let mut receiver = crate::receiver::MultiReceiver::new(writer.clone(), None, false);
+        for pkt in &pkts {
+            receiver.push(&endpoint, pkt, now).unwrap();
+        }
+
+        let fdt = writer.fdt.borrow();
+        let fdt = crate::common::fdtinstance::FdtInstance::parse(fdt.as_ref().unwrap().as_bytes())
+            .unwrap();
+        assert!(fdt.get_file(&toi).is_some());
+    }
+
+    #[test]
+    pub fn test_sender_read_fdt_and_data_packets() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+
+        let now = std::time::SystemTime::now();
+        sender
+            .add_object(0, create_obj(oti.encoding_symbol_length as usize * 3))
+            .unwrap();
+        sender.publish(now).unwrap();
+
+        let toi = |pkt: &Vec<u8>| crate::common::alc::parse_alc_pkt(pkt).unwrap().lct.toi;
+
+        let data_pkts: Vec<Vec<u8>> = std::iter::from_fn(|| sender.read_data_packet(now)).collect();
+        assert_eq!(data_pkts.len(), 3);
+        assert!(data_pkts.iter().all(|pkt| toi(pkt) != lct::TOI_FDT));
+
+        let fdt_pkts: Vec<Vec<u8>> = std::iter::from_fn(|| sender.read_fdt_packet(now)).collect();
+        assert!(!fdt_pkts.is_empty());
+        assert!(fdt_pkts.iter().all(|pkt| toi(pkt) == lct::TOI_FDT));
+        assert!(!sender.is_fdt_pending());
+    }
+
+    #[test]
+    pub fn test_sender_next_activity() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let config = super::Config {
+            fdt_carousel: std::time::Duration::from_secs(3600),
+            ..Default::default()
+        };
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &config);
+
+        let now = std::time::SystemTime::now();
+        assert_eq!(sender.next_activity(now), super::SenderActivity::Idle);
+
+        let carousel_delay = std::time::Duration::from_secs(2);
+        let mut obj = create_obj(oti.encoding_symbol_length as usize * 3);
+        obj.carousel_delay = Some(carousel_delay);
+        sender.add_object(0, obj).unwrap();
+        sender.publish(now).unwrap();
+        assert_eq!(
+            sender.next_activity(now),
+            super::SenderActivity::PacketReady
+        );
+
+        while sender.read(now).is_some() {}
+
+        let wait_until = match sender.next_activity(now) {
+            super::SenderActivity::WaitUntil(time) => time,
+            activity => panic!("Unexpected activity {:?}", activity),
+        };
+        let delay = wait_until.duration_since(now).unwrap();
+        assert!(delay >= carousel_delay);
+        assert!(delay < carousel_delay + std::time::Duration::from_millis(10));
+
+        let before = wait_until - std::time::Duration::from_millis(1);
+        assert!(sender.read(before).is_none());
+        assert_eq!(
+            sender.next_activity(wait_until),
+            super::SenderActivity::PacketReady
+        );
+        let pkt = sender.read(wait_until).unwrap();
+        let alc = crate::common::alc::parse_alc_pkt(&pkt).unwrap();
+        assert_ne!(alc.lct.toi, lct::TOI_FDT);
+
+        sender.pause(wait_until);
+        assert_eq!(
+            sender.next_activity(wait_until),
+            super::SenderActivity::Idle
+        );
+    }
+
+    #[test]
+    pub fn test_sender_next_activity_target_acquisition() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+
+        let now = std::time::SystemTime::now();
+        let mut obj = create_obj(oti.encoding_symbol_length as usize * 4);
+        obj.target_acquisition = Some(objectdesc::TargetAcquisition::WithinDuration(
+            std::time::Duration::from_secs(4),
+        ));
+        sender.add_object(0, obj).unwrap();
+        sender.publish(now).unwrap();
+
+        let mut time = now;
+        let mut nb_pkts = 0;
+        for _ in 0..100 {
+            match sender.next_activity(time) {
+                super::SenderActivity::PacketReady => {
+                    if let Some(pkt) = sender.read(time) {
+                        let alc = crate::common::alc::parse_alc_pkt(&pkt).unwrap();
+                        if alc.lct.toi != lct::TOI_FDT {
+                            nb_pkts += 1;
+                        }
+                    }
+                }
+                super::SenderActivity::WaitUntil(next) => {
+                    assert!(next > time);
+                    time = next;
+                }
+                super::SenderActivity::Idle => break,
+            }
+        }
+
+        assert_eq!(nb_pkts, 4);
+        assert_eq!(sender.nb_objects(), 0);
+        let elapsed = time.duration_since(now).unwrap();
+        assert!(elapsed >= std::time::Duration::from_secs(2));
+        assert!(elapsed <= std::time::Duration::from_secs(4));
+    }
+
     #[test]
     pub fn sender_complete() {
         crate::tests::init();
@@ -498,4 +1807,43 @@ mod tests {
         let result = sender.add_object(0, object2);
         assert!(result.is_err());
     }
+
+    #[test]
+    pub fn test_sender_prune_expired_objects() {
+        crate::tests::init();
+
+        let oti: oti::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 1234);
+        let mut sender = super::Sender::new(endpoint, 1, &oti, &Default::default());
+        let now = std::time::SystemTime::now();
+
+        for i in 0..3 {
+            let mut obj = create_obj(1024);
+            obj.content_location = url::Url::parse(&format!("file:///object{}", i)).unwrap();
+            obj.cache_control = Some(objectdesc::CacheControl::Expires(
+                std::time::Duration::from_secs(1),
+            ));
+            sender.add_object(0, obj).unwrap();
+        }
+        sender.publish(now).unwrap();
+        assert_eq!(sender.nb_objects(), 3);
+
+        // Objects are not expired yet
+        assert_eq!(sender.prune_expired_objects(now), 0);
+        assert_eq!(sender.nb_objects(), 3);
+
+        let later = now + std::time::Duration::from_secs(2);
+        assert_eq!(sender.prune_expired_objects(later), 3);
+        assert_eq!(sender.nb_objects(), 0);
+
+        // Object that is not announced in a published FDT yet is kept
+        let mut obj = create_obj(1024);
+        obj.cache_control = Some(objectdesc::CacheControl::Expires(
+            std::time::Duration::from_secs(1),
+        ));
+        let toi = sender.add_object(0, obj).unwrap();
+        let later = later + std::time::Duration::from_secs(10);
+        assert_eq!(sender.prune_expired_objects(later), 0);
+        assert!(sender.is_added(toi));
+    }
 }
@@ -0,0 +1,283 @@
+use super::sender::{Config, PriorityQueue, Sender};
+use crate::common::oti::{self, FECEncodingID, SchemeSpecific};
+use crate::common::{lct, Profile};
+use crate::core::UDPEndpoint;
+use crate::tools::error::{FluteError, Result};
+
+///
+/// Build a FLUTE `Sender`
+///
+/// The configuration is validated by `build()`,
+/// incompatible combinations are rejected instead of failing during the transfer of the objects.
+///
+/// # Example
+///
+/// ```
+/// use flute::core::{Oti, UDPEndpoint};
+/// use flute::sender::{PriorityQueue, SenderBuilder};
+///
+/// let sender = SenderBuilder::new()
+///     .endpoint(UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3400))
+///     .tsi(1)
+///     .oti(Oti::new_raptorq(1400, 60, 4, 1, 4).unwrap())
+///     .priority_queue(1, PriorityQueue::new(1))
+///     .interleave_blocks(2)
+///     .build()
+///     .unwrap();
+/// assert_eq!(sender.get_tsi(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct SenderBuilder {
+    endpoint: Option<UDPEndpoint>,
+    tsi: u64,
+    oti: oti::Oti,
+    config: Config,
+}
+
+impl SenderBuilder {
+    /// Return a new `SenderBuilder` with the default `Oti` and the default `Config`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the UDP endpoint of the FLUTE session, mandatory
+    pub fn endpoint(mut self, endpoint: UDPEndpoint) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Set the Transport Session Identifier (TSI) of the FLUTE session, default 0
+    pub fn tsi(mut self, tsi: u64) -> Self {
+        self.tsi = tsi;
+        self
+    }
+
+    /// Set the default Object Transmission Information (OTI) of the objects and of the FDT
+    pub fn oti(mut self, oti: oti::Oti) -> Self {
+        self.oti = oti;
+        self
+    }
+
+    /// Replace the whole `Config` of the `Sender`, the methods called after this one modify it
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Insert or replace a priority queue, see `Config::set_priority_queue()`
+    pub fn priority_queue(mut self, priority: u32, queue: PriorityQueue) -> Self {
+        self.config.set_priority_queue(priority, queue);
+        self
+    }
+
+    /// Set the max number of blocks interleaved during the transmission of an object, see `Config::interleave_blocks`
+    pub fn interleave_blocks(mut self, interleave_blocks: u8) -> Self {
+        self.config.interleave_blocks = interleave_blocks;
+        self
+    }
+
+    /// Set the Content Encoding of the FDT, see `Config::fdt_cenc`
+    pub fn fdt_cenc(mut self, cenc: lct::Cenc) -> Self {
+        self.config.fdt_cenc = cenc;
+        self
+    }
+
+    /// Set the FLUTE profile of the session, see `Config::profile`
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.config.profile = profile;
+        self
+    }
+
+    ///
+    /// Validate the configuration and build the `Sender`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if
+    /// * the endpoint is not set
+    /// * the FEC scheme of the OTI is not compatible with the FLUTE profile (ex: RaptorQ with `Profile::RFC3926`)
+    /// * the scheme specific parameters of the OTI are missing or do not match the FEC scheme
+    /// * the encoding symbol length is not a multiple of the symbol alignment (Raptor, RaptorQ)
+    /// * the packets exceed `Config::enforce_mtu`
+    /// * there is no priority queue, or a priority queue does not transfer any object at a time
+    /// * `Config::interleave_blocks` or `Config::toi_initial_value` is 0
+    ///
+    pub fn build(self) -> Result<Sender> {
+        let endpoint = self
+            .endpoint
+            .as_ref()
+            .ok_or_else(|| FluteError::new("Sender endpoint is not set"))?;
+        self.validate()?;
+        Ok(Sender::new(
+            endpoint.clone(),
+            self.tsi,
+            &self.oti,
+            &self.config,
+        ))
+    }
+
+    fn validate(&self) -> Result<()> {
+        let oti = &self.oti;
+        let config = &self.config;
+
+        if !oti.is_compatible_with(&config.profile) {
+            return Err(FluteError::new(format!(
+                "FEC Encoding ID {:?} is not compatible with the profile {:?}",
+                oti.fec_encoding_id, config.profile
+            )));
+        }
+
+        if oti.encoding_symbol_length == 0 || oti.maximum_source_block_length == 0 {
+            return Err(FluteError::new(
+                "Encoding symbol length and maximum source block length must be > 0",
+            ));
+        }
+
+        let symbol_alignment = match (oti.fec_encoding_id, oti.scheme_specific.as_ref()) {
+            (FECEncodingID::RaptorQ, Some(SchemeSpecific::RaptorQ(scheme))) => {
+                Some(scheme.symbol_alignment)
+            }
+            (FECEncodingID::Raptor, Some(SchemeSpecific::Raptor(scheme))) => {
+                Some(scheme.symbol_alignment)
+            }
+            (FECEncodingID::RaptorQ, _)
+            | (FECEncodingID::Raptor, _)
+            | (FECEncodingID::ReedSolomonGF2M, None) => {
+                return Err(FluteError::new(format!(
+                    "Scheme specific parameters of FEC Encoding ID {:?} are missing",
+                    oti.fec_encoding_id
+                )))
+            }
+            _ => None,
+        };
+
+        if let Some(symbol_alignment) = symbol_alignment {
+            if symbol_alignment == 0 || oti.encoding_symbol_length % symbol_alignment as u16 != 0 {
+                return Err(FluteError::new(format!(
+                    "Encoding symbol length {} is not a multiple of the symbol alignment {}",
+                    oti.encoding_symbol_length, symbol_alignment
+                )));
+            }
+        }
+
+        if let Some(mtu) = config.enforce_mtu {
+            let packet_size =
+                oti.encoding_symbol_length as usize + oti.estimated_packet_overhead_bytes();
+            if packet_size > mtu {
+                return Err(FluteError::new(format!(
+                    "Packets of {} bytes exceed the MTU of {} bytes",
+                    packet_size, mtu
+                )));
+            }
+        }
+
+        if config.priority_queues.is_empty() {
+            return Err(FluteError::new("Sender has no priority queue"));
+        }
+
+        if let Some((priority, _)) = config
+            .priority_queues
+            .iter()
+            .find(|(_, queue)| queue.multiplex_files == 0)
+        {
+            return Err(FluteError::new(format!(
+                "Priority queue {} does not transfer any object (multiplex_files is 0)",
+                priority
+            )));
+        }
+
+        if config.interleave_blocks == 0 {
+            return Err(FluteError::new("Interleave blocks must be > 0"));
+        }
+
+        if config.toi_initial_value == Some(0) {
+            return Err(FluteError::new("TOI initial value must be > 0"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SenderBuilder;
+    use crate::common::{lct, oti, Profile};
+    use crate::core::UDPEndpoint;
+    use crate::sender::PriorityQueue;
+
+    fn endpoint() -> UDPEndpoint {
+        UDPEndpoint::new(None, "224.0.0.1".to_owned(), 3400)
+    }
+
+    #[test]
+    pub fn test_sender_builder() {
+        crate::tests::init();
+        let mut sender = SenderBuilder::new()
+            .endpoint(endpoint())
+            .tsi(42)
+            .oti(oti::Oti::new_reed_solomon_rs28(1400, 60, 4).unwrap())
+            .priority_queue(1, PriorityQueue::new(1))
+            .interleave_blocks(1)
+            .fdt_cenc(lct::Cenc::Gzip)
+            .build()
+            .unwrap();
+
+        assert_eq!(sender.get_tsi(), 42);
+        assert_eq!(sender.get_udp_endpoint(), &endpoint());
+        assert!(sender.read(std::time::SystemTime::now()).is_some());
+    }
+
+    #[test]
+    pub fn test_sender_builder_invalid() {
+        crate::tests::init();
+        let raptorq = oti::Oti::new_raptorq(1400, 60, 4, 1, 4).unwrap();
+
+        // No endpoint
+        assert!(SenderBuilder::new().build().is_err());
+
+        // RaptorQ is not specified for FLUTE version 1
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .oti(raptorq.clone())
+            .profile(Profile::RFC3926)
+            .build()
+            .is_err());
+
+        // RaptorQ without its scheme specific parameters
+        let mut oti = raptorq.clone();
+        oti.scheme_specific = None;
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .oti(oti)
+            .build()
+            .is_err());
+
+        // Encoding symbol length not aligned
+        let mut oti = raptorq.clone();
+        oti.encoding_symbol_length = 1401;
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .oti(oti)
+            .build()
+            .is_err());
+
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .priority_queue(0, PriorityQueue::new(0))
+            .build()
+            .is_err());
+
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .interleave_blocks(0)
+            .build()
+            .is_err());
+
+        assert!(SenderBuilder::new()
+            .endpoint(endpoint())
+            .oti(raptorq)
+            .profile(Profile::RFC6726)
+            .build()
+            .is_ok());
+    }
+}
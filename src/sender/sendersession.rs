@@ -1,11 +1,14 @@
 use super::blockencoder::BlockEncoder;
 use super::fdt::Fdt;
 use super::filedesc::FileDesc;
+use super::layer::Layering;
 #[cfg(feature = "opentelemetry")]
 use super::objectsenderlogger::ObjectSenderLogger;
+use super::observer::{ErrorKind, SenderError, TransferStats};
 use super::Profile;
 use crate::common::alc;
 use crate::core::UDPEndpoint;
+use crate::tools::error::{FluteError, Result};
 use std::sync::Arc;
 use std::time::SystemTime;
 
@@ -17,9 +20,13 @@ pub struct SenderSession {
     tsi: u64,
     file: Option<Arc<FileDesc>>,
     encoder: Option<BlockEncoder>,
+    packets_sent: u64,
+    bytes_on_wire: u64,
     interleave_blocks: usize,
     transfer_fdt_only: bool,
     profile: Profile,
+    pad_last_symbol: bool,
+    enforce_mtu: Option<usize>,
     #[cfg(feature = "opentelemetry")]
     logger: Option<ObjectSenderLogger>,
 }
@@ -31,6 +38,7 @@ impl SenderSession {
         interleave_blocks: usize,
         transfer_fdt_only: bool,
         profile: Profile,
+        pad_last_symbol: bool,
         endpoint: UDPEndpoint,
     ) -> SenderSession {
         SenderSession {
@@ -39,15 +47,53 @@ impl SenderSession {
             tsi,
             file: None,
             encoder: None,
+            packets_sent: 0,
+            bytes_on_wire: 0,
             interleave_blocks,
             transfer_fdt_only,
             profile,
+            pad_last_symbol,
+            enforce_mtu: None,
             #[cfg(feature = "opentelemetry")]
             logger: None,
         }
     }
 
-    pub fn run(&mut self, fdt: &mut Fdt, now: SystemTime) -> Option<Vec<u8>> {
+    /// Fail the transfer of the objects whose packets exceed the `mtu`
+    pub fn set_enforce_mtu(&mut self, mtu: Option<usize>) {
+        self.enforce_mtu = mtu;
+    }
+
+    /// Check that an ALC/LCT packet fits within the `mtu`
+    pub fn check_pkt_size(pkt: &[u8], mtu: usize) -> Result<()> {
+        if pkt.len() > mtu {
+            return Err(FluteError::new(format!(
+                "ALC/LCT packet of {} bytes exceeds the MTU of {} bytes",
+                pkt.len(),
+                mtu
+            )));
+        }
+        Ok(())
+    }
+
+    /// Earliest time when `run()` returns a packet of the file in transfer,
+    /// `None` if no file is in transfer
+    pub fn next_packet_time(&self) -> Option<SystemTime> {
+        self.encoder.as_ref()?;
+        let file = self.file.as_ref()?;
+        Some(
+            file.get_next_transfer_timestamp()
+                .unwrap_or(SystemTime::UNIX_EPOCH),
+        )
+    }
+
+    /// Return the next packet and its layer
+    pub fn run(
+        &mut self,
+        fdt: &mut Fdt,
+        layering: &Layering,
+        now: SystemTime,
+    ) -> Option<(usize, Vec<u8>)> {
         loop {
             if self.encoder.is_none() {
                 self.get_next(fdt, now);
@@ -72,20 +118,47 @@ impl SenderSession {
 
             let pkt = encoder.read(must_stop_transfer);
             if pkt.is_none() {
-                self.release_file(fdt, now);
+                match encoder.error().cloned() {
+                    Some(error) => self.fail_file(fdt, error, now),
+                    None => self.release_file(fdt, now),
+                }
                 continue;
             }
 
             file.inc_next_transfer_timestamp();
             let pkt = pkt.as_ref().unwrap();
-            return Some(alc::new_alc_pkt(
-                &file.oti,
-                &0u128,
-                self.tsi,
-                pkt,
-                self.profile,
-                now,
-            ));
+            let (layer, cci) = layering.select(pkt);
+            let data = match alc::new_alc_pkt(&file.oti, &cci, self.tsi, pkt, self.profile, now) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::error!("{:?}", e);
+                    let error = SenderError {
+                        toi: file.toi,
+                        sbn: pkt.sbn,
+                        kind: ErrorKind::Packet,
+                        message: e.to_string(),
+                    };
+                    self.fail_file(fdt, error, now);
+                    continue;
+                }
+            };
+            if let Some(mtu) = self.enforce_mtu {
+                if let Err(e) = Self::check_pkt_size(&data, mtu) {
+                    log::error!("{:?}", e);
+                    let error = SenderError {
+                        toi: file.toi,
+                        sbn: pkt.sbn,
+                        kind: ErrorKind::Mtu,
+                        message: e.to_string(),
+                    };
+                    self.fail_file(fdt, error, now);
+                    continue;
+                }
+            }
+            file.inc_symbols_sent();
+            self.packets_sent += 1;
+            self.bytes_on_wire += data.len() as u64;
+            return Some((layer, data));
         }
     }
 
@@ -115,23 +188,62 @@ impl SenderSession {
 
         let file = self.file.as_ref().unwrap().clone();
         let is_last_transfer = file.is_last_transfer();
-        let block_encoder = BlockEncoder::new(file, self.interleave_blocks, is_last_transfer);
-        if block_encoder.is_err() {
-            log::error!("Fail to open Block Encoder");
-            self.release_file(fdt, now);
-            return;
-        }
+        let block_encoder = BlockEncoder::new(
+            file,
+            self.interleave_blocks,
+            is_last_transfer,
+            self.pad_last_symbol,
+        );
+        let block_encoder = match block_encoder {
+            Ok(block_encoder) => block_encoder,
+            Err(e) => {
+                log::error!("Fail to open Block Encoder");
+                fdt.report_error(&SenderError {
+                    toi: self.file.as_ref().unwrap().toi,
+                    sbn: 0,
+                    kind: ErrorKind::Io,
+                    message: e.0.to_string(),
+                });
+                self.release_file(fdt, now);
+                return;
+            }
+        };
 
-        self.encoder = block_encoder.ok();
+        self.encoder = Some(block_encoder);
     }
 
     fn release_file(&mut self, fdt: &mut Fdt, now: SystemTime) {
         if let Some(file) = &self.file {
-            fdt.transfer_done(file.clone(), now)
+            let stats = self.transfer_stats(file);
+            fdt.transfer_done(file.clone(), stats, now)
         };
 
+        self.reset();
+    }
+
+    fn fail_file(&mut self, fdt: &mut Fdt, error: SenderError, now: SystemTime) {
+        if let Some(file) = &self.file {
+            let stats = self.transfer_stats(file);
+            fdt.transfer_failed(file.clone(), &error.message, stats, now);
+            fdt.report_error(&error);
+        };
+
+        self.reset();
+    }
+
+    fn transfer_stats(&self, file: &FileDesc) -> TransferStats {
+        TransferStats {
+            toi: file.toi,
+            packets_sent: self.packets_sent,
+            bytes_on_wire: self.bytes_on_wire,
+        }
+    }
+
+    fn reset(&mut self) {
         self.file = None;
         self.encoder = None;
+        self.packets_sent = 0;
+        self.bytes_on_wire = 0;
 
         #[cfg(feature = "opentelemetry")]
         {
@@ -3,12 +3,14 @@ use std::sync::{Arc, Mutex};
 use rand::Rng;
 
 use crate::common::lct;
+use crate::tools::error::{FluteError, Result};
 
 use super::TOIMaxLength;
 
 #[derive(Debug)]
 struct ToiAllocatorInternal {
-    toi_reserved: std::collections::HashSet<u128>,
+    /// Reserved TOIs with their number of references
+    toi_reserved: std::collections::HashMap<u128, usize>,
     toi: u128,
     toi_max_length: TOIMaxLength,
 }
@@ -55,7 +57,7 @@ impl ToiAllocatorInternal {
         }
 
         Self {
-            toi_reserved: std::collections::HashSet::new(),
+            toi_reserved: std::collections::HashMap::new(),
             toi,
             toi_max_length,
         }
@@ -68,33 +70,78 @@ impl ToiAllocatorInternal {
             TOIMaxLength::ToiMax48 => toi & 0xFFFFFFFFFFFFu128,
             TOIMaxLength::ToiMax64 => toi & 0xFFFFFFFFFFFFFFFFu128,
             TOIMaxLength::ToiMax80 => toi & 0xFFFFFFFFFFFFFFFFFFFFu128,
-            TOIMaxLength::ToiMax112 => toi,
+            TOIMaxLength::ToiMax112 => toi & 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFu128,
         }
     }
 
+    /// Number of TOIs that are not reserved, TOI 0 of the FDT excluded
+    fn nb_free(&self) -> u128 {
+        Self::to_max_length(u128::MAX, self.toi_max_length) - self.toi_reserved.len() as u128
+    }
+
     fn allocate(&mut self) -> u128 {
         let ret = self.toi;
-        assert!(!self.toi_reserved.contains(&ret));
-        self.toi_reserved.insert(ret);
+        assert!(!self.toi_reserved.contains_key(&ret));
+        self.toi_reserved.insert(ret, 1);
+        self.next();
+        ret
+    }
+
+    fn reserve(&mut self, toi: u128) -> bool {
+        if toi == lct::TOI_FDT
+            || Self::to_max_length(toi, self.toi_max_length) != toi
+            || self.toi_reserved.contains_key(&toi)
+        {
+            return false;
+        }
 
+        self.toi_reserved.insert(toi, 1);
+        if self.toi == toi {
+            self.next();
+        }
+        true
+    }
+
+    /// Move to the next free TOI
+    /// The TOI wraps around when it reaches the TOI max length, TOI 0 is skipped as it is reserved for the FDT
+    fn next(&mut self) {
+        let start = self.toi;
         loop {
             self.toi = Self::to_max_length(self.toi + 1, self.toi_max_length);
             if self.toi == lct::TOI_FDT {
                 self.toi = 1;
             }
 
-            if !self.toi_reserved.contains(&self.toi) {
+            if !self.toi_reserved.contains_key(&self.toi) {
+                break;
+            }
+
+            if self.toi == start {
+                log::error!("All the TOIs are used, no TOI is available");
                 break;
             }
 
             log::warn!("TOI {} is already used by a file or reserved", self.toi)
         }
-        ret
+    }
+
+    fn retain(&mut self, toi: u128) {
+        let count = self.toi_reserved.get_mut(&toi);
+        debug_assert!(count.is_some());
+        if let Some(count) = count {
+            *count += 1;
+        }
     }
 
     fn release(&mut self, toi: u128) {
-        let success = self.toi_reserved.remove(&toi);
-        debug_assert!(success);
+        let count = self.toi_reserved.get_mut(&toi);
+        debug_assert!(count.is_some());
+        if let Some(count) = count {
+            *count -= 1;
+            if *count == 0 {
+                self.toi_reserved.remove(&toi);
+            }
+        }
     }
 }
 
@@ -114,6 +161,41 @@ impl ToiAllocator {
         })
     }
 
+    /// Allocate `count` consecutive TOIs while holding the lock once
+    /// The TOIs reserved by other objects are skipped, the range wraps around at the TOI max length
+    /// Return an error if less than `count` TOIs are free
+    pub fn allocate_range(allocator: &Arc<Self>, count: usize) -> Result<Vec<Toi>> {
+        let mut db = allocator.internal.lock().unwrap();
+        if count as u128 > db.nb_free() {
+            return Err(FluteError::new(format!(
+                "Can not allocate {} TOIs, only {} TOIs are free",
+                count,
+                db.nb_free()
+            )));
+        }
+
+        Ok((0..count)
+            .map(|_| Toi {
+                allocator: allocator.clone(),
+                value: db.allocate(),
+            })
+            .collect())
+    }
+
+    /// Reserve a TOI chosen by the caller
+    /// Return `None` if the TOI is the TOI of the FDT, does not fit the TOI max length or is already reserved
+    pub fn reserve(allocator: &Arc<Self>, toi: u128) -> Option<Box<Toi>> {
+        let mut db = allocator.internal.lock().unwrap();
+        if !db.reserve(toi) {
+            return None;
+        }
+
+        Some(Box::new(Toi {
+            allocator: allocator.clone(),
+            value: toi,
+        }))
+    }
+
     pub fn allocate_toi_fdt(allocator: &Arc<Self>) -> Box<Toi> {
         Box::new(Toi {
             allocator: allocator.clone(),
@@ -121,6 +203,20 @@ impl ToiAllocator {
         })
     }
 
+    /// Return a new reference to an allocated TOI
+    /// The TOI is released when all its references are dropped
+    pub fn retain(toi: &Toi) -> Box<Toi> {
+        if toi.value != lct::TOI_FDT {
+            let mut db = toi.allocator.internal.lock().unwrap();
+            db.retain(toi.value);
+        }
+
+        Box::new(Toi {
+            allocator: toi.allocator.clone(),
+            value: toi.value,
+        })
+    }
+
     pub fn release(&self, toi: u128) {
         if toi == lct::TOI_FDT {
             return;
@@ -131,3 +227,81 @@ impl ToiAllocator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{TOIMaxLength, ToiAllocator};
+
+    #[test]
+    pub fn test_toi_allocator_wraparound() {
+        crate::tests::init();
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax16, Some(0xFFFE));
+        let tois: Vec<_> = (0..4).map(|_| ToiAllocator::allocate(&allocator)).collect();
+        let values: Vec<u128> = tois.iter().map(|toi| toi.get()).collect();
+        assert_eq!(values, vec![0xFFFE, 0xFFFF, 1, 2]);
+    }
+
+    #[test]
+    pub fn test_toi_allocator_wraparound_skip_reserved() {
+        crate::tests::init();
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax16, Some(0xFFFF));
+        let reserved = ToiAllocator::reserve(&allocator, 1).unwrap();
+        let toi = ToiAllocator::allocate(&allocator);
+        assert_eq!(toi.get(), 0xFFFF);
+        let toi = ToiAllocator::allocate(&allocator);
+        assert_eq!(toi.get(), 2);
+        assert_eq!(reserved.get(), 1);
+        assert!(ToiAllocator::reserve(&allocator, 0x10000).is_none());
+    }
+
+    #[test]
+    pub fn test_toi_allocator_range() {
+        crate::tests::init();
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax16, Some(10));
+        let first = ToiAllocator::allocate(&allocator);
+        let reserved = ToiAllocator::reserve(&allocator, 13).unwrap();
+        let tois = ToiAllocator::allocate_range(&allocator, 4).unwrap();
+        let values: Vec<u128> = tois.iter().map(|toi| toi.get()).collect();
+        assert_eq!(values, vec![11, 12, 14, 15]);
+        assert_eq!(ToiAllocator::allocate(&allocator).get(), 16);
+        assert_eq!(first.get(), 10);
+        assert_eq!(reserved.get(), 13);
+
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax16, Some(0xFFFE));
+        let tois = ToiAllocator::allocate_range(&allocator, 3).unwrap();
+        let values: Vec<u128> = tois.iter().map(|toi| toi.get()).collect();
+        assert_eq!(values, vec![0xFFFE, 0xFFFF, 1]);
+        assert!(ToiAllocator::allocate_range(&allocator, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    pub fn test_toi_allocator_range_exhausted() {
+        crate::tests::init();
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax16, Some(1));
+        let first = ToiAllocator::allocate(&allocator);
+        assert!(ToiAllocator::allocate_range(&allocator, 65535).is_err());
+        assert!(ToiAllocator::allocate_range(&allocator, 100000).is_err());
+
+        // The allocator is still usable
+        let tois = ToiAllocator::allocate_range(&allocator, 65534).unwrap();
+        assert_eq!(tois.len(), 65534);
+        assert_eq!(tois.first().unwrap().get(), 2);
+        assert_eq!(tois.last().unwrap().get(), 0xFFFF);
+        assert!(ToiAllocator::allocate_range(&allocator, 1).is_err());
+        drop(tois);
+        assert_ne!(ToiAllocator::allocate(&allocator).get(), first.get());
+        assert_eq!(first.get(), 1);
+    }
+
+    #[test]
+    pub fn test_toi_allocator_max112() {
+        crate::tests::init();
+        let allocator = ToiAllocator::new(TOIMaxLength::ToiMax112, Some(u128::MAX));
+        let toi = ToiAllocator::allocate(&allocator);
+        assert_eq!(toi.get(), (1u128 << 112) - 1);
+        let toi = ToiAllocator::allocate(&allocator);
+        assert_eq!(toi.get(), 1);
+    }
+}
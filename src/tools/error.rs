@@ -1,6 +1,12 @@
 /// Generic FLUTE Error
+///
+/// The underlying error (io, XML...) is kept as the `source()` of the FLUTE Error
+/// and can be retrieved with `std::error::Error::source()` then downcasted.
 #[derive(Debug)]
-pub struct FluteError(pub std::io::Error);
+pub struct FluteError(
+    pub std::io::Error,
+    Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+);
 
 ///
 pub type Result<T> = std::result::Result<T, FluteError>;
@@ -12,7 +18,7 @@ impl FluteError {
         E: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
     {
         log::error!("{:?}", msg);
-        FluteError(std::io::Error::new(std::io::ErrorKind::Other, msg))
+        FluteError(std::io::Error::new(std::io::ErrorKind::Other, msg), None)
     }
 
     /// Return a new FLUTE Error
@@ -21,13 +27,87 @@ impl FluteError {
         E: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
     {
         log::error!("{:?}", msg);
-        FluteError(std::io::Error::new(kind, msg))
+        FluteError(std::io::Error::new(kind, msg), None)
+    }
+
+    /// Return a new FLUTE Error of kind `WouldBlock`, see `ObjectWriter::ready()`
+    /// Not logged, the operation is retried later
+    pub fn would_block<E>(msg: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + std::fmt::Debug,
+    {
+        FluteError(
+            std::io::Error::new(std::io::ErrorKind::WouldBlock, msg),
+            None,
+        )
+    }
+
+    /// Return a new FLUTE Error caused by `source`
+    pub fn with_source<E>(kind: std::io::ErrorKind, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        log::error!("{:?}", source);
+        FluteError(
+            std::io::Error::new(kind, source.to_string()),
+            Some(Box::new(source)),
+        )
+    }
+
+    /// Kind of the error
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+}
+
+impl std::fmt::Display for FluteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for FluteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
     }
 }
 
 impl From<std::io::Error> for FluteError {
     fn from(err: std::io::Error) -> Self {
-        log::error!("{:?}", err);
-        FluteError(err)
+        FluteError::with_source(err.kind(), err)
+    }
+}
+
+impl From<quick_xml::DeError> for FluteError {
+    fn from(err: quick_xml::DeError) -> Self {
+        FluteError::with_source(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+impl From<quick_xml::SeError> for FluteError {
+    fn from(err: quick_xml::SeError) -> Self {
+        FluteError::with_source(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+impl From<std::array::TryFromSliceError> for FluteError {
+    fn from(err: std::array::TryFromSliceError) -> Self {
+        FluteError::with_source(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Log the error of a `Result` that can't be propagated to the caller
+pub(crate) trait LogError {
+    /// Log the error with a context message and drop it
+    fn log_error(self, context: &str);
+}
+
+impl<T> LogError for Result<T> {
+    fn log_error(self, context: &str) {
+        if let Err(err) = self {
+            log::error!("{}: {}", context, err);
+        }
     }
 }
@@ -0,0 +1,116 @@
+/// Hash algorithm used to compute the ETag of an object, formatted as `<algorithm>-<hex>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ETagAlgorithm {
+    #[cfg(feature = "blake3")]
+    Blake3,
+    Md5,
+}
+
+impl ETagAlgorithm {
+    /// Algorithm used to compute new ETags
+    pub fn preferred() -> Self {
+        #[cfg(feature = "blake3")]
+        return ETagAlgorithm::Blake3;
+        #[cfg(not(feature = "blake3"))]
+        return ETagAlgorithm::Md5;
+    }
+
+    /// Return the algorithm that has computed this ETag
+    /// `None` if the ETag has not been computed by this crate or if the algorithm is not supported
+    pub fn from_etag(etag: &str) -> Option<Self> {
+        let (algorithm, _) = etag.split_once('-')?;
+        match algorithm {
+            #[cfg(feature = "blake3")]
+            "blake3" => Some(ETagAlgorithm::Blake3),
+            "md5" => Some(ETagAlgorithm::Md5),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "blake3")]
+            ETagAlgorithm::Blake3 => "blake3",
+            ETagAlgorithm::Md5 => "md5",
+        }
+    }
+}
+
+/// Incremental computation of an ETag
+pub enum ETagHasher {
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+    Md5(md5::Context),
+}
+
+impl std::fmt::Debug for ETagHasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ETagHasher")
+            .field(&self.algorithm())
+            .finish()
+    }
+}
+
+impl ETagHasher {
+    pub fn new(algorithm: ETagAlgorithm) -> Self {
+        match algorithm {
+            #[cfg(feature = "blake3")]
+            ETagAlgorithm::Blake3 => ETagHasher::Blake3(Box::new(blake3::Hasher::new())),
+            ETagAlgorithm::Md5 => ETagHasher::Md5(md5::Context::new()),
+        }
+    }
+
+    pub fn algorithm(&self) -> ETagAlgorithm {
+        match self {
+            #[cfg(feature = "blake3")]
+            ETagHasher::Blake3(_) => ETagAlgorithm::Blake3,
+            ETagHasher::Md5(_) => ETagAlgorithm::Md5,
+        }
+    }
+
+    pub fn consume(&mut self, data: &[u8]) {
+        match self {
+            #[cfg(feature = "blake3")]
+            ETagHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            ETagHasher::Md5(ctx) => ctx.consume(data),
+        }
+    }
+
+    /// Return the ETag `<algorithm>-<hex>` of the consumed data
+    pub fn compute(self) -> String {
+        let algorithm = self.algorithm();
+        let hash: Vec<u8> = match self {
+            #[cfg(feature = "blake3")]
+            ETagHasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            ETagHasher::Md5(ctx) => ctx.compute().0.to_vec(),
+        };
+
+        let hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}-{}", algorithm.name(), hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ETagAlgorithm, ETagHasher};
+
+    #[test]
+    pub fn test_etag_format() {
+        crate::tests::init();
+        let mut hasher = ETagHasher::new(ETagAlgorithm::Md5);
+        hasher.consume(b"hello ");
+        hasher.consume(b"world");
+        let etag = hasher.compute();
+        assert_eq!(etag, "md5-5eb63bbbe01eeed093cb22bb8f5acdc3");
+        assert_eq!(ETagAlgorithm::from_etag(&etag), Some(ETagAlgorithm::Md5));
+        assert_eq!(ETagAlgorithm::from_etag("\"manual-etag\""), None);
+
+        let etag = ETagHasher::new(ETagAlgorithm::preferred()).compute();
+        assert_eq!(
+            ETagAlgorithm::from_etag(&etag),
+            Some(ETagAlgorithm::preferred())
+        );
+    }
+}
@@ -2,8 +2,10 @@ use self::error::FluteError;
 use self::error::Result;
 use std::time::SystemTime;
 
+pub mod crc32;
 /// Handle errors
 pub mod error;
+pub mod etag;
 pub mod ringbuffer;
 
 /// Convert the `SystemTime`into NTP.
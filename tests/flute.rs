@@ -1,4 +1,5 @@
 mod tests {
+    use base64::Engine;
     use flute::core::UDPEndpoint;
     use flute::receiver::MultiReceiverListener;
     use flute::receiver::ReceiverEndpoint;
@@ -46,6 +47,142 @@ mod tests {
         }
     }
 
+    struct TestFdtListener {
+        fdts: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl MultiReceiverListener for TestFdtListener {
+        fn on_session_open(&self, _endpoint: &ReceiverEndpoint) {}
+
+        fn on_session_closed(&self, _endpoint: &ReceiverEndpoint) {}
+
+        fn on_fdt(&self, _endpoint: &ReceiverEndpoint, fdt_xml: &str, _now: std::time::SystemTime) {
+            self.fdts.borrow_mut().push(fdt_xml.to_string());
+        }
+    }
+
+    type FdtFiles = Vec<(u128, receiver::writer::ObjectMetadata)>;
+
+    struct TestFdtFilesListener {
+        files: Rc<RefCell<Vec<FdtFiles>>>,
+    }
+
+    impl MultiReceiverListener for TestFdtFilesListener {
+        fn on_session_open(&self, _endpoint: &ReceiverEndpoint) {}
+
+        fn on_session_closed(&self, _endpoint: &ReceiverEndpoint) {}
+
+        fn on_fdt_files(
+            &self,
+            _endpoint: &ReceiverEndpoint,
+            files: &[(u128, receiver::writer::ObjectMetadata)],
+            _now: std::time::SystemTime,
+        ) {
+            self.files.borrow_mut().push(files.to_vec());
+        }
+    }
+
+    struct TestWriterBuilder {
+        inner: receiver::writer::ObjectWriterBufferBuilder,
+        diffs: RefCell<Vec<receiver::FdtDiff>>,
+        filter: fn(&receiver::writer::ObjectMetadata) -> receiver::writer::ObjectFilterDecision,
+        metadata_only: RefCell<Vec<url::Url>>,
+    }
+
+    impl TestWriterBuilder {
+        fn new() -> Self {
+            Self {
+                inner: receiver::writer::ObjectWriterBufferBuilder::new(),
+                diffs: RefCell::new(Vec::new()),
+                filter: |_| receiver::writer::ObjectFilterDecision::Receive,
+                metadata_only: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl receiver::writer::ObjectWriterBuilder for TestWriterBuilder {
+        fn new_object_writer(
+            &self,
+            endpoint: &UDPEndpoint,
+            tsi: &u64,
+            toi: &u128,
+            meta: &receiver::writer::ObjectMetadata,
+            now: std::time::SystemTime,
+        ) -> Box<dyn receiver::writer::ObjectWriter> {
+            self.inner.new_object_writer(endpoint, tsi, toi, meta, now)
+        }
+
+        fn set_cache_duration(
+            &self,
+            endpoint: &UDPEndpoint,
+            tsi: &u64,
+            toi: &u128,
+            content_location: &url::Url,
+            duration: &std::time::Duration,
+            now: std::time::SystemTime,
+        ) {
+            self.inner
+                .set_cache_duration(endpoint, tsi, toi, content_location, duration, now)
+        }
+
+        fn fdt_received(
+            &self,
+            endpoint: &UDPEndpoint,
+            tsi: &u64,
+            fdt_xml: &str,
+            expires: std::time::SystemTime,
+            meta: &receiver::writer::ObjectMetadata,
+            transfer_duration: std::time::Duration,
+            now: std::time::SystemTime,
+            ext_time: Option<std::time::SystemTime>,
+        ) {
+            self.inner.fdt_received(
+                endpoint,
+                tsi,
+                fdt_xml,
+                expires,
+                meta,
+                transfer_duration,
+                now,
+                ext_time,
+            )
+        }
+
+        fn fdt_changed(
+            &self,
+            _endpoint: &UDPEndpoint,
+            _tsi: &u64,
+            diff: &receiver::FdtDiff,
+            _now: std::time::SystemTime,
+        ) {
+            self.diffs.borrow_mut().push(diff.clone());
+        }
+
+        fn object_filter(
+            &self,
+            _endpoint: &UDPEndpoint,
+            _tsi: &u64,
+            _toi: &u128,
+            meta: &receiver::writer::ObjectMetadata,
+            _now: std::time::SystemTime,
+        ) -> receiver::writer::ObjectFilterDecision {
+            (self.filter)(meta)
+        }
+
+        fn on_object_metadata(
+            &self,
+            _endpoint: &UDPEndpoint,
+            _tsi: &u64,
+            _toi: &u128,
+            meta: &receiver::writer::ObjectMetadata,
+            _now: std::time::SystemTime,
+        ) {
+            self.metadata_only
+                .borrow_mut()
+                .push(meta.content_location.clone());
+        }
+    }
+
     pub fn init() {
         // std::env::set_var("RUST_LOG", "debug");
         env_logger::builder().is_test(true).try_init().ok();
@@ -158,6 +295,15 @@ mod tests {
     }
 
     fn run_loss(sender: &mut sender::Sender, receiver: &mut receiver::MultiReceiver) {
+        run_loss_every(sender, receiver, 8)
+    }
+
+    /// Drop one packet out of `nb_pkts`
+    fn run_loss_every(
+        sender: &mut sender::Sender,
+        receiver: &mut receiver::MultiReceiver,
+        nb_pkts: u32,
+    ) {
         let mut i = 0u32;
         let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
         loop {
@@ -168,7 +314,7 @@ mod tests {
             }
 
             if data.is_some() {
-                if (i & 7) == 0 {
+                if (i % nb_pkts) == 0 {
                     log::info!("ALC pkt {} is lost", i)
                 } else {
                     receiver
@@ -231,6 +377,35 @@ mod tests {
         }
     }
 
+    fn check_stats(
+        oti: &flute::core::Oti,
+        with_loss: bool,
+        output: &receiver::writer::ObjectWriterBufferBuilder,
+    ) {
+        let output_session = output.objects.borrow();
+        let output_object = output_session[0].as_ref().borrow();
+        let stats = output_object.stats.as_ref().unwrap();
+        log::info!("Reception stats {:?}", stats);
+
+        assert!(stats.packets_received > 0);
+        assert!(stats.first_packet_time.unwrap() <= stats.completion_time.unwrap());
+        assert!(stats.fdt_attach_time.is_some());
+
+        let is_reed_solomon = matches!(
+            oti.fec_encoding_id,
+            flute::core::FECEncodingID::ReedSolomonGF28
+                | flute::core::FECEncodingID::ReedSolomonGF28UnderSpecified
+        );
+
+        if !with_loss {
+            assert!(stats.repair_symbols_used == 0);
+            assert!(stats.blocks_decoded_with_fec == 0);
+        } else if is_reed_solomon {
+            assert!(stats.repair_symbols_used > 0);
+            assert!(stats.blocks_decoded_with_fec > 0);
+        }
+    }
+
     fn create_file_buffer(file_size: usize) -> (Vec<u8>, url::Url) {
         let input_content_location = url::Url::parse("file:///hello").unwrap();
         let mut input_file_buffer: Vec<u8> = Vec::new();
@@ -300,6 +475,7 @@ mod tests {
             target_acquisition,
             &output,
         );
+        check_stats(object_oti.unwrap_or(oti), with_loss, &output);
     }
 
     #[test]
@@ -352,6 +528,183 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_receiver_generator() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let total_length = 100003;
+        let generator: sender::ContentGenerator = std::sync::Arc::new(|offset, length| {
+            (offset..offset + length as u64)
+                .map(|i| (i % 256) as u8)
+                .collect()
+        });
+        let expected: Vec<u8> = (0..total_length).map(|i| (i % 256) as u8).collect();
+
+        let mut source = sender::ObjectDataSource::from_generator(generator.clone(), total_length);
+        let mut read_content = Vec::new();
+        std::io::Read::read_to_end(&mut source, &mut read_content).unwrap();
+        assert_eq!(read_content, expected);
+
+        let content_location = url::Url::parse("file:///generated.bin").unwrap();
+        let content_type = "application/octet-stream";
+        let obj = sender::ObjectDesc::create_from_generator(
+            generator,
+            total_length,
+            content_type,
+            &content_location,
+            sender::ObjectParams::default(),
+        )
+        .unwrap();
+        assert!(obj.content.is_none());
+        assert_eq!(
+            obj.md5.as_ref().unwrap(),
+            &base64::engine::general_purpose::STANDARD.encode(md5::compute(&expected).0)
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        check_output(&expected, &content_location, content_type, None, &output);
+    }
+
+    #[cfg(feature = "crypto")]
+    fn create_encrypted_object(
+        buffer: &[u8],
+        content_location: &url::Url,
+        cenc: flute::core::lct::Cenc,
+        cipher: std::sync::Arc<flute::core::cipher::Aes128GcmCipher>,
+    ) -> Box<sender::ObjectDesc> {
+        let mut obj = sender::ObjectDesc::create_from_buffer(
+            buffer,
+            "application/octet-stream",
+            content_location,
+            1,
+            None,
+            None,
+            None,
+            None,
+            cenc,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+        let transfer_length = obj.transfer_length;
+        obj.set_encryption(cipher).unwrap();
+        assert!(obj.transfer_length > transfer_length);
+        assert!(obj.set_encryption(create_cipher()).is_err());
+        obj
+    }
+
+    #[cfg(feature = "crypto")]
+    fn create_cipher() -> std::sync::Arc<flute::core::cipher::Aes128GcmCipher> {
+        std::sync::Arc::new(flute::core::cipher::Aes128GcmCipher::new(
+            &[0x2a; 16],
+            "https://example.com/keys/1",
+        ))
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    pub fn test_receiver_encrypted() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (buffer, content_location) = create_file_buffer(100000);
+
+        for cenc in [flute::core::lct::Cenc::Null, flute::core::lct::Cenc::Gzip] {
+            let cipher = create_cipher();
+            let obj = create_encrypted_object(&buffer, &content_location, cenc, cipher.clone());
+
+            let mut output = receiver::writer::ObjectWriterBufferBuilder::new();
+            output.add_decipher("https://example.com/keys/1", cipher);
+            let output = Rc::new(output);
+            let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+            let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+            run(&mut sender, &mut receiver);
+
+            check_output(
+                &buffer,
+                &content_location,
+                "application/octet-stream",
+                None,
+                &output,
+            );
+            let output_session = output.objects.borrow();
+            let output_object = output_session[0].as_ref().borrow();
+            assert!(!output_object.meta.md5_mismatch);
+            assert!(output_object.decryption_key_missing.is_none());
+        }
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    pub fn test_receiver_encrypted_no_key() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (buffer, content_location) = create_file_buffer(10000);
+        let obj = create_encrypted_object(
+            &buffer,
+            &content_location,
+            flute::core::lct::Cenc::Null,
+            create_cipher(),
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert!(!output_session.is_empty());
+        for output_object in output_session.iter() {
+            let output_object = output_object.as_ref().borrow();
+            assert!(!output_object.complete);
+            assert!(output_object.error);
+            assert!(output_object.data.is_empty());
+            assert_eq!(
+                output_object.decryption_key_missing.as_deref(),
+                Some("https://example.com/keys/1")
+            );
+        }
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    pub fn test_receiver_encrypted_too_large() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (buffer, content_location) = create_file_buffer(100000);
+        let cipher = create_cipher();
+        let obj = create_encrypted_object(
+            &buffer,
+            &content_location,
+            flute::core::lct::Cenc::Null,
+            cipher.clone(),
+        );
+
+        let config = receiver::Config {
+            object_max_cache_size: Some(50000),
+            ..Default::default()
+        };
+        let mut output = receiver::writer::ObjectWriterBufferBuilder::new();
+        output.add_decipher("https://example.com/keys/1", cipher);
+        let output = Rc::new(output);
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), Some(config), false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert!(!output_session.is_empty());
+        for output_object in output_session.iter() {
+            let output_object = output_object.as_ref().borrow();
+            assert!(!output_object.complete);
+            assert!(output_object.error);
+            assert!(output_object.data.is_empty());
+        }
+    }
+
     #[test]
     pub fn test_receiver_no_code_large_temp_file() {
         init();
@@ -493,6 +846,26 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_receiver_no_code_object_raptor_outband_fti() {
+        crate::tests::init();
+        let oti: flute::core::Oti = Default::default();
+        let mut oti_object: flute::core::Oti =
+            flute::core::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        oti_object.inband_fti = false;
+        test_receiver_with_oti(
+            &oti,
+            Some(&oti_object),
+            false,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            100000,
+            false,
+            None,
+        );
+    }
+
     #[test]
     pub fn test_receiver_reed_solomon_gf28_outband_fti() {
         crate::tests::init();
@@ -546,6 +919,108 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_receiver_source_block_timeout() {
+        init();
+        let oti = flute::core::Oti::new_reed_solomon_rs28(1400, 64, 10).unwrap();
+        let (obj, _) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let now = std::time::SystemTime::now();
+        let pkts: Vec<Vec<u8>> = std::iter::from_fn(|| sender.read(now)).collect();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let config = receiver::Config {
+            source_block_timeout: Some(std::time::Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), Some(config), false);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        // 30% of loss in each block, more than its 10 parity symbols can repair.
+        // The last packet is lost too, its close object flag would interrupt the reception
+        for pkt in &pkts {
+            let alc = flute::core::alc::parse_alc_pkt(pkt).unwrap();
+            if alc.lct.toi != 0 {
+                let payload_id = flute::core::alc::parse_payload_id(&alc, &oti).unwrap();
+                if payload_id.esi % 10 < 3 || alc.lct.close_object {
+                    continue;
+                }
+            }
+            receiver.push(&endpoint, pkt, now).unwrap();
+        }
+
+        let is_error = || {
+            let objects = output.objects.borrow();
+            assert_eq!(objects.len(), 1);
+            let object = objects[0].borrow();
+            assert!(!object.complete);
+            object.error
+        };
+        assert!(!is_error());
+
+        receiver.cleanup(now + std::time::Duration::from_millis(400));
+        assert!(!is_error());
+
+        // The expired block is detected by the cleanup, without waiting for another packet
+        receiver.cleanup(now + std::time::Duration::from_millis(600));
+        assert!(is_error());
+    }
+
+    fn run_receiver_raptorq_adaptive(transfer_file_size: usize) {
+        let content_type = "application/octet-stream";
+        let oti =
+            flute::core::Oti::new_raptorq_adaptive(1500, transfer_file_size as u64, 0.25).unwrap();
+        log::info!("Adaptive RaptorQ {:?}", oti);
+        assert!(
+            oti.encoding_symbol_length as usize + oti.estimated_packet_overhead_bytes() <= 1500
+        );
+
+        let (obj, input_file_buffer) = create_object(
+            transfer_file_size,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let input_content_location = obj.content_location.clone();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        // 20% loss
+        run_loss_every(&mut sender, &mut receiver, 5);
+
+        check_output(
+            &input_file_buffer,
+            &input_content_location,
+            content_type,
+            None,
+            &output,
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_raptorq_adaptive() {
+        crate::tests::init();
+        run_receiver_raptorq_adaptive(512 * 1024);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "RaptorQ is too slow without optimizations")]
+    pub fn test_receiver_raptorq_adaptive_10mb() {
+        crate::tests::init();
+        run_receiver_raptorq_adaptive(10 * 1024 * 1024);
+    }
+
     #[test]
     pub fn test_receiver_raptorq_outband_fti() {
         crate::tests::init();
@@ -681,57 +1156,3641 @@ mod tests {
         assert!(nb_error_objects == 0);
     }
 
-    #[test]
-    pub fn test_receiver_empty_file() {
-        init();
-        test_receiver_with_oti(
-            &flute::core::Oti::new_no_code(1400, 64),
-            None,
-            false,
-            flute::core::lct::Cenc::Null,
-            true,
-            None,
-            0,
-            false,
-            None,
-        );
-    }
-
-    #[test]
-    fn test_priority_queues() {
+    fn run_receiver_clock_ahead(clock_source: receiver::ClockSource) -> (usize, usize) {
+        let oti: flute::core::Oti = Default::default();
         let content_type = "application/octet-stream";
-
-        let oti = flute::core::Oti::new_no_code(1400, 64);
-
-        let (high_priority_obj, high_priority_buffer) = create_object(
-            1024,
+        let (obj, _) = create_object(
+            100000,
             content_type,
             flute::core::lct::Cenc::Null,
             true,
-            Some(&oti),
             None,
-        );
-
-        let (low_priority_obj, low_priority_buffer) = create_object(
-            1024,
-            content_type,
-            flute::core::lct::Cenc::Null,
-            true,
-            Some(&oti),
             None,
         );
-
         let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
-        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
-
-        let mut sender_config: sender::Config = Default::default();
-        sender_config.set_priority_queue(PriorityQueue::HIGHEST, PriorityQueue::new(3));
-        sender_config.set_priority_queue(PriorityQueue::LOW, PriorityQueue::new(3));
+        let receiver_config = receiver::Config {
+            clock_source,
+            ..Default::default()
+        };
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+        let mut sender = create_sender(
+            vec![obj],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            Some(sender::Config {
+                fdt_duration: std::time::Duration::from_secs(3600),
+                fdt_inband_sct: true,
+                ..Default::default()
+            }),
+        );
 
         let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
-        let mut sender = Box::new(sender::Sender::new(endpoint, 1, &oti, &sender_config));
 
-        sender
+        loop {
+            let now_sender = std::time::SystemTime::now();
+            let data = sender.read(now_sender);
+            if data.is_none() {
+                break;
+            }
+
+            // Simulate a receiver with a clock 2 hours ahead of the sender
+            let now_receiver =
+                std::time::SystemTime::now() + std::time::Duration::from_secs(2 * 3600);
+            receiver
+                .push(&endpoint, data.as_ref().unwrap(), now_receiver)
+                .unwrap();
+            receiver.cleanup(now_receiver);
+        }
+
+        let objects = output.as_ref().objects.borrow();
+        let nb_complete_objects = objects.iter().filter(|&obj| obj.borrow().complete).count();
+        let nb_error_objects = objects.iter().filter(|&obj| obj.borrow().error).count();
+        (nb_complete_objects, nb_error_objects)
+    }
+
+    #[test]
+    pub fn test_receiver_clock_ahead_sender_sct() {
+        init();
+        let (nb_complete_objects, nb_error_objects) =
+            run_receiver_clock_ahead(receiver::ClockSource::SenderSct {
+                max_skew: std::time::Duration::from_secs(3 * 3600),
+            });
+        assert!(nb_complete_objects == 1);
+        assert!(nb_error_objects == 0);
+    }
+
+    #[test]
+    pub fn test_receiver_clock_ahead_local() {
+        init();
+        let (nb_complete_objects, nb_error_objects) =
+            run_receiver_clock_ahead(receiver::ClockSource::Local);
+        assert!(nb_complete_objects == 0);
+        assert!(nb_error_objects == 0);
+    }
+
+    #[test]
+    pub fn test_receiver_fs_cache_expired() {
+        init();
+        let content_type = "application/octet-stream";
+        let (buffer, content_location) = create_file_buffer(10000);
+        let obj = sender::ObjectDesc::create_from_buffer(
+            &buffer,
+            content_type,
+            &content_location,
+            1,
+            None,
+            None,
+            Some(sender::CacheControl::Expires(
+                std::time::Duration::from_secs(1),
+            )),
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let dest = tempfile::tempdir().unwrap();
+        let output = Rc::new(receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap());
+        let mut receiver = receiver::MultiReceiver::new(output, None, false);
+
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let file_path = dest.path().join("hello");
+        assert!(file_path.is_file());
+
+        let now = std::time::SystemTime::now() + std::time::Duration::from_secs(2);
+        receiver.cleanup(now);
+        assert!(!file_path.is_file());
+    }
+
+    #[test]
+    pub fn test_receiver_fs_cache_not_expired() {
+        init();
+        let (buffer, _) = create_file_buffer(10000);
+        let objects = [
+            (
+                "file:///expires",
+                Some(sender::CacheControl::Expires(
+                    std::time::Duration::from_secs(1),
+                )),
+            ),
+            ("file:///nocache", Some(sender::CacheControl::NoCache)),
+            ("file:///default", None),
+        ]
+        .into_iter()
+        .map(|(content_location, cache_control)| {
+            sender::ObjectDesc::create_from_buffer(
+                &buffer,
+                "application/octet-stream",
+                &url::Url::parse(content_location).unwrap(),
+                1,
+                None,
+                None,
+                cache_control,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap()
+        })
+        .collect();
+
+        let dest = tempfile::tempdir().unwrap();
+        let output = Rc::new(receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap());
+        let mut receiver = receiver::MultiReceiver::new(output, None, false);
+
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(objects, &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        for name in ["expires", "nocache", "default"] {
+            assert!(dest.path().join(name).is_file());
+        }
+
+        // Only the object with an explicit Expires cache-control is evicted,
+        // even after the expiration of the FDT
+        for delay in [2, 3600 * 24] {
+            let now = std::time::SystemTime::now() + std::time::Duration::from_secs(delay);
+            receiver.cleanup(now);
+            assert!(!dest.path().join("expires").is_file());
+            assert!(dest.path().join("nocache").is_file());
+            assert!(dest.path().join("default").is_file());
+        }
+    }
+
+    #[cfg(feature = "fs-workers")]
+    #[test]
+    pub fn test_receiver_fs_worker_threads() {
+        init();
+        let queue_size = 2;
+        let mut rng = rand::thread_rng();
+        let objects: Vec<(Vec<u8>, url::Url)> = (0..100)
+            .map(|i| {
+                let mut buffer = vec![0u8; 1000 + i * 37];
+                rng.fill_bytes(&mut buffer);
+                let content_location =
+                    url::Url::parse(&format!("file:///objects/{}.bin", i)).unwrap();
+                (buffer, content_location)
+            })
+            .collect();
+
+        let sender_objects = objects
+            .iter()
+            .map(|(buffer, content_location)| {
+                sender::ObjectDesc::create_from_buffer(
+                    buffer,
+                    "application/octet-stream",
+                    content_location,
+                    1,
+                    None,
+                    None,
+                    None,
+                    None,
+                    flute::core::lct::Cenc::Null,
+                    true,
+                    None,
+                    true,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let dest = tempfile::tempdir().unwrap();
+        let mut output = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        output.set_worker_threads(2, queue_size);
+        let output = Rc::new(output);
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+
+        // Small blocks, so each object is written in several parts
+        let oti = flute::core::Oti::new_no_code(100, 4);
+        let mut sender = create_sender(sender_objects, &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        // The blocks that were waiting for the queues are written by the cleanup
+        let is_written = || {
+            objects.iter().all(|(buffer, content_location)| {
+                let path = dest.path().join(&content_location.path()[1..]);
+                std::fs::read(path)
+                    .map(|data| data.eq(buffer))
+                    .unwrap_or(false)
+            })
+        };
+        let mut now = std::time::SystemTime::now();
+        for _ in 0..50 {
+            output.flush().unwrap();
+            if is_written() {
+                break;
+            }
+            now += std::time::Duration::from_millis(200);
+            receiver.cleanup(now);
+        }
+
+        assert!(is_written());
+        log::info!("Max queued writes {}", output.max_queued_writes());
+        assert!(output.max_queued_writes() > 0);
+        assert!(output.max_queued_writes() <= queue_size);
+    }
+
+    #[cfg(feature = "fs-workers")]
+    #[test]
+    pub fn test_receiver_fs_worker_threads_fsync() {
+        use receiver::writer::{FsyncPolicy, ObjectWriterBuilder};
+        init();
+
+        let dest = tempfile::tempdir().unwrap();
+        let mut builder = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        builder.set_worker_threads(1, 1);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+
+        for (i, policy) in [FsyncPolicy::OnComplete, FsyncPolicy::PerBlock]
+            .into_iter()
+            .enumerate()
+        {
+            builder.set_fsync_policy(policy);
+            let meta = create_meta(&format!("file:///file{}.txt", i));
+            let writer = builder.new_object_writer(&endpoint, &1, &1, &meta, now);
+            writer.open(now).unwrap();
+            writer.ready(now).unwrap();
+            writer.write(b"hello", now);
+            writer.ready(now).unwrap();
+            writer.write(b" world", now);
+            writer.complete(now);
+
+            // The file is synced by the worker, flush() reports its errors
+            builder.flush().unwrap();
+            let path = dest.path().join(format!("file{}.txt", i));
+            assert_eq!(std::fs::read(path).unwrap(), b"hello world");
+        }
+    }
+
+    #[test]
+    pub fn test_sender_compute_etag() {
+        init();
+        let (buffer, content_location) = create_file_buffer(100000);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&buffer).unwrap();
+
+        let etag_buffer =
+            sender::ObjectDesc::compute_e_tag(&sender::ObjectDataSource::Buffer(buffer.clone()))
+                .unwrap();
+        let etag_file = sender::ObjectDesc::compute_e_tag(&sender::ObjectDataSource::File(
+            file.path().to_path_buf(),
+        ))
+        .unwrap();
+        assert_eq!(etag_buffer, etag_file);
+
+        let obj = sender::ObjectDesc::create_from_buffer_with_etag(
+            buffer,
+            "application/octet-stream",
+            &content_location,
+            sender::ObjectParams {
+                cenc: flute::core::lct::Cenc::Gzip,
+                md5: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(obj.file_etag, Some(etag_buffer));
+    }
+
+    #[test]
+    pub fn test_receiver_etag_check() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let receiver_config = receiver::Config {
+            enable_etag_check: true,
+            ..Default::default()
+        };
+
+        for valid in [true, false] {
+            let (buffer, content_location) = create_file_buffer(100000);
+            let mut obj = sender::ObjectDesc::create_from_buffer_with_etag(
+                buffer.clone(),
+                "application/octet-stream",
+                &content_location,
+                sender::ObjectParams {
+                    cenc: flute::core::lct::Cenc::Gzip,
+                    md5: false,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            if !valid {
+                let other_content = sender::ObjectDataSource::Buffer(vec![0; 10]);
+                obj.file_etag = Some(sender::ObjectDesc::compute_e_tag(&other_content).unwrap());
+            }
+
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver =
+                receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+            let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+            run(&mut sender, &mut receiver);
+
+            let objects = output.objects.borrow();
+            assert_eq!(objects.len(), 1);
+            let object = objects[0].borrow();
+            assert_eq!(object.complete, valid);
+            assert_eq!(object.error, !valid);
+            if valid {
+                assert!(object.data.eq(&buffer));
+            }
+        }
+    }
+
+    fn run_drop_last_block(
+        sender: &mut sender::Sender,
+        receiver: &mut receiver::MultiReceiver,
+        oti: &flute::core::Oti,
+        last_sbn: u32,
+    ) {
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        while let Some(data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != 0 {
+                let payload_id = flute::core::alc::parse_payload_id(&pkt, oti).unwrap();
+                if payload_id.sbn == last_sbn {
+                    continue;
+                }
+            }
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+
+        // Objects with missing blocks time out
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        receiver.cleanup(now);
+    }
+
+    #[test]
+    pub fn test_multireceiver_session_config() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let mut config = receiver::MultiReceiverConfig::default();
+        config.session_configs.insert(
+            1,
+            receiver::Config {
+                object_timeout: Some(std::time::Duration::from_millis(1)),
+                ..Default::default()
+            },
+        );
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new_with_config(output.clone(), config);
+        receiver.set_session_config(
+            2,
+            receiver::Config {
+                object_timeout: Some(std::time::Duration::from_secs(3600)),
+                ..Default::default()
+            },
+        );
+
+        // Push the FDT and the beginning of an object on both sessions
+        for tsi in [1, 2] {
+            let (obj, _) = create_object(
+                100000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            let mut sender =
+                sender::Sender::new(endpoint.clone(), tsi, &oti, &sender::Config::default());
+            sender.add_object(0, obj).unwrap();
+            sender.publish(std::time::SystemTime::now()).unwrap();
+            for _ in 0..5 {
+                let now = std::time::SystemTime::now();
+                let pkt = sender.read(now).unwrap();
+                receiver.push(&endpoint, &pkt, now).unwrap();
+            }
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        receiver.cleanup(std::time::SystemTime::now());
+
+        let mut in_progress: Vec<(u64, usize)> = receiver
+            .all_objects_in_progress()
+            .into_iter()
+            .map(|(_, tsi, objects)| (tsi, objects.len()))
+            .collect();
+        in_progress.sort();
+        assert_eq!(in_progress, vec![(1, 0), (2, 1)]);
+
+        assert_eq!(output.objects.borrow().len(), 2);
+
+        // Cleanup is done by push() once the interval is elapsed
+        let config = receiver::MultiReceiverConfig {
+            default_session_config: receiver::Config {
+                object_timeout: Some(std::time::Duration::from_millis(1)),
+                ..Default::default()
+            },
+            auto_cleanup_interval: Some(std::time::Duration::from_millis(5)),
+            ..Default::default()
+        };
+        let mut receiver = receiver::MultiReceiver::new_with_config(output.clone(), config);
+        let (obj, _) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = sender::Sender::new(endpoint.clone(), 1, &oti, &sender::Config::default());
+        sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        let start = std::time::SystemTime::now();
+        for _ in 0..5 {
+            let pkt = sender.read(start).unwrap();
+            receiver.push(&endpoint, &pkt, start).unwrap();
+        }
+        assert_eq!(receiver.nb_objects(), 1);
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        // FDT of another session
+        let mut sender = sender::Sender::new(endpoint.clone(), 2, &oti, &sender::Config::default());
+        sender.publish(start).unwrap();
+        let now = start + std::time::Duration::from_millis(10);
+        let pkt = sender.read(now).unwrap();
+        receiver.push(&endpoint, &pkt, now).unwrap();
+        assert_eq!(receiver.nb_objects(), 0);
+    }
+
+    #[test]
+    pub fn test_receiver_cleanup_interval() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let interval = std::time::Duration::from_millis(200);
+        let config = receiver::Config {
+            object_timeout: Some(std::time::Duration::from_millis(20)),
+            cleanup_interval: interval,
+            ..Default::default()
+        };
+
+        let create_receiver = |start: std::time::SystemTime| {
+            let (obj, _) = create_object(
+                100000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver = receiver::MultiReceiver::new(output, Some(config), false);
+            for _ in 0..5 {
+                let pkt = sender.read(start).unwrap();
+                receiver.push(&endpoint, &pkt, start).unwrap();
+            }
+            receiver.cleanup(start);
+            assert_eq!(receiver.nb_objects(), 1);
+            receiver
+        };
+
+        // The object expires at the next scan, once the interval is elapsed
+        let start = std::time::SystemTime::now();
+        let mut receiver = create_receiver(start);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        receiver.cleanup(start + std::time::Duration::from_millis(50));
+        assert_eq!(receiver.nb_objects(), 1);
+        receiver.cleanup(start + interval);
+        assert_eq!(receiver.nb_objects(), 0);
+
+        // The monotonic clock also triggers the scan
+        let start = std::time::SystemTime::now();
+        let mut receiver = create_receiver(start);
+        std::thread::sleep(interval);
+        receiver.cleanup(start);
+        assert_eq!(receiver.nb_objects(), 0);
+    }
+
+    #[test]
+    pub fn test_receiver_partial_delivery() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let transfer_length = 300000;
+        let layout = flute::core::partition::simulate(&oti, transfer_length as u64);
+        assert!(layout.nb_blocks > 1);
+        let last_sbn = (layout.nb_blocks - 1) as u32;
+        let received_length =
+            (layout.total_symbols - layout.last_block_symbols) * oti.encoding_symbol_length as u64;
+
+        let receiver_config = receiver::Config {
+            enable_partial_delivery: true,
+            object_timeout: Some(std::time::Duration::from_millis(1)),
+            ..Default::default()
+        };
+
+        // Object is kept in a buffer, with the byte ranges received
+        let (obj, buffer) = create_object(
+            transfer_length,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut output = receiver::writer::ObjectWriterBufferBuilder::new();
+        output.set_partial_delivery(true);
+        let output = Rc::new(output);
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run_drop_last_block(&mut sender, &mut receiver, &oti, last_sbn);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(!object.complete);
+        assert!(!object.error);
+        assert!(object.meta.partial);
+        let received_byte_ranges = object.received_byte_ranges.as_ref().unwrap();
+        assert_eq!(received_byte_ranges.len(), 1);
+        assert_eq!(received_byte_ranges[0], 0..received_length);
+        assert!(object.data.eq(&buffer[..received_length as usize]));
+
+        // Partial file is kept on the file system
+        let (obj, _) = create_object(
+            transfer_length,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let dest = tempfile::tempdir().unwrap();
+        let mut output = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        output.set_partial_delivery(true);
+        let mut receiver =
+            receiver::MultiReceiver::new(Rc::new(output), Some(receiver_config), false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run_drop_last_block(&mut sender, &mut receiver, &oti, last_sbn);
+
+        let file_path = dest.path().join("hello");
+        assert!(file_path.is_file());
+        assert_eq!(
+            std::fs::metadata(&file_path).unwrap().len(),
+            received_length
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_max_burst_loss() {
+        init();
+        // Single block of 29 source symbols and 20 parity symbols
+        let oti = flute::core::Oti::new_reed_solomon_rs28(1400, 60, 20).unwrap();
+        let (obj, buffer) = create_object(
+            40000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        // Burst loss of 7 consecutive symbols
+        let lost_esi = 5..12;
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        while let Some(data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != 0 {
+                let payload_id = flute::core::alc::parse_payload_id(&pkt, &oti).unwrap();
+                if lost_esi.contains(&payload_id.esi) {
+                    continue;
+                }
+            }
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(object.data.eq(&buffer));
+        assert_eq!(object.meta.max_burst_loss, 7);
+        assert_eq!(object.stats.as_ref().unwrap().max_burst_loss, 7);
+    }
+
+    fn create_meta(content_location: &str) -> receiver::writer::ObjectMetadata {
+        receiver::writer::ObjectMetadata {
+            content_location: url::Url::parse(content_location).unwrap(),
+            content_length: None,
+            transfer_length: None,
+            content_type: None,
+            cache_duration: None,
+            groups: None,
+            md5: None,
+            optel_propagator: None,
+            oti: None,
+            cenc: None,
+            content_encoding: None,
+            alternate_locations: None,
+            base_urls: None,
+            independent_unit_positions: None,
+            partial: false,
+            max_burst_loss: 0,
+            md5_mismatch: false,
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_fs_writer_file_name() {
+        use receiver::writer::ObjectWriterBuilder;
+        init();
+
+        let dest = tempfile::tempdir().unwrap();
+        let mut builder = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        let write = |builder: &receiver::writer::ObjectWriterFSBuilder, content_location: &str| {
+            let meta = create_meta(content_location);
+            let writer = builder.new_object_writer(&endpoint, &1, &1, &meta, now);
+            writer.open(now)?;
+            writer.write(b"hello", now);
+            writer.complete(now);
+            Ok::<(), flute::error::FluteError>(())
+        };
+
+        write(&builder, "file:///my%20file.txt").unwrap();
+        assert!(dest.path().join("my file.txt").is_file());
+
+        write(&builder, "file:///dossier/café.txt").unwrap();
+        assert!(dest.path().join("dossier").join("café.txt").is_file());
+
+        // Encoded path separators and control characters are rejected
+        for content_location in [
+            "file:///a%2Fb.txt",
+            "file:///a%5Cb.txt",
+            "file:///line%0Abreak.txt",
+        ] {
+            let err = write(&builder, content_location).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+
+        builder.set_preserve_directories(false);
+        assert!(write(&builder, "file:///dossier/file.txt").is_err());
+        write(&builder, "file:///file.txt").unwrap();
+        assert!(dest.path().join("file.txt").is_file());
+    }
+
+    #[test]
+    pub fn test_receiver_fs_writer_file_name_policy() {
+        use receiver::writer::{FileNamePolicy, FsyncPolicy, ObjectWriterBuilder};
+        init();
+
+        let dest = tempfile::tempdir().unwrap();
+        let mut builder = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        let write = |builder: &receiver::writer::ObjectWriterFSBuilder, content_location: &str| {
+            let meta = create_meta(content_location);
+            let writer = builder.new_object_writer(&endpoint, &1, &1, &meta, now);
+            writer.open(now)?;
+            writer.ready(now)?;
+            writer.write(b"hello", now);
+            writer.ready(now)?;
+            writer.write(b" world", now);
+            writer.complete(now);
+            Ok::<(), flute::error::FluteError>(())
+        };
+
+        // Default is FileNamePolicy::Sanitize, the query is not part of the file name
+        write(&builder, "file:///a:b?c").unwrap();
+        assert_eq!(
+            std::fs::read(dest.path().join("a_b")).unwrap(),
+            b"hello world"
+        );
+        write(&builder, "file:///aux.txt").unwrap();
+        assert!(dest.path().join("_aux.txt").is_file());
+        write(&builder, "file:///dir:1/what%3F.").unwrap();
+        assert!(dest.path().join("dir_1").join("what__").is_file());
+
+        builder.set_file_name_policy(FileNamePolicy::PercentEncode);
+        builder.set_fsync_policy(FsyncPolicy::PerBlock);
+        write(&builder, "file:///a:b").unwrap();
+        assert!(dest.path().join("a%3Ab").is_file());
+        write(&builder, "file:///Con").unwrap();
+        assert!(dest.path().join("%43on").is_file());
+
+        builder.set_file_name_policy(FileNamePolicy::Reject);
+        builder.set_fsync_policy(FsyncPolicy::OnComplete);
+        let err = write(&builder, "file:///a:b").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        write(&builder, "file:///valid.txt").unwrap();
+        assert_eq!(
+            std::fs::read(dest.path().join("valid.txt")).unwrap(),
+            b"hello world"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    pub fn test_receiver_fs_writer_windows_names() {
+        use receiver::writer::ObjectWriterBuilder;
+        init();
+
+        let dest = tempfile::tempdir().unwrap();
+        let builder = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        let write = |content_location: &str| {
+            let meta = create_meta(content_location);
+            let writer = builder.new_object_writer(&endpoint, &1, &1, &meta, now);
+            writer.open(now)?;
+            writer.write(b"hello", now);
+            writer.complete(now);
+            Ok::<(), flute::error::FluteError>(())
+        };
+
+        for (content_location, file_name) in [
+            ("file:///CON", "_CON"),
+            ("file:///nul.txt", "_nul.txt"),
+            ("file:///COM1.tar.gz", "_COM1.tar.gz"),
+            ("file:///LPT9", "_LPT9"),
+            ("file:///end.", "end_"),
+        ] {
+            write(content_location).unwrap();
+            assert!(dest.path().join(file_name).is_file(), "{}", file_name);
+        }
+
+        // Path longer than MAX_PATH
+        let long_name = "x".repeat(200);
+        let content_location = format!("file:///{}/{}", long_name, long_name);
+        write(&content_location).unwrap();
+        let path = format!(r"\\?\{}\{}\{}", dest.path().display(), long_name, long_name);
+        assert!(std::path::Path::new(&path).is_file());
+    }
+
+    #[test]
+    pub fn test_receiver_fs_writer_error_source() {
+        use receiver::writer::ObjectWriterBuilder;
+        init();
+
+        let dest = tempfile::tempdir().unwrap();
+        // A file is blocking the creation of the destination folder
+        std::fs::write(dest.path().join("folder"), b"").unwrap();
+
+        let builder = receiver::writer::ObjectWriterFSBuilder::new(dest.path()).unwrap();
+        let meta = create_meta("file:///folder/hello");
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        let writer = builder.new_object_writer(&endpoint, &1, &1, &meta, now);
+
+        let err = writer.open(now).unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        let io_err = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_err.kind(), err.kind());
+    }
+
+    #[test]
+    pub fn test_receiver_listener_on_fdt() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+
+        let fdts = Rc::new(RefCell::new(Vec::new()));
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        receiver.add_listener(TestFdtListener { fdts: fdts.clone() });
+
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let mut tois = Vec::new();
+        for _ in 0..2 {
+            let (obj, _) = create_object(
+                1024,
+                content_type,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            tois.push(sender.add_object(0, obj).unwrap());
+            sender.publish(std::time::SystemTime::now()).unwrap();
+            run(&mut sender, &mut receiver);
+        }
+
+        // First FDT is the empty FDT published when the sender is created
+        let fdts = fdts.borrow();
+        assert!(fdts.len() == 3);
+        for (fdt_xml, toi) in fdts.iter().skip(1).zip(tois) {
+            assert!(fdt_xml.contains("FDT-Instance"));
+            assert!(fdt_xml.contains(&format!("TOI=\"{}\"", toi)));
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_listener_on_fdt_files() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+
+        let mut objects = Vec::new();
+        for (i, size) in [1024usize, 2048, 4096].iter().enumerate() {
+            let (mut obj, _) = create_object(
+                *size,
+                content_type,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            obj.content_location = url::Url::parse(&format!("file:///file{}", i)).unwrap();
+            objects.push(obj);
+        }
+
+        let files = Rc::new(RefCell::new(Vec::new()));
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        receiver.add_listener(TestFdtFilesListener {
+            files: files.clone(),
+        });
+
+        let mut sender = create_sender(objects, &oti, flute::core::lct::Cenc::Null, None);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        while files.borrow().is_empty() {
+            let data = sender.read(now).unwrap();
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+
+        // Files are enumerable before any object is received
+        assert!(output.objects.borrow().is_empty());
+
+        let files = files.borrow();
+        let mut fdt_files = files[0].clone();
+        fdt_files.sort_by_key(|(toi, _)| *toi);
+        assert_eq!(fdt_files.len(), 3);
+        for (i, (toi, meta)) in fdt_files.iter().enumerate() {
+            assert_eq!(*toi, i as u128 + 1);
+            assert_eq!(meta.content_location.as_str(), format!("file:///file{}", i));
+            assert_eq!(meta.content_length, Some(1024 << i));
+            assert_eq!(meta.content_type.as_deref(), Some(content_type));
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_session_directory() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        receiver.add_listener(TestMultiReceiverObserver::new());
+        assert!(receiver.session_directory().is_empty());
+
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        for i in 0..2 {
+            let (obj, _) = create_object(
+                1024 << i,
+                content_type,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            sender.add_object(0, obj).unwrap();
+        }
+        sender.set_complete();
+        let start = std::time::SystemTime::now();
+        sender.publish(start).unwrap();
+
+        while output
+            .objects
+            .borrow()
+            .iter()
+            .filter(|obj| obj.borrow().complete)
+            .count()
+            < 2
+        {
+            let now = std::time::SystemTime::now();
+            let pkt = sender.read(now).unwrap();
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+
+        let sessions = receiver.session_directory();
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.endpoint, endpoint);
+        assert_eq!(session.tsi, 1);
+        assert!(session.first_seen >= start);
+        assert!(session.last_activity >= session.first_seen);
+        assert!(session.latest_fdt_instance_id.is_some());
+        assert!(session.fdt_expires.unwrap() > start);
+        assert_eq!(session.announced_files, 2);
+        assert_eq!(session.announced_bytes, 1024 + 2048);
+        assert_eq!(session.objects_completed, 2);
+        assert!(session.complete);
+
+        assert!(!receiver.remove_session(&endpoint, 2));
+        assert!(receiver.remove_session(&endpoint, 1));
+        assert!(receiver.session_directory().is_empty());
+        assert!(!receiver.remove_session(&endpoint, 1));
+    }
+
+    #[test]
+    pub fn test_receiver_validate_writer() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let output = Rc::new(receiver::writer::ObjectWriterValidateBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+
+        for name in ["valid", "corrupted"] {
+            let obj = sender::ObjectDesc::create_from_buffer(
+                &vec![0xAAu8; 10000],
+                "application/octet-stream",
+                &url::Url::parse(&format!("file:///{}", name)).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap();
+            sender.add_object(0, obj).unwrap();
+        }
+        let corrupted_toi = 2;
+
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+        while let Some(mut data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi == corrupted_toi {
+                // Corrupt the payload of the packets of the second object
+                *data.last_mut().unwrap() ^= 0xFF;
+            }
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+
+        let mut results = output.take_results();
+        results.sort_by(|a, b| a.content_location.cmp(&b.content_location));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].content_location.path(), "/corrupted");
+        assert!(!results[0].md5_ok);
+        assert_eq!(results[1].content_location.path(), "/valid");
+        assert!(results[1].md5_ok);
+        assert!(results[1].length_ok);
+        assert!(output.results().is_empty());
+    }
+
+    #[test]
+    pub fn test_receiver_quarantine_errored_toi() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let (obj, _) = create_object(
+            10000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        let now = std::time::SystemTime::now();
+        let mut packets = Vec::new();
+        while let Some(mut data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != 0 {
+                // Corrupt the payload so the MD5 of the object does not match
+                *data.last_mut().unwrap() ^= 0xFF;
+            }
+            packets.push(data);
+        }
+
+        let quarantine = std::time::Duration::from_secs(60);
+        let config = receiver::Config {
+            max_objects_error: 0,
+            quarantine_errored_toi: quarantine,
+            ..Default::default()
+        };
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, output.clone(), Some(config));
+
+        let mut push_carousel = |now: std::time::SystemTime| {
+            for data in &packets {
+                receiver.push_data(data, now).unwrap();
+            }
+            receiver.cleanup(now);
+        };
+
+        push_carousel(now);
+        assert_eq!(output.objects.borrow().len(), 1);
+        assert!(output.objects.borrow()[0].borrow().error);
+
+        // Packets of the object are discarded during the quarantine
+        push_carousel(now + std::time::Duration::from_secs(10));
+        push_carousel(now + std::time::Duration::from_secs(50));
+        assert_eq!(output.objects.borrow().len(), 1);
+
+        // Reception restarts once the quarantine is over
+        push_carousel(now + quarantine + std::time::Duration::from_secs(1));
+        assert_eq!(output.objects.borrow().len(), 2);
+        assert!(output.objects.borrow()[1].borrow().error);
+    }
+
+    #[test]
+    pub fn test_receiver_objects_in_group() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+
+        let mut tois = Vec::new();
+        for group in ["GroupA", "GroupB"] {
+            let obj = sender::ObjectDesc::create_from_buffer(
+                &vec![0u8; 1024],
+                "text/plain",
+                &url::Url::parse(&format!("file:///{}", group)).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                Some(vec![group.to_owned()]),
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap();
+            tois.push(sender.add_object(0, obj).unwrap());
+        }
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        while let Some(data) = sender.read(now) {
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+        assert_eq!(output.objects.borrow().len(), 2);
+
+        let session = receiver::ReceiverEndpoint { endpoint, tsi: 1 };
+        assert_eq!(
+            receiver.objects_in_group("GroupA"),
+            vec![(session.clone(), tois[0])]
+        );
+        assert_eq!(
+            receiver.objects_in_group("GroupB"),
+            vec![(session, tois[1])]
+        );
+        assert!(receiver.objects_in_group("GroupC").is_empty());
+    }
+
+    #[test]
+    pub fn test_receiver_once_callbacks() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let (session_tx, session_rx) = std::sync::mpsc::channel();
+        let (object_tx, object_rx) = std::sync::mpsc::channel();
+        let (closed_tx, closed_rx) = std::sync::mpsc::channel();
+        receiver.on_next_session_open(move |endpoint| {
+            session_tx.send(endpoint.clone()).unwrap();
+        });
+        receiver.on_next_session_closed(1, move |endpoint| {
+            closed_tx.send(endpoint.clone()).unwrap();
+        });
+
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let mut tois = Vec::new();
+        for _ in 0..2 {
+            let (obj, _) = create_object(
+                1024,
+                content_type,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            tois.push(sender.add_object(0, obj).unwrap());
+        }
+        receiver.on_next_object_completed(Some(tois[1]), move |event| {
+            object_tx.send(event.toi).unwrap();
+        });
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        run(&mut sender, &mut receiver);
+
+        let endpoint = session_rx.try_recv().unwrap();
+        assert_eq!(endpoint.tsi, 1);
+        assert_eq!(endpoint.endpoint.port, 5000);
+        assert!(session_rx.try_recv().is_err());
+        assert_eq!(object_rx.try_recv().unwrap(), tois[1]);
+        assert!(object_rx.try_recv().is_err());
+        assert!(closed_rx.try_recv().is_err());
+
+        drop(receiver);
+        assert_eq!(closed_rx.try_recv().unwrap(), endpoint);
+    }
+
+    #[test]
+    pub fn test_fdt_default_content_type() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let sender_config = sender::Config {
+            fdt_content_type: Some("video/mp4".to_owned()),
+            ..Default::default()
+        };
+
+        let fdts = Rc::new(RefCell::new(Vec::new()));
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        receiver.add_listener(TestFdtListener { fdts: fdts.clone() });
+
+        let mut sender = create_sender(
+            Vec::new(),
+            &oti,
+            flute::core::lct::Cenc::Null,
+            Some(sender_config),
+        );
+        for _ in 0..10 {
+            let (obj, _) = create_object(
+                1024,
+                "video/mp4",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            sender.add_object(0, obj).unwrap();
+        }
+        let (obj, _) = create_object(
+            1024,
+            "text/plain",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        run(&mut sender, &mut receiver);
+
+        // Only the FDT-Instance and the text file define a Content-Type
+        let fdts = fdts.borrow();
+        let fdt_xml = fdts.last().unwrap();
+        assert_eq!(fdt_xml.matches("Content-Type=").count(), 2);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 11);
+        let nb_video = objects
+            .iter()
+            .filter(|obj| obj.borrow().meta.content_type.as_deref() == Some("video/mp4"))
+            .count();
+        let nb_text = objects
+            .iter()
+            .filter(|obj| obj.borrow().meta.content_type.as_deref() == Some("text/plain"))
+            .count();
+        assert_eq!(nb_video, 10);
+        assert_eq!(nb_text, 1);
+    }
+
+    #[test]
+    pub fn test_content_location_normalization() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let locations = [
+            "HTTP://Example.COM/Data/File.bin",
+            "http://Bücher.example/file",
+            "http://example.com/a%2fb",
+            "http://example.com",
+            "http://example.com/%7euser/caf%c3%a9",
+            "http://example.com/a/./b/../c",
+            "file:///Data/File%20Name.bin",
+        ];
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        for location in locations {
+            let obj = sender::ObjectDesc::create_from_buffer(
+                b"content",
+                "text/plain",
+                &url::Url::parse(location).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap();
+            sender.add_object(0, obj).unwrap();
+        }
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        run(&mut sender, &mut receiver);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), locations.len());
+        let received: std::collections::HashSet<String> = objects
+            .iter()
+            .map(|obj| obj.borrow().meta.content_location.to_string())
+            .collect();
+        for location in locations {
+            let normalized = flute::core::content_location::normalize(location).unwrap();
+            assert!(
+                received.contains(&normalized),
+                "{} not received",
+                normalized
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_packet_capture() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let receiver_config = receiver::Config {
+            packet_capture_size: 4,
+            ..Default::default()
+        };
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+        let (obj, _) = create_object(
+            10000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let now = std::time::SystemTime::now();
+        for _ in 0..6 {
+            let pkt = sender.read(now).unwrap();
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+        assert!(receiver.last_packet_error().is_none());
+
+        let malformed = vec![0x10, 0x00, 0x01];
+        assert!(receiver.push(&endpoint, &malformed, now).is_err());
+
+        let packets = receiver.last_packets();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets.last().unwrap(), &malformed);
+        assert!(receiver.last_packet_error().is_some());
+    }
+
+    /// Read the packets of a layered session, grouped by layer
+    fn read_layers(sender: &mut sender::Sender) -> Vec<Vec<Vec<u8>>> {
+        let mut layers = vec![Vec::new(); sender.nb_layers()];
+        loop {
+            let now = std::time::SystemTime::now();
+            match sender.read_layered(now) {
+                Some((layer, data)) => layers[layer].push(data),
+                None if sender.get_objects_in_fdt().is_empty() => break,
+                None => {}
+            }
+        }
+        layers
+    }
+
+    /// Push the packets of the layers in parallel, return the number of layer 0 packets pushed until the object is completed
+    fn receive_layers(layers: &[&[Vec<u8>]]) -> Option<usize> {
+        let endpoints: Vec<UDPEndpoint> = (0..layers.len())
+            .map(|layer| UDPEndpoint::new(None, format!("224.0.0.{}", layer + 1), 5000))
+            .collect();
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        for alias in endpoints.iter().skip(1) {
+            receiver.add_endpoint_alias(alias.clone(), endpoints[0].clone());
+        }
+
+        let now = std::time::SystemTime::now();
+        let nb_pkts = layers.iter().map(|pkts| pkts.len()).max().unwrap();
+        for index in 0..nb_pkts {
+            for (layer, pkts) in layers.iter().enumerate() {
+                if let Some(pkt) = pkts.get(index) {
+                    receiver.push(&endpoints[layer], pkt, now).unwrap();
+                }
+            }
+
+            let objects = output.objects.borrow();
+            if objects.iter().any(|obj| obj.borrow().complete) {
+                return Some(index + 1);
+            }
+        }
+        None
+    }
+
+    #[test]
+    pub fn test_sender_layered() {
+        init();
+        let oti = flute::core::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        let sender_config = sender::Config {
+            layers: vec![
+                UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000),
+                UDPEndpoint::new(None, "224.0.0.2".to_owned(), 5000),
+            ],
+            layer_policy: sender::LayerPolicy::RepairOnUpperLayers,
+            ..Default::default()
+        };
+        let (obj, _) = create_object(
+            20 * 1400,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+
+        let mut sender = create_sender(
+            vec![obj],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            Some(sender_config),
+        );
+        assert_eq!(sender.nb_layers(), 2);
+        assert!(sender.set_layer_cci(2, 1).is_err());
+        sender.set_layer_cci(1, 0x10).unwrap();
+        let layers = read_layers(&mut sender);
+        assert!(!layers[1].is_empty());
+        for pkt in &layers[1] {
+            let alc = flute::core::alc::parse_alc_pkt(pkt).unwrap();
+            assert_eq!(alc.lct.cci, 0x10);
+        }
+
+        // Layer 0 alone carries all the source symbols
+        let layer0_only = receive_layers(&[&layers[0]]).unwrap();
+        // Repair symbols of layer 1 complete the object before the end of layer 0
+        let both_layers = receive_layers(&[&layers[0], &layers[1]]).unwrap();
+        assert!(both_layers < layer0_only);
+    }
+
+    #[test]
+    pub fn test_receiver_object_write_progress() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 10);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let (obj, _) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let toi = sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, output.clone(), None);
+        let now = std::time::SystemTime::now();
+        let mut progress = Vec::new();
+        let mut nb_pkts = 0;
+        while let Some(pkt) = sender.read(now) {
+            receiver.push_data(&pkt, now).unwrap();
+            nb_pkts += 1;
+            if nb_pkts % 10 == 0 {
+                if let Some(value) = receiver.object_write_progress(toi) {
+                    progress.push(value);
+                }
+            }
+        }
+
+        assert!(progress.len() > 2);
+        assert!(progress.windows(2).all(|w| w[0] <= w[1]));
+        assert!(progress.first() < progress.last());
+        assert!(progress.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert!(output.objects.borrow()[0].borrow().complete);
+        assert!(receiver.object_write_progress(toi).is_none());
+    }
+
+    #[test]
+    pub fn test_receiver_push_batch() {
+        init();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let oti: flute::core::Oti = Default::default();
+        let (obj, buffer) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let now = std::time::SystemTime::now();
+        let mut packets = Vec::new();
+        while let Some(pkt) = sender.read(now) {
+            packets.push(pkt);
+        }
+
+        // Invalid packet in the middle of a batch
+        let invalid_index = 10;
+        packets.insert(invalid_index, vec![0u8; 4]);
+        let packets: Vec<&[u8]> = packets.iter().map(|pkt| pkt.as_slice()).collect();
+
+        let check_results = |results: &[flute::error::Result<()>]| {
+            assert_eq!(results.len(), packets.len());
+            for (index, ret) in results.iter().enumerate() {
+                assert_eq!(ret.is_err(), index == invalid_index);
+            }
+        };
+
+        let check_output = |output: &receiver::writer::ObjectWriterBufferBuilder| {
+            let objects = output.objects.borrow();
+            assert_eq!(objects.len(), 1);
+            let object = objects[0].borrow();
+            assert!(object.complete);
+            assert!(object.data.eq(&buffer));
+        };
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let results: Vec<_> = packets
+            .chunks(64)
+            .flat_map(|batch| receiver.push_batch(&endpoint, batch, now))
+            .collect();
+        check_results(&results);
+        check_output(&output);
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, output.clone(), None);
+        let results: Vec<_> = packets
+            .chunks(64)
+            .flat_map(|batch| receiver.push_data_batch(batch, now))
+            .collect();
+        check_results(&results);
+        check_output(&output);
+    }
+
+    #[test]
+    pub fn test_receiver_buffer_retention() {
+        init();
+        let nb_objects = 1000;
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        for i in 0..nb_objects {
+            let obj = sender::ObjectDesc::create_from_buffer(
+                &[i as u8; 100],
+                "application/octet-stream",
+                &url::Url::parse(&format!("file:///object{}", i)).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap();
+            sender.add_object(0, obj).unwrap();
+        }
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let evicted_cb = evicted.clone();
+        let output = Rc::new(
+            receiver::writer::ObjectWriterBufferBuilder::new()
+                .with_max_objects(10)
+                .on_evict(move |obj| {
+                    assert!(obj.complete);
+                    assert_eq!(obj.data.len(), 100);
+                    evicted_cb
+                        .borrow_mut()
+                        .push(obj.meta.content_location.to_string());
+                }),
+        );
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let now = std::time::SystemTime::now();
+        while let Some(pkt) = sender.read(now) {
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+
+        let evicted = evicted.borrow();
+        assert_eq!(output.objects.borrow().len(), 10);
+        assert_eq!(evicted.len(), nb_objects - 10);
+        assert_eq!(
+            evicted.iter().collect::<HashSet<_>>().len(),
+            nb_objects - 10
+        );
+
+        // Remaining objects are moved out of the builder
+        let completed = output.take_completed();
+        assert_eq!(completed.len(), 10);
+        assert!(completed
+            .iter()
+            .all(|obj| obj.complete && obj.end_time.is_some()));
+        assert!(completed
+            .iter()
+            .all(|obj| !evicted.contains(&obj.meta.content_location.to_string())));
+        assert!(output.objects.borrow().is_empty());
+    }
+
+    #[test]
+    pub fn test_sender_pause_resume() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let target = std::time::Duration::from_secs(10);
+        let pause = std::time::Duration::from_secs(120);
+        let (obj, buffer) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            Some(TargetAcquisition::WithinDuration(target)),
+        );
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let start = std::time::SystemTime::now();
+        sender.add_object(0, obj).unwrap();
+        sender.publish(start).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, output.clone(), None);
+
+        let mut now = start;
+        let mut paused = false;
+        let mut pkt_times = Vec::new();
+        let mut nb_pkts_before_pause = 0;
+        while now < start + std::time::Duration::from_secs(300) {
+            if !paused && now >= start + target / 2 {
+                // Simulate a maintenance window of 2 minutes in the middle of the transfer
+                sender.pause(now);
+                assert!(sender.is_paused());
+                assert!(sender.read(now + pause / 2).is_none());
+                now += pause;
+                sender.resume(now).unwrap();
+                paused = true;
+                nb_pkts_before_pause = pkt_times.len();
+            }
+
+            while let Some(data) = sender.read(now) {
+                let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+                if pkt.lct.toi != 0 {
+                    pkt_times.push(now);
+                }
+                receiver.push_data(&data, now).unwrap();
+            }
+
+            if output
+                .objects
+                .borrow()
+                .iter()
+                .any(|obj| obj.borrow().complete)
+            {
+                break;
+            }
+            now += std::time::Duration::from_millis(10);
+        }
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        assert!(objects[0].borrow().complete);
+        assert_eq!(objects[0].borrow().data, buffer);
+
+        // Transfer is paced before and after the pause
+        assert!(nb_pkts_before_pause > 0);
+        assert!(nb_pkts_before_pause < pkt_times.len());
+        let transfer_duration = pkt_times
+            .last()
+            .unwrap()
+            .duration_since(*pkt_times.first().unwrap())
+            .unwrap()
+            - pause;
+        log::info!(
+            "Transfer duration excluding the pause {:?}",
+            transfer_duration
+        );
+        assert!(transfer_duration > target.mul_f64(0.9));
+        assert!(transfer_duration <= target);
+    }
+
+    #[test]
+    pub fn test_receiver_objects_in_progress() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 10);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let (obj, _) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let content_location = obj.content_location.to_string();
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let toi = sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let now = std::time::SystemTime::now();
+        let mut pkts = Vec::new();
+        while let Some(pkt) = sender.read(now) {
+            pkts.push(pkt);
+        }
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, output.clone(), None);
+        let mut multi_receiver = receiver::MultiReceiver::new(
+            Rc::new(receiver::writer::ObjectWriterBufferBuilder::new()),
+            None,
+            false,
+        );
+        assert!(receiver.objects_in_progress().is_empty());
+        for pkt in &pkts[..pkts.len() / 2] {
+            receiver.push_data(pkt, now).unwrap();
+            multi_receiver.push(&endpoint, pkt, now).unwrap();
+        }
+
+        let objects = receiver.objects_in_progress();
+        assert_eq!(objects.len(), 1);
+        let (object_toi, info) = &objects[0];
+        assert_eq!(*object_toi, toi);
+        assert_eq!(
+            info.content_location.as_deref(),
+            Some(content_location.as_str())
+        );
+        assert_eq!(info.transfer_length, Some(100000));
+        assert_eq!(
+            info.content_type.as_deref(),
+            Some("application/octet-stream")
+        );
+        let written = output.objects.borrow()[0].borrow().data.len();
+        assert!(written > 0);
+        assert_eq!(info.write_progress, written as f64 / 100000.0);
+        assert_eq!(
+            Some(info.write_progress),
+            receiver.object_write_progress(toi)
+        );
+        assert_eq!(info.blocks_total, 8);
+        assert!(info.blocks_done > 0 && info.blocks_done < info.blocks_total);
+
+        let sessions = multi_receiver.all_objects_in_progress();
+        assert_eq!(sessions.len(), 1);
+        let (session, tsi, objects) = &sessions[0];
+        assert_eq!(session.endpoint, endpoint);
+        assert_eq!(*tsi, 1);
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].0, toi);
+        assert_eq!(objects[0].1.write_progress, info.write_progress);
+
+        for pkt in &pkts[pkts.len() / 2..] {
+            receiver.push_data(pkt, now).unwrap();
+        }
+        assert!(output.objects.borrow()[0].borrow().complete);
+        assert!(receiver.objects_in_progress().is_empty());
+    }
+
+    struct TestSenderEvents {
+        events: std::sync::Mutex<Vec<sender::Event>>,
+    }
+
+    impl sender::Subscriber for TestSenderEvents {
+        fn on_sender_event(&self, evt: &sender::Event, _now: std::time::SystemTime) {
+            self.events.lock().unwrap().push(evt.clone());
+        }
+    }
+
+    #[test]
+    pub fn test_sender_pad_last_symbol() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        for pad_last_symbol in [false, true] {
+            let (obj, buffer) = create_object(
+                10000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            let config = sender::Config {
+                pad_last_symbol,
+                ..Default::default()
+            };
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+            let mut sender =
+                create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, Some(config));
+
+            let mut payload_sizes = Vec::new();
+            let now = std::time::SystemTime::now();
+            while let Some(data) = sender.read(now) {
+                let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+                if pkt.lct.toi != 0 {
+                    payload_sizes.push(pkt.data.len() - pkt.data_payload_offset);
+                }
+                receiver.push(&endpoint, &data, now).unwrap();
+            }
+
+            let objects = output.objects.borrow();
+            assert_eq!(objects.len(), 1);
+            let object = objects[0].borrow();
+            assert!(object.complete);
+            assert!(!object.error);
+            assert!(object.data.eq(&buffer));
+
+            assert_eq!(payload_sizes.len(), 8);
+            match pad_last_symbol {
+                true => assert!(payload_sizes.iter().all(|size| *size == 1400)),
+                false => assert_eq!(payload_sizes.last(), Some(&(10000 % 1400))),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_sender_queue_backpressure() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let config = sender::Config {
+            max_queue_depth_per_priority: Some(std::collections::HashMap::from([(0, 3)])),
+            ..Default::default()
+        };
+        let create_obj = || {
+            create_object(
+                5000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            )
+            .0
+        };
+        let objects = (0..3).map(|_| create_obj()).collect();
+        let mut sender = create_sender(objects, &oti, flute::core::lct::Cenc::Null, Some(config));
+
+        let state = sender.priority_queue_backpressure();
+        assert_eq!(
+            state.get(&0),
+            Some(&sender::BackpressureState {
+                queue_depth: 3,
+                max_depth: Some(3),
+                is_blocked: true,
+            })
+        );
+
+        let err = sender.add_object(0, create_obj()).unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::WouldBlock);
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let state = sender.priority_queue_backpressure();
+        assert_eq!(state.get(&0).unwrap().queue_depth, 0);
+        assert!(!state.get(&0).unwrap().is_blocked);
+        sender.add_object(0, create_obj()).unwrap();
+    }
+
+    #[test]
+    pub fn test_sender_file_read_error() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+
+        // File is truncated after being added to the sender, reading the second block fails
+        let (buffer, content_location) = create_file_buffer(200000);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&buffer).unwrap();
+        let file_obj = sender::ObjectDesc::create_from_file(
+            file.path(),
+            Some(&content_location),
+            content_type,
+            false,
+            1,
+            None,
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            false,
+        )
+        .unwrap();
+        let (buffer_obj, expected) = create_object(
+            5000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let subscriber = std::sync::Arc::new(TestSenderEvents {
+            events: std::sync::Mutex::new(Vec::new()),
+        });
+        sender.subscribe(subscriber.clone());
+        let file_toi = sender.add_object(0, file_obj).unwrap();
+        let buffer_toi = sender.add_object(0, buffer_obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        file.as_file().set_len(100000).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let events = subscriber.events.lock().unwrap();
+        assert!(events.iter().any(|evt| matches!(
+            evt,
+            sender::Event::ObjectTransferFailed { toi, .. } if *toi == file_toi
+        )));
+        assert!(!events.iter().any(|evt| matches!(
+            evt,
+            sender::Event::ObjectTransferFailed { toi, .. } if *toi == buffer_toi
+        )));
+        assert!(!sender.is_added(file_toi));
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 2);
+        let file_output = objects
+            .iter()
+            .find(|obj| obj.borrow().meta.content_length == Some(200000))
+            .unwrap()
+            .borrow();
+        assert!(file_output.error);
+        assert!(!file_output.complete);
+
+        let buffer_output = objects
+            .iter()
+            .find(|obj| obj.borrow().meta.content_length == Some(5000))
+            .unwrap()
+            .borrow();
+        assert!(buffer_output.complete);
+        assert!(buffer_output.data == expected);
+    }
+
+    #[test]
+    pub fn test_sender_replace_object_content() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let versions: Vec<Vec<u8>> = (0..3).map(|_| create_file_buffer(10000).0).collect();
+        let content_location = url::Url::parse("file:///radar").unwrap();
+        let obj = sender::ObjectDesc::create_from_buffer(
+            &versions[0],
+            content_type,
+            &content_location,
+            1,
+            Some(std::time::Duration::from_secs(3600)),
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let receiver_config = receiver::Config {
+            object_receive_once: false,
+            ..Default::default()
+        };
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let toi = *sender.get_objects_in_fdt().keys().next().unwrap();
+
+        for (index, version) in versions.iter().enumerate() {
+            let now = std::time::SystemTime::now();
+            if index > 0 {
+                sender
+                    .replace_object_content(
+                        toi,
+                        sender::ObjectDataSource::Buffer(version.clone()),
+                        None,
+                        now,
+                    )
+                    .unwrap();
+            }
+
+            while let Some(data) = sender.read(now) {
+                receiver.push(&endpoint, &data, now).unwrap();
+            }
+            assert!(sender.is_added(toi));
+        }
+
+        let objects = output.objects.borrow();
+        assert!(objects.len() == versions.len());
+        for (object, version) in objects.iter().zip(versions.iter()) {
+            let object = object.borrow();
+            let md5 = base64::engine::general_purpose::STANDARD.encode(md5::compute(version).0);
+            assert!(object.complete);
+            assert!(!object.error);
+            assert!(object.data.eq(version));
+            assert!(object.meta.content_location.eq(&content_location));
+            assert!(object.meta.md5.as_ref().unwrap().eq(&md5));
+        }
+    }
+
+    fn run_toi_reuse(
+        detect_toi_reuse: bool,
+    ) -> (
+        Vec<Vec<u8>>,
+        Rc<receiver::writer::ObjectWriterBufferBuilder>,
+    ) {
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let versions: Vec<Vec<u8>> = (0..2).map(|_| create_file_buffer(10000).0).collect();
+        let content_location = url::Url::parse("file:///radar").unwrap();
+        let obj = sender::ObjectDesc::create_from_buffer(
+            &versions[0],
+            content_type,
+            &content_location,
+            1,
+            Some(std::time::Duration::from_secs(3600)),
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let receiver_config = receiver::Config {
+            detect_toi_reuse,
+            ..Default::default()
+        };
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let toi = *sender.get_objects_in_fdt().keys().next().unwrap();
+
+        for (index, version) in versions.iter().enumerate() {
+            let now = std::time::SystemTime::now();
+            if index > 0 {
+                sender
+                    .replace_object_content(
+                        toi,
+                        sender::ObjectDataSource::Buffer(version.clone()),
+                        None,
+                        now,
+                    )
+                    .unwrap();
+            }
+
+            // Packets of the object are received in reverse order, the first packet received is not ESI 0
+            let mut object_pkts = Vec::new();
+            while let Some(data) = sender.read(now) {
+                let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+                match pkt.lct.toi {
+                    0 => receiver.push(&endpoint, &data, now).unwrap(),
+                    _ => object_pkts.push(data),
+                }
+            }
+            for data in object_pkts.iter().rev() {
+                receiver.push(&endpoint, data, now).unwrap();
+            }
+        }
+
+        (versions, output)
+    }
+
+    #[test]
+    pub fn test_receiver_detect_toi_reuse() {
+        init();
+        let (versions, output) = run_toi_reuse(true);
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), versions.len());
+        for (object, version) in objects.iter().zip(versions.iter()) {
+            let object = object.borrow();
+            assert!(object.complete);
+            assert!(!object.error);
+            assert!(object.data.eq(version));
+        }
+
+        let (_, output) = run_toi_reuse(false);
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+    }
+
+    #[test]
+    pub fn test_sender_set_oti_for_object() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+        let (obj, buffer) = create_object(
+            100000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let toi = *sender.get_objects_in_fdt().keys().next().unwrap();
+        let now = std::time::SystemTime::now();
+
+        let rs28 = flute::core::Oti::new_reed_solomon_rs28(1400, 60, 4).unwrap();
+        assert!(!sender
+            .set_oti_for_object(toi + 1, rs28.clone(), now)
+            .unwrap());
+        assert!(sender.set_oti_for_object(toi, rs28.clone(), now).unwrap());
+
+        // OTI can not be changed once the object is being transferred
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        while let Some(data) = sender.read(now) {
+            receiver.push(&endpoint, &data, now).unwrap();
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi == toi {
+                break;
+            }
+        }
+        let err = sender.set_oti_for_object(toi, oti.clone(), now);
+        assert!(err.is_err());
+        assert_eq!(
+            err.err().unwrap().0.kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        run(&mut sender, &mut receiver);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(!object.error);
+        assert!(object.data.eq(&buffer));
+        let received_oti = object.meta.oti.as_ref().unwrap();
+        assert_eq!(
+            received_oti.fec_encoding_id,
+            flute::core::FECEncodingID::ReedSolomonGF28
+        );
+        assert_eq!(received_oti.maximum_source_block_length, 60);
+    }
+
+    #[test]
+    pub fn test_receiver_session_stats() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti: flute::core::Oti = Default::default();
+        let file_size = 100000;
+        let (obj, _) = create_object(
+            file_size,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        assert!(receiver.get_session_stats(&endpoint, 2).is_none());
+        let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+        log::info!("Session stats {:?}", stats);
+        assert!(stats.objects_completed == 1);
+        assert!(stats.objects_in_progress == 0);
+        assert!(stats.objects_error == 0);
+        assert!(stats.fdt_instances_received == 1);
+        assert!(stats.current_fdt_id.is_some());
+        assert!(stats.total_bytes_received > file_size as u64);
+
+        let all_stats = receiver.all_session_stats();
+        assert!(all_stats.len() == 1);
+    }
+
+    #[test]
+    pub fn test_receiver_inject_close_session() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        for _ in 0..2 {
+            let (obj, _) = create_object(
+                100000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            sender.add_object(0, obj).unwrap();
+        }
+        let now = std::time::SystemTime::now();
+        sender.publish(now).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        // Checks on drop that on_session_closed() is called for each session opened
+        receiver.add_listener(TestMultiReceiverObserver::new());
+        let (closed_tx, closed_rx) = std::sync::mpsc::channel();
+        receiver.on_next_session_closed(1, move |endpoint| {
+            closed_tx.send(endpoint.clone()).unwrap();
+        });
+
+        // The transfer stops in the middle of the objects
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        for _ in 0..60 {
+            let pkt = sender.read(now).unwrap();
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+        assert_eq!(receiver.nb_objects(), 2);
+        assert!(closed_rx.try_recv().is_err());
+
+        assert!(receiver.inject_close_session(&endpoint, 1, now));
+        assert_eq!(closed_rx.try_recv().unwrap().tsi, 1);
+        assert_eq!(receiver.nb_objects(), 0);
+        assert!(receiver.get_session_stats(&endpoint, 1).is_none());
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 2);
+        for object in objects.iter() {
+            let object = object.borrow();
+            assert!(!object.complete);
+            assert!(object.error);
+            assert!(object.end_time.is_some());
+        }
+
+        // Unknown session
+        assert!(!receiver.inject_close_session(&endpoint, 1, now));
+    }
+
+    #[test]
+    pub fn test_receiver_fdt_timeout() {
+        init();
+        // Small symbols, the FDT is split across several packets
+        let oti = flute::core::Oti::new_no_code(64, 64);
+        let (obj, _) = create_object(
+            1000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        let fdt_timeout = std::time::Duration::from_secs(2);
+        let config = receiver::Config {
+            fdt_timeout: Some(fdt_timeout),
+            cleanup_interval: std::time::Duration::ZERO,
+            ..Default::default()
+        };
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output, Some(config), false);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        // Only the first packet of the FDT is received
+        let now = std::time::SystemTime::now();
+        let pkt = sender.read(now).unwrap();
+        let alc = flute::core::alc::parse_alc_pkt(&pkt).unwrap();
+        assert_eq!(alc.lct.toi, 0);
+        assert!(!alc.lct.close_object);
+        receiver.push(&endpoint, &pkt, now).unwrap();
+
+        let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+        assert_eq!(stats.fdt_instances_in_progress, 1);
+
+        receiver.cleanup(now + fdt_timeout / 2);
+        let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+        assert_eq!(stats.fdt_instances_in_progress, 1);
+        assert_eq!(stats.fdt_instances_timed_out, 0);
+
+        receiver.cleanup(now + fdt_timeout * 2);
+        let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+        assert_eq!(stats.fdt_instances_in_progress, 0);
+        assert_eq!(stats.fdt_instances_timed_out, 1);
+        assert_eq!(stats.fdt_instances_received, 0);
+    }
+
+    fn run_receiver_gzip_without_cenc(autodetect_cenc: bool) -> (Vec<u8>, Vec<u8>) {
+        let plaintext = "Hello FLUTE ! ".repeat(10000).into_bytes();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&plaintext).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let obj = sender::ObjectDesc::create_from_buffer(
+            &compressed,
+            "text/plain",
+            &url::Url::parse("file:///hello.txt").unwrap(),
+            1,
+            None,
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let receiver_config = receiver::Config {
+            autodetect_cenc,
+            ..Default::default()
+        };
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let objects = output.objects.borrow();
+        assert!(objects.len() == 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(!object.error);
+        (object.data.clone(), plaintext)
+    }
+
+    #[test]
+    pub fn test_receiver_autodetect_cenc() {
+        init();
+        let (data, plaintext) = run_receiver_gzip_without_cenc(true);
+        assert!(data.eq(&plaintext));
+    }
+
+    #[test]
+    pub fn test_receiver_autodetect_cenc_disabled() {
+        init();
+        let (data, plaintext) = run_receiver_gzip_without_cenc(false);
+        assert!(data.len() < plaintext.len());
+        assert!(data[..2] == [0x1f, 0x8b]);
+    }
+
+    #[test]
+    pub fn test_receiver_content_encoding() {
+        init();
+        let plaintext = "Hello FLUTE ! ".repeat(10000).into_bytes();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&plaintext).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        for cenc in [flute::core::lct::Cenc::Gzip, flute::core::lct::Cenc::Null] {
+            let mut obj = sender::ObjectDesc::create_from_buffer(
+                &compressed,
+                "text/plain",
+                &url::Url::parse("file:///hello.txt").unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                cenc,
+                true,
+                None,
+                true,
+            )
+            .unwrap();
+            obj.content_encoding = Some("gzip".to_owned());
+
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let receiver_config = receiver::Config {
+                autodetect_cenc: true,
+                ..Default::default()
+            };
+            let mut receiver =
+                receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+
+            let oti: flute::core::Oti = Default::default();
+            let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+            let fdt = String::from_utf8(sender.fdt_xml_data(std::time::SystemTime::now()).unwrap())
+                .unwrap();
+            assert!(fdt.contains("X-Content-Coding=\"gzip\""));
+            run(&mut sender, &mut receiver);
+
+            let objects = output.objects.borrow();
+            assert!(objects.len() == 1);
+            let object = objects[0].borrow();
+            assert!(object.complete);
+            assert!(!object.error);
+            // Only the transfer coding is reversed, the content is delivered pre-compressed
+            assert!(object.data.eq(&compressed));
+            assert_eq!(object.meta.cenc, Some(cenc));
+            assert_eq!(object.meta.content_encoding.as_deref(), Some("gzip"));
+            assert!(!object.meta.md5_mismatch);
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_alternate_content_locations() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (mut obj, input_file_buffer) = create_object(
+            10000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let alternate_locations = vec![
+            url::Url::parse("https://example.com/hello").unwrap(),
+            url::Url::parse("https://example.org/hello").unwrap(),
+        ];
+        obj.alternate_content_locations = Some(alternate_locations.clone());
+        let input_content_location = obj.content_location.clone();
+
+        let base_urls = vec!["https://example.com/".to_owned()];
+        let sender_config = sender::Config {
+            base_urls: Some(base_urls.clone()),
+            ..Default::default()
+        };
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(
+            vec![obj],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            Some(sender_config),
+        );
+        run(&mut sender, &mut receiver);
+
+        check_output(
+            &input_file_buffer,
+            &input_content_location,
+            content_type,
+            None,
+            &output,
+        );
+
+        let output_session = output.objects.borrow();
+        let meta = &output_session[0].borrow().meta;
+        let expected_locations: Vec<String> = alternate_locations
+            .iter()
+            .map(|location| location.to_string())
+            .collect();
+        assert_eq!(meta.alternate_locations, Some(expected_locations));
+        assert_eq!(meta.base_urls, Some(base_urls));
+    }
+
+    #[test]
+    pub fn test_partition_simulate_matches_sender() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1424, 64);
+        let transfer_length = 1_000_000;
+        let layout = flute::core::partition::simulate(&oti, transfer_length as u64);
+
+        let (obj, _) = create_object(
+            transfer_length,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        let mut symbols_per_block = std::collections::BTreeMap::<u32, u64>::new();
+        let now = std::time::SystemTime::now();
+        while let Some(data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            // Skip the FDT
+            if pkt.lct.toi == 0 {
+                continue;
+            }
+            let payload_id = flute::core::alc::parse_payload_id(&pkt, &oti).unwrap();
+            *symbols_per_block.entry(payload_id.sbn).or_default() += 1;
+        }
+
+        assert_eq!(symbols_per_block.len() as u64, layout.nb_blocks);
+        assert_eq!(
+            symbols_per_block.values().sum::<u64>(),
+            layout.total_symbols
+        );
+        assert_eq!(
+            *symbols_per_block.values().last().unwrap(),
+            layout.last_block_symbols
+        );
+        assert_eq!(
+            symbols_per_block
+                .values()
+                .filter(|&&nb| nb == layout.large_block_symbols)
+                .count() as u64,
+            layout.nb_large_blocks
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_fdt_diff() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = |content_location: &str, content: &[u8]| {
+            sender::ObjectDesc::create_from_buffer(
+                content,
+                "text/plain",
+                &url::Url::parse(content_location).unwrap(),
+                1,
+                // Keep the objects in the FDT once transferred
+                Some(std::time::Duration::from_secs(3600)),
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap()
+        };
+
+        let mut sender = create_sender(
+            vec![
+                create_obj("file:///a", b"content a"),
+                create_obj("file:///b", b"content b"),
+            ],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            None,
+        );
+        let objects = sender.get_objects_in_fdt();
+        let toi_a = *objects
+            .iter()
+            .find(|(_, obj)| obj.content_location.as_str() == "file:///a")
+            .unwrap()
+            .0;
+        let toi_b = *objects
+            .iter()
+            .find(|(_, obj)| obj.content_location.as_str() == "file:///b")
+            .unwrap()
+            .0;
+
+        let writer = Rc::new(TestWriterBuilder::new());
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, writer.clone(), None);
+        let mut push_all = |sender: &mut sender::Sender| {
+            let now = std::time::SystemTime::now();
+            while let Some(pkt) = sender.read(now) {
+                receiver.push_data(&pkt, now).unwrap();
+            }
+            receiver.fdt_diff().unwrap()
+        };
+
+        // Publish 1: a and b are added
+        let diff = push_all(&mut sender);
+        let added: Vec<&str> = diff
+            .added
+            .iter()
+            .map(|file| file.content_location.as_str())
+            .collect();
+        assert_eq!(added, vec!["file:///a", "file:///b"]);
+        assert!(diff.removed.is_empty());
+        assert!(diff.modified.is_empty());
+
+        // Publish 2: MD5 of a is modified
+        sender
+            .replace_object_content(
+                toi_a,
+                sender::ObjectDataSource::Buffer(b"new content a".to_vec()),
+                None,
+                std::time::SystemTime::now(),
+            )
+            .unwrap();
+        let diff = push_all(&mut sender);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.modified.len(), 1);
+        let (old, new) = &diff.modified[0];
+        assert_eq!(new.content_location, "file:///a");
+        assert_eq!(new.toi, toi_a);
+        assert!(old.md5.is_some() && new.md5.is_some());
+        assert_ne!(old.md5, new.md5);
+        assert_eq!(new.size, 13);
+
+        // Publish 3: b is removed
+        assert!(sender.remove_object(toi_b));
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        let diff = push_all(&mut sender);
+        assert!(diff.added.is_empty());
+        assert!(diff.modified.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].content_location, "file:///b");
+        assert_eq!(diff.removed[0].toi, toi_b);
+
+        assert_eq!(writer.diffs.borrow().len(), 3);
+        assert_eq!(writer.diffs.borrow().last(), Some(&diff));
+    }
+
+    #[test]
+    pub fn test_receiver_md5_check_filter() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = |content_location: &str| {
+            let mut obj = sender::ObjectDesc::create_from_buffer(
+                &vec![0xAAu8; 10000],
+                "application/octet-stream",
+                &url::Url::parse(content_location).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                false,
+            )
+            .unwrap();
+            // Announce a wrong MD5
+            obj.md5 = Some(base64::engine::general_purpose::STANDARD.encode(md5::compute(b"").0));
+            obj
+        };
+
+        let mut output = receiver::writer::ObjectWriterBufferBuilder::new();
+        output.set_md5_check_filter(|meta| meta.content_location.path().starts_with("/firmware"));
+        let output = Rc::new(output);
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(
+            vec![
+                create_obj("file:///firmware/image.bin"),
+                create_obj("file:///telemetry/report.json"),
+            ],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            None,
+        );
+        run(&mut sender, &mut receiver);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 2);
+        for object in objects.iter() {
+            let object = object.borrow();
+            let md5_checked = object.meta.content_location.path().starts_with("/firmware");
+            assert_eq!(object.complete, !md5_checked);
+            assert_eq!(object.error, md5_checked);
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_fdt_version_rollback() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = || {
+            create_object(
+                1000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            )
+            .0
+        };
+
+        // Publish FDT instances [start_id, start_id + nb_publish)
+        let create_sender = |fdt_start_id: u32| {
+            let config = sender::Config {
+                fdt_start_id,
+                ..Default::default()
+            };
+            let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+            sender::Sender::new(endpoint, 1, &oti, &config)
+        };
+        let push_all =
+            |sender: &mut sender::Sender, receiver: &mut receiver::Receiver, nb_publish| {
+                let now = std::time::SystemTime::now();
+                for _ in 0..nb_publish {
+                    sender.add_object(0, create_obj()).unwrap();
+                    sender.publish(now).unwrap();
+                    while let Some(pkt) = sender.read(now) {
+                        receiver.push_data(&pkt, now).unwrap();
+                    }
+                }
+            };
+
+        for allow_fdt_version_rollback in [false, true] {
+            let config = receiver::Config {
+                allow_fdt_version_rollback,
+                ..Default::default()
+            };
+            let writer = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+            let mut receiver = receiver::Receiver::new(&endpoint, 1, writer, Some(config));
+
+            // FDT 5, 6, 7
+            push_all(&mut create_sender(5), &mut receiver, 3);
+            assert_eq!(receiver.stats().current_fdt_id, Some(7));
+
+            // The sender restarts: FDT 1, 2
+            push_all(&mut create_sender(1), &mut receiver, 2);
+            let stats = receiver.stats();
+            if allow_fdt_version_rollback {
+                assert_eq!(stats.fdt_instances_received, 5);
+                assert_eq!(stats.current_fdt_id, Some(2));
+            } else {
+                assert_eq!(stats.fdt_instances_received, 3);
+                assert_eq!(stats.current_fdt_id, Some(7));
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_sender_toi_max16_wraparound() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let contents: Vec<(url::Url, Vec<u8>)> = (0..3)
+            .map(|i| {
+                let (buffer, _) = create_file_buffer(5000 + i);
+                let url = url::Url::parse(&format!("file:///object{}", i)).unwrap();
+                (url, buffer)
+            })
+            .collect();
+        let objects = contents
+            .iter()
+            .map(|(url, buffer)| {
+                sender::ObjectDesc::create_from_buffer(
+                    buffer,
+                    "application/octet-stream",
+                    url,
+                    1,
+                    None,
+                    None,
+                    None,
+                    None,
+                    flute::core::lct::Cenc::Null,
+                    true,
+                    None,
+                    true,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let config = sender::Config {
+            toi_max_length: sender::TOIMaxLength::ToiMax16,
+            toi_initial_value: Some(0xFFFE),
+            ..Default::default()
+        };
+        let mut sender = create_sender(objects, &oti, flute::core::lct::Cenc::Null, Some(config));
+
+        // TOI 0 is reserved for the FDT and skipped on wraparound
+        let mut tois: Vec<(u128, String)> = sender
+            .get_objects_in_fdt()
+            .iter()
+            .map(|(toi, obj)| (*toi, obj.content_location.to_string()))
+            .collect();
+        tois.sort();
+        assert_eq!(
+            tois,
+            vec![
+                (1, "file:///object2".to_owned()),
+                (0xFFFE, "file:///object0".to_owned()),
+                (0xFFFF, "file:///object1".to_owned()),
+            ]
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), contents.len());
+        for (url, buffer) in &contents {
+            let object = output_session
+                .iter()
+                .find(|object| object.borrow().meta.content_location == *url)
+                .unwrap()
+                .borrow();
+            assert!(object.complete);
+            assert!(!object.error);
+            assert_eq!(&object.data, buffer);
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_object_filter() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = |content_location: &str, size: usize| {
+            sender::ObjectDesc::create_from_buffer(
+                &vec![0u8; size],
+                "application/octet-stream",
+                &url::Url::parse(content_location).unwrap(),
+                1,
+                None,
+                None,
+                None,
+                None,
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                true,
+            )
+            .unwrap()
+        };
+
+        // 1 block for the map, 2 blocks for the video and the logo
+        let mut sender = create_sender(
+            vec![
+                create_obj("file:///maps/map.bin", 10000),
+                create_obj("file:///video.bin", 100000),
+                create_obj("file:///logo.bin", 100000),
+            ],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            None,
+        );
+
+        let mut writer = TestWriterBuilder::new();
+        writer.filter = |meta| match meta.content_location.path() {
+            path if path.starts_with("/maps/") => receiver::writer::ObjectFilterDecision::Receive,
+            "/logo.bin" => receiver::writer::ObjectFilterDecision::MetadataOnly,
+            _ => receiver::writer::ObjectFilterDecision::Discard,
+        };
+        let writer = Rc::new(writer);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let mut receiver = receiver::Receiver::new(&endpoint, 1, writer.clone(), None);
+        loop {
+            let now = std::time::SystemTime::now();
+            let data = sender.read(now);
+            if data.is_none() && sender.get_objects_in_fdt().is_empty() {
+                break;
+            }
+
+            if let Some(data) = data {
+                receiver.push_data(&data, now).unwrap();
+            }
+        }
+
+        let stats = receiver.stats();
+        assert_eq!(stats.objects_skipped, 2);
+        assert_eq!(stats.blocks_allocated, 1);
+
+        let objects = writer.inner.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert_eq!(object.meta.content_location.path(), "/maps/map.bin");
+        assert_eq!(
+            *writer.metadata_only.borrow(),
+            vec![url::Url::parse("file:///logo.bin").unwrap()]
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_push_parsed() {
+        init();
+        let content_type = "application/octet-stream";
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (obj, input_file_buffer) = create_object(
+            100000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let input_content_location = obj.content_location.clone();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        loop {
+            let now = std::time::SystemTime::now();
+            let data = sender.read(now);
+            if data.is_none() && sender.get_objects_in_fdt().is_empty() {
+                break;
+            }
+
+            if let Some(data) = data {
+                let alc = flute::core::alc::parse_alc_pkt(&data).unwrap();
+                receiver.push_parsed(&endpoint, &alc, now).unwrap();
+            }
+            receiver.cleanup(now);
+        }
+
+        check_output(
+            &input_file_buffer,
+            &input_content_location,
+            content_type,
+            None,
+            &output,
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_max_object_transfer_length() {
+        use flute::core::alc::PacketBuilder;
+        init();
+
+        // The sender advertises a 100 GB object in the EXT_FTI of its first packet
+        let oti = flute::core::Oti::new_no_code(1400, 4096);
+        let transfer_length = 100 * 1024 * 1024 * 1024;
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let pkt = PacketBuilder::new()
+            .tsi(1)
+            .toi(1)
+            .oti(oti)
+            .fti(transfer_length)
+            .payload(vec![0; 1400])
+            .build()
+            .unwrap();
+
+        for max_object_transfer_length in [None, Some(1024 * 1024 * 1024)] {
+            let config = receiver::Config {
+                max_object_transfer_length,
+                max_objects_error: 10,
+                ..Default::default()
+            };
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver = receiver::MultiReceiver::new(output.clone(), Some(config), false);
+            let now = std::time::SystemTime::now();
+            receiver.push(&endpoint, &pkt, now).unwrap();
+
+            let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+            let objects = output.objects.borrow();
+            let rejected = output.transfer_length_exceeded.borrow();
+            match max_object_transfer_length {
+                None => {
+                    assert_eq!(stats.objects_in_progress, 1);
+                    assert_eq!(stats.objects_error, 0);
+                    assert!(rejected.is_empty());
+                }
+                Some(_) => {
+                    assert_eq!(stats.objects_in_progress, 0);
+                    assert_eq!(stats.objects_error, 1);
+                    assert_eq!(*rejected, vec![1]);
+                    assert!(objects.iter().all(|object| {
+                        let object = object.borrow();
+                        object.error && !object.complete && object.data.is_empty()
+                    }));
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_dedup_by_etag() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = || {
+            let (mut obj, _) = create_object(
+                10000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            obj.file_etag = Some("\"v1\"".to_owned());
+            obj
+        };
+
+        for dedup_by_etag in [false, true] {
+            let config = receiver::Config {
+                dedup_by_etag,
+                ..Default::default()
+            };
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver = receiver::MultiReceiver::new(output.clone(), Some(config), false);
+            let mut sender =
+                create_sender(vec![create_obj()], &oti, flute::core::lct::Cenc::Null, None);
+            run(&mut sender, &mut receiver);
+
+            // Same file offered again under a new TOI
+            sender.add_object(0, create_obj()).unwrap();
+            sender.publish(std::time::SystemTime::now()).unwrap();
+            run(&mut sender, &mut receiver);
+
+            let output_session = output.objects.borrow();
+            assert!(output_session.iter().all(|object| object.borrow().complete));
+            assert_eq!(output_session.len(), if dedup_by_etag { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_dedup_by_etag_forgotten() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let create_obj = || {
+            let (mut obj, _) = create_object(
+                10000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            obj.file_etag = Some("\"v1\"".to_owned());
+            obj
+        };
+
+        let config = receiver::Config {
+            dedup_by_etag: true,
+            ..Default::default()
+        };
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), Some(config), false);
+        let mut sender =
+            create_sender(vec![create_obj()], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        // The object leaves the FDT, its ETag is forgotten
+        let mut other = create_obj();
+        other.file_etag = Some("\"v2\"".to_owned());
+        sender.add_object(0, other).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        run(&mut sender, &mut receiver);
+
+        sender.add_object(0, create_obj()).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), 3);
+        assert!(output_session.iter().all(|object| object.borrow().complete));
+    }
+
+    #[test]
+    pub fn test_independent_unit_positions() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (mut obj, _) = create_object(
+            10000,
+            "video/mp4",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        obj.independent_unit_positions = Some(vec![0, 2048, 6144]);
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), 1);
+        let object = output_session[0].borrow();
+        assert!(object.complete);
+        assert_eq!(
+            object.meta.independent_unit_positions,
+            Some(vec![0, 2048, 6144])
+        );
+    }
+
+    #[cfg(feature = "sdp")]
+    #[test]
+    pub fn test_receiver_from_sdp() {
+        init();
+        let sdp = "v=0\r\n\
+                   o=- 0 0 IN IP4 127.0.0.1\r\n\
+                   s=FLUTE session\r\n\
+                   c=IN IP4 224.0.0.1/255\r\n\
+                   t=0 0\r\n\
+                   a=flute-tsi:1\r\n\
+                   a=flute-fec-encoding-id:0\r\n\
+                   a=flute-encoding-symbol-length:1400\r\n\
+                   a=flute-max-source-block-length:64\r\n\
+                   m=application 5000 FLUTE/UDP 0\r\n";
+
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let (mut receiver, endpoint, tsi) =
+            receiver::MultiReceiver::from_sdp(sdp, output.clone(), None).unwrap();
+        assert_eq!(
+            endpoint,
+            UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000)
+        );
+        assert_eq!(tsi, 1);
+
+        let out_of_band_oti = receiver.out_of_band_oti(&endpoint, tsi).unwrap();
+        assert_eq!(out_of_band_oti.fec_encoding_id, oti.fec_encoding_id);
+        assert_eq!(out_of_band_oti.encoding_symbol_length, 1400);
+        assert_eq!(out_of_band_oti.maximum_source_block_length, 64);
+
+        let (obj, buffer) = create_object(
+            10000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        run(&mut sender, &mut receiver);
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), 1);
+        let object = output_session[0].borrow();
+        assert!(object.complete);
+        assert_eq!(object.data, buffer);
+
+        // SSM session
+        let sdp = "c=IN IP4 232.1.1.1\r\n\
+                   a=source-filter: incl IN IP4 232.1.1.1 10.0.0.1\r\n\
+                   a=flute-tsi:42\r\n\
+                   m=application 4000 FLUTE/UDP 0\r\n";
+        let writer = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let (receiver, endpoint, tsi) =
+            receiver::MultiReceiver::from_sdp(sdp, writer, None).unwrap();
+        assert_eq!(
+            endpoint,
+            UDPEndpoint::new(Some("10.0.0.1".to_owned()), "232.1.1.1".to_owned(), 4000)
+        );
+        assert_eq!(tsi, 42);
+        assert!(receiver.out_of_band_oti(&endpoint, tsi).is_none());
+
+        let writer = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        assert!(
+            receiver::MultiReceiver::from_sdp("m=application 4000 FLUTE/UDP 0", writer, None)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "sdp")]
+    #[test]
+    pub fn test_sdp_mbms_round_trip() {
+        init();
+        // 3GPP TS 26.346 FLUTE session description example
+        let sdp = "v=0\r\n\
+                   o=user123 2890844526 2890842807 IN IP6 2201:056D::112E:144A:1E24\r\n\
+                   s=File delivery session example\r\n\
+                   i=More information\r\n\
+                   t=2873397496 2873404696\r\n\
+                   a=mbms-mode:broadcast 1234\r\n\
+                   a=FEC-declaration:0 encoding-id=1\r\n\
+                   a=source-filter: incl IN IP6 * 2001:210:1:2:240:96FF:FE25:8EC9\r\n\
+                   a=flute-tsi:3\r\n\
+                   m=application 12345 FLUTE/UDP 0\r\n\
+                   c=IN IP6 FF1E:03AD::7F2E:172A:1E24/1\r\n\
+                   a=lang:EN\r\n\
+                   a=FEC:0\r\n";
+
+        let sessions = flute::sdp::SessionDescription::from_sdp(sdp).unwrap();
+        assert_eq!(sessions.len(), 1);
+        let endpoint = UDPEndpoint::new(
+            Some("2001:210:1:2:240:96FF:FE25:8EC9".to_owned()),
+            "FF1E:03AD::7F2E:172A:1E24".to_owned(),
+            12345,
+        );
+        assert_eq!(sessions[0].endpoint, endpoint);
+        assert_eq!(sessions[0].tsi, 3);
+        // The FEC declaration alone does not carry the OTI
+        assert!(sessions[0].oti.is_none());
+
+        // Announce the session with its out-of-band OTI
+        let oti = flute::core::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        let mut session = sessions[0].clone();
+        let mut out_of_band_oti = oti.clone();
+        out_of_band_oti.inband_fti = false;
+        session.oti = Some(out_of_band_oti);
+        let description =
+            flute::sdp::SessionDescription::new("File delivery session example", vec![session]);
+        let generated = description.to_sdp();
+        log::info!("{}", generated);
+        let parsed = flute::sdp::SessionDescription::from_sdp(&generated).unwrap();
+        assert_eq!(parsed, description.sessions);
+
+        // Configure a receiver from the SDP of the sender
+        let sender_config = sender::Config::default();
+        let mut sender = sender::Sender::new(endpoint.clone(), 3, &oti, &sender_config);
+        let (obj, buffer) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let (mut receiver, sdp_endpoint, tsi) =
+            receiver::MultiReceiver::from_sdp(&sender.to_sdp(), output.clone(), None).unwrap();
+        assert_eq!(sdp_endpoint, endpoint);
+        assert_eq!(tsi, 3);
+        let out_of_band_oti = receiver.out_of_band_oti(&endpoint, tsi).unwrap();
+        assert_eq!(out_of_band_oti.fec_encoding_id, oti.fec_encoding_id);
+        assert_eq!(out_of_band_oti.encoding_symbol_length, 1400);
+        assert_eq!(out_of_band_oti.maximum_source_block_length, 64);
+
+        while let Some(pkt) = sender.read(std::time::SystemTime::now()) {
+            let now = std::time::SystemTime::now();
+            receiver.push(&endpoint, &pkt, now).unwrap();
+            receiver.cleanup(now);
+        }
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), 1);
+        let object = output_session[0].borrow();
+        assert!(object.complete);
+        assert_eq!(object.data, buffer);
+    }
+
+    #[test]
+    pub fn test_sender_object_inband_sct() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let content_type = "application/octet-stream";
+        let (mut obj_sct, _) = create_object(
+            10000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        obj_sct.inband_sct = true;
+        obj_sct.content_location = url::Url::parse("file:///sct.bin").unwrap();
+        let (obj, _) = create_object(
+            10000,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+
+        let sender_config = sender::Config {
+            fdt_inband_sct: false,
+            ..Default::default()
+        };
+        let mut sender = create_sender(
+            vec![obj_sct, obj],
+            &oti,
+            flute::core::lct::Cenc::Null,
+            Some(sender_config),
+        );
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        // Simulated send-loop clock, one packet every 10ms
+        let mut now = std::time::SystemTime::now();
+        let mut last_sct_time = None;
+        loop {
+            now += std::time::Duration::from_millis(10);
+            let data = sender.read(now);
+            if data.is_none() && sender.get_objects_in_fdt().is_empty() {
+                break;
+            }
+
+            if let Some(data) = data {
+                let alc = flute::core::alc::parse_alc_pkt(&data).unwrap();
+                if let Some(server_time) = alc.server_time {
+                    assert_eq!(server_time_delta(server_time, now).as_millis(), 0);
+                    last_sct_time = Some(now);
+                }
+                receiver.push_parsed(&endpoint, &alc, now).unwrap();
+            }
+        }
+
+        let output_session = output.objects.borrow();
+        assert_eq!(output_session.len(), 2);
+        for object in output_session.iter() {
+            let object = object.borrow();
+            assert!(object.complete);
+            let sct = object.stats.as_ref().unwrap().sender_current_time;
+            if object.meta.content_location.path() == "/sct.bin" {
+                let delta = server_time_delta(sct.unwrap(), last_sct_time.unwrap());
+                assert!(delta < std::time::Duration::from_millis(1));
+            } else {
+                assert!(sct.is_none());
+            }
+        }
+    }
+
+    fn server_time_delta(
+        a: std::time::SystemTime,
+        b: std::time::SystemTime,
+    ) -> std::time::Duration {
+        a.duration_since(b)
+            .or_else(|_| b.duration_since(a))
+            .unwrap()
+    }
+
+    #[test]
+    pub fn test_sender_offline_encoder() {
+        init();
+        let oti = flute::core::Oti::new_reed_solomon_rs28(1400, 64, 20).unwrap();
+        let (buffer, content_location) = create_file_buffer(200000);
+        let object = sender::ObjectDesc::create_from_buffer(
+            &buffer,
+            "application/octet-stream",
+            &content_location,
+            1,
+            None,
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let encoder =
+            sender::offline::ObjectEncoder::new(object, &oti, 1, 10, sender::Profile::RFC6726, 1)
+                .unwrap();
+        assert_eq!(encoder.toi(), 10);
+        let nb_packets = encoder.nb_packets();
+
+        let publish_time = std::time::SystemTime::now();
+        let config = sender::offline::FdtConfig {
+            oti: oti.clone(),
+            ..Default::default()
+        };
+        let mut fdt_encoder =
+            sender::offline::FdtEncoder::new(&[&encoder], 1, 1, publish_time, &config).unwrap();
+        fdt_encoder.set_sender_current_time(Some(publish_time));
+
+        // Store the packets, FDT first
+        let fdt_packets: Vec<Vec<u8>> = fdt_encoder.collect();
+        let object_packets: Vec<Vec<u8>> = encoder.collect();
+        assert!(!fdt_packets.is_empty());
+        assert_eq!(object_packets.len() as u64, nb_packets);
+
+        // Replay the packets with losses
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let now = std::time::SystemTime::now();
+        for pkt in fdt_packets.iter().chain(
+            object_packets
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| index % 8 != 0)
+                .map(|(_, pkt)| pkt),
+        ) {
+            receiver.push(&endpoint, pkt, now).unwrap();
+        }
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        let md5 = base64::engine::general_purpose::STANDARD.encode(md5::compute(&buffer).0);
+        assert!(object.complete);
+        assert!(!object.error);
+        assert!(object.data.eq(&buffer));
+        assert_eq!(object.meta.content_location, content_location);
+        assert_eq!(object.meta.md5.as_deref(), Some(md5.as_str()));
+    }
+
+    #[test]
+    pub fn test_sender_read_symbol_count_estimate() {
+        init();
+        let oti = flute::core::Oti::new_reed_solomon_rs28(1400, 60, 4).unwrap();
+        let content_type = "application/octet-stream";
+        let objects = [10000, 50000, 200000]
+            .iter()
+            .map(|size| {
+                create_object(
+                    *size,
+                    content_type,
+                    flute::core::lct::Cenc::Null,
+                    true,
+                    None,
+                    None,
+                )
+                .0
+            })
+            .collect();
+        let mut sender = create_sender(objects, &oti, flute::core::lct::Cenc::Null, None);
+
+        let now = std::time::SystemTime::now();
+        let estimate = sender.read_symbol_count_estimate(now);
+        let expected: u64 = [10000, 50000, 200000]
+            .iter()
+            .map(|size| oti.packets_per_object(*size))
+            .sum();
+        assert!(estimate > expected);
+
+        let mut nb_pkts: u64 = 0;
+        while sender.read(now).is_some() {
+            nb_pkts += 1;
+        }
+        assert!(sender.get_objects_in_fdt().is_empty());
+        assert!(nb_pkts >= estimate);
+        // The FDT is repeated during the transfer of the objects
+        assert!(nb_pkts <= estimate + 10);
+        assert_eq!(sender.read_symbol_count_estimate(now), 0);
+
+        let (obj, _) = create_object(
+            0,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        sender.add_object(0, obj).unwrap();
+        assert_eq!(sender.read_symbol_count_estimate(now), u64::MAX);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    pub async fn test_receiver_object_writer_channel() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (obj, buffer) = create_object(
+            300000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let writer = Rc::new(receiver::writer::ObjectWriterChannelBuilder::new(16));
+        let mut receiver = receiver::MultiReceiver::new(writer.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let mut object = writer.pop_object().unwrap();
+        assert!(writer.pop_object().is_none());
+
+        let mut data = Vec::new();
+        while let Some(chunk) = object.receiver.recv().await {
+            data.extend_from_slice(&chunk);
+        }
+
+        assert_eq!(
+            object.status(),
+            receiver::writer::ObjectChannelStatus::Complete
+        );
+        assert_eq!(object.meta.content_length, Some(buffer.len()));
+        assert!(data == buffer);
+    }
+
+    #[test]
+    pub fn test_parse_alc_pkt_fuzz_corpus() {
+        crate::tests::init();
+        let corpus =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/parse_alc_pkt");
+        let mut nb_regressions = 0;
+        for entry in std::fs::read_dir(corpus).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            let data = std::fs::read(&path).unwrap();
+
+            // Same calls as the fuzz target, none of them must panic
+            let pkt = match flute::core::alc::parse_alc_pkt(&data) {
+                Ok(pkt) => pkt,
+                Err(_) => {
+                    assert!(!name.starts_with("valid_"), "{}", name);
+                    continue;
+                }
+            };
+            let _ = flute::core::alc::get_sender_current_time(&pkt);
+            if let Some(oti) = pkt.oti.as_ref() {
+                let _ = flute::core::alc::parse_payload_id(&pkt, oti);
+            }
+            let _ = flute::core::alc::parse_payload_id(&pkt, &Default::default());
+
+            if name.starts_with("regression_") {
+                nb_regressions += 1;
+            }
+        }
+        assert!(nb_regressions > 0);
+    }
+
+    #[test]
+    pub fn test_receiver_fuzz() {
+        use rand::Rng;
+        init();
+        let otis = vec![
+            flute::core::Oti::new_no_code(1400, 64),
+            flute::core::Oti::new_reed_solomon_rs28(1400, 64, 20).unwrap(),
+            flute::core::Oti::new_reed_solomon_rs28_under_specified(1400, 64, 20).unwrap(),
+            flute::core::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap(),
+            flute::core::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap(),
+        ];
+
+        let mut rng = rand::thread_rng();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        for oti in &otis {
+            let (object, _) = create_object(
+                100000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            let mut sender = create_sender(vec![object], oti, flute::core::lct::Cenc::Null, None);
+            let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+            let mut receiver = receiver::MultiReceiver::new(output, None, false);
+
+            // Corrupt a few bytes of the packets, the receiver must not panic
+            let now = std::time::SystemTime::now();
+            while let Some(mut pkt) = sender.read(now) {
+                for _ in 0..rng.gen_range(0..4) {
+                    let index = rng.gen_range(0..pkt.len());
+                    pkt[index] = rng.gen();
+                }
+                let _ = receiver.push(&endpoint, &pkt, now);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_receiver_forged_packets() {
+        use flute::core::alc::{parse_alc_pkt, PacketBuilder};
+        init();
+
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let (object, buffer) = create_object(
+            5000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![object], &oti, flute::core::lct::Cenc::Null, None);
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let now = std::time::SystemTime::now();
+        let packets: Vec<Vec<u8>> = std::iter::from_fn(|| sender.read(now)).collect();
+        let (fdt, object): (Vec<_>, Vec<_>) = packets
+            .into_iter()
+            .partition(|pkt| parse_alc_pkt(pkt).unwrap().lct.toi == 0);
+        let toi = parse_alc_pkt(&object[0]).unwrap().lct.toi;
+        for pkt in &fdt {
+            receiver.push(&endpoint, pkt, now).unwrap();
+        }
+
+        // Packets of the resiliency issue: null encoding symbol length in the EXT_FTI,
+        // ESI out of range, HDR_LEN beyond the end of the packet
+        let mut null_esl = oti.clone();
+        null_esl.encoding_symbol_length = 0;
+        let forged = vec![
+            PacketBuilder::new()
+                .oti(null_esl)
+                .toi(toi)
+                .fti(5000)
+                .payload(vec![0; 16]),
+            PacketBuilder::new()
+                .oti(oti.clone())
+                .toi(toi)
+                .payload_id(0, 1000)
+                .payload(vec![0; 1400]),
+            PacketBuilder::new()
+                .oti(oti.clone())
+                .toi(toi)
+                .hdr_len(255)
+                .payload(vec![0; 1400]),
+        ];
+        for builder in forged {
+            assert!(builder.build().is_err());
+            let _ = receiver.push(&endpoint, &builder.build_unchecked(), now);
+        }
+
+        // The forged packets do not prevent the reception of the object
+        for pkt in &object {
+            let _ = receiver.push(&endpoint, pkt, now);
+        }
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(object.data == buffer);
+    }
+
+    #[test]
+    pub fn test_receiver_empty_file() {
+        init();
+        test_receiver_with_oti(
+            &flute::core::Oti::new_no_code(1400, 64),
+            None,
+            false,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            0,
+            false,
+            None,
+        );
+    }
+
+    fn test_receiver_tiny_file(oti: &flute::core::Oti, transfer_file_size: usize) {
+        test_receiver_with_oti(
+            oti,
+            None,
+            false,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            transfer_file_size,
+            false,
+            None,
+        );
+    }
+
+    #[test]
+    pub fn test_receiver_raptorq_empty_file() {
+        init();
+        let oti = flute::core::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        test_receiver_tiny_file(&oti, 0);
+    }
+
+    #[test]
+    pub fn test_receiver_raptorq_single_byte_file() {
+        init();
+        let oti = flute::core::Oti::new_raptorq(1400, 64, 20, 1, 4).unwrap();
+        test_receiver_tiny_file(&oti, 1);
+    }
+
+    #[test]
+    pub fn test_receiver_raptor_empty_file() {
+        init();
+        let oti = flute::core::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        test_receiver_tiny_file(&oti, 0);
+    }
+
+    #[test]
+    pub fn test_receiver_raptor_single_byte_file() {
+        init();
+        let oti = flute::core::Oti::new_raptor(1400, 64, 20, 1, 4).unwrap();
+        test_receiver_tiny_file(&oti, 1);
+    }
+
+    #[test]
+    fn test_priority_queues() {
+        let content_type = "application/octet-stream";
+
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+
+        let (high_priority_obj, high_priority_buffer) = create_object(
+            1024,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            Some(&oti),
+            None,
+        );
+
+        let (low_priority_obj, low_priority_buffer) = create_object(
+            1024,
+            content_type,
+            flute::core::lct::Cenc::Null,
+            true,
+            Some(&oti),
+            None,
+        );
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+
+        let mut sender_config: sender::Config = Default::default();
+        sender_config.set_priority_queue(PriorityQueue::HIGHEST, PriorityQueue::new(3));
+        sender_config.set_priority_queue(PriorityQueue::LOW, PriorityQueue::new(3));
+
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let mut sender = Box::new(sender::Sender::new(endpoint, 1, &oti, &sender_config));
+
+        sender
             .add_object(PriorityQueue::LOW, low_priority_obj)
             .unwrap();
         sender
@@ -781,6 +4840,153 @@ mod tests {
         assert!(toi_value == toi_result);
     }
 
+    #[test]
+    pub fn test_sender_allocate_toi_range() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let tois = sender.allocate_toi_range(100).unwrap();
+        assert_eq!(tois.len(), 100);
+        let values: Vec<u128> = tois.iter().map(|toi| toi.get()).collect();
+        let first = values[0];
+        assert_eq!(values, (first..first + 100).collect::<Vec<u128>>());
+
+        let mut buffers = HashSet::new();
+        for toi in tois {
+            let (mut obj, buffer) = create_object(
+                1000,
+                "application/octet-stream",
+                flute::core::lct::Cenc::Null,
+                true,
+                None,
+                None,
+            );
+            let toi_value = toi.get();
+            obj.set_toi(toi);
+            assert_eq!(sender.add_object(0, obj).unwrap(), toi_value);
+            buffers.insert(buffer);
+        }
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 100);
+        let received: HashSet<Vec<u8>> = objects
+            .iter()
+            .map(|obj| obj.borrow().data.clone())
+            .collect();
+        assert_eq!(received.len(), 100);
+        for object in objects.iter() {
+            let object = object.borrow();
+            assert!(object.complete);
+            assert!(buffers.contains(&object.data));
+        }
+    }
+
+    #[test]
+    pub fn test_sender_inject_raw_packet() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let (obj, _) = create_object(
+            10000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+
+        // Not a valid ALC/LCT packet, must be transferred as is
+        let raw_packets = vec![vec![0xFFu8; 3], vec![1, 2, 3, 4, 5]];
+        for pkt in &raw_packets {
+            sender.inject_raw_packet(pkt.clone());
+        }
+
+        let now = std::time::SystemTime::now();
+        for pkt in &raw_packets {
+            assert_eq!(sender.read(now).as_ref(), Some(pkt));
+        }
+
+        let mut nb_pkts = 0;
+        while let Some(pkt) = sender.read(now) {
+            let alc = flute::core::alc::parse_alc_pkt(&pkt).unwrap();
+            assert_eq!(alc.lct.tsi, 1);
+            nb_pkts += 1;
+        }
+        assert!(nb_pkts > 1);
+
+        sender.inject_raw_packet(raw_packets[1].clone());
+        assert_eq!(sender.read(now), Some(raw_packets[1].clone()));
+        assert!(sender.read(now).is_none());
+    }
+
+    #[test]
+    pub fn test_sender_rewind() {
+        crate::tests::init();
+
+        let oti: flute::core::Oti = Default::default();
+        let (mut obj, buffer) = create_object(
+            100000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        obj.max_transfer_count = 1;
+        obj.carousel_delay = Some(std::time::Duration::from_secs(3600));
+        let content_location = obj.content_location.clone();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let receiver_config = receiver::Config {
+            object_receive_once: false,
+            ..Default::default()
+        };
+        let mut receiver =
+            receiver::MultiReceiver::new(output.clone(), Some(receiver_config), false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        let nb_complete_objects = |output: &receiver::writer::ObjectWriterBufferBuilder| {
+            output
+                .objects
+                .borrow()
+                .iter()
+                .filter(|obj| obj.borrow().complete)
+                .count()
+        };
+
+        let now = std::time::SystemTime::now();
+        while let Some(data) = sender.read(now) {
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+        assert_eq!(nb_complete_objects(&output), 1);
+        let toi = *sender.get_objects_in_fdt().keys().next().unwrap();
+        assert_eq!(sender.nb_transfers(toi), Some(1));
+
+        // The object waits for its next carousel round
+        assert!(sender.read(now).is_none());
+
+        let fdt_xml = sender.fdt_xml_data(now).unwrap();
+        sender.rewind(now);
+        assert!(!sender.rewind_object(toi + 1, now));
+        while let Some(data) = sender.read(now) {
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+        assert_eq!(sender.nb_transfers(toi), Some(2));
+        assert_eq!(sender.fdt_xml_data(now).unwrap(), fdt_xml);
+
+        assert_eq!(nb_complete_objects(&output), 2);
+        let objects = output.objects.borrow();
+        let last = objects.last().unwrap().borrow();
+        assert_eq!(last.meta.content_location, content_location);
+        assert!(last.data.eq(&buffer));
+    }
+
     #[test]
     pub fn test_receiver_disable_received_once() {
         crate::tests::init();
@@ -841,4 +5047,314 @@ mod tests {
         assert!(nb_complete_objects == max_transfert_count);
         assert!(nb_error_objects == 0);
     }
+
+    #[test]
+    pub fn test_sender_events_file_info() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let mut sender = sender::Sender::new(endpoint.clone(), 1, &oti, &Default::default());
+
+        let all = std::sync::Arc::new(TestSenderEvents {
+            events: std::sync::Mutex::new(Vec::new()),
+        });
+        let stop_only = std::sync::Arc::new(TestSenderEvents {
+            events: std::sync::Mutex::new(Vec::new()),
+        });
+        sender.subscribe(all.clone());
+        sender.subscribe_filtered(stop_only.clone(), sender::EventKindMask::STOP_TRANSFER);
+
+        let (obj, buffer) = create_object(
+            5000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let content_location = obj.content_location.to_string();
+        let toi = sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let expected = sender::FileInfo {
+            toi,
+            content_location,
+            content_length: buffer.len() as u64,
+            transfer_length: buffer.len() as u64,
+            priority: 0,
+            endpoint,
+            tsi: 1,
+        };
+
+        let events = all.events.lock().unwrap();
+        assert!(events.contains(&sender::Event::StartTransfer(expected.clone())));
+        assert!(events.contains(&sender::Event::StopTransfer(expected.clone())));
+        assert!(events.iter().any(|evt| matches!(
+            evt,
+            sender::Event::FdtPublished { instance_id: 1, bytes } if *bytes > 0
+        )));
+
+        let events = stop_only.events.lock().unwrap();
+        assert!(!events.is_empty());
+        assert!(events
+            .iter()
+            .all(|evt| *evt == sender::Event::StopTransfer(expected.clone())));
+    }
+
+    #[test]
+    pub fn test_multireceiver_source_filtering() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let endpoint =
+            UDPEndpoint::new(Some("192.168.1.1".to_owned()), "232.0.0.1".to_owned(), 5000);
+        let (obj, buffer) = create_object(
+            5000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let mut sender = sender::Sender::new(endpoint.clone(), 1, &oti, &sender::Config::default());
+        sender.add_object(0, obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let expected_source: std::net::IpAddr = "192.168.1.1".parse().unwrap();
+        let spoofed_source: std::net::IpAddr = "192.168.1.2".parse().unwrap();
+
+        let mut packets = Vec::new();
+        let now = std::time::SystemTime::now();
+        while let Some(pkt) = sender.read(now) {
+            packets.push(pkt);
+        }
+
+        // Packets from another source are ignored
+        for pkt in &packets {
+            receiver
+                .push_with_source(&endpoint, spoofed_source, pkt, now)
+                .unwrap();
+        }
+        assert!(output.objects.borrow().is_empty());
+        assert!(receiver.session_directory().is_empty());
+
+        for pkt in &packets {
+            receiver
+                .push_with_source(&endpoint, expected_source, pkt, now)
+                .unwrap();
+        }
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        assert!(objects[0].borrow().complete);
+        assert_eq!(objects[0].borrow().data, buffer);
+
+        // Any source is accepted when the endpoint has no source address
+        let any_source = UDPEndpoint::new(None, "232.0.0.1".to_owned(), 5000);
+        assert!(any_source.is_source_allowed(&spoofed_source));
+        assert!(!endpoint.is_source_allowed(&spoofed_source));
+    }
+
+    #[test]
+    pub fn test_multireceiver_force_complete() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        let block_size = 1400 * 64;
+        let (obj, buffer) = create_object(
+            block_size * 4,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        // Only the first 2 blocks of the object are received
+        let now = std::time::SystemTime::now();
+        let mut toi = None;
+        while let Some(data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != 0 {
+                toi = Some(pkt.lct.toi);
+                let payload_id = flute::core::alc::parse_payload_id(&pkt, &oti).unwrap();
+                if payload_id.sbn >= 2 {
+                    continue;
+                }
+            }
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+        let toi = toi.unwrap();
+        assert!(!output.objects.borrow()[0].borrow().complete);
+
+        assert!(!receiver.force_complete(&endpoint, 1, toi + 1, now));
+        assert!(!receiver.force_complete(&endpoint, 2, toi, now));
+        assert!(receiver.force_complete(&endpoint, 1, toi, now));
+        // Already delivered
+        assert!(!receiver.force_complete(&endpoint, 1, toi, now));
+        assert_eq!(receiver.nb_objects(), 0);
+
+        let objects = output.objects.borrow();
+        assert_eq!(objects.len(), 1);
+        let object = objects[0].borrow();
+        assert!(!object.complete);
+        assert!(object.meta.partial);
+        assert_eq!(object.data, buffer[..block_size * 2]);
+        let received_byte_ranges = object.received_byte_ranges.as_ref().unwrap();
+        assert_eq!(received_byte_ranges.len(), 1);
+        assert_eq!(received_byte_ranges[0], 0..(block_size * 2) as u64);
+    }
+
+    #[test]
+    pub fn test_receiver_mbms_session_identity_expiry() {
+        init();
+        let oti: flute::core::Oti = Default::default();
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+        let config = sender::Config {
+            mbms_session_identity_expiry: Some(vec![(1, 3900000000), (2, 3900003600)]),
+            ..Default::default()
+        };
+        let mut sender =
+            create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, Some(config));
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let now = std::time::SystemTime::now();
+        for pkt in sender.read_until_fdt_sent(now) {
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+        let sessions = receiver.session_directory();
+        assert_eq!(
+            sessions[0].mbms_session_identity_expiry,
+            vec![(1, 3900000000), (2, 3900003600)]
+        );
+
+        // Dynamic update of the MBMS Session Identities
+        sender.set_mbms_session_identity_expiry(vec![(3, 3900007200)]);
+        sender.publish(now).unwrap();
+        for pkt in sender.read_until_fdt_sent(now) {
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+        let sessions = receiver.session_directory();
+        assert_eq!(
+            sessions[0].mbms_session_identity_expiry,
+            vec![(3, 3900007200)]
+        );
+
+        sender.set_mbms_session_identity_expiry(Vec::new());
+        sender.publish(now).unwrap();
+        for pkt in sender.read_until_fdt_sent(now) {
+            receiver.push(&endpoint, &pkt, now).unwrap();
+        }
+        assert!(receiver.session_directory()[0]
+            .mbms_session_identity_expiry
+            .is_empty());
+    }
+
+    #[test]
+    pub fn test_receiver_reject_truncated_symbol() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 64);
+        // The last symbol of the object is shorter than the encoding symbol length
+        let (obj, buffer) = create_object(
+            200000,
+            "application/octet-stream",
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            None,
+        );
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        let mut sender = create_sender(vec![obj], &oti, flute::core::lct::Cenc::Null, None);
+        let endpoint = UDPEndpoint::new(None, "224.0.0.1".to_owned(), 5000);
+
+        // A symbol in the middle of the first block is split by the sender,
+        // then retransmitted with the next symbol of the last block
+        let now = std::time::SystemTime::now();
+        let mut truncated = None;
+        while let Some(data) = sender.read(now) {
+            let pkt = flute::core::alc::parse_alc_pkt(&data).unwrap();
+            if pkt.lct.toi != 0 {
+                let payload_id = flute::core::alc::parse_payload_id(&pkt, &oti).unwrap();
+                if payload_id.sbn == 0 && payload_id.esi == 10 {
+                    receiver
+                        .push(&endpoint, &data[..data.len() - 100], now)
+                        .unwrap();
+                    truncated = Some(data);
+                    continue;
+                }
+
+                if payload_id.sbn == 2 && truncated.is_some() {
+                    let stats = receiver.get_session_stats(&endpoint, 1).unwrap();
+                    assert_eq!(stats.symbols_invalid_length, 1);
+                    receiver
+                        .push(&endpoint, &truncated.take().unwrap(), now)
+                        .unwrap();
+                }
+            }
+            receiver.push(&endpoint, &data, now).unwrap();
+        }
+
+        let objects = output.objects.borrow();
+        let object = objects[0].borrow();
+        assert!(object.complete);
+        assert!(object.data.eq(&buffer));
+        let stats = object.stats.as_ref().unwrap();
+        assert_eq!(stats.symbols_invalid_length, 1);
+        assert_eq!(stats.last_invalid_symbol_length, Some((1400, 1300)));
+    }
+
+    #[test]
+    pub fn test_sender_error_events() {
+        init();
+        let oti = flute::core::Oti::new_no_code(1400, 4);
+        let block_size = 1400 * 4;
+
+        // File is truncated after being added to the sender, the 5th read of a block fails
+        let (buffer, content_location) = create_file_buffer(block_size * 10);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&buffer).unwrap();
+        let file_obj = sender::ObjectDesc::create_from_file(
+            file.path(),
+            Some(&content_location),
+            "application/octet-stream",
+            false,
+            1,
+            None,
+            None,
+            None,
+            None,
+            flute::core::lct::Cenc::Null,
+            true,
+            None,
+            false,
+        )
+        .unwrap();
+
+        let mut sender = create_sender(Vec::new(), &oti, flute::core::lct::Cenc::Null, None);
+        let errors = sender.subscribe_error_events();
+        let toi = sender.add_object(0, file_obj).unwrap();
+        sender.publish(std::time::SystemTime::now()).unwrap();
+        file.as_file().set_len(block_size as u64 * 4).unwrap();
+
+        let output = Rc::new(receiver::writer::ObjectWriterBufferBuilder::new());
+        let mut receiver = receiver::MultiReceiver::new(output.clone(), None, false);
+        run(&mut sender, &mut receiver);
+
+        let error = errors.try_recv().unwrap();
+        assert_eq!(error.toi, toi);
+        assert_eq!(error.sbn, 4);
+        assert_eq!(error.kind, sender::ErrorKind::Io);
+        assert!(!error.message.is_empty());
+        assert!(errors.try_recv().is_err());
+        assert!(!sender.is_added(toi));
+    }
 }